@@ -0,0 +1,224 @@
+//! Generates typed WDB record structs from the built-in FF13 schema pack.
+//!
+//! `src/modules/wdb/schemas/ff13_1.json` is the serialized pack that
+//! `default_schema_pack(GameCode::FF13_1)` builds (a unit test keeps the
+//! two in sync). This script writes one struct per sheet and one type per
+//! schema enum to `$OUT_DIR/wdb_records.rs`, which `wdb::records` includes.
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const SCHEMA_PACK: &str = "src/modules/wdb/schemas/ff13_1.json";
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// A schema field: name and bound enum, if any.
+struct Field {
+    name: String,
+    enum_type: Option<String>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", SCHEMA_PACK);
+
+    let text = fs::read_to_string(SCHEMA_PACK).expect("read schema pack");
+    let pack: Value = serde_json::from_str(&text).expect("parse schema pack");
+    let str_of = |v: &Value, key: &str| v[key].as_str().unwrap_or_default().to_string();
+
+    let mut out = String::from("// Generated by build.rs from schemas/ff13_1.json. Do not edit.\n\n");
+    let mut sheet_types = HashSet::new();
+    for sheet in pack["sheets"].as_array().expect("sheets") {
+        let fields: Vec<Field> = sheet["fields"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|f| Field { name: str_of(f, "name"), enum_type: f["enum"].as_str().map(str::to_string) })
+            .collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let sheet_name = str_of(sheet, "name");
+        sheet_types.insert(type_name(&sheet_name));
+        out.push_str(&sheet_struct(&sheet_name, &fields));
+    }
+    for e in pack["enums"].as_array().into_iter().flatten() {
+        let name = str_of(e, "name");
+        assert!(!sheet_types.contains(&name), "enum {} has the same name as a sheet", name);
+        out.push_str(&enum_type(&name, e));
+    }
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("wdb_records.rs");
+    fs::write(path, out).expect("write wdb_records.rs");
+}
+
+/// A schema enum as a `schema_enum!` or `schema_flags!` invocation.
+fn enum_type(name: &str, e: &Value) -> String {
+    let flags = e["flags"].as_bool().unwrap_or(false);
+    let description = e["description"].as_str().map(|d| format!("{}.", d.trim_end_matches('.')));
+    let mut used = HashSet::from(["Other".to_string()]);
+    let values: Vec<String> = e["values"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| {
+            let value_name = v["name"].as_str().expect("enum value name");
+            let mut ident = if flags { const_name(value_name) } else { variant_name(value_name) };
+            while !used.insert(ident.clone()) {
+                ident.push('_');
+            }
+            format!("        {} = {:?} => {},\n", ident, value_name, v["value"].as_i64().expect("enum value"))
+        })
+        .collect();
+    format!(
+        "{}!(\n    /// {}\n    {} {{\n{}    }}\n);\n\n",
+        if flags { "schema_flags" } else { "schema_enum" },
+        description.unwrap_or_else(|| format!("`{}` values from the schema pack.", name)),
+        name,
+        values.concat()
+    )
+}
+
+/// `Fire` -> `Fire`, `key item` -> `KeyItem`.
+fn variant_name(name: &str) -> String {
+    let mut ident: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, 'V');
+    }
+    ident
+}
+
+/// `KeyItem` -> `KEY_ITEM`.
+fn const_name(name: &str) -> String {
+    let mut ident = String::new();
+    for (i, c) in variant_name(name).chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            ident.push('_');
+        }
+        ident.push(c.to_ascii_uppercase());
+    }
+    ident
+}
+
+/// `bt_chainbonus` -> `BtChainbonus`, `crystal` -> `Crystal`.
+fn type_name(sheet: &str) -> String {
+    sheet
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Width encoded after the type prefix, as the reader derives it.
+fn width(field: &str) -> usize {
+    let digits: String = field.chars().skip(1).take(2).take_while(char::is_ascii_digit).collect();
+    digits.parse().unwrap_or(0)
+}
+
+/// `u8NodeType` -> `node_type`, `u1On2ndtLayerPS3` -> `on2ndt_layer_ps3`.
+fn member_name(field: &str) -> String {
+    let rest: String = field.chars().skip(1).skip_while(char::is_ascii_digit).collect();
+    let base = if rest.is_empty() { field.to_string() } else { rest };
+    let chars: Vec<char> = base.chars().collect();
+    let mut name = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_lower) {
+                name.push('_');
+            }
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// Rust type of a field, from its enum binding or its prefix and width.
+///
+/// Bound fields use the enum's own type: a built-in one from `wdb::enums`
+/// or the type generated for a schema enum of the same name.
+fn rust_type(field: &Field) -> String {
+    if let Some(enum_type) = &field.enum_type {
+        return enum_type.clone();
+    }
+    match (field.name.chars().next(), width(&field.name)) {
+        (Some('s'), _) => "String",
+        (Some('u'), 1) => "bool",
+        (Some('u'), 2..=8) => "u8",
+        (Some('u'), 9..=16) => "u16",
+        (Some('i'), 1..=8) => "i8",
+        (Some('i'), 9..=16) => "i16",
+        (Some('f'), 0) => "f32",
+        (Some('u'), _) => "u32",
+        _ => "i32",
+    }
+    .to_string()
+}
+
+fn sheet_struct(sheet: &str, fields: &[Field]) -> String {
+    let name = type_name(sheet);
+    let mut used: HashSet<String> = HashSet::from(["record".to_string()]);
+    let members: Vec<(String, &String, String)> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let mut member = member_name(&field.name);
+            if !used.insert(member.clone()) {
+                member = format!("{}_{}", member, i);
+                used.insert(member.clone());
+            }
+            (member, &field.name, rust_type(field))
+        })
+        .collect();
+
+    let mut s = format!(
+        "/// A `{sheet}` record.\n#[derive(Debug, Clone, PartialEq, Default)]\npub struct {name} {{\n    /// Record id (`record`)\n    pub record: String,\n"
+    );
+    for (member, field, ty) in &members {
+        s.push_str(&format!("    /// `{field}`\n    pub {member}: {ty},\n"));
+    }
+    s.push_str("}\n\n");
+
+    let field_list: Vec<String> = fields.iter().map(|f| format!("{:?}", f.name)).collect();
+    s.push_str(&format!(
+        "impl WdbSheetRecord for {name} {{\n    const SHEET: &'static str = {sheet:?};\n    const FIELDS: &'static [&'static str] = &[{}];\n}}\n\n",
+        field_list.join(", ")
+    ));
+
+    s.push_str(&format!(
+        "impl TryFrom<&WdbRecord> for {name} {{\n    type Error = anyhow::Error;\n\n    fn try_from(record: &WdbRecord) -> Result<Self> {{\n        Ok(Self {{\n            record: field(record, \"record\")?,\n"
+    ));
+    for (member, field, _) in &members {
+        s.push_str(&format!("            {member}: field(record, {field:?})?,\n"));
+    }
+    s.push_str("        })\n    }\n}\n\n");
+
+    s.push_str(&format!(
+        "impl From<{name}> for WdbRecord {{\n    fn from(r: {name}) -> Self {{\n        let mut record = WdbRecord::with_capacity({});\n        record.insert(\"record\".to_string(), r.record.into_value());\n",
+        fields.len() + 1
+    ));
+    for (member, field, _) in &members {
+        s.push_str(&format!("        record.insert({field:?}.to_string(), r.{member}.into_value());\n"));
+    }
+    s.push_str("        record\n    }\n}\n\n");
+    s
+}
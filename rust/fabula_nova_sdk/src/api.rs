@@ -461,15 +461,7 @@ fn map_game_code(code: i32) -> GameCode {
 }
 
 fn determine_wdb_game_code(data: &WdbData) -> GameCode {
-    if let Some(crate::modules::wdb::structs::WdbValue::String(s)) = data.header.get("gameCode") {
-        match s.as_str() {
-            "FF13_2" => GameCode::FF13_2,
-            "LR" | "FF13_3" => GameCode::FF13_3,
-            _ => GameCode::FF13_1,
-        }
-    } else {
-        GameCode::FF13_1
-    }
+    wdb_api::game_code_from_header(&data.header)
 }
 
 // ============================================================================
@@ -6,14 +6,14 @@
 //!
 //! WDB files store game data in a binary database format with a header
 //! section and multiple record sections. This FFI layer marshals Rust
-//! HashMaps to C-compatible structs for cross-language access.
+//! ordered maps to C-compatible structs for cross-language access.
 //!
 //! ## Data Structure Mapping
 //!
 //! ```text
 //! Rust Side                          C Side
 //! ─────────────────────────────────────────────────────────
-//! HashMap<String, WdbValue>   ──►    WDBSectionCInternal
+//! IndexMap<String, WdbValue>  ──►    WDBSectionCInternal
 //!   ├── "key1" => Int(42)            ├── entries: *WDBEntryInternal
 //!   └── "key2" => String("val")      └── entry_count: i32
 //! ```
@@ -40,7 +40,7 @@
 use std::ffi::{c_char, CStr, CString};
 use std::ptr;
 use std::mem::ManuallyDrop;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use crate::modules::wdb::{api, structs::*};
 
 // --- FFI EXPORTS ---
@@ -239,18 +239,18 @@ pub unsafe extern "C" fn WDB_WriteFile(
 
 // --- MARSHALLING HELPERS ---
 //
-// These functions convert between Rust types (HashMap, WdbValue) and
+// These functions convert between Rust types (IndexMap, WdbValue) and
 // C-compatible structures (WDBEntryInternal, WDBValueInternal).
 //
 // Memory ownership:
 // - marshal_* functions allocate memory that must be freed by free_* functions
 // - unmarshal_* functions read C data without taking ownership
 
-/// Converts a Rust HashMap to a C-compatible entry array.
+/// Converts a header or record map to a C-compatible entry array.
 ///
 /// Returns a tuple of (pointer to first entry, entry count).
 /// The caller is responsible for freeing this memory.
-unsafe fn marshal_map_to_c(map: &HashMap<String, WdbValue>) -> (*mut WDBEntryInternal, i32) {
+unsafe fn marshal_map_to_c(map: &IndexMap<String, WdbValue>) -> (*mut WDBEntryInternal, i32) {
     let mut entries_vec = Vec::with_capacity(map.len());
     for (k, v) in map {
         let key_c = CString::new(k.clone()).unwrap().into_raw();
@@ -329,8 +329,8 @@ unsafe fn marshal_value_to_c(val: &WdbValue) -> WDBValueInternal {
     }
 }
 
-unsafe fn unmarshal_section_from_c(section: &WDBSectionCInternal) -> HashMap<String, WdbValue> {
-    let mut map = HashMap::new();
+unsafe fn unmarshal_section_from_c(section: &WDBSectionCInternal) -> WdbHeader {
+    let mut map = WdbHeader::new();
     if !section.entries.is_null() && section.entry_count > 0 {
         let entries = std::slice::from_raw_parts(section.entries, section.entry_count as usize);
         for e in entries {
@@ -342,8 +342,8 @@ unsafe fn unmarshal_section_from_c(section: &WDBSectionCInternal) -> HashMap<Str
     map
 }
 
-unsafe fn unmarshal_record_from_c(record: &WDBRecordCInternal) -> HashMap<String, WdbValue> {
-    let mut map = HashMap::new();
+unsafe fn unmarshal_record_from_c(record: &WDBRecordCInternal) -> WdbRecord {
+    let mut map = WdbRecord::new();
     if !record.entries.is_null() && record.entry_count > 0 {
         let entries = std::slice::from_raw_parts(record.entries, record.entry_count as usize);
         for e in entries {
@@ -2684,7 +2684,7 @@ impl SseDecode
     }
 }
 
-impl SseDecode for std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue> {
+impl SseDecode for indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
         let mut inner =
@@ -3203,15 +3203,15 @@ impl SseDecode for crate::modules::img::structs::ImgData {
     }
 }
 
-impl SseDecode for Vec<std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>> {
+impl SseDecode for Vec<indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
         let mut len_ = <i32>::sse_decode(deserializer);
         let mut ans_ = vec![];
         for idx_ in 0..len_ {
-            ans_.push(<std::collections::HashMap<
+            ans_.push(<indexmap::IndexMap<
                 String,
-                crate::modules::wdb::structs::WdbValue,
+                crate::modules::wdb::structs::WdbValue
             >>::sse_decode(deserializer));
         }
         return ans_;
@@ -4425,12 +4425,12 @@ impl SseDecode for crate::api::WbtFileEntry {
 impl SseDecode for crate::modules::wdb::structs::WdbData {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_header = <std::collections::HashMap<
+        let mut var_header = <indexmap::IndexMap<
             String,
-            crate::modules::wdb::structs::WdbValue,
+            crate::modules::wdb::structs::WdbValue
         >>::sse_decode(deserializer);
         let mut var_records = <Vec<
-            std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>,
+            indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>,
         >>::sse_decode(deserializer);
         return crate::modules::wdb::structs::WdbData {
             header: var_header,
@@ -5937,8 +5937,12 @@ impl flutter_rust_bridge::IntoIntoDart<crate::api::WbtFileEntry> for crate::api:
 impl flutter_rust_bridge::IntoDart for crate::modules::wdb::structs::WdbData {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.header.into_into_dart().into_dart(),
-            self.records.into_into_dart().into_dart(),
+            self.header.into_iter().collect::<Vec<_>>().into_dart(),
+            self.records
+                .into_iter()
+                .map(|r| r.into_iter().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+                .into_dart(),
         ]
         .into_dart()
     }
@@ -6242,7 +6246,7 @@ impl SseEncode
     }
 }
 
-impl SseEncode for std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue> {
+impl SseEncode for indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
         <Vec<(String, crate::modules::wdb::structs::WdbValue)>>::sse_encode(
@@ -6669,12 +6673,12 @@ impl SseEncode for crate::modules::img::structs::ImgData {
     }
 }
 
-impl SseEncode for Vec<std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>> {
+impl SseEncode for Vec<indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
         <i32>::sse_encode(self.len() as _, serializer);
         for item in self {
-            <std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>>::sse_encode(
+            <indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>>::sse_encode(
                 item, serializer,
             );
         }
@@ -7604,11 +7608,11 @@ impl SseEncode for crate::api::WbtFileEntry {
 impl SseEncode for crate::modules::wdb::structs::WdbData {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>>::sse_encode(
+        <indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>>::sse_encode(
             self.header,
             serializer,
         );
-        <Vec<std::collections::HashMap<String, crate::modules::wdb::structs::WdbValue>>>::sse_encode(self.records, serializer);
+        <Vec<indexmap::IndexMap<String, crate::modules::wdb::structs::WdbValue>>>::sse_encode(self.records, serializer);
     }
 }
 
@@ -44,6 +44,22 @@
 //!         Overwrites IMGB at original offsets
 //! ```
 //!
+//! ## Repacking From PNG
+//!
+//! [`repack_img_from_png`] skips the DDS step entirely: the PNG becomes
+//! mip 0, the remaining levels are regenerated, and every level is encoded
+//! to the texture's own GTEX format before being written in place.
+//! [`repack_img_regenerate_mips`] does the same from the base level of a
+//! DDS, for editors that save DDS files without (or with stale) mips.
+//!
+//! ## Cubemaps and Volumes
+//!
+//! Multi-layer textures (see [`super::layout`]) are extracted to DDS with
+//! the matching caps, and to PNG either per face/slice
+//! ([`extract_img_layers_to_png`]) or as a cubemap cross
+//! ([`extract_cubemap_to_cross_png`]). Both forms are accepted back by the
+//! PNG repack functions.
+//!
 //! ## Mipmap Table
 //!
 //! The GTEX header contains an offset to a mipmap table:
@@ -65,10 +81,17 @@ use std::io::{BufReader, BufWriter, Read, Write, Seek, SeekFrom, Cursor};
 use anyhow::Result;
 use byteorder::{BigEndian, ReadBytesExt};
 use image::{ImageBuffer, RgbaImage};
-use ddsfile::{Dds, D3DFormat};
+use ddsfile::Dds;
 use super::reader::ImgReader;
 use super::writer::ImgWriter;
-use super::structs::ImgData;
+use super::structs::{GtexHeader, ImgData, MipFilter};
+use super::decoder::{decode_dds_base_layers, decode_dds_to_rgba, decode_gtex_surface};
+use super::encoder::encode_gtex_surface;
+use super::mipmap::{generate_mip_chain, generate_volume_mip_chain};
+use super::layout::{
+    cross_to_cubemap, cubemap_to_cross, gtex_surfaces, read_surface,
+    GtexImageKind, GtexSurface, CUBEMAP_FACE_NAMES,
+};
 
 /// Extracts a texture to a DDS file.
 ///
@@ -128,8 +151,8 @@ pub fn extract_img_to_dds<P: AsRef<Path>>(
     // Open IMGB file for reading pixel data
     let mut imgb_file = BufReader::new(File::open(imgb_path)?);
 
-    // Copy each mipmap level from IMGB to DDS
-    for _m in 0..gtex_header.mip_count {
+    // Copy each mipmap table entry (every face of a cubemap) from IMGB to DDS
+    for _m in 0..gtex_header.mip_entry_count() {
         // Read mip entry: offset (4 bytes) + size (4 bytes)
         header_file.seek(SeekFrom::Start(mip_table_pos))?;
         let mip_start = header_file.read_u32::<BigEndian>()?;
@@ -198,7 +221,7 @@ pub fn extract_img_to_memory<P: AsRef<Path>>(
     let mut imgb_file = BufReader::new(File::open(imgb_path)?);
 
     // Copy all mipmap levels to buffer
-    for _m in 0..gtex_header.mip_count {
+    for _m in 0..gtex_header.mip_entry_count() {
         header_file.seek(SeekFrom::Start(mip_table_pos))?;
         let mip_start = header_file.read_u32::<BigEndian>()?;
         let mip_size = header_file.read_u32::<BigEndian>()?;
@@ -240,14 +263,18 @@ pub fn extract_img_to_memory<P: AsRef<Path>>(
 ///
 /// 1. Read GTEX header to get mipmap table
 /// 2. Read DDS header and validate dimensions
-/// 3. For each mipmap level:
-///    - Read `mip_size` bytes from DDS (starting at offset 128)
+/// 3. For each mipmap table entry (cubemaps have one per face and level):
+///    - Read `mip_size` bytes from DDS (starting after the header, 128 or
+///      148 bytes with a DX10 extension)
 ///    - Write to IMGB at `mip_offset` from the table
 ///
 /// # Warning
 ///
 /// This function modifies the IMGB file in-place. Make a backup if needed.
 ///
+/// Mip levels are copied as they are in the DDS. If only mip 0 was
+/// edited, use [`repack_img_regenerate_mips`] instead.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -293,11 +320,12 @@ pub fn repack_img_strict<P: AsRef<Path>>(
     let mip_table_offset = header_file.read_u32::<BigEndian>()?;
     let mut mip_table_pos = gtex_pos + mip_table_offset as u64;
 
-    // DDS pixel data starts at byte 128 (after 4-byte magic + 124-byte header)
-    let mut dds_data_pos: u64 = 128;
+    // DDS pixel data starts at byte 128 (after 4-byte magic + 124-byte header),
+    // or 148 when a DX10 extension header follows
+    let mut dds_data_pos: u64 = dds_header.data_offset();
 
-    // 5. Copy each mipmap level from DDS to IMGB
-    for mip_index in 0..gtex_header.mip_count {
+    // 5. Copy each mipmap table entry from DDS to IMGB
+    for mip_index in 0..gtex_header.mip_entry_count() {
         // Read mip entry from header
         header_file.seek(SeekFrom::Start(mip_table_pos))?;
         let mip_start = header_file.read_u32::<BigEndian>()?;
@@ -329,6 +357,337 @@ pub fn repack_img_strict<P: AsRef<Path>>(
     Ok(())
 }
 
+/// Repacks a DDS or PNG into an IMGB container, rebuilding every mip level.
+///
+/// Only the base level of the input is used: mip 0 of each face or slice
+/// of a DDS (any further mips in the file are ignored), or the PNG as in
+/// [`repack_img_from_png`]. Every level listed in the GTEX mipmap table is
+/// then regenerated with `filter` and re-encoded to the texture's original
+/// GTEX format, so editors that save only the base level (or leave stale
+/// mips behind) still give correct distant LODs.
+///
+/// The DDS may use any format [`convert_dds_to_png`] reads; it doesn't
+/// have to match the GTEX format.
+///
+/// # Arguments
+///
+/// * `header_path` - Path to the original texture header file (unchanged)
+/// * `imgb_path` - Path to the IMGB file to modify
+/// * `image_path` - Edited `.dds`, or any image `image` can read
+/// * `filter` - Resampling filter for the generated mip levels
+/// * `allow_unverified` - Accept GTEX format codes not seen in retail files
+///   (see [`super::format`])
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The DDS dimensions or layer count differ from the GTEX texture
+/// - The GTEX format has no encoder, or is unverified and `allow_unverified`
+///   is not set
+/// - An encoded level doesn't match the size of its IMGB slot
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // DDS saved by an editor with only mip 0
+/// repack_img_regenerate_mips("tex.txbh", "data.imgb", "tex.dds", MipFilter::Box, false)?;
+/// ```
+pub fn repack_img_regenerate_mips<P: AsRef<Path>>(
+    header_path: P,
+    imgb_path: P,
+    image_path: P,
+    filter: MipFilter,
+    allow_unverified: bool,
+) -> Result<()> {
+    let is_dds = image_path
+        .as_ref()
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("dds"))
+        .unwrap_or(false);
+    if !is_dds {
+        return repack_img_from_png(header_path, imgb_path, image_path, filter, allow_unverified);
+    }
+
+    let (gtex_header, surfaces) = read_gtex_layout(header_path)?;
+    let dds = Dds::read(BufReader::new(File::open(image_path)?))?;
+    let layers = decode_dds_base_layers(&dds)?;
+    if layers.len() != gtex_header.layer_count() as usize {
+        anyhow::bail!(
+            "DDS has {} layer(s) but the texture has {}",
+            layers.len(), gtex_header.layer_count()
+        );
+    }
+
+    write_layers(&gtex_header, &surfaces, imgb_path.as_ref(), &layers, filter, allow_unverified)
+}
+
+/// Repacks a PNG image into an IMGB container (strict mode).
+///
+/// The PNG replaces mip 0. All further levels listed in the GTEX mipmap
+/// table are regenerated with `filter`, then each level is encoded to the
+/// texture's GTEX format and written at its original offset.
+///
+/// For cubemaps the PNG may be a horizontal cross (see
+/// [`extract_cubemap_to_cross_png`]); other multi-layer textures need
+/// [`repack_img_layers_from_png`].
+///
+/// # Arguments
+///
+/// * `header_path` - Path to the original texture header file (unchanged)
+/// * `imgb_path` - Path to the IMGB file to modify
+/// * `png_path` - Path to the edited PNG (any format `image` can read works)
+/// * `filter` - Resampling filter for the generated mip levels
+/// * `allow_unverified` - Accept GTEX format codes not seen in retail files
+///   (see [`super::format`])
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The image dimensions differ from the GTEX dimensions
+/// - The GTEX format has no encoder, or is unverified and `allow_unverified`
+///   is not set
+/// - An encoded level doesn't match the size of its IMGB slot
+///
+/// Nothing is written to the IMGB unless every level encodes successfully.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// repack_img_from_png("tex.txbh", "data.imgb", "tex.png", MipFilter::Box, false)?;
+/// ```
+pub fn repack_img_from_png<P: AsRef<Path>>(
+    header_path: P,
+    imgb_path: P,
+    png_path: P,
+    filter: MipFilter,
+    allow_unverified: bool,
+) -> Result<()> {
+    let (gtex_header, surfaces) = read_gtex_layout(header_path)?;
+    let image = image::open(png_path)?.to_rgba8();
+
+    let layers = match gtex_header.image_kind() {
+        GtexImageKind::Texture2D => vec![image],
+        GtexImageKind::Cubemap => cross_to_cubemap(&image, gtex_header.width as u32)?,
+        GtexImageKind::Volume => anyhow::bail!(
+            "Volume texture has {} slices; use repack_img_layers_from_png",
+            gtex_header.layer_count()
+        ),
+    };
+
+    write_layers(&gtex_header, &surfaces, imgb_path.as_ref(), &layers, filter, allow_unverified)
+}
+
+/// Repacks one PNG per face or slice into an IMGB container (strict mode).
+///
+/// `png_paths` lists the images in layer order: the six cubemap faces in
+/// DDS order (+X, -X, +Y, -Y, +Z, -Z), or every volume slice. Mip levels
+/// are regenerated as in [`repack_img_from_png`]; volume levels also halve
+/// in depth.
+///
+/// # Errors
+///
+/// Returns an error if the number of images doesn't match the layer
+/// count, or for any of the reasons listed on [`repack_img_from_png`].
+pub fn repack_img_layers_from_png<P: AsRef<Path>, Q: AsRef<Path>>(
+    header_path: P,
+    imgb_path: P,
+    png_paths: &[Q],
+    filter: MipFilter,
+    allow_unverified: bool,
+) -> Result<()> {
+    let (gtex_header, surfaces) = read_gtex_layout(header_path)?;
+    if png_paths.len() != gtex_header.layer_count() as usize {
+        anyhow::bail!(
+            "Texture has {} layer(s) but {} image(s) were given",
+            gtex_header.layer_count(), png_paths.len()
+        );
+    }
+
+    let layers = png_paths
+        .iter()
+        .map(|p| Ok(image::open(p)?.to_rgba8()))
+        .collect::<Result<Vec<_>>>()?;
+
+    write_layers(&gtex_header, &surfaces, imgb_path.as_ref(), &layers, filter, allow_unverified)
+}
+
+/// Extracts mip 0 of every face or slice to separate PNG files.
+///
+/// Files are named after the header: `name.png` for 2D textures,
+/// `name_px.png` ... `name_nz.png` for cubemap faces and
+/// `name_slice0.png` ... for volume slices. `allow_unverified` enables
+/// the GTEX format codes not seen in retail files (see [`super::format`]).
+///
+/// # Returns
+///
+/// The written PNG paths, in layer order (the order
+/// [`repack_img_layers_from_png`] expects).
+pub fn extract_img_layers_to_png<P: AsRef<Path>>(
+    header_path: P,
+    imgb_path: P,
+    output_dir: P,
+    allow_unverified: bool,
+) -> Result<Vec<String>> {
+    let header_path = header_path.as_ref();
+    let stem = header_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "texture".to_string());
+
+    let (gtex_header, surfaces) = read_gtex_layout(header_path)?;
+    let layers = decode_base_layers(&gtex_header, &surfaces, imgb_path.as_ref(), allow_unverified)?;
+
+    std::fs::create_dir_all(output_dir.as_ref())?;
+    let mut written = Vec::with_capacity(layers.len());
+    for (index, layer) in layers.iter().enumerate() {
+        let file_name = match gtex_header.image_kind() {
+            GtexImageKind::Texture2D => format!("{}.png", stem),
+            GtexImageKind::Cubemap => format!("{}_{}.png", stem, CUBEMAP_FACE_NAMES[index]),
+            GtexImageKind::Volume => format!("{}_slice{}.png", stem, index),
+        };
+        let out_path = output_dir.as_ref().join(file_name);
+        layer.save(&out_path)?;
+        written.push(out_path.to_string_lossy().to_string());
+    }
+
+    Ok(written)
+}
+
+/// Extracts mip 0 of a cubemap as a single horizontal-cross PNG.
+///
+/// `allow_unverified` is passed on as in [`extract_img_layers_to_png`].
+///
+/// # Returns
+///
+/// The (width, height) of the cross image: 4 × 3 faces.
+///
+/// # Errors
+///
+/// Returns an error if the texture is not a cubemap.
+pub fn extract_cubemap_to_cross_png<P: AsRef<Path>>(
+    header_path: P,
+    imgb_path: P,
+    png_path: P,
+    allow_unverified: bool,
+) -> Result<(u32, u32)> {
+    let (gtex_header, surfaces) = read_gtex_layout(header_path)?;
+    if gtex_header.image_kind() != GtexImageKind::Cubemap {
+        anyhow::bail!("Texture is not a cubemap (img_type {})", gtex_header.img_type);
+    }
+
+    let faces = decode_base_layers(&gtex_header, &surfaces, imgb_path.as_ref(), allow_unverified)?;
+    let cross = cubemap_to_cross(&faces)?;
+    cross.save(png_path)?;
+    Ok(cross.dimensions())
+}
+
+/// Reads the GTEX header of a header file and splits its mip table into surfaces.
+fn read_gtex_layout<P: AsRef<Path>>(header_path: P) -> Result<(GtexHeader, Vec<GtexSurface>)> {
+    read_gtex_layout_from(BufReader::new(File::open(header_path)?))
+}
+
+/// Like [`read_gtex_layout`], for header data already in memory.
+pub(crate) fn read_gtex_layout_from<R: Read + Seek>(mut header: R) -> Result<(GtexHeader, Vec<GtexSurface>)> {
+    let mut img_reader = ImgReader::new(&mut header);
+    let (gtex_header, gtex_pos) = img_reader.read_gtex()?
+        .ok_or_else(|| anyhow::anyhow!("GTEX chunk not found"))?;
+    let mip_table = img_reader.read_mip_table(gtex_pos, gtex_header.mip_entry_count())?;
+    let surfaces = gtex_surfaces(&gtex_header, &mip_table)?;
+    Ok((gtex_header, surfaces))
+}
+
+/// Decodes mip 0 of every layer to an RGBA image.
+pub(crate) fn decode_base_layers(
+    gtex_header: &GtexHeader,
+    surfaces: &[GtexSurface],
+    imgb_path: &Path,
+    allow_unverified: bool,
+) -> Result<Vec<RgbaImage>> {
+    let mut imgb_file = BufReader::new(File::open(imgb_path)?);
+    surfaces
+        .iter()
+        .filter(|s| s.mip == 0)
+        .map(|surface| {
+            let data = read_surface(&mut imgb_file, surface)?;
+            let rgba = decode_gtex_surface(
+                gtex_header.format,
+                &data,
+                surface.width as usize,
+                surface.height as usize,
+                allow_unverified,
+            )?;
+            ImageBuffer::from_raw(surface.width, surface.height, rgba)
+                .ok_or_else(|| anyhow::anyhow!("Failed to create image buffer"))
+        })
+        .collect()
+}
+
+/// Regenerates mips for each layer, encodes every surface and writes them in place.
+///
+/// All surfaces are encoded and size-checked before the IMGB is opened for
+/// writing, so a failure leaves the file untouched.
+pub(crate) fn write_layers(
+    gtex_header: &GtexHeader,
+    surfaces: &[GtexSurface],
+    imgb_path: &Path,
+    layers: &[RgbaImage],
+    filter: MipFilter,
+    allow_unverified: bool,
+) -> Result<()> {
+    let expected = (gtex_header.width as u32, gtex_header.height as u32);
+    for layer in layers {
+        if layer.dimensions() != expected {
+            anyhow::bail!(
+                "Image dimensions ({}x{}) do not match GTEX ({}x{})",
+                layer.width(), layer.height(), expected.0, expected.1
+            );
+        }
+    }
+
+    // levels[layer][mip] for 2D/cubemaps; volumes are built per mip, then transposed
+    let mip_count = gtex_header.mip_count as u32;
+    let lookup: Vec<Vec<RgbaImage>> = match gtex_header.image_kind() {
+        GtexImageKind::Volume => generate_volume_mip_chain(layers, mip_count, filter),
+        _ => layers.iter().map(|l| generate_mip_chain(l, mip_count, filter)).collect(),
+    };
+    let level = |surface: &GtexSurface| -> Option<&RgbaImage> {
+        match gtex_header.image_kind() {
+            GtexImageKind::Volume => lookup.get(surface.mip as usize)?.get(surface.layer as usize),
+            _ => lookup.get(surface.layer as usize)?.get(surface.mip as usize),
+        }
+    };
+
+    let mut encoded = Vec::with_capacity(surfaces.len());
+    for surface in surfaces {
+        let image = level(surface).ok_or_else(|| {
+            anyhow::anyhow!("No image for layer {} mip {}", surface.layer, surface.mip)
+        })?;
+        let data = encode_gtex_surface(
+            gtex_header.format,
+            image.as_raw(),
+            image.width() as usize,
+            image.height() as usize,
+            allow_unverified,
+        )?;
+        if data.len() != surface.size as usize {
+            anyhow::bail!(
+                "Encoded layer {} mip {} is {} bytes but its IMGB slot is {} bytes",
+                surface.layer, surface.mip, data.len(), surface.size
+            );
+        }
+        encoded.push(data);
+    }
+
+    let mut imgb_file = OpenOptions::new().write(true).open(imgb_path)?;
+    for (data, surface) in encoded.iter().zip(surfaces.iter()) {
+        imgb_file.seek(SeekFrom::Start(surface.offset as u64))?;
+        imgb_file.write_all(data)?;
+    }
+
+    log::info!("Repacked {} surface(s) from PNG.", encoded.len());
+    Ok(())
+}
+
 /// Converts a DDS file to PNG format.
 ///
 /// Supports common DDS formats including DXT1, DXT3, DXT5, and uncompressed RGBA.
@@ -480,7 +839,7 @@ pub fn extract_img_to_dds_bytes<P: AsRef<Path>>(
     let mut imgb_file = BufReader::new(File::open(imgb_path)?);
 
     // Copy all mipmap levels to buffer
-    for _m in 0..gtex_header.mip_count {
+    for _m in 0..gtex_header.mip_entry_count() {
         header_cursor.seek(SeekFrom::Start(mip_table_pos))?;
         let mip_start = header_cursor.read_u32::<BigEndian>()?;
         let mip_size = header_cursor.read_u32::<BigEndian>()?;
@@ -499,334 +858,3 @@ pub fn extract_img_to_dds_bytes<P: AsRef<Path>>(
         format: format!("{:?}", gtex_header.format),
     }, buffer.into_inner()))
 }
-
-/// Decodes DDS pixel data to RGBA format.
-fn decode_dds_to_rgba(dds: &Dds) -> Result<Vec<u8>> {
-    let width = dds.get_width() as usize;
-    let height = dds.get_height() as usize;
-    let data = dds.get_data(0)?; // Get mip level 0
-
-    // Get the format
-    let format = dds.get_d3d_format().ok_or_else(|| {
-        anyhow::anyhow!("Unsupported DDS format: no D3D format specified")
-    })?;
-
-    match format {
-        D3DFormat::DXT1 => decode_dxt1(data, width, height),
-        D3DFormat::DXT3 => decode_dxt3(data, width, height),
-        D3DFormat::DXT5 => decode_dxt5(data, width, height),
-        D3DFormat::A8R8G8B8 => decode_argb8888(data, width, height),
-        D3DFormat::X8R8G8B8 => decode_xrgb8888(data, width, height),
-        D3DFormat::R8G8B8 => decode_rgb888(data, width, height),
-        D3DFormat::A8B8G8R8 => decode_abgr8888(data, width, height),
-        _ => Err(anyhow::anyhow!("Unsupported DDS format: {:?}", format)),
-    }
-}
-
-/// Decodes DXT1 (BC1) compressed data to RGBA.
-fn decode_dxt1(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
-
-    for by in 0..blocks_y {
-        for bx in 0..blocks_x {
-            let block_idx = (by * blocks_x + bx) * 8;
-            if block_idx + 8 > data.len() {
-                break;
-            }
-
-            let block = &data[block_idx..block_idx + 8];
-            decode_dxt1_block(block, &mut output, bx * 4, by * 4, width, height);
-        }
-    }
-
-    Ok(output)
-}
-
-/// Decodes a single DXT1 4x4 block.
-fn decode_dxt1_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
-    let c0 = u16::from_le_bytes([block[0], block[1]]);
-    let c1 = u16::from_le_bytes([block[2], block[3]]);
-
-    let mut colors = [[0u8; 4]; 4];
-    colors[0] = rgb565_to_rgba(c0);
-    colors[1] = rgb565_to_rgba(c1);
-
-    if c0 > c1 {
-        // 4-color mode
-        colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
-        colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
-    } else {
-        // 3-color mode with transparency
-        colors[2] = interpolate_color(&colors[0], &colors[1], 1, 2);
-        colors[3] = [0, 0, 0, 0]; // Transparent
-    }
-
-    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
-
-    for py in 0..4 {
-        for px in 0..4 {
-            let pixel_x = x + px;
-            let pixel_y = y + py;
-            if pixel_x >= width || pixel_y >= height {
-                continue;
-            }
-
-            let idx = ((indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
-            let out_idx = (pixel_y * width + pixel_x) * 4;
-
-            output[out_idx] = colors[idx][0];
-            output[out_idx + 1] = colors[idx][1];
-            output[out_idx + 2] = colors[idx][2];
-            output[out_idx + 3] = colors[idx][3];
-        }
-    }
-}
-
-/// Decodes DXT3 (BC2) compressed data to RGBA.
-fn decode_dxt3(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
-
-    for by in 0..blocks_y {
-        for bx in 0..blocks_x {
-            let block_idx = (by * blocks_x + bx) * 16;
-            if block_idx + 16 > data.len() {
-                break;
-            }
-
-            let block = &data[block_idx..block_idx + 16];
-            decode_dxt3_block(block, &mut output, bx * 4, by * 4, width, height);
-        }
-    }
-
-    Ok(output)
-}
-
-/// Decodes a single DXT3 4x4 block.
-fn decode_dxt3_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
-    // First 8 bytes are explicit alpha values (4 bits per pixel)
-    let alpha_block = &block[0..8];
-    // Last 8 bytes are DXT1 color block
-    let color_block = &block[8..16];
-
-    let c0 = u16::from_le_bytes([color_block[0], color_block[1]]);
-    let c1 = u16::from_le_bytes([color_block[2], color_block[3]]);
-
-    let mut colors = [[0u8; 4]; 4];
-    colors[0] = rgb565_to_rgba(c0);
-    colors[1] = rgb565_to_rgba(c1);
-    colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
-    colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
-
-    let indices = u32::from_le_bytes([color_block[4], color_block[5], color_block[6], color_block[7]]);
-
-    for py in 0..4 {
-        for px in 0..4 {
-            let pixel_x = x + px;
-            let pixel_y = y + py;
-            if pixel_x >= width || pixel_y >= height {
-                continue;
-            }
-
-            let idx = ((indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
-            let out_idx = (pixel_y * width + pixel_x) * 4;
-
-            // Get alpha from explicit alpha block
-            let alpha_idx = py * 4 + px;
-            let alpha_byte = alpha_block[alpha_idx / 2];
-            let alpha = if alpha_idx % 2 == 0 {
-                (alpha_byte & 0x0F) * 17 // Scale 0-15 to 0-255
-            } else {
-                (alpha_byte >> 4) * 17
-            };
-
-            output[out_idx] = colors[idx][0];
-            output[out_idx + 1] = colors[idx][1];
-            output[out_idx + 2] = colors[idx][2];
-            output[out_idx + 3] = alpha;
-        }
-    }
-}
-
-/// Decodes DXT5 (BC3) compressed data to RGBA.
-fn decode_dxt5(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-
-    let blocks_x = (width + 3) / 4;
-    let blocks_y = (height + 3) / 4;
-
-    for by in 0..blocks_y {
-        for bx in 0..blocks_x {
-            let block_idx = (by * blocks_x + bx) * 16;
-            if block_idx + 16 > data.len() {
-                break;
-            }
-
-            let block = &data[block_idx..block_idx + 16];
-            decode_dxt5_block(block, &mut output, bx * 4, by * 4, width, height);
-        }
-    }
-
-    Ok(output)
-}
-
-/// Decodes a single DXT5 4x4 block.
-fn decode_dxt5_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
-    // First 8 bytes are interpolated alpha
-    let alpha0 = block[0];
-    let alpha1 = block[1];
-
-    // Build alpha lookup table
-    let mut alphas = [0u8; 8];
-    alphas[0] = alpha0;
-    alphas[1] = alpha1;
-    if alpha0 > alpha1 {
-        for i in 0..6 {
-            alphas[2 + i] = ((6 - i) as u16 * alpha0 as u16 + (1 + i) as u16 * alpha1 as u16) as u8 / 7;
-        }
-    } else {
-        for i in 0..4 {
-            alphas[2 + i] = ((4 - i) as u16 * alpha0 as u16 + (1 + i) as u16 * alpha1 as u16) as u8 / 5;
-        }
-        alphas[6] = 0;
-        alphas[7] = 255;
-    }
-
-    // Alpha indices are 3 bits each, packed into 6 bytes (bytes 2-7)
-    let alpha_indices = u64::from_le_bytes([
-        block[2], block[3], block[4], block[5], block[6], block[7], 0, 0
-    ]);
-
-    // Last 8 bytes are DXT1 color block
-    let color_block = &block[8..16];
-
-    let c0 = u16::from_le_bytes([color_block[0], color_block[1]]);
-    let c1 = u16::from_le_bytes([color_block[2], color_block[3]]);
-
-    let mut colors = [[0u8; 4]; 4];
-    colors[0] = rgb565_to_rgba(c0);
-    colors[1] = rgb565_to_rgba(c1);
-    colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
-    colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
-
-    let color_indices = u32::from_le_bytes([color_block[4], color_block[5], color_block[6], color_block[7]]);
-
-    for py in 0..4 {
-        for px in 0..4 {
-            let pixel_x = x + px;
-            let pixel_y = y + py;
-            if pixel_x >= width || pixel_y >= height {
-                continue;
-            }
-
-            let color_idx = ((color_indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
-            let alpha_idx = ((alpha_indices >> ((py * 4 + px) * 3)) & 0x7) as usize;
-
-            let out_idx = (pixel_y * width + pixel_x) * 4;
-
-            output[out_idx] = colors[color_idx][0];
-            output[out_idx + 1] = colors[color_idx][1];
-            output[out_idx + 2] = colors[color_idx][2];
-            output[out_idx + 3] = alphas[alpha_idx];
-        }
-    }
-}
-
-/// Converts RGB565 to RGBA8888.
-fn rgb565_to_rgba(color: u16) -> [u8; 4] {
-    let r = ((color >> 11) & 0x1F) as u8;
-    let g = ((color >> 5) & 0x3F) as u8;
-    let b = (color & 0x1F) as u8;
-
-    [
-        (r << 3) | (r >> 2),
-        (g << 2) | (g >> 4),
-        (b << 3) | (b >> 2),
-        255,
-    ]
-}
-
-/// Interpolates between two colors.
-fn interpolate_color(c0: &[u8; 4], c1: &[u8; 4], num: u8, denom: u8) -> [u8; 4] {
-    let d = denom as u16;
-    let n = num as u16;
-    [
-        ((c0[0] as u16 * (d - n) + c1[0] as u16 * n) / d) as u8,
-        ((c0[1] as u16 * (d - n) + c1[1] as u16 * n) / d) as u8,
-        ((c0[2] as u16 * (d - n) + c1[2] as u16 * n) / d) as u8,
-        255,
-    ]
-}
-
-/// Decodes A8R8G8B8 to RGBA.
-fn decode_argb8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-    for i in 0..(width * height) {
-        let src = i * 4;
-        let dst = i * 4;
-        if src + 4 > data.len() {
-            break;
-        }
-        output[dst] = data[src + 2];     // R
-        output[dst + 1] = data[src + 1]; // G
-        output[dst + 2] = data[src];     // B
-        output[dst + 3] = data[src + 3]; // A
-    }
-    Ok(output)
-}
-
-/// Decodes X8R8G8B8 to RGBA.
-fn decode_xrgb8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-    for i in 0..(width * height) {
-        let src = i * 4;
-        let dst = i * 4;
-        if src + 4 > data.len() {
-            break;
-        }
-        output[dst] = data[src + 2];     // R
-        output[dst + 1] = data[src + 1]; // G
-        output[dst + 2] = data[src];     // B
-        output[dst + 3] = 255;           // A (fully opaque)
-    }
-    Ok(output)
-}
-
-/// Decodes R8G8B8 to RGBA.
-fn decode_rgb888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-    for i in 0..(width * height) {
-        let src = i * 3;
-        let dst = i * 4;
-        if src + 3 > data.len() {
-            break;
-        }
-        output[dst] = data[src + 2];     // R
-        output[dst + 1] = data[src + 1]; // G
-        output[dst + 2] = data[src];     // B
-        output[dst + 3] = 255;           // A
-    }
-    Ok(output)
-}
-
-/// Decodes A8B8G8R8 to RGBA.
-fn decode_abgr8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
-    let mut output = vec![0u8; width * height * 4];
-    for i in 0..(width * height) {
-        let src = i * 4;
-        let dst = i * 4;
-        if src + 4 > data.len() {
-            break;
-        }
-        output[dst] = data[src];         // R
-        output[dst + 1] = data[src + 1]; // G
-        output[dst + 2] = data[src + 2]; // B
-        output[dst + 3] = data[src + 3]; // A
-    }
-    Ok(output)
-}
@@ -0,0 +1,420 @@
+//! # Batch Texture Conversion
+//!
+//! This module converts every texture under a directory tree to PNG in one
+//! call, and puts edited PNGs back afterwards.
+//!
+//! ## Pairing
+//!
+//! Headers (`.xgr`, `.txbh`, `.trb`) are paired with the `.imgb` of the
+//! same stem in the same folder. A header is either a WPD package holding
+//! several texture records, or a bare GTEX header:
+//!
+//! ```text
+//! input/                              output/
+//! ├── ui/                             ├── ui/
+//! │   ├── menu.xgr  ┐ WPD package     │   └── menu/
+//! │   └── menu.imgb ┘                 │       ├── icon_a.png
+//! │                                   │       └── icon_b.png
+//! └── chr/                            ├── chr/
+//!     ├── face.txbh ┐ bare GTEX       │   └── face.png
+//!     └── face.imgb ┘                 └── texture_manifest.json
+//! ```
+//!
+//! ## Manifest
+//!
+//! [`batch_export_textures`] writes [`TEXTURE_MANIFEST_NAME`] next to the
+//! PNGs. Each entry records the header, record name, GTEX format and image
+//! type of its PNG(s); [`batch_import_textures`] reads it back to re-encode
+//! every PNG that is still present. Delete the PNGs you didn't edit to
+//! leave those textures untouched.
+//!
+//! Pairs are converted in parallel; a failing header or record is recorded
+//! in the manifest instead of aborting the job.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use rayon::prelude::*;
+use walkdir::WalkDir;
+use super::api::{read_gtex_layout_from, write_layers};
+use super::layout::{cross_to_cubemap, gtex_format_name, GtexImageKind};
+use super::structs::{
+    GtexHeader, MipFilter, TextureBatchFailure, TextureImportReport, TextureManifest,
+    TextureManifestEntry,
+};
+use super::xgr::{find_gtex_quiet, gtex_preview_images, read_xgr_records};
+
+/// File name of the manifest written into the output directory.
+pub const TEXTURE_MANIFEST_NAME: &str = "texture_manifest.json";
+
+/// Current manifest format version.
+const TEXTURE_MANIFEST_VERSION: u32 = 1;
+
+/// Header extensions that are paired with an IMGB.
+const HEADER_EXTENSIONS: [&str; 3] = ["xgr", "txbh", "trb"];
+
+/// Converts every texture under `input_dir` to PNG in `output_dir`.
+///
+/// The folder structure of `input_dir` is mirrored in `output_dir`. WPD
+/// package headers get a folder named after the header holding one PNG per
+/// texture record; bare headers give a single `stem.png`. Cubemaps are
+/// written as a horizontal cross, volumes as `_sliceN` files.
+///
+/// # Arguments
+///
+/// * `input_dir` - Directory to scan recursively
+/// * `output_dir` - Directory for the PNGs and the manifest (created if needed)
+///
+/// # Returns
+///
+/// The manifest that was written to `output_dir`.
+///
+/// # Errors
+///
+/// Returns an error only if the directories or the manifest can't be
+/// accessed; per-texture errors are listed in [`TextureManifest::failures`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let manifest = batch_export_textures("extracted/", "pngs/")?;
+/// println!("{} textures, {} failures", manifest.entries.len(), manifest.failures.len());
+/// ```
+pub fn batch_export_textures<P: AsRef<Path>>(input_dir: P, output_dir: P) -> Result<TextureManifest> {
+    let input_dir = input_dir.as_ref().canonicalize()?;
+    let output_dir = output_dir.as_ref();
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut headers: Vec<PathBuf> = WalkDir::new(&input_dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && is_header_file(p))
+        .collect();
+    headers.sort();
+
+    let results: Vec<(Vec<TextureManifestEntry>, Vec<TextureBatchFailure>)> = headers
+        .par_iter()
+        .map(|header| export_header(header, &input_dir, output_dir))
+        .collect();
+
+    let mut manifest = TextureManifest {
+        version: TEXTURE_MANIFEST_VERSION,
+        source_dir: input_dir.to_string_lossy().to_string(),
+        entries: Vec::new(),
+        failures: Vec::new(),
+    };
+    for (entries, failures) in results {
+        manifest.entries.extend(entries);
+        manifest.failures.extend(failures);
+    }
+
+    let json = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(output_dir.join(TEXTURE_MANIFEST_NAME), json)?;
+
+    log::info!(
+        "Exported {} texture(s) from {} header(s), {} failure(s).",
+        manifest.entries.len(), headers.len(), manifest.failures.len()
+    );
+    Ok(manifest)
+}
+
+/// Imports edited PNGs listed in a batch manifest back into their IMGBs.
+///
+/// Every entry whose PNG(s) all exist is re-encoded to its original GTEX
+/// format with a regenerated mip chain and written in place, as
+/// [`super::api::repack_img_from_png`] does. Entries with missing PNGs are
+/// skipped. IMGBs are processed in parallel, the textures of one IMGB one
+/// after another.
+///
+/// # Arguments
+///
+/// * `manifest_path` - Manifest written by [`batch_export_textures`]
+/// * `filter` - Resampling filter for the generated mip levels
+///
+/// # Errors
+///
+/// Returns an error only if the manifest can't be read; per-texture errors
+/// are listed in [`TextureImportReport::failures`].
+pub fn batch_import_textures<P: AsRef<Path>>(manifest_path: P, filter: MipFilter) -> Result<TextureImportReport> {
+    let manifest_path = manifest_path.as_ref();
+    let manifest: TextureManifest = serde_json::from_reader(File::open(manifest_path)?)?;
+    let png_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let source_dir = Path::new(&manifest.source_dir);
+
+    let mut by_imgb: BTreeMap<&str, Vec<&TextureManifestEntry>> = BTreeMap::new();
+    for entry in &manifest.entries {
+        by_imgb.entry(entry.imgb.as_str()).or_default().push(entry);
+    }
+
+    let reports: Vec<TextureImportReport> = by_imgb
+        .par_iter()
+        .map(|(_, entries)| {
+            let mut report = TextureImportReport::default();
+            for entry in entries {
+                let pngs: Vec<PathBuf> = entry.pngs.iter().map(|p| png_dir.join(p)).collect();
+                if pngs.iter().any(|p| !p.is_file()) {
+                    report.skipped.extend(entry.pngs.iter().cloned());
+                    continue;
+                }
+                match import_entry(entry, source_dir, &pngs, filter) {
+                    Ok(()) => report.imported.extend(entry.pngs.iter().cloned()),
+                    Err(e) => report.failures.push(TextureBatchFailure {
+                        header: entry.header.clone(),
+                        record: entry.record.clone(),
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            report
+        })
+        .collect();
+
+    let mut report = TextureImportReport::default();
+    for part in reports {
+        report.imported.extend(part.imported);
+        report.skipped.extend(part.skipped);
+        report.failures.extend(part.failures);
+    }
+
+    log::info!(
+        "Imported {} PNG(s), skipped {}, {} failure(s).",
+        report.imported.len(), report.skipped.len(), report.failures.len()
+    );
+    Ok(report)
+}
+
+/// Returns true for files with a texture header extension.
+fn is_header_file(path: &Path) -> bool {
+    path.extension()
+        .map(|e| HEADER_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Returns true if the file starts with the WPD magic.
+fn is_wpd_package(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path)?;
+    Ok(file.read(&mut magic)? == 4 && magic == *b"WPD\0")
+}
+
+/// Finds the `.imgb` with the same stem next to a header.
+fn paired_imgb(header: &Path) -> Option<PathBuf> {
+    ["imgb", "IMGB"]
+        .iter()
+        .map(|ext| header.with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+/// Path of `path` relative to `base`, with `/` separators.
+fn relative_string(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Exports every texture of one header/IMGB pair.
+fn export_header(
+    header_path: &Path,
+    input_dir: &Path,
+    output_dir: &Path,
+) -> (Vec<TextureManifestEntry>, Vec<TextureBatchFailure>) {
+    let header = relative_string(header_path, input_dir);
+    let failure = |record: Option<String>, error: String| TextureBatchFailure {
+        header: header.clone(),
+        record,
+        error,
+    };
+
+    let Some(imgb_path) = paired_imgb(header_path) else {
+        return (Vec::new(), vec![failure(None, "No paired .imgb file".to_string())]);
+    };
+    let imgb = relative_string(&imgb_path, input_dir);
+
+    let rel_dir = Path::new(&header).parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = header_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "texture".to_string());
+
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    let mut export = |header_data: &[u8], record: Option<String>, out_dir: &Path, name: &str| {
+        match export_texture(header_data, &imgb_path, output_dir, out_dir, name) {
+            Ok((gtex, pngs)) => entries.push(TextureManifestEntry {
+                header: header.clone(),
+                imgb: imgb.clone(),
+                record,
+                pngs,
+                width: gtex.width,
+                height: gtex.height,
+                format: gtex.format,
+                format_name: gtex_format_name(gtex.format),
+                img_type: gtex.img_type,
+            }),
+            Err(e) => failures.push(failure(record, e.to_string())),
+        }
+    };
+
+    match is_wpd_package(header_path) {
+        Ok(true) => match read_xgr_records(header_path) {
+            Ok(records) => {
+                let out_dir = rel_dir.join(&stem);
+                for record in records.iter().filter(|r| find_gtex_quiet(&r.data)) {
+                    export(&record.data, Some(record.name.clone()), &out_dir, &record.name);
+                }
+            }
+            Err(e) => failures.push(failure(None, e.to_string())),
+        },
+        Ok(false) => match std::fs::read(header_path) {
+            Ok(data) => export(&data, None, &rel_dir, &stem),
+            Err(e) => failures.push(failure(None, e.to_string())),
+        },
+        Err(e) => failures.push(failure(None, e.to_string())),
+    }
+
+    (entries, failures)
+}
+
+/// Decodes one texture and writes its PNG(s) under `output_dir/rel_dir`.
+///
+/// Returns the GTEX header and the PNG paths relative to `output_dir`.
+fn export_texture(
+    header_data: &[u8],
+    imgb_path: &Path,
+    output_dir: &Path,
+    rel_dir: &Path,
+    name: &str,
+) -> Result<(GtexHeader, Vec<String>)> {
+    let (gtex, images) = gtex_preview_images(header_data, imgb_path)?;
+    std::fs::create_dir_all(output_dir.join(rel_dir))?;
+
+    let mut pngs = Vec::with_capacity(images.len());
+    for (suffix, image) in images {
+        let rel_path = rel_dir.join(format!("{}{}.png", name, suffix));
+        image.save(output_dir.join(&rel_path))?;
+        pngs.push(rel_path.to_string_lossy().replace('\\', "/"));
+    }
+    Ok((gtex, pngs))
+}
+
+/// Re-encodes the PNG(s) of one manifest entry into its IMGB.
+fn import_entry(entry: &TextureManifestEntry, source_dir: &Path, pngs: &[PathBuf], filter: MipFilter) -> Result<()> {
+    let header_path = source_dir.join(&entry.header);
+    let header_data = match &entry.record {
+        Some(name) => read_xgr_records(&header_path)?
+            .into_iter()
+            .find(|r| &r.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Record {} not found in {}", name, entry.header))?
+            .data,
+        None => std::fs::read(&header_path)?,
+    };
+
+    let (gtex, surfaces) = read_gtex_layout_from(Cursor::new(header_data.as_slice()))?;
+    if (gtex.width, gtex.height, gtex.format, gtex.img_type)
+        != (entry.width, entry.height, entry.format, entry.img_type)
+    {
+        anyhow::bail!("Header no longer matches the manifest entry");
+    }
+
+    let images = pngs
+        .iter()
+        .map(|p| Ok(image::open(p)?.to_rgba8()))
+        .collect::<Result<Vec<_>>>()?;
+    let layers = match gtex.image_kind() {
+        GtexImageKind::Cubemap => cross_to_cubemap(&images[0], gtex.width as u32)?,
+        _ => images,
+    };
+
+    write_layers(&gtex, &surfaces, &source_dir.join(&entry.imgb), &layers, filter, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::img::encoder::encode_gtex_surface;
+    use crate::modules::wpd::structs::WpdRecord;
+    use crate::modules::wpd::writer::WpdWriter;
+
+    /// Bare GTEX header with one mip at `offset`.
+    fn gtex_header(format: u8, size: u16, offset: u32, bytes: u32) -> Vec<u8> {
+        let mut data = b"GTEX".to_vec();
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&[format, 1, 0, 0]);
+        data.extend_from_slice(&size.to_be_bytes());
+        data.extend_from_slice(&size.to_be_bytes());
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&24u32.to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&offset.to_be_bytes());
+        data.extend_from_slice(&bytes.to_be_bytes());
+        data
+    }
+
+    fn solid(rgba: [u8; 4]) -> Vec<u8> {
+        (0..16).flat_map(|_| rgba).collect()
+    }
+
+    #[test]
+    fn test_batch_export_and_import() {
+        let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        root.push("target/test_img_batch");
+        let _ = std::fs::remove_dir_all(&root);
+        let input = root.join("in");
+        let output = root.join("out");
+        std::fs::create_dir_all(input.join("ui")).unwrap();
+        std::fs::create_dir_all(input.join("chr/c000")).unwrap();
+
+        // ui/menu.xgr: WPD package with two DXT1 textures
+        let mut imgb = encode_gtex_surface(24, &solid([255, 0, 0, 255]), 4, 4, false).unwrap();
+        imgb.extend(encode_gtex_surface(24, &solid([0, 255, 0, 255]), 4, 4, false).unwrap());
+        let records = vec![
+            WpdRecord { name: "icon_a".into(), extension: "txbh".into(), data: gtex_header(24, 4, 0, 8) },
+            WpdRecord { name: "icon_b".into(), extension: "txbh".into(), data: gtex_header(24, 4, 8, 8) },
+        ];
+        WpdWriter::new(File::create(input.join("ui/menu.xgr")).unwrap()).write(&records).unwrap();
+        std::fs::write(input.join("ui/menu.imgb"), imgb).unwrap();
+
+        // chr/c000/face.txbh: bare ARGB header; orphan.txbh has no IMGB
+        std::fs::write(input.join("chr/c000/face.txbh"), gtex_header(3, 4, 0, 64)).unwrap();
+        std::fs::write(
+            input.join("chr/c000/face.imgb"),
+            encode_gtex_surface(3, &solid([0, 0, 255, 255]), 4, 4, false).unwrap(),
+        ).unwrap();
+        std::fs::write(input.join("chr/c000/orphan.txbh"), gtex_header(3, 4, 0, 64)).unwrap();
+
+        let manifest = batch_export_textures(&input, &output).unwrap();
+        let pngs: Vec<&str> = manifest.entries.iter().map(|e| e.pngs[0].as_str()).collect();
+        assert_eq!(pngs, vec!["chr/c000/face.png", "ui/menu/icon_a.png", "ui/menu/icon_b.png"]);
+        assert_eq!(manifest.entries[1].record.as_deref(), Some("icon_a"));
+        assert_eq!(manifest.entries[1].format_name, "DXT1");
+        assert_eq!(manifest.failures.len(), 1);
+        assert_eq!(manifest.failures[0].header, "chr/c000/orphan.txbh");
+        assert!(output.join(TEXTURE_MANIFEST_NAME).is_file());
+        let face = image::open(output.join("chr/c000/face.png")).unwrap().to_rgba8();
+        assert_eq!(face.get_pixel(0, 0).0, [0, 0, 255, 255]);
+
+        // Edit icon_b, drop the PNGs that weren't edited
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]))
+            .save(output.join("ui/menu/icon_b.png"))
+            .unwrap();
+        std::fs::remove_file(output.join("ui/menu/icon_a.png")).unwrap();
+        std::fs::remove_file(output.join("chr/c000/face.png")).unwrap();
+
+        let report = batch_import_textures(output.join(TEXTURE_MANIFEST_NAME), MipFilter::Box).unwrap();
+        assert_eq!(report.imported, vec!["ui/menu/icon_b.png".to_string()]);
+        assert_eq!(report.skipped.len(), 2);
+        assert!(report.failures.is_empty());
+
+        let reexport = batch_export_textures(&input, &root.join("out2")).unwrap();
+        let icon_a = image::open(root.join("out2/ui/menu/icon_a.png")).unwrap().to_rgba8();
+        let icon_b = image::open(root.join("out2/ui/menu/icon_b.png")).unwrap().to_rgba8();
+        assert_eq!(icon_a.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(icon_b.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(reexport.entries.len(), 3);
+    }
+}
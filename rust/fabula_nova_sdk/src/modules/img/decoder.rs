@@ -0,0 +1,635 @@
+//! # IMG Pixel Decoder
+//!
+//! This module converts texture pixel data to tightly packed RGBA8 buffers
+//! for previews, PNG export and as the reference for the encoder.
+//!
+//! ## Supported Layouts
+//!
+//! | Source             | Layout                                   |
+//! |--------------------|------------------------------------------|
+//! | DXT1 / BC1         | 8-byte 4x4 blocks, 1-bit alpha           |
+//! | DXT3 / BC2         | 16-byte blocks, explicit 4-bit alpha     |
+//! | DXT5 / BC3         | 16-byte blocks, interpolated alpha       |
+//! | A8R8G8B8           | 32-bit, stored B, G, R, A in memory      |
+//! | X8R8G8B8 / R8G8B8  | 32/24-bit without alpha                  |
+//! | A8B8G8R8           | 32-bit, stored R, G, B, A in memory      |
+//! | L8 / A8 / A8L8     | Grey, white + alpha, grey + alpha        |
+//! | 565 / 4444 / 1555  | 16-bit little-endian words               |
+//! | BC4 / BC5          | One/two BC3 alpha-style channel blocks   |
+//!
+//! Blocks that extend past the image edge are clipped, so non-multiple-of-4
+//! sizes (and the 2x2 / 1x1 tail of a mip chain) decode correctly.
+
+use anyhow::Result;
+use ddsfile::{Dds, D3DFormat, DxgiFormat, PixelFormat, PixelFormatFlags};
+use image::RgbaImage;
+use super::format::GtexPixelFormat;
+
+/// Decodes a raw GTEX surface (one mip level) to RGBA.
+///
+/// `data` is the pixel data exactly as stored in the IMGB file.
+/// `allow_unverified` enables the format codes not seen in retail files
+/// (see [`super::format`]).
+///
+/// # Errors
+///
+/// Returns an error for GTEX format codes without a known pixel layout,
+/// and for unverified codes unless `allow_unverified` is set.
+pub fn decode_gtex_surface(
+    format: u8,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    allow_unverified: bool,
+) -> Result<Vec<u8>> {
+    let format = GtexPixelFormat::from_code_checked(format, allow_unverified)?;
+    decode_surface(format, data, width, height)
+}
+
+/// Decodes one surface of a known GTEX pixel format to RGBA.
+fn decode_surface(format: GtexPixelFormat, data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    match format {
+        GtexPixelFormat::L8 => decode_l8(data, width, height),
+        GtexPixelFormat::A8 => decode_a8(data, width, height),
+        // Uncompressed, stored as DDS A8R8G8B8 (B, G, R, A byte order)
+        GtexPixelFormat::Argb8888 => decode_argb8888(data, width, height),
+        GtexPixelFormat::A8L8 => decode_a8l8(data, width, height),
+        GtexPixelFormat::R5G6B5 => decode_r5g6b5(data, width, height),
+        GtexPixelFormat::A4R4G4B4 => decode_a4r4g4b4(data, width, height),
+        GtexPixelFormat::A1R5G5B5 => decode_a1r5g5b5(data, width, height),
+        GtexPixelFormat::Dxt1 => decode_dxt1(data, width, height),
+        GtexPixelFormat::Dxt3 => decode_dxt3(data, width, height),
+        GtexPixelFormat::Dxt5 => decode_dxt5(data, width, height),
+        GtexPixelFormat::Bc4 => decode_bc4(data, width, height),
+        GtexPixelFormat::Bc5 => decode_bc5(data, width, height),
+    }
+}
+
+/// Pixel layouts accepted from DDS files, from either a D3D or a DX10 header.
+///
+/// Everything a GTEX texture can be exported as, plus a few common layouts
+/// editors save in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DdsInputFormat {
+    Gtex(GtexPixelFormat),
+    Xrgb8888,
+    Rgb888,
+    Abgr8888,
+}
+
+impl DdsInputFormat {
+    fn of(dds: &Dds) -> Result<Self> {
+        use GtexPixelFormat as G;
+        if let Some(format) = dds.get_d3d_format() {
+            return match format {
+                D3DFormat::L8 => Ok(Self::Gtex(G::L8)),
+                D3DFormat::A8 => Ok(Self::Gtex(G::A8)),
+                D3DFormat::A8R8G8B8 => Ok(Self::Gtex(G::Argb8888)),
+                D3DFormat::A8L8 => Ok(Self::Gtex(G::A8L8)),
+                D3DFormat::R5G6B5 => Ok(Self::Gtex(G::R5G6B5)),
+                D3DFormat::A4R4G4B4 => Ok(Self::Gtex(G::A4R4G4B4)),
+                D3DFormat::A1R5G5B5 => Ok(Self::Gtex(G::A1R5G5B5)),
+                D3DFormat::DXT1 => Ok(Self::Gtex(G::Dxt1)),
+                D3DFormat::DXT3 => Ok(Self::Gtex(G::Dxt3)),
+                D3DFormat::DXT5 => Ok(Self::Gtex(G::Dxt5)),
+                D3DFormat::X8R8G8B8 => Ok(Self::Xrgb8888),
+                D3DFormat::R8G8B8 => Ok(Self::Rgb888),
+                D3DFormat::A8B8G8R8 => Ok(Self::Abgr8888),
+                _ => Err(anyhow::anyhow!("Unsupported DDS format: {:?}", format)),
+            };
+        }
+        if dds.header10.is_none() {
+            if let Some(format) = luminance_alpha_format(&dds.header.spf) {
+                return Ok(Self::Gtex(format));
+            }
+        }
+        match dds.get_dxgi_format() {
+            Some(DxgiFormat::R8_UNorm) => Ok(Self::Gtex(G::L8)),
+            Some(DxgiFormat::A8_UNorm) => Ok(Self::Gtex(G::A8)),
+            Some(DxgiFormat::B8G8R8A8_UNorm | DxgiFormat::B8G8R8A8_UNorm_sRGB) => Ok(Self::Gtex(G::Argb8888)),
+            Some(DxgiFormat::R8G8_UNorm) => Ok(Self::Gtex(G::A8L8)),
+            Some(DxgiFormat::B5G6R5_UNorm) => Ok(Self::Gtex(G::R5G6B5)),
+            Some(DxgiFormat::B4G4R4A4_UNorm) => Ok(Self::Gtex(G::A4R4G4B4)),
+            Some(DxgiFormat::B5G5R5A1_UNorm) => Ok(Self::Gtex(G::A1R5G5B5)),
+            Some(DxgiFormat::BC1_UNorm | DxgiFormat::BC1_UNorm_sRGB) => Ok(Self::Gtex(G::Dxt1)),
+            Some(DxgiFormat::BC2_UNorm | DxgiFormat::BC2_UNorm_sRGB) => Ok(Self::Gtex(G::Dxt3)),
+            Some(DxgiFormat::BC3_UNorm | DxgiFormat::BC3_UNorm_sRGB) => Ok(Self::Gtex(G::Dxt5)),
+            Some(DxgiFormat::BC4_UNorm) => Ok(Self::Gtex(G::Bc4)),
+            Some(DxgiFormat::BC5_UNorm) => Ok(Self::Gtex(G::Bc5)),
+            Some(DxgiFormat::B8G8R8X8_UNorm) => Ok(Self::Xrgb8888),
+            Some(DxgiFormat::R8G8B8A8_UNorm | DxgiFormat::R8G8B8A8_UNorm_sRGB) => Ok(Self::Abgr8888),
+            Some(format) => Err(anyhow::anyhow!("Unsupported DDS format: {:?}", format)),
+            None => Err(anyhow::anyhow!("Unsupported DDS format: no D3D format specified")),
+        }
+    }
+
+    /// Byte size of one surface.
+    fn surface_size(self, width: usize, height: usize) -> usize {
+        match self {
+            Self::Gtex(format) => format.surface_size(width as u32, height as u32) as usize,
+            Self::Rgb888 => width * height * 3,
+            Self::Xrgb8888 | Self::Abgr8888 => width * height * 4,
+        }
+    }
+
+    fn decode(self, data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+        match self {
+            Self::Gtex(format) => decode_surface(format, data, width, height),
+            Self::Xrgb8888 => decode_xrgb8888(data, width, height),
+            Self::Rgb888 => decode_rgb888(data, width, height),
+            Self::Abgr8888 => decode_abgr8888(data, width, height),
+        }
+    }
+}
+
+/// Recognises legacy L8, A8 and A8L8 pixel formats.
+///
+/// `ddsfile` only keeps the colour masks and bit count for some flag
+/// combinations, so spec-conforming luminance and alpha-only headers
+/// (including the ones [`super::writer`] produces) are not detected there.
+fn luminance_alpha_format(spf: &PixelFormat) -> Option<GtexPixelFormat> {
+    let luminance = spf.flags.contains(PixelFormatFlags::LUMINANCE);
+    let alpha_pixels = spf.flags.contains(PixelFormatFlags::ALPHA_PIXELS);
+    let alpha_only = spf.flags.contains(PixelFormatFlags::ALPHA);
+    match (luminance, alpha_pixels, alpha_only, spf.rgb_bit_count, spf.a_bit_mask) {
+        (true, false, false, Some(8), _) => Some(GtexPixelFormat::L8),
+        (true, true, false, Some(16), Some(0xFF00)) => Some(GtexPixelFormat::A8L8),
+        (false, false, true, _, Some(0xFF)) => Some(GtexPixelFormat::A8),
+        _ => None,
+    }
+}
+
+/// Decodes DDS pixel data to RGBA format.
+pub(crate) fn decode_dds_to_rgba(dds: &Dds) -> Result<Vec<u8>> {
+    let width = dds.get_width() as usize;
+    let height = dds.get_height() as usize;
+    let data = dds.get_data(0)?; // Get mip level 0
+    DdsInputFormat::of(dds)?.decode(data, width, height)
+}
+
+/// Decodes mip 0 of every face, array layer or volume slice of a DDS.
+///
+/// Any mip levels stored after each base level are skipped, whatever
+/// their content, so a DDS saved with only the base level or with stale
+/// mips gives the same result.
+///
+/// # Errors
+///
+/// Returns an error for unsupported formats or truncated pixel data.
+pub(crate) fn decode_dds_base_layers(dds: &Dds) -> Result<Vec<RgbaImage>> {
+    let format = DdsInputFormat::of(dds)?;
+    let (width, height) = (dds.get_width() as usize, dds.get_height() as usize);
+    let base_size = format.surface_size(width, height);
+
+    // Volume slices are packed inside mip 0; faces and array layers each
+    // carry a full mip chain
+    let depth = dds.get_depth() as usize;
+    let (layers, stride) = if depth > 1 {
+        (depth, base_size)
+    } else {
+        let chain: usize = (0..dds.get_num_mipmap_levels().max(1))
+            .map(|mip| format.surface_size((width >> mip).max(1), (height >> mip).max(1)))
+            .sum();
+        (dds.get_num_array_layers().max(1) as usize, chain)
+    };
+
+    (0..layers)
+        .map(|layer| {
+            let start = layer * stride;
+            let data = dds.data.get(start..start + base_size).ok_or_else(|| {
+                anyhow::anyhow!("DDS data too short for layer {} of {}", layer, layers)
+            })?;
+            let rgba = format.decode(data, width, height)?;
+            RgbaImage::from_raw(width as u32, height as u32, rgba)
+                .ok_or_else(|| anyhow::anyhow!("Failed to create image buffer"))
+        })
+        .collect()
+}
+
+/// Decodes DXT1 (BC1) compressed data to RGBA.
+pub(crate) fn decode_dxt1(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let block_idx = (by * blocks_x + bx) * 8;
+            if block_idx + 8 > data.len() {
+                break;
+            }
+
+            let block = &data[block_idx..block_idx + 8];
+            decode_dxt1_block(block, &mut output, bx * 4, by * 4, width, height);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Decodes a single DXT1 4x4 block.
+fn decode_dxt1_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+
+    let mut colors = [[0u8; 4]; 4];
+    colors[0] = rgb565_to_rgba(c0);
+    colors[1] = rgb565_to_rgba(c1);
+
+    if c0 > c1 {
+        // 4-color mode
+        colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
+        colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
+    } else {
+        // 3-color mode with transparency
+        colors[2] = interpolate_color(&colors[0], &colors[1], 1, 2);
+        colors[3] = [0, 0, 0, 0]; // Transparent
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    for py in 0..4 {
+        for px in 0..4 {
+            let pixel_x = x + px;
+            let pixel_y = y + py;
+            if pixel_x >= width || pixel_y >= height {
+                continue;
+            }
+
+            let idx = ((indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
+            let out_idx = (pixel_y * width + pixel_x) * 4;
+
+            output[out_idx] = colors[idx][0];
+            output[out_idx + 1] = colors[idx][1];
+            output[out_idx + 2] = colors[idx][2];
+            output[out_idx + 3] = colors[idx][3];
+        }
+    }
+}
+
+/// Decodes DXT3 (BC2) compressed data to RGBA.
+pub(crate) fn decode_dxt3(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let block_idx = (by * blocks_x + bx) * 16;
+            if block_idx + 16 > data.len() {
+                break;
+            }
+
+            let block = &data[block_idx..block_idx + 16];
+            decode_dxt3_block(block, &mut output, bx * 4, by * 4, width, height);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Decodes a single DXT3 4x4 block.
+fn decode_dxt3_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
+    // First 8 bytes are explicit alpha values (4 bits per pixel)
+    let alpha_block = &block[0..8];
+    // Last 8 bytes are DXT1 color block
+    let color_block = &block[8..16];
+
+    let c0 = u16::from_le_bytes([color_block[0], color_block[1]]);
+    let c1 = u16::from_le_bytes([color_block[2], color_block[3]]);
+
+    let mut colors = [[0u8; 4]; 4];
+    colors[0] = rgb565_to_rgba(c0);
+    colors[1] = rgb565_to_rgba(c1);
+    colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
+    colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
+
+    let indices = u32::from_le_bytes([color_block[4], color_block[5], color_block[6], color_block[7]]);
+
+    for py in 0..4 {
+        for px in 0..4 {
+            let pixel_x = x + px;
+            let pixel_y = y + py;
+            if pixel_x >= width || pixel_y >= height {
+                continue;
+            }
+
+            let idx = ((indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
+            let out_idx = (pixel_y * width + pixel_x) * 4;
+
+            // Get alpha from explicit alpha block
+            let alpha_idx = py * 4 + px;
+            let alpha_byte = alpha_block[alpha_idx / 2];
+            let alpha = if alpha_idx % 2 == 0 {
+                (alpha_byte & 0x0F) * 17 // Scale 0-15 to 0-255
+            } else {
+                (alpha_byte >> 4) * 17
+            };
+
+            output[out_idx] = colors[idx][0];
+            output[out_idx + 1] = colors[idx][1];
+            output[out_idx + 2] = colors[idx][2];
+            output[out_idx + 3] = alpha;
+        }
+    }
+}
+
+/// Decodes DXT5 (BC3) compressed data to RGBA.
+pub(crate) fn decode_dxt5(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let block_idx = (by * blocks_x + bx) * 16;
+            if block_idx + 16 > data.len() {
+                break;
+            }
+
+            let block = &data[block_idx..block_idx + 16];
+            decode_dxt5_block(block, &mut output, bx * 4, by * 4, width, height);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Decodes a single DXT5 4x4 block.
+fn decode_dxt5_block(block: &[u8], output: &mut [u8], x: usize, y: usize, width: usize, height: usize) {
+    // First 8 bytes are interpolated alpha
+    let alphas = decode_alpha_block(block);
+
+    // Last 8 bytes are DXT1 color block
+    let color_block = &block[8..16];
+
+    let c0 = u16::from_le_bytes([color_block[0], color_block[1]]);
+    let c1 = u16::from_le_bytes([color_block[2], color_block[3]]);
+
+    let mut colors = [[0u8; 4]; 4];
+    colors[0] = rgb565_to_rgba(c0);
+    colors[1] = rgb565_to_rgba(c1);
+    colors[2] = interpolate_color(&colors[0], &colors[1], 1, 3);
+    colors[3] = interpolate_color(&colors[0], &colors[1], 2, 3);
+
+    let color_indices = u32::from_le_bytes([color_block[4], color_block[5], color_block[6], color_block[7]]);
+
+    for py in 0..4 {
+        for px in 0..4 {
+            let pixel_x = x + px;
+            let pixel_y = y + py;
+            if pixel_x >= width || pixel_y >= height {
+                continue;
+            }
+
+            let color_idx = ((color_indices >> ((py * 4 + px) * 2)) & 0x3) as usize;
+
+            let out_idx = (pixel_y * width + pixel_x) * 4;
+
+            output[out_idx] = colors[color_idx][0];
+            output[out_idx + 1] = colors[color_idx][1];
+            output[out_idx + 2] = colors[color_idx][2];
+            output[out_idx + 3] = alphas[py * 4 + px];
+        }
+    }
+}
+
+/// Decodes an 8-byte BC3-style alpha block (also a BC4/BC5 channel) to 16 values.
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let alpha0 = block[0];
+    let alpha1 = block[1];
+
+    // Build alpha lookup table
+    let mut palette = [0u8; 8];
+    palette[0] = alpha0;
+    palette[1] = alpha1;
+    if alpha0 > alpha1 {
+        for i in 0..6 {
+            palette[2 + i] = (((6 - i) as u16 * alpha0 as u16 + (1 + i) as u16 * alpha1 as u16) / 7) as u8;
+        }
+    } else {
+        for i in 0..4 {
+            palette[2 + i] = (((4 - i) as u16 * alpha0 as u16 + (1 + i) as u16 * alpha1 as u16) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    // Indices are 3 bits each, packed into 6 bytes (bytes 2-7)
+    let indices = u64::from_le_bytes([
+        block[2], block[3], block[4], block[5], block[6], block[7], 0, 0
+    ]);
+
+    let mut values = [0u8; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (i * 3)) & 0x7) as usize];
+    }
+    values
+}
+
+/// Converts RGB565 to RGBA8888.
+pub(crate) fn rgb565_to_rgba(color: u16) -> [u8; 4] {
+    let r = ((color >> 11) & 0x1F) as u8;
+    let g = ((color >> 5) & 0x3F) as u8;
+    let b = (color & 0x1F) as u8;
+
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+        255,
+    ]
+}
+
+/// Interpolates between two colors.
+pub(crate) fn interpolate_color(c0: &[u8; 4], c1: &[u8; 4], num: u8, denom: u8) -> [u8; 4] {
+    let d = denom as u16;
+    let n = num as u16;
+    [
+        ((c0[0] as u16 * (d - n) + c1[0] as u16 * n) / d) as u8,
+        ((c0[1] as u16 * (d - n) + c1[1] as u16 * n) / d) as u8,
+        ((c0[2] as u16 * (d - n) + c1[2] as u16 * n) / d) as u8,
+        255,
+    ]
+}
+
+/// Decodes A8R8G8B8 to RGBA.
+pub(crate) fn decode_argb8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for i in 0..(width * height) {
+        let src = i * 4;
+        let dst = i * 4;
+        if src + 4 > data.len() {
+            break;
+        }
+        output[dst] = data[src + 2];     // R
+        output[dst + 1] = data[src + 1]; // G
+        output[dst + 2] = data[src];     // B
+        output[dst + 3] = data[src + 3]; // A
+    }
+    Ok(output)
+}
+
+/// Decodes X8R8G8B8 to RGBA.
+pub(crate) fn decode_xrgb8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for i in 0..(width * height) {
+        let src = i * 4;
+        let dst = i * 4;
+        if src + 4 > data.len() {
+            break;
+        }
+        output[dst] = data[src + 2];     // R
+        output[dst + 1] = data[src + 1]; // G
+        output[dst + 2] = data[src];     // B
+        output[dst + 3] = 255;           // A (fully opaque)
+    }
+    Ok(output)
+}
+
+/// Decodes R8G8B8 to RGBA.
+pub(crate) fn decode_rgb888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for i in 0..(width * height) {
+        let src = i * 3;
+        let dst = i * 4;
+        if src + 3 > data.len() {
+            break;
+        }
+        output[dst] = data[src + 2];     // R
+        output[dst + 1] = data[src + 1]; // G
+        output[dst + 2] = data[src];     // B
+        output[dst + 3] = 255;           // A
+    }
+    Ok(output)
+}
+
+/// Decodes A8B8G8R8 to RGBA.
+pub(crate) fn decode_abgr8888(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for i in 0..(width * height) {
+        let src = i * 4;
+        let dst = i * 4;
+        if src + 4 > data.len() {
+            break;
+        }
+        output[dst] = data[src];         // R
+        output[dst + 1] = data[src + 1]; // G
+        output[dst + 2] = data[src + 2]; // B
+        output[dst + 3] = data[src + 3]; // A
+    }
+    Ok(output)
+}
+
+/// Decodes L8 (luminance) to grey RGBA.
+pub(crate) fn decode_l8(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for (px, &l) in output.chunks_exact_mut(4).zip(data.iter()) {
+        px.copy_from_slice(&[l, l, l, 255]);
+    }
+    Ok(output)
+}
+
+/// Decodes A8 (alpha only) to white RGBA with that alpha.
+pub(crate) fn decode_a8(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for (px, &a) in output.chunks_exact_mut(4).zip(data.iter()) {
+        px.copy_from_slice(&[255, 255, 255, a]);
+    }
+    Ok(output)
+}
+
+/// Decodes A8L8 (L, A byte order) to grey RGBA.
+pub(crate) fn decode_a8l8(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for (px, la) in output.chunks_exact_mut(4).zip(data.chunks_exact(2)) {
+        px.copy_from_slice(&[la[0], la[0], la[0], la[1]]);
+    }
+    Ok(output)
+}
+
+/// Decodes little-endian 16-bit pixels with the given unpacking function.
+fn decode_16bit(data: &[u8], width: usize, height: usize, unpack: fn(u16) -> [u8; 4]) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    for (px, word) in output.chunks_exact_mut(4).zip(data.chunks_exact(2)) {
+        px.copy_from_slice(&unpack(u16::from_le_bytes([word[0], word[1]])));
+    }
+    Ok(output)
+}
+
+/// Decodes R5G6B5 to RGBA.
+pub(crate) fn decode_r5g6b5(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    decode_16bit(data, width, height, rgb565_to_rgba)
+}
+
+/// Decodes A4R4G4B4 to RGBA.
+pub(crate) fn decode_a4r4g4b4(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    decode_16bit(data, width, height, |c| {
+        let nibble = |shift: u16| ((c >> shift) & 0xF) as u8 * 17;
+        [nibble(8), nibble(4), nibble(0), nibble(12)]
+    })
+}
+
+/// Decodes A1R5G5B5 to RGBA.
+pub(crate) fn decode_a1r5g5b5(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    decode_16bit(data, width, height, |c| {
+        let five = |shift: u16| {
+            let v = ((c >> shift) & 0x1F) as u8;
+            (v << 3) | (v >> 2)
+        };
+        [five(10), five(5), five(0), if c & 0x8000 != 0 { 255 } else { 0 }]
+    })
+}
+
+/// Decodes BC4 (one channel) to grey RGBA.
+pub(crate) fn decode_bc4(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    decode_channel_blocks(data, width, height, 1)
+}
+
+/// Decodes BC5 (two channels) to RGBA with red and green set, blue 0.
+pub(crate) fn decode_bc5(data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    decode_channel_blocks(data, width, height, 2)
+}
+
+/// Decodes BC4/BC5 data made of `channels` alpha-style blocks per 4x4 block.
+fn decode_channel_blocks(data: &[u8], width: usize, height: usize, channels: usize) -> Result<Vec<u8>> {
+    let mut output = vec![0u8; width * height * 4];
+    let block_size = channels * 8;
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let block_idx = (by * blocks_x + bx) * block_size;
+            if block_idx + block_size > data.len() {
+                break;
+            }
+
+            let red = decode_alpha_block(&data[block_idx..block_idx + 8]);
+            let green = if channels == 2 {
+                decode_alpha_block(&data[block_idx + 8..block_idx + 16])
+            } else {
+                red
+            };
+
+            for py in 0..4 {
+                for px in 0..4 {
+                    let pixel_x = bx * 4 + px;
+                    let pixel_y = by * 4 + py;
+                    if pixel_x >= width || pixel_y >= height {
+                        continue;
+                    }
+                    let i = py * 4 + px;
+                    let blue = if channels == 2 { 0 } else { red[i] };
+                    let out_idx = (pixel_y * width + pixel_x) * 4;
+                    output[out_idx..out_idx + 4].copy_from_slice(&[red[i], green[i], blue, 255]);
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
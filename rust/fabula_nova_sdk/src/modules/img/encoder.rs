@@ -0,0 +1,883 @@
+//! # IMG Pixel Encoder
+//!
+//! This module converts RGBA8 images back into the pixel layouts stored in
+//! IMGB files, so edited PNGs can be written without external DDS tools.
+//!
+//! ## Supported GTEX Formats
+//!
+//! | GTEX | Output        | Block Size | Notes                              |
+//! |------|---------------|------------|------------------------------------|
+//! | 3, 4 | A8R8G8B8      | 4 bytes/px | Stored B, G, R, A                  |
+//! | 24   | DXT1 / BC1    | 8 bytes    | 1-bit alpha via 3-colour blocks    |
+//! | 25   | DXT3 / BC2    | 16 bytes   | Explicit 4-bit alpha               |
+//! | 26   | DXT5 / BC3    | 16 bytes   | Interpolated 8-step alpha          |
+//! | 1, 2 | L8, A8        | 1 byte/px  | Rec. 601 luma / alpha channel      |
+//! | 5    | A8L8          | 2 bytes/px | Luma + alpha                       |
+//! | 6-8  | 565/4444/1555 | 2 bytes/px | Rounded to nearest, 1-bit alpha    |
+//! | 27   | BC4           | 8 bytes    | Red, fitted like BC3 alpha         |
+//! | 28   | BC5           | 16 bytes   | Red + green, fitted like BC3 alpha |
+//!
+//! ## Block Compression
+//!
+//! Each 4x4 colour block is fitted in three stages:
+//!
+//! ```text
+//! 1. Start endpoints   principal axis of the block colours (PCA),
+//!                      or an optimal single-colour table for flat blocks
+//! 2. Least squares     re-solve both endpoints from the current indices
+//! 3. Local search      nudge each 565 channel by ±1 while the error drops
+//! ```
+//!
+//! Error is always measured against the palette produced by
+//! [`super::decoder`], so what the encoder optimises is exactly what the
+//! previews and the game show. BC1 tries both the 4-colour and 3-colour
+//! block modes and keeps the better one; BC3 alpha tries both the 8-step
+//! and the 6-step (with explicit 0/255) modes.
+//!
+//! Blocks are encoded in parallel, one row of blocks per task.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use super::decoder::{interpolate_color, rgb565_to_rgba};
+use super::format::GtexPixelFormat;
+
+/// Encodes an RGBA image to the pixel layout of a GTEX format code.
+///
+/// `rgba` must hold `width * height` tightly packed RGBA8 pixels. The
+/// result is one mip level, sized exactly like the level stored in IMGB.
+/// `allow_unverified` enables the format codes not seen in retail files
+/// (see [`super::format`]).
+///
+/// # Errors
+///
+/// Returns an error if the buffer size doesn't match the dimensions or the
+/// format code has no encoder (or is unverified and `allow_unverified` is
+/// not set).
+pub fn encode_gtex_surface(
+    format: u8,
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    allow_unverified: bool,
+) -> Result<Vec<u8>> {
+    if rgba.len() != width * height * 4 {
+        anyhow::bail!(
+            "RGBA buffer is {} bytes, expected {} for {}x{}",
+            rgba.len(), width * height * 4, width, height
+        );
+    }
+
+    let format = GtexPixelFormat::from_code_checked(format, allow_unverified)?;
+    Ok(match format {
+        GtexPixelFormat::L8 => encode_l8(rgba),
+        GtexPixelFormat::A8 => encode_a8(rgba),
+        GtexPixelFormat::Argb8888 => encode_argb8888(rgba),
+        GtexPixelFormat::A8L8 => encode_a8l8(rgba),
+        GtexPixelFormat::R5G6B5 => encode_r5g6b5(rgba),
+        GtexPixelFormat::A4R4G4B4 => encode_a4r4g4b4(rgba),
+        GtexPixelFormat::A1R5G5B5 => encode_a1r5g5b5(rgba),
+        GtexPixelFormat::Dxt1 => encode_bc1(rgba, width, height),
+        GtexPixelFormat::Dxt3 => encode_bc2(rgba, width, height),
+        GtexPixelFormat::Dxt5 => encode_bc3(rgba, width, height),
+        GtexPixelFormat::Bc4 => encode_bc4(rgba, width, height),
+        GtexPixelFormat::Bc5 => encode_bc5(rgba, width, height),
+    })
+}
+
+/// Encodes RGBA to A8R8G8B8 (B, G, R, A byte order).
+pub fn encode_argb8888(rgba: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(rgba.len());
+    for px in rgba.chunks_exact(4) {
+        output.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+    }
+    output
+}
+
+/// Rec. 601 luma of an RGBA pixel.
+fn luminance(px: &[u8]) -> u8 {
+    ((px[0] as u32 * 77 + px[1] as u32 * 150 + px[2] as u32 * 29 + 128) >> 8) as u8
+}
+
+/// Rounds an 8-bit value to `bits` bits.
+fn quantize(v: u8, bits: u32) -> u16 {
+    let max = (1u32 << bits) - 1;
+    ((v as u32 * max + 127) / 255) as u16
+}
+
+/// Encodes RGBA to L8 (luma of the colour).
+pub fn encode_l8(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4).map(luminance).collect()
+}
+
+/// Encodes RGBA to A8 (alpha channel only).
+pub fn encode_a8(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4).map(|px| px[3]).collect()
+}
+
+/// Encodes RGBA to A8L8 (L, A byte order).
+pub fn encode_a8l8(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4).flat_map(|px| [luminance(px), px[3]]).collect()
+}
+
+/// Encodes RGBA to little-endian R5G6B5.
+pub fn encode_r5g6b5(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|px| (quantize(px[0], 5) << 11 | quantize(px[1], 6) << 5 | quantize(px[2], 5)).to_le_bytes())
+        .collect()
+}
+
+/// Encodes RGBA to little-endian A4R4G4B4.
+pub fn encode_a4r4g4b4(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|px| {
+            (quantize(px[3], 4) << 12 | quantize(px[0], 4) << 8 | quantize(px[1], 4) << 4 | quantize(px[2], 4))
+                .to_le_bytes()
+        })
+        .collect()
+}
+
+/// Encodes RGBA to little-endian A1R5G5B5 (alpha below 128 is transparent).
+pub fn encode_a1r5g5b5(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|px| {
+            let a = if px[3] >= 128 { 0x8000 } else { 0 };
+            (a | quantize(px[0], 5) << 10 | quantize(px[1], 5) << 5 | quantize(px[2], 5)).to_le_bytes()
+        })
+        .collect()
+}
+
+/// Encodes the red channel of RGBA to BC4.
+pub fn encode_bc4(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    encode_blocks(rgba, width, height, 8, |block, out| {
+        out.copy_from_slice(&encode_bc3_alpha(&block_channel(block, 0)));
+    })
+}
+
+/// Encodes the red and green channels of RGBA to BC5.
+pub fn encode_bc5(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    encode_blocks(rgba, width, height, 16, |block, out| {
+        out[0..8].copy_from_slice(&encode_bc3_alpha(&block_channel(block, 0)));
+        out[8..16].copy_from_slice(&encode_bc3_alpha(&block_channel(block, 1)));
+    })
+}
+
+/// Extracts one channel of a 4x4 block.
+fn block_channel(block: &[[u8; 4]; 16], channel: usize) -> [u8; 16] {
+    let mut values = [0u8; 16];
+    for (v, px) in values.iter_mut().zip(block.iter()) {
+        *v = px[channel];
+    }
+    values
+}
+
+/// Encodes RGBA to DXT1 (BC1).
+///
+/// Pixels with alpha below 128 become transparent (3-colour block mode).
+pub fn encode_bc1(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    encode_blocks(rgba, width, height, 8, |block, out| {
+        out.copy_from_slice(&encode_bc1_block(block));
+    })
+}
+
+/// Encodes RGBA to DXT3 (BC2).
+pub fn encode_bc2(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    encode_blocks(rgba, width, height, 16, |block, out| {
+        out[0..8].copy_from_slice(&encode_bc2_alpha(block));
+        out[8..16].copy_from_slice(&encode_color_block(block, &[true; 16], false).to_bytes());
+    })
+}
+
+/// Encodes RGBA to DXT5 (BC3).
+pub fn encode_bc3(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    encode_blocks(rgba, width, height, 16, |block, out| {
+        out[0..8].copy_from_slice(&encode_bc3_alpha(&block_channel(block, 3)));
+        out[8..16].copy_from_slice(&encode_color_block(block, &[true; 16], false).to_bytes());
+    })
+}
+
+/// Runs a block encoder over every 4x4 block of the image in parallel.
+fn encode_blocks<F>(rgba: &[u8], width: usize, height: usize, block_size: usize, encode: F) -> Vec<u8>
+where
+    F: Fn(&[[u8; 4]; 16], &mut [u8]) + Sync,
+{
+    let blocks_x = width.div_ceil(4).max(1);
+    let blocks_y = height.div_ceil(4).max(1);
+    let mut output = vec![0u8; blocks_x * blocks_y * block_size];
+
+    output
+        .par_chunks_mut(blocks_x * block_size)
+        .enumerate()
+        .for_each(|(by, row)| {
+            for (bx, out) in row.chunks_exact_mut(block_size).enumerate() {
+                let block = load_block(rgba, width, height, bx * 4, by * 4);
+                encode(&block, out);
+            }
+        });
+
+    output
+}
+
+/// Reads a 4x4 block, replicating edge pixels past the image border.
+fn load_block(rgba: &[u8], width: usize, height: usize, x: usize, y: usize) -> [[u8; 4]; 16] {
+    let mut block = [[0u8; 4]; 16];
+    if width == 0 || height == 0 {
+        return block;
+    }
+    for py in 0..4 {
+        for px in 0..4 {
+            let sx = (x + px).min(width - 1);
+            let sy = (y + py).min(height - 1);
+            let src = (sy * width + sx) * 4;
+            block[py * 4 + px].copy_from_slice(&rgba[src..src + 4]);
+        }
+    }
+    block
+}
+
+// ============================================================================
+// Colour blocks
+// ============================================================================
+
+/// A fitted colour block: two RGB565 endpoints and 16 palette indices.
+#[derive(Debug, Clone, Copy)]
+struct ColorBlock {
+    c0: u16,
+    c1: u16,
+    indices: [u8; 16],
+    error: u32,
+}
+
+impl ColorBlock {
+    fn to_bytes(self) -> [u8; 8] {
+        let mut bits = 0u32;
+        for (i, &idx) in self.indices.iter().enumerate() {
+            bits |= (idx as u32 & 0x3) << (i * 2);
+        }
+        let mut out = [0u8; 8];
+        out[0..2].copy_from_slice(&self.c0.to_le_bytes());
+        out[2..4].copy_from_slice(&self.c1.to_le_bytes());
+        out[4..8].copy_from_slice(&bits.to_le_bytes());
+        out
+    }
+}
+
+/// Encodes one BC1 block, choosing between 4-colour and 3-colour modes.
+fn encode_bc1_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let mut opaque = [true; 16];
+    for (o, px) in opaque.iter_mut().zip(block.iter()) {
+        *o = px[3] >= 128;
+    }
+
+    if opaque.iter().all(|&o| o) {
+        let four = encode_color_block(block, &opaque, false);
+        let three = encode_color_block(block, &opaque, true);
+        return if three.error < four.error { three.to_bytes() } else { four.to_bytes() };
+    }
+
+    // Transparent pixels force 3-colour mode, where index 3 is transparent
+    let mut fitted = encode_color_block(block, &opaque, true);
+    for (idx, &o) in fitted.indices.iter_mut().zip(opaque.iter()) {
+        if !o {
+            *idx = 3;
+        }
+    }
+    fitted.to_bytes()
+}
+
+/// Fits endpoints and indices for the `active` pixels of a block.
+///
+/// In 4-colour mode the result always has `c0 > c1` (or `c0 == c1` with
+/// all indices 0); in 3-colour mode it has `c0 <= c1` and never uses
+/// index 3, which callers reserve for transparent pixels.
+fn encode_color_block(block: &[[u8; 4]; 16], active: &[bool; 16], three_color: bool) -> ColorBlock {
+    let pixels: Vec<[i32; 3]> = block
+        .iter()
+        .zip(active.iter())
+        .filter(|(_, &a)| a)
+        .map(|(px, _)| [px[0] as i32, px[1] as i32, px[2] as i32])
+        .collect();
+
+    if pixels.is_empty() {
+        return ColorBlock { c0: 0, c1: 0, indices: [3; 16], error: 0 };
+    }
+
+    let start = if pixels.iter().all(|p| *p == pixels[0]) {
+        single_color_endpoints(pixels[0], three_color)
+    } else {
+        principal_axis_endpoints(&pixels)
+    };
+
+    let mut best = fit_endpoints(&pixels, start, three_color);
+    for _ in 0..2 {
+        let refined = least_squares_endpoints(&pixels, &best, three_color);
+        match refined {
+            Some(ep) => {
+                let candidate = fit_endpoints(&pixels, ep, three_color);
+                if candidate.2 >= best.2 {
+                    break;
+                }
+                best = candidate;
+            }
+            None => break,
+        }
+    }
+    best = local_search(&pixels, best, three_color);
+
+    let (mut c0, mut c1, _) = best;
+    let (palette, usable) = block_palette(c0, c1, three_color);
+    let mut indices = [0u8; 16];
+    let mut error = 0u32;
+    for (i, px) in block.iter().enumerate() {
+        if !active[i] {
+            continue;
+        }
+        let (idx, err) = nearest(&palette[..usable], [px[0] as i32, px[1] as i32, px[2] as i32]);
+        indices[i] = idx;
+        error += err;
+    }
+
+    // Enforce the endpoint order the decoder uses to pick the block mode
+    if three_color {
+        if c0 > c1 {
+            std::mem::swap(&mut c0, &mut c1);
+            for idx in indices.iter_mut() {
+                *idx = match *idx { 0 => 1, 1 => 0, other => other };
+            }
+        }
+    } else if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+        for idx in indices.iter_mut() {
+            *idx ^= 1;
+        }
+    } else if c0 == c1 {
+        indices = [0; 16];
+    }
+
+    ColorBlock { c0, c1, indices, error }
+}
+
+/// Endpoints as unpacked 565 components: `[[r5, g6, b5]; 2]`.
+type Endpoints = [[i32; 3]; 2];
+
+fn pack_565(c: [i32; 3]) -> u16 {
+    ((c[0] as u16) << 11) | ((c[1] as u16) << 5) | c[2] as u16
+}
+
+fn quantize_565(c: [f32; 3]) -> [i32; 3] {
+    [
+        ((c[0] / 255.0) * 31.0).round().clamp(0.0, 31.0) as i32,
+        ((c[1] / 255.0) * 63.0).round().clamp(0.0, 63.0) as i32,
+        ((c[2] / 255.0) * 31.0).round().clamp(0.0, 31.0) as i32,
+    ]
+}
+
+/// Builds the decoded palette for two endpoints and the number of usable
+/// entries (4, or 3 when index 3 is reserved for transparency).
+fn block_palette(c0: u16, c1: u16, three_color: bool) -> ([[i32; 3]; 4], usize) {
+    let e0 = rgb565_to_rgba(c0);
+    let e1 = rgb565_to_rgba(c1);
+    let (p2, p3, usable) = if three_color {
+        (interpolate_color(&e0, &e1, 1, 2), [0, 0, 0, 0], 3)
+    } else {
+        (interpolate_color(&e0, &e1, 1, 3), interpolate_color(&e0, &e1, 2, 3), 4)
+    };
+    let rgb = |c: [u8; 4]| [c[0] as i32, c[1] as i32, c[2] as i32];
+    ([rgb(e0), rgb(e1), rgb(p2), rgb(p3)], usable)
+}
+
+fn nearest(palette: &[[i32; 3]], px: [i32; 3]) -> (u8, u32) {
+    let mut best = (0u8, u32::MAX);
+    for (i, c) in palette.iter().enumerate() {
+        let dr = c[0] - px[0];
+        let dg = c[1] - px[1];
+        let db = c[2] - px[2];
+        let err = (dr * dr + dg * dg + db * db) as u32;
+        if err < best.1 {
+            best = (i as u8, err);
+        }
+    }
+    best
+}
+
+/// Quantises endpoints and returns them with the total block error.
+fn fit_endpoints(pixels: &[[i32; 3]], ep: Endpoints, three_color: bool) -> (u16, u16, u32) {
+    let c0 = pack_565(ep[0]);
+    let c1 = pack_565(ep[1]);
+    (c0, c1, block_error(pixels, c0, c1, three_color))
+}
+
+fn block_error(pixels: &[[i32; 3]], c0: u16, c1: u16, three_color: bool) -> u32 {
+    // Equal endpoints switch BC1 into 3-colour mode; both modes then
+    // decode every usable index to the same colour, so one palette fits.
+    let (palette, usable) = block_palette(c0, c1, three_color);
+    pixels.iter().map(|&px| nearest(&palette[..usable], px).1).sum()
+}
+
+fn unpack_565(c: u16) -> [i32; 3] {
+    [((c >> 11) & 0x1F) as i32, ((c >> 5) & 0x3F) as i32, (c & 0x1F) as i32]
+}
+
+/// Starting endpoints from the extremes along the principal colour axis.
+fn principal_axis_endpoints(pixels: &[[i32; 3]]) -> Endpoints {
+    let n = pixels.len() as f32;
+    let mut mean = [0f32; 3];
+    for p in pixels {
+        for c in 0..3 {
+            mean[c] += p[c] as f32 / n;
+        }
+    }
+
+    let mut cov = [[0f32; 3]; 3];
+    for p in pixels {
+        let d = [p[0] as f32 - mean[0], p[1] as f32 - mean[1], p[2] as f32 - mean[2]];
+        for (row, &di) in cov.iter_mut().zip(d.iter()) {
+            for (cell, &dj) in row.iter_mut().zip(d.iter()) {
+                *cell += di * dj;
+            }
+        }
+    }
+
+    // Power iteration, seeded with the channel of largest variance
+    let seed = (0..3).max_by(|&a, &b| cov[a][a].total_cmp(&cov[b][b])).unwrap_or(0);
+    let mut axis = [0f32; 3];
+    axis[seed] = 1.0;
+    for _ in 0..8 {
+        let next = [
+            cov[0][0] * axis[0] + cov[0][1] * axis[1] + cov[0][2] * axis[2],
+            cov[1][0] * axis[0] + cov[1][1] * axis[1] + cov[1][2] * axis[2],
+            cov[2][0] * axis[0] + cov[2][1] * axis[1] + cov[2][2] * axis[2],
+        ];
+        let len = (next[0] * next[0] + next[1] * next[1] + next[2] * next[2]).sqrt();
+        if len < 1e-6 {
+            break;
+        }
+        axis = [next[0] / len, next[1] / len, next[2] / len];
+    }
+
+    let mut min_t = f32::MAX;
+    let mut max_t = f32::MIN;
+    for p in pixels {
+        let t = (p[0] as f32 - mean[0]) * axis[0]
+            + (p[1] as f32 - mean[1]) * axis[1]
+            + (p[2] as f32 - mean[2]) * axis[2];
+        min_t = min_t.min(t);
+        max_t = max_t.max(t);
+    }
+
+    let point = |t: f32| {
+        [mean[0] + axis[0] * t, mean[1] + axis[1] * t, mean[2] + axis[2] * t]
+    };
+    [quantize_565(point(max_t)), quantize_565(point(min_t))]
+}
+
+/// Re-solves both endpoints by least squares from the current indices.
+fn least_squares_endpoints(
+    pixels: &[[i32; 3]],
+    current: &(u16, u16, u32),
+    three_color: bool,
+) -> Option<Endpoints> {
+    let (palette, usable) = block_palette(current.0, current.1, three_color);
+    let weights: &[f32] = if three_color { &[0.0, 1.0, 0.5] } else { &[0.0, 1.0, 1.0 / 3.0, 2.0 / 3.0] };
+
+    // Minimise sum |(1-w) A + w B - x|^2 over A (c0) and B (c1)
+    let (mut aa, mut bb, mut ab) = (0f32, 0f32, 0f32);
+    let mut ax = [0f32; 3];
+    let mut bx = [0f32; 3];
+    for &px in pixels {
+        let (idx, _) = nearest(&palette[..usable], px);
+        let w = weights[idx as usize];
+        let a = 1.0 - w;
+        aa += a * a;
+        bb += w * w;
+        ab += a * w;
+        for c in 0..3 {
+            ax[c] += a * px[c] as f32;
+            bx[c] += w * px[c] as f32;
+        }
+    }
+
+    let det = aa * bb - ab * ab;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    let mut a = [0f32; 3];
+    let mut b = [0f32; 3];
+    for c in 0..3 {
+        a[c] = (bb * ax[c] - ab * bx[c]) / det;
+        b[c] = (aa * bx[c] - ab * ax[c]) / det;
+    }
+    Some([quantize_565(a), quantize_565(b)])
+}
+
+/// Greedy ±1 search over each 565 endpoint channel.
+fn local_search(pixels: &[[i32; 3]], start: (u16, u16, u32), three_color: bool) -> (u16, u16, u32) {
+    const MAX: [i32; 3] = [31, 63, 31];
+    let mut best = start;
+    let mut improved = true;
+    let mut rounds = 0;
+    while improved && rounds < 8 {
+        improved = false;
+        rounds += 1;
+        for endpoint in 0..2 {
+            for channel in 0..3 {
+                for delta in [-1i32, 1] {
+                    let mut ep = [unpack_565(best.0), unpack_565(best.1)];
+                    let v = ep[endpoint][channel] + delta;
+                    if v < 0 || v > MAX[channel] {
+                        continue;
+                    }
+                    ep[endpoint][channel] = v;
+                    let candidate = fit_endpoints(pixels, ep, three_color);
+                    if candidate.2 < best.2 {
+                        best = candidate;
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Best `(e0, e1)` pairs per 8-bit value so that the first interpolated
+/// palette entry reproduces the value: `[5-bit table, 6-bit table]`.
+static SINGLE_COLOR_TABLES: Lazy<[[[u8; 2]; 256]; 2]> = Lazy::new(|| {
+    [build_single_color_table(5, 3), build_single_color_table(6, 3)]
+});
+
+static SINGLE_COLOR_TABLES_HALF: Lazy<[[[u8; 2]; 256]; 2]> = Lazy::new(|| {
+    [build_single_color_table(5, 2), build_single_color_table(6, 2)]
+});
+
+fn build_single_color_table(bits: u32, denom: i32) -> [[u8; 2]; 256] {
+    let levels = 1i32 << bits;
+    let expand = |v: i32| if bits == 5 { (v << 3) | (v >> 2) } else { (v << 2) | (v >> 4) };
+    let mut table = [[0u8; 2]; 256];
+    for (target, entry) in table.iter_mut().enumerate() {
+        let mut best_err = i32::MAX;
+        for e0 in 0..levels {
+            for e1 in 0..levels {
+                let (x0, x1) = (expand(e0), expand(e1));
+                let value = (x0 * (denom - 1) + x1) / denom;
+                // Prefer close endpoints when errors tie
+                let err = (value - target as i32).abs() * 100 + (x0 - x1).abs();
+                if err < best_err {
+                    best_err = err;
+                    *entry = [e0 as u8, e1 as u8];
+                }
+            }
+        }
+    }
+    table
+}
+
+/// Endpoints that hit a flat colour through the first interpolated entry.
+fn single_color_endpoints(color: [i32; 3], three_color: bool) -> Endpoints {
+    let tables = if three_color { &*SINGLE_COLOR_TABLES_HALF } else { &*SINGLE_COLOR_TABLES };
+    let r = tables[0][color[0] as usize];
+    let g = tables[1][color[1] as usize];
+    let b = tables[0][color[2] as usize];
+    [
+        [r[0] as i32, g[0] as i32, b[0] as i32],
+        [r[1] as i32, g[1] as i32, b[1] as i32],
+    ]
+}
+
+// ============================================================================
+// Alpha blocks
+// ============================================================================
+
+/// Encodes the explicit 4-bit alpha half of a DXT3 block.
+fn encode_bc2_alpha(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    for (i, px) in block.iter().enumerate() {
+        let a = ((px[3] as u32 * 15 + 127) / 255) as u8;
+        out[i / 2] |= if i % 2 == 0 { a } else { a << 4 };
+    }
+    out
+}
+
+/// Builds the DXT5 alpha palette exactly as the decoder does.
+fn alpha_palette(a0: u8, a1: u8) -> [u8; 8] {
+    let mut alphas = [0u8; 8];
+    alphas[0] = a0;
+    alphas[1] = a1;
+    if a0 > a1 {
+        for i in 0..6 {
+            alphas[2 + i] = (((6 - i) as u16 * a0 as u16 + (1 + i) as u16 * a1 as u16) / 7) as u8;
+        }
+    } else {
+        for i in 0..4 {
+            alphas[2 + i] = (((4 - i) as u16 * a0 as u16 + (1 + i) as u16 * a1 as u16) / 5) as u8;
+        }
+        alphas[6] = 0;
+        alphas[7] = 255;
+    }
+    alphas
+}
+
+fn fit_alpha(alphas: &[u8; 16], a0: u8, a1: u8) -> ([u8; 16], u32) {
+    let palette = alpha_palette(a0, a1);
+    let mut indices = [0u8; 16];
+    let mut error = 0u32;
+    for (idx, &a) in indices.iter_mut().zip(alphas.iter()) {
+        let mut best = (0u8, u32::MAX);
+        for (i, &p) in palette.iter().enumerate() {
+            let d = (p as i32 - a as i32).unsigned_abs();
+            if d * d < best.1 {
+                best = (i as u8, d * d);
+            }
+        }
+        *idx = best.0;
+        error += best.1;
+    }
+    (indices, error)
+}
+
+/// Encodes the interpolated alpha half of a DXT5 block.
+///
+/// Tries the 8-step mode over the full range and the 6-step mode over the
+/// values strictly between 0 and 255, refining both endpoints by ±1 steps.
+fn encode_bc3_alpha(alphas: &[u8; 16]) -> [u8; 8] {
+    let min = *alphas.iter().min().unwrap_or(&0);
+    let max = *alphas.iter().max().unwrap_or(&0);
+
+    let mut candidates: Vec<(u8, u8)> = Vec::new();
+    if min == max {
+        candidates.push((min, min));
+    } else {
+        candidates.push((max, min));
+    }
+    let inner: Vec<u8> = alphas.iter().copied().filter(|&a| a != 0 && a != 255).collect();
+    match (inner.iter().min(), inner.iter().max()) {
+        (Some(&lo), Some(&hi)) => candidates.push((lo, hi)),
+        _ => candidates.push((0, 0)),
+    }
+
+    let mut best: Option<(u8, u8, [u8; 16], u32)> = None;
+    for (a0, a1) in candidates {
+        let eight_step = a0 > a1;
+        let (mut e0, mut e1) = (a0, a1);
+        let (mut indices, mut error) = fit_alpha(alphas, e0, e1);
+        let mut improved = true;
+        while improved && error > 0 {
+            improved = false;
+            for (d0, d1) in [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)] {
+                let n0 = e0 as i32 + d0;
+                let n1 = e1 as i32 + d1;
+                if !(0..=255).contains(&n0) || !(0..=255).contains(&n1) {
+                    continue;
+                }
+                // Stay in the mode this candidate started in
+                if (n0 > n1) != eight_step {
+                    continue;
+                }
+                let (cand_indices, cand_error) = fit_alpha(alphas, n0 as u8, n1 as u8);
+                if cand_error < error {
+                    e0 = n0 as u8;
+                    e1 = n1 as u8;
+                    indices = cand_indices;
+                    error = cand_error;
+                    improved = true;
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|b| error < b.3) {
+            best = Some((e0, e1, indices, error));
+        }
+    }
+
+    let (a0, a1, indices, _) = best.unwrap_or((0, 0, [0; 16], 0));
+    let mut bits = 0u64;
+    for (i, &idx) in indices.iter().enumerate() {
+        bits |= (idx as u64 & 0x7) << (i * 3);
+    }
+    let mut out = [0u8; 8];
+    out[0] = a0;
+    out[1] = a1;
+    out[2..8].copy_from_slice(&bits.to_le_bytes()[0..6]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::img::decoder::decode_gtex_surface;
+
+    fn psnr(a: &[u8], b: &[u8], channels: &[usize]) -> f64 {
+        let mut sum = 0f64;
+        let mut count = 0f64;
+        for (pa, pb) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+            for &c in channels {
+                let d = pa[c] as f64 - pb[c] as f64;
+                sum += d * d;
+                count += 1.0;
+            }
+        }
+        let mse = sum / count;
+        if mse == 0.0 { f64::INFINITY } else { 10.0 * (255.0 * 255.0 / mse).log10() }
+    }
+
+    /// Smooth gradients with a little structure, like typical diffuse maps.
+    fn synthetic_image(width: usize, height: usize) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let fx = x as f32 / width as f32;
+                let fy = y as f32 / height as f32;
+                let wave = ((fx * 12.0).sin() * 0.5 + 0.5) * 255.0;
+                rgba.extend_from_slice(&[
+                    (fx * 255.0) as u8,
+                    (fy * 200.0 + 30.0) as u8,
+                    wave as u8,
+                    ((1.0 - fx) * 255.0) as u8,
+                ]);
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_bc1_gradient_quality() {
+        let rgba = synthetic_image(64, 64);
+        let encoded = encode_gtex_surface(24, &rgba, 64, 64, false).unwrap();
+        assert_eq!(encoded.len(), 16 * 16 * 8);
+
+        let mut opaque = rgba.clone();
+        opaque.chunks_exact_mut(4).for_each(|p| p[3] = 255);
+        let encoded = encode_gtex_surface(24, &opaque, 64, 64, false).unwrap();
+        let decoded = decode_gtex_surface(24, &encoded, 64, 64, false).unwrap();
+        assert!(psnr(&opaque, &decoded, &[0, 1, 2]) > 38.0);
+    }
+
+    #[test]
+    fn test_bc1_flat_colors_are_exact_or_close() {
+        for color in [[0u8, 0, 0], [255, 255, 255], [200, 17, 93], [127, 128, 129]] {
+            let rgba: Vec<u8> = (0..16).flat_map(|_| [color[0], color[1], color[2], 255]).collect();
+            let encoded = encode_bc1(&rgba, 4, 4);
+            let decoded = decode_gtex_surface(24, &encoded, 4, 4, false).unwrap();
+            for px in decoded.chunks_exact(4) {
+                for c in 0..3 {
+                    assert!((px[c] as i32 - color[c] as i32).abs() <= 2, "{:?} -> {:?}", color, px);
+                }
+                assert_eq!(px[3], 255);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bc1_punch_through_alpha() {
+        let mut rgba = synthetic_image(8, 8);
+        for (i, px) in rgba.chunks_exact_mut(4).enumerate() {
+            px[3] = if i % 3 == 0 { 0 } else { 255 };
+        }
+        let encoded = encode_bc1(&rgba, 8, 8);
+        let decoded = decode_gtex_surface(24, &encoded, 8, 8, false).unwrap();
+        for (src, dst) in rgba.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+            assert_eq!(src[3] >= 128, dst[3] == 255);
+        }
+    }
+
+    #[test]
+    fn test_bc2_bc3_alpha_quality() {
+        let rgba = synthetic_image(32, 32);
+
+        let bc2 = encode_gtex_surface(25, &rgba, 32, 32, false).unwrap();
+        let decoded = decode_gtex_surface(25, &bc2, 32, 32, false).unwrap();
+        assert!(psnr(&rgba, &decoded, &[3]) > 30.0);
+
+        let bc3 = encode_gtex_surface(26, &rgba, 32, 32, false).unwrap();
+        let decoded = decode_gtex_surface(26, &bc3, 32, 32, false).unwrap();
+        assert!(psnr(&rgba, &decoded, &[3]) > 45.0);
+        assert!(psnr(&rgba, &decoded, &[0, 1, 2]) > 32.0);
+    }
+
+    #[test]
+    fn test_bc3_alpha_extremes() {
+        let alphas = [0u8, 255, 0, 255, 128, 64, 0, 255, 0, 255, 0, 255, 0, 255, 0, 255];
+        let rgba: Vec<u8> = alphas.iter().flat_map(|&a| [10, 20, 30, a]).collect();
+        let encoded = encode_bc3(&rgba, 4, 4);
+        let decoded = decode_gtex_surface(26, &encoded, 4, 4, false).unwrap();
+        for (src, dst) in rgba.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+            if src[3] == 0 || src[3] == 255 {
+                assert_eq!(src[3], dst[3]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_multiple_of_four_and_argb() {
+        let rgba = synthetic_image(5, 3);
+        let encoded = encode_gtex_surface(26, &rgba, 5, 3, false).unwrap();
+        assert_eq!(encoded.len(), 2 * 16);
+
+        let raw = encode_gtex_surface(3, &rgba, 5, 3, false).unwrap();
+        assert_eq!(decode_gtex_surface(3, &raw, 5, 3, false).unwrap(), rgba);
+
+        assert!(encode_gtex_surface(26, &rgba, 4, 4, false).is_err());
+    }
+
+    #[test]
+    fn test_unverified_formats_rejected_by_default() {
+        let rgba = synthetic_image(4, 4);
+        for code in [1, 2, 5, 6, 7, 8, 27, 28] {
+            assert!(encode_gtex_surface(code, &rgba, 4, 4, false).is_err(), "code {}", code);
+            let encoded = encode_gtex_surface(code, &rgba, 4, 4, true).unwrap();
+            assert!(decode_gtex_surface(code, &encoded, 4, 4, false).is_err(), "code {}", code);
+        }
+    }
+
+    #[test]
+    fn test_extended_formats_roundtrip() {
+        let (w, h) = (30, 18);
+        let src = synthetic_image(w, h);
+        let luma: Vec<u8> = src.chunks_exact(4).map(luminance).collect();
+
+        for format in GtexPixelFormat::ALL {
+            let encoded = encode_gtex_surface(format.code(), &src, w, h, true).unwrap();
+            assert_eq!(encoded.len() as u32, format.surface_size(w as u32, h as u32), "{:?}", format);
+            let out = decode_gtex_surface(format.code(), &encoded, w, h, true).unwrap();
+
+            let pixels = src.chunks_exact(4).zip(out.chunks_exact(4)).zip(luma.iter());
+            let max_diff = |range: std::ops::Range<usize>| {
+                src.chunks_exact(4)
+                    .zip(out.chunks_exact(4))
+                    .flat_map(|(a, b)| range.clone().map(move |c| (a[c] as i32 - b[c] as i32).abs()))
+                    .max()
+                    .unwrap()
+            };
+            match format {
+                GtexPixelFormat::L8 | GtexPixelFormat::A8L8 => {
+                    for ((s, o), &l) in pixels {
+                        assert_eq!(&o[0..3], &[l, l, l]);
+                        let alpha = if format == GtexPixelFormat::L8 { 255 } else { s[3] };
+                        assert_eq!(o[3], alpha);
+                    }
+                }
+                GtexPixelFormat::A8 => {
+                    for ((s, o), _) in pixels {
+                        assert_eq!(o, &[255, 255, 255, s[3]]);
+                    }
+                }
+                GtexPixelFormat::R5G6B5 => {
+                    assert!(max_diff(0..3) <= 4);
+                    assert!(out.chunks_exact(4).all(|p| p[3] == 255));
+                }
+                GtexPixelFormat::A4R4G4B4 => assert!(max_diff(0..4) <= 8),
+                GtexPixelFormat::A1R5G5B5 => {
+                    assert!(max_diff(0..3) <= 4);
+                    for ((s, o), _) in pixels {
+                        assert_eq!(o[3], if s[3] >= 128 { 255 } else { 0 });
+                    }
+                }
+                GtexPixelFormat::Bc4 => {
+                    assert!(psnr(&src, &out, &[0]) > 40.0);
+                    assert!(out.chunks_exact(4).all(|p| p[0] == p[1] && p[1] == p[2]));
+                }
+                GtexPixelFormat::Bc5 => assert!(psnr(&src, &out, &[0, 1]) > 40.0),
+                _ => {}
+            }
+        }
+    }
+}
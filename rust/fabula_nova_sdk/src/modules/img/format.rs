@@ -0,0 +1,329 @@
+//! # GTEX Pixel Formats
+//!
+//! This module catalogues the GTEX format codes and everything the rest of
+//! the IMG module needs to know about each one: surface sizes, the DDS
+//! pixel format written on export, and the DXGI equivalent.
+//!
+//! ## Catalogue
+//!
+//! | Code | Format     | Size            | DDS pixel format              | DXGI | Source     |
+//! |------|------------|-----------------|-------------------------------|------|------------|
+//! | 1    | L8         | 1 byte/px       | LUMINANCE, 8-bit              | 61   | Unverified |
+//! | 2    | A8         | 1 byte/px       | ALPHA, 8-bit                  | 65   | Unverified |
+//! | 3, 4 | A8R8G8B8   | 4 bytes/px      | RGB + ALPHA, 32-bit           | 87   | Retail     |
+//! | 5    | A8L8       | 2 bytes/px      | LUMINANCE + ALPHA, 16-bit     | 49   | Unverified |
+//! | 6    | R5G6B5     | 2 bytes/px      | RGB, 16-bit                   | 85   | Unverified |
+//! | 7    | A4R4G4B4   | 2 bytes/px      | RGB + ALPHA, 16-bit           | 115  | Unverified |
+//! | 8    | A1R5G5B5   | 2 bytes/px      | RGB + ALPHA, 16-bit           | 86   | Unverified |
+//! | 24   | DXT1 / BC1 | 8 bytes/block   | FourCC `DXT1`                 | 71   | Retail     |
+//! | 25   | DXT3 / BC2 | 16 bytes/block  | FourCC `DXT3`                 | 74   | Retail     |
+//! | 26   | DXT5 / BC3 | 16 bytes/block  | FourCC `DXT5`                 | 77   | Retail     |
+//! | 27   | BC4        | 8 bytes/block   | FourCC `ATI1`                 | 80   | Unverified |
+//! | 28   | BC5        | 16 bytes/block  | FourCC `ATI2`                 | 83   | Unverified |
+//!
+//! Only the retail codes are seen in shipped files. The unverified
+//! mappings are guesses that have only been tested with synthetic
+//! textures, so [`GtexPixelFormat::from_code`] rejects them (as unknown
+//! formats). The decode, encode and repack entry points take an
+//! `allow_unverified` argument instead; without it, a real file using one
+//! of those codes fails to convert instead of being decoded with a
+//! possibly wrong layout.
+//!
+//! ## Pixel Layout
+//!
+//! IMGB surfaces are copied into DDS files byte for byte, so each format is
+//! stored exactly as its DDS counterpart: 16-bit formats are little-endian
+//! words, A8L8 is L then A, and BC4/BC5 hold one/two BC3-style alpha blocks
+//! (red, then green).
+
+use serde::{Serialize, Deserialize};
+use super::structs::DdsPixelFormat;
+
+/// A GTEX pixel format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GtexPixelFormat {
+    /// 8-bit luminance (code 1)
+    L8,
+    /// 8-bit alpha only (code 2)
+    A8,
+    /// 32-bit ARGB, stored B, G, R, A (codes 3 and 4)
+    Argb8888,
+    /// 8-bit luminance + 8-bit alpha (code 5)
+    A8L8,
+    /// 16-bit RGB 5:6:5 (code 6)
+    R5G6B5,
+    /// 16-bit ARGB 4:4:4:4 (code 7)
+    A4R4G4B4,
+    /// 16-bit ARGB 1:5:5:5 (code 8)
+    A1R5G5B5,
+    /// BC1 (code 24)
+    Dxt1,
+    /// BC2 (code 25)
+    Dxt3,
+    /// BC3 (code 26)
+    Dxt5,
+    /// One-channel BC4 (code 27)
+    Bc4,
+    /// Two-channel BC5 (code 28)
+    Bc5,
+}
+
+impl GtexPixelFormat {
+    /// Every known format, in code order.
+    pub const ALL: [GtexPixelFormat; 12] = [
+        Self::L8, Self::A8, Self::Argb8888, Self::A8L8, Self::R5G6B5, Self::A4R4G4B4,
+        Self::A1R5G5B5, Self::Dxt1, Self::Dxt3, Self::Dxt5, Self::Bc4, Self::Bc5,
+    ];
+
+    /// Looks up a retail GTEX format code.
+    ///
+    /// Unverified codes are treated as unknown; use
+    /// [`from_code_with`](Self::from_code_with) to accept them.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::from_code_with(code, false)
+    }
+
+    /// Looks up a GTEX format code, accepting unverified codes only if
+    /// `allow_unverified` is set.
+    ///
+    /// Turn it on only when working with textures known to use these
+    /// layouts, e.g. ones created with this SDK.
+    pub fn from_code_with(code: u8, allow_unverified: bool) -> Option<Self> {
+        Self::lookup(code).filter(|format| allow_unverified || format.is_retail())
+    }
+
+    /// Like [`from_code_with`](Self::from_code_with), with an error that
+    /// says whether the code is unknown or only needs the opt-in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for unknown codes, and for unverified codes unless
+    /// `allow_unverified` is set.
+    pub fn from_code_checked(code: u8, allow_unverified: bool) -> anyhow::Result<Self> {
+        match Self::lookup(code) {
+            Some(format) if allow_unverified || format.is_retail() => Ok(format),
+            Some(format) => Err(anyhow::anyhow!(
+                "GTEX format {} ({}) has not been seen in retail files; pass allow_unverified to use it",
+                code,
+                format.name()
+            )),
+            None => Err(anyhow::anyhow!("Unsupported GTEX format: {}", code)),
+        }
+    }
+
+    fn lookup(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::L8),
+            2 => Some(Self::A8),
+            3 | 4 => Some(Self::Argb8888),
+            5 => Some(Self::A8L8),
+            6 => Some(Self::R5G6B5),
+            7 => Some(Self::A4R4G4B4),
+            8 => Some(Self::A1R5G5B5),
+            24 => Some(Self::Dxt1),
+            25 => Some(Self::Dxt3),
+            26 => Some(Self::Dxt5),
+            27 => Some(Self::Bc4),
+            28 => Some(Self::Bc5),
+            _ => None,
+        }
+    }
+
+    /// Whether this format has been seen in retail files.
+    pub fn is_retail(self) -> bool {
+        matches!(self, Self::Argb8888 | Self::Dxt1 | Self::Dxt3 | Self::Dxt5)
+    }
+
+    /// Canonical GTEX code (3 for ARGB8888, which also appears as 4).
+    pub fn code(self) -> u8 {
+        match self {
+            Self::L8 => 1,
+            Self::A8 => 2,
+            Self::Argb8888 => 3,
+            Self::A8L8 => 5,
+            Self::R5G6B5 => 6,
+            Self::A4R4G4B4 => 7,
+            Self::A1R5G5B5 => 8,
+            Self::Dxt1 => 24,
+            Self::Dxt3 => 25,
+            Self::Dxt5 => 26,
+            Self::Bc4 => 27,
+            Self::Bc5 => 28,
+        }
+    }
+
+    /// Display name (e.g., "DXT5").
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::L8 => "L8",
+            Self::A8 => "A8",
+            Self::Argb8888 => "ARGB8888",
+            Self::A8L8 => "A8L8",
+            Self::R5G6B5 => "R5G6B5",
+            Self::A4R4G4B4 => "A4R4G4B4",
+            Self::A1R5G5B5 => "A1R5G5B5",
+            Self::Dxt1 => "DXT1",
+            Self::Dxt3 => "DXT3",
+            Self::Dxt5 => "DXT5",
+            Self::Bc4 => "BC4",
+            Self::Bc5 => "BC5",
+        }
+    }
+
+    /// Bytes per 4x4 block, or `None` for uncompressed formats.
+    pub fn block_size(self) -> Option<u32> {
+        match self {
+            Self::Dxt1 | Self::Bc4 => Some(8),
+            Self::Dxt3 | Self::Dxt5 | Self::Bc5 => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Bits per pixel for uncompressed formats.
+    pub fn bits_per_pixel(self) -> Option<u32> {
+        match self {
+            Self::L8 | Self::A8 => Some(8),
+            Self::A8L8 | Self::R5G6B5 | Self::A4R4G4B4 | Self::A1R5G5B5 => Some(16),
+            Self::Argb8888 => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Byte size of one surface.
+    pub fn surface_size(self, width: u32, height: u32) -> u32 {
+        match self.block_size() {
+            Some(block) => width.div_ceil(4).max(1) * height.div_ceil(4).max(1) * block,
+            None => width * height * self.bits_per_pixel().unwrap_or(32) / 8,
+        }
+    }
+
+    /// Row pitch (uncompressed) or level-0 linear size (block compressed).
+    pub fn pitch_or_linear_size(self, width: u32, height: u32) -> u32 {
+        match self.bits_per_pixel() {
+            Some(bits) => (width * bits).div_ceil(8),
+            None => self.surface_size(width, height),
+        }
+    }
+
+    /// The DDS pixel format block written for this format.
+    pub fn dds_pixel_format(self) -> DdsPixelFormat {
+        // (flags, bit count, R, G, B, A masks) for uncompressed formats
+        let masks = |flags, bits, r, g, b, a| DdsPixelFormat {
+            size: 32,
+            flags,
+            four_cc: [0; 4],
+            rgb_bit_count: bits,
+            r_bit_mask: r,
+            g_bit_mask: g,
+            b_bit_mask: b,
+            a_bit_mask: a,
+        };
+        let four_cc = |code: &[u8; 4]| DdsPixelFormat {
+            size: 32,
+            flags: 0x04,
+            four_cc: *code,
+            rgb_bit_count: 0,
+            r_bit_mask: 0,
+            g_bit_mask: 0,
+            b_bit_mask: 0,
+            a_bit_mask: 0,
+        };
+
+        match self {
+            Self::L8 => masks(0x20000, 8, 0xFF, 0, 0, 0),
+            Self::A8 => masks(0x02, 8, 0, 0, 0, 0xFF),
+            Self::Argb8888 => masks(0x41, 32, 0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000),
+            Self::A8L8 => masks(0x20001, 16, 0x00FF, 0, 0, 0xFF00),
+            Self::R5G6B5 => masks(0x40, 16, 0xF800, 0x07E0, 0x001F, 0),
+            Self::A4R4G4B4 => masks(0x41, 16, 0x0F00, 0x00F0, 0x000F, 0xF000),
+            Self::A1R5G5B5 => masks(0x41, 16, 0x7C00, 0x03E0, 0x001F, 0x8000),
+            Self::Dxt1 => four_cc(b"DXT1"),
+            Self::Dxt3 => four_cc(b"DXT3"),
+            Self::Dxt5 => four_cc(b"DXT5"),
+            Self::Bc4 => four_cc(b"ATI1"),
+            Self::Bc5 => four_cc(b"ATI2"),
+        }
+    }
+
+    /// The DXGI_FORMAT value used in DX10 headers.
+    pub fn dxgi_format(self) -> u32 {
+        match self {
+            Self::L8 => 61,        // R8_UNORM
+            Self::A8 => 65,        // A8_UNORM
+            Self::Argb8888 => 87,  // B8G8R8A8_UNORM
+            Self::A8L8 => 49,      // R8G8_UNORM
+            Self::R5G6B5 => 85,    // B5G6R5_UNORM
+            Self::A4R4G4B4 => 115, // B4G4R4A4_UNORM
+            Self::A1R5G5B5 => 86,  // B5G5R5A1_UNORM
+            Self::Dxt1 => 71,      // BC1_UNORM
+            Self::Dxt3 => 74,      // BC2_UNORM
+            Self::Dxt5 => 77,      // BC3_UNORM
+            Self::Bc4 => 80,       // BC4_UNORM
+            Self::Bc5 => 83,       // BC5_UNORM
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_round_trip() {
+        for format in GtexPixelFormat::ALL {
+            assert_eq!(GtexPixelFormat::from_code_with(format.code(), true), Some(format));
+        }
+        assert_eq!(GtexPixelFormat::from_code_with(4, false), Some(GtexPixelFormat::Argb8888));
+        assert_eq!(GtexPixelFormat::Argb8888.code(), 3);
+        for code in [0, 9, 23, 29, 255] {
+            assert_eq!(GtexPixelFormat::from_code_with(code, true), None);
+        }
+    }
+
+    #[test]
+    fn test_unverified_codes_need_opt_in() {
+        let retail: Vec<u8> = GtexPixelFormat::ALL
+            .into_iter()
+            .filter_map(|f| GtexPixelFormat::from_code_with(f.code(), false))
+            .map(GtexPixelFormat::code)
+            .collect();
+        assert_eq!(retail, vec![3, 24, 25, 26]);
+        for code in [1, 2, 5, 6, 7, 8, 27, 28] {
+            assert_eq!(GtexPixelFormat::from_code_with(code, false), None, "code {}", code);
+            assert!(GtexPixelFormat::from_code_checked(code, false).is_err());
+            assert!(GtexPixelFormat::from_code_checked(code, true).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_block_and_surface_sizes() {
+        use GtexPixelFormat as G;
+        let blocks: Vec<_> = G::ALL.into_iter().map(|f| (f.block_size(), f.bits_per_pixel())).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (None, Some(8)),
+                (None, Some(8)),
+                (None, Some(32)),
+                (None, Some(16)),
+                (None, Some(16)),
+                (None, Some(16)),
+                (None, Some(16)),
+                (Some(8), None),
+                (Some(16), None),
+                (Some(16), None),
+                (Some(8), None),
+                (Some(16), None),
+            ]
+        );
+
+        // Partial blocks round up, and the smallest mips still take one block
+        assert_eq!(G::Dxt1.surface_size(5, 3), 2 * 8);
+        assert_eq!(G::Dxt5.surface_size(1, 1), 16);
+        assert_eq!(G::Bc5.surface_size(8, 8), 4 * 16);
+        assert_eq!(G::Argb8888.surface_size(5, 3), 5 * 3 * 4);
+        assert_eq!(G::R5G6B5.surface_size(3, 3), 18);
+        assert_eq!(G::R5G6B5.pitch_or_linear_size(3, 3), 6);
+        assert_eq!(G::Dxt3.pitch_or_linear_size(8, 4), 32);
+    }
+}
@@ -0,0 +1,242 @@
+//! # IMG Surface Layout
+//!
+//! This module maps a GTEX header and its mipmap table to the individual
+//! surfaces (face or slice × mip level) stored in the IMGB file.
+//!
+//! ## Image Types
+//!
+//! | `img_type` | Kind      | Layers          | Mip table entries         |
+//! |------------|-----------|-----------------|---------------------------|
+//! | 0, 4       | 2D        | 1               | `mip_count`               |
+//! | 1          | Cubemap   | 6 faces         | `6 * mip_count`           |
+//! | 2          | Volume    | `depth` slices  | `mip_count`               |
+//!
+//! ## Entry Order
+//!
+//! Both multi-layer kinds store their entries in the same order DDS uses,
+//! so a DDS body is simply every table entry concatenated:
+//!
+//! ```text
+//! Cubemap (face-major):            Volume (mip-major):
+//! ┌──────────────────────┐         ┌──────────────────────────────┐
+//! │ +X mip0, mip1, ...   │         │ mip0: slice 0, 1, ..., d-1   │
+//! │ -X mip0, mip1, ...   │         │ mip1: slice 0, ..., d/2-1    │
+//! │ +Y, -Y, +Z, -Z ...   │         │ ...                          │
+//! └──────────────────────┘         └──────────────────────────────┘
+//! ```
+//!
+//! Volume levels halve in depth as well as width and height; every slice
+//! of a level lives inside that level's single table entry.
+//!
+//! ## Cross Layout
+//!
+//! Cubemaps can also be exported as a single horizontal cross
+//! (4 × 3 faces, unused cells transparent):
+//!
+//! ```text
+//!        ┌────┐
+//!        │ +Y │
+//! ┌────┬─┴──┬─┴──┬────┐
+//! │ -X │ +Z │ +X │ -Z │
+//! └────┴─┬──┬─┴──┴────┘
+//!        │ -Y │
+//!        └────┘
+//! ```
+
+use std::io::{Read, Seek, SeekFrom};
+use anyhow::Result;
+use image::{imageops, RgbaImage};
+use serde::{Serialize, Deserialize};
+use super::format::GtexPixelFormat;
+use super::structs::{GtexHeader, GtexMipEntry};
+
+/// Names of the cubemap faces in DDS/GTEX order.
+pub const CUBEMAP_FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Texture kind described by [`GtexHeader::img_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GtexImageKind {
+    /// Plain 2D texture (types 0 and 4)
+    Texture2D,
+    /// Six-face environment map (type 1)
+    Cubemap,
+    /// Stack of `depth` slices (type 2)
+    Volume,
+}
+
+impl GtexHeader {
+    /// Returns the texture kind encoded in `img_type`.
+    pub fn image_kind(&self) -> GtexImageKind {
+        match self.img_type {
+            1 => GtexImageKind::Cubemap,
+            2 => GtexImageKind::Volume,
+            _ => GtexImageKind::Texture2D,
+        }
+    }
+
+    /// Number of faces or slices at mip level 0.
+    pub fn layer_count(&self) -> u32 {
+        match self.image_kind() {
+            GtexImageKind::Texture2D => 1,
+            GtexImageKind::Cubemap => 6,
+            GtexImageKind::Volume => (self.depth as u32).max(1),
+        }
+    }
+
+    /// Number of entries in the mipmap table.
+    pub fn mip_entry_count(&self) -> usize {
+        match self.image_kind() {
+            GtexImageKind::Cubemap => 6 * self.mip_count as usize,
+            _ => self.mip_count as usize,
+        }
+    }
+}
+
+/// One 2D surface of a texture, located in the IMGB file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GtexSurface {
+    /// Face index (cubemap) or slice index (volume); 0 for 2D textures
+    pub layer: u32,
+    /// Mip level
+    pub mip: u32,
+    /// Surface width in pixels
+    pub width: u32,
+    /// Surface height in pixels
+    pub height: u32,
+    /// Byte offset in the IMGB file
+    pub offset: u32,
+    /// Size in bytes
+    pub size: u32,
+}
+
+/// Returns a display name for a GTEX format code.
+pub fn gtex_format_name(format: u8) -> String {
+    match GtexPixelFormat::from_code(format) {
+        Some(known) => known.name().to_string(),
+        None => format!("Unknown({})", format),
+    }
+}
+
+/// Returns the byte size of one surface, or `None` for unknown formats.
+pub fn surface_size(format: u8, width: u32, height: u32) -> Option<u32> {
+    GtexPixelFormat::from_code(format).map(|f| f.surface_size(width, height))
+}
+
+/// Splits the mipmap table into individual surfaces.
+///
+/// 2D and cubemap entries map one-to-one onto surfaces. Volume entries are
+/// divided into their slices, which requires a known pixel format.
+///
+/// # Errors
+///
+/// Returns an error if the table is shorter than the header implies, or a
+/// volume level is too small to hold its slices.
+pub fn gtex_surfaces(header: &GtexHeader, mip_table: &[GtexMipEntry]) -> Result<Vec<GtexSurface>> {
+    let mip_count = header.mip_count as u32;
+    if mip_table.len() < header.mip_entry_count() {
+        anyhow::bail!(
+            "Mip table has {} entries, expected {}",
+            mip_table.len(), header.mip_entry_count()
+        );
+    }
+
+    let level_size = |mip: u32| {
+        (
+            ((header.width as u32) >> mip).max(1),
+            ((header.height as u32) >> mip).max(1),
+        )
+    };
+
+    let mut surfaces = Vec::new();
+    match header.image_kind() {
+        GtexImageKind::Texture2D | GtexImageKind::Cubemap => {
+            for layer in 0..header.layer_count() {
+                for mip in 0..mip_count {
+                    let entry = mip_table[(layer * mip_count + mip) as usize];
+                    let (width, height) = level_size(mip);
+                    surfaces.push(GtexSurface {
+                        layer, mip, width, height,
+                        offset: entry.offset,
+                        size: entry.size,
+                    });
+                }
+            }
+        }
+        GtexImageKind::Volume => {
+            for mip in 0..mip_count {
+                let entry = mip_table[mip as usize];
+                let (width, height) = level_size(mip);
+                let slices = (header.layer_count() >> mip).max(1);
+                let slice_size = surface_size(header.format, width, height)
+                    .ok_or_else(|| anyhow::anyhow!("Unsupported GTEX format: {}", header.format))?;
+                if slice_size * slices > entry.size {
+                    anyhow::bail!(
+                        "Volume mip {} is {} bytes, too small for {} slice(s) of {} bytes",
+                        mip, entry.size, slices, slice_size
+                    );
+                }
+                for layer in 0..slices {
+                    surfaces.push(GtexSurface {
+                        layer, mip, width, height,
+                        offset: entry.offset + layer * slice_size,
+                        size: slice_size,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(surfaces)
+}
+
+/// Reads the pixel data of one surface from an IMGB stream.
+pub fn read_surface<R: Read + Seek>(imgb: &mut R, surface: &GtexSurface) -> Result<Vec<u8>> {
+    imgb.seek(SeekFrom::Start(surface.offset as u64))?;
+    let mut data = vec![0u8; surface.size as usize];
+    imgb.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Cell (column, row) of each face in the horizontal cross, in face order.
+const CROSS_CELLS: [(u32, u32); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+/// Assembles six square faces into a horizontal cross image.
+///
+/// # Errors
+///
+/// Returns an error unless there are six faces of identical square size.
+pub fn cubemap_to_cross(faces: &[RgbaImage]) -> Result<RgbaImage> {
+    if faces.len() != 6 {
+        anyhow::bail!("Cubemap needs 6 faces, got {}", faces.len());
+    }
+    let size = faces[0].width();
+    if faces.iter().any(|f| f.dimensions() != (size, size)) {
+        anyhow::bail!("Cubemap faces must all be {}x{}", size, size);
+    }
+
+    let mut cross = RgbaImage::new(size * 4, size * 3);
+    for (face, &(col, row)) in faces.iter().zip(CROSS_CELLS.iter()) {
+        imageops::replace(&mut cross, face, (col * size) as i64, (row * size) as i64);
+    }
+    Ok(cross)
+}
+
+/// Splits a horizontal cross image into six faces in DDS order.
+///
+/// # Errors
+///
+/// Returns an error if the image isn't a 4:3 cross of `face_size` faces.
+pub fn cross_to_cubemap(cross: &RgbaImage, face_size: u32) -> Result<Vec<RgbaImage>> {
+    if cross.dimensions() != (face_size * 4, face_size * 3) {
+        anyhow::bail!(
+            "Cross image is {}x{}, expected {}x{}",
+            cross.width(), cross.height(), face_size * 4, face_size * 3
+        );
+    }
+    Ok(CROSS_CELLS
+        .iter()
+        .map(|&(col, row)| {
+            imageops::crop_imm(cross, col * face_size, row * face_size, face_size, face_size).to_image()
+        })
+        .collect())
+}
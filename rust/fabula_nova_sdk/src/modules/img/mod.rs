@@ -37,6 +37,13 @@
 //! - [`structs`] - Image data structures
 //! - [`reader`] - Binary image parser
 //! - [`writer`] - Binary image generator
+//! - [`decoder`] - Pixel data to RGBA conversion
+//! - [`encoder`] - RGBA to DXT1/3/5 and ARGB8888 encoding
+//! - [`mipmap`] - Mip chain generation
+//! - [`format`] - GTEX pixel format catalogue
+//! - [`layout`] - Cubemap/volume surface layout
+//! - [`xgr`] - Multi-texture XGR/IMGB listing and batch extraction
+//! - [`batch`] - Directory-wide PNG export/import with a manifest
 //! - [`api`] - High-level public API
 //!
 //! ## Usage Example
@@ -49,23 +56,286 @@
 //!
 //! // Repack DDS back (strict mode - must match original size)
 //! img::repack_img_strict("texture.txbh", "data.imgb", "modified.dds")?;
+//!
+//! // Or repack straight from an edited PNG, regenerating all mips
+//! img::repack_img_from_png("texture.txbh", "data.imgb", "edited.png", MipFilter::Box, false)?;
 //! ```
 
 pub mod structs;
 pub mod reader;
 pub mod writer;
+pub mod decoder;
+pub mod encoder;
+pub mod mipmap;
+pub mod format;
+pub mod layout;
+pub mod xgr;
+pub mod batch;
 pub mod api;
 
 // Re-export all public items
 pub use structs::*;
 pub use reader::*;
 pub use writer::*;
+pub use decoder::*;
+pub use encoder::*;
+pub use mipmap::*;
+pub use format::*;
+pub use layout::*;
+pub use xgr::*;
+pub use batch::*;
 pub use api::*;
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
-    use super::api::{extract_img_to_dds, repack_img_strict};
+    use std::path::{Path, PathBuf};
+    use super::api::{
+        convert_dds_to_png, extract_cubemap_to_cross_png, extract_img_layers_to_png, extract_img_to_dds,
+        repack_img_from_png, repack_img_layers_from_png, repack_img_regenerate_mips, repack_img_strict,
+    };
+    use super::structs::MipFilter;
+    use super::writer::ImgWriter;
+    use super::reader::ImgReader;
+
+    /// Builds a GTEX header file with a contiguous mip table and a blank IMGB.
+    fn write_synthetic_texture(
+        dir: &Path,
+        format: u8,
+        img_type: u8,
+        size: (u16, u16, u16),
+        entry_sizes: &[u32],
+    ) -> (PathBuf, PathBuf) {
+        let mut header = Vec::new();
+        header.extend_from_slice(b"GTEX");
+        header.extend_from_slice(&0u16.to_be_bytes());
+        header.extend_from_slice(&[format, 0, 0, img_type]);
+        header[7] = if img_type == 1 { (entry_sizes.len() / 6) as u8 } else { entry_sizes.len() as u8 };
+        header.extend_from_slice(&size.0.to_be_bytes());
+        header.extend_from_slice(&size.1.to_be_bytes());
+        header.extend_from_slice(&size.2.to_be_bytes());
+        header.extend_from_slice(&24u32.to_be_bytes());
+        header.extend_from_slice(&[0; 4]);
+        let mut offset = 0u32;
+        for &entry_size in entry_sizes {
+            header.extend_from_slice(&offset.to_be_bytes());
+            header.extend_from_slice(&entry_size.to_be_bytes());
+            offset += entry_size;
+        }
+
+        std::fs::create_dir_all(dir).unwrap();
+        let header_path = dir.join("tex.txbh");
+        let imgb_path = dir.join("tex.imgb");
+        std::fs::write(&header_path, header).unwrap();
+        std::fs::write(&imgb_path, vec![0u8; offset as usize]).unwrap();
+        (header_path, imgb_path)
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push(name);
+        if dir.exists() {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        dir
+    }
+
+    #[test]
+    fn test_cubemap_cross_roundtrip() {
+        let dir = test_dir("test_img_cubemap");
+        // 8x8 DXT5 cubemap with 2 mips: 64 + 16 bytes per face
+        let entries: Vec<u32> = (0..6).flat_map(|_| [64, 16]).collect();
+        let (header_path, imgb_path) = write_synthetic_texture(&dir, 26, 1, (8, 8, 0), &entries);
+
+        let face_colors: [[u8; 4]; 6] = [
+            [255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255],
+            [255, 255, 0, 255], [0, 255, 255, 255], [255, 0, 255, 255],
+        ];
+        let faces: Vec<image::RgbaImage> = face_colors
+            .iter()
+            .map(|&c| image::RgbaImage::from_pixel(8, 8, image::Rgba(c)))
+            .collect();
+        let cross_path = dir.join("cross.png");
+        super::layout::cubemap_to_cross(&faces).unwrap().save(&cross_path).unwrap();
+
+        repack_img_from_png(&header_path, &imgb_path, &cross_path, MipFilter::Box, false).unwrap();
+
+        let pngs = extract_img_layers_to_png(&header_path, &imgb_path, &dir.join("faces"), false).unwrap();
+        assert_eq!(pngs.len(), 6);
+        assert!(pngs[0].ends_with("tex_px.png"));
+        for (png, color) in pngs.iter().zip(face_colors.iter()) {
+            let face = image::open(png).unwrap().to_rgba8();
+            assert_eq!(face.get_pixel(3, 3).0, *color);
+        }
+
+        let cross_out = dir.join("cross_out.png");
+        let dims = extract_cubemap_to_cross_png(&header_path, &imgb_path, &cross_out, false).unwrap();
+        assert_eq!(dims, (32, 24));
+
+        // DDS export carries cubemap caps and every face
+        let dds_path = dir.join("tex.dds");
+        extract_img_to_dds(&header_path, &imgb_path, &dds_path).unwrap();
+        let dds = ddsfile::Dds::read(std::fs::File::open(&dds_path).unwrap()).unwrap();
+        assert_eq!(dds.get_num_array_layers(), 6);
+        assert_eq!(std::fs::metadata(&dds_path).unwrap().len(), 128 + 6 * 80);
+    }
+
+    #[test]
+    fn test_volume_slices_roundtrip() {
+        let dir = test_dir("test_img_volume");
+        // 4x4x4 ARGB volume, 3 mips: 4 slices, 2 slices, 1 slice
+        let (header_path, imgb_path) =
+            write_synthetic_texture(&dir, 3, 2, (4, 4, 4), &[4 * 64, 2 * 16, 4]);
+
+        let mut slice_paths = Vec::new();
+        for i in 0..4u8 {
+            let path = dir.join(format!("in_slice{}.png", i));
+            image::RgbaImage::from_pixel(4, 4, image::Rgba([i * 40, 10, 20, 255]))
+                .save(&path)
+                .unwrap();
+            slice_paths.push(path);
+        }
+        repack_img_layers_from_png(&header_path, &imgb_path, &slice_paths, MipFilter::Box, false).unwrap();
+        assert!(repack_img_from_png(&header_path, &imgb_path, &slice_paths[0], MipFilter::Box, false).is_err());
+
+        // Mip 1 slice 1 averages source slices 2 and 3 (stored B, G, R, A)
+        let imgb = std::fs::read(&imgb_path).unwrap();
+        assert_eq!(&imgb[256 + 16..256 + 20], &[20, 10, 100, 255]);
+
+        let pngs = extract_img_layers_to_png(&header_path, &imgb_path, &dir.join("out"), false).unwrap();
+        assert_eq!(pngs.len(), 4);
+        let slice3 = image::open(&pngs[3]).unwrap().to_rgba8();
+        assert_eq!(slice3.get_pixel(0, 0).0, [120, 10, 20, 255]);
+
+        let dds_path = dir.join("tex.dds");
+        extract_img_to_dds(&header_path, &imgb_path, &dds_path).unwrap();
+        let mut dds_file = std::fs::File::open(&dds_path).unwrap();
+        let dds_header = ImgReader::new(&mut dds_file).read_dds().unwrap();
+        assert_eq!(dds_header.depth, 4);
+        assert_eq!(dds_header.caps2, 0x200000);
+    }
+
+    #[test]
+    fn test_dx10_dds_repack() {
+        let dir = test_dir("test_img_dx10");
+        let entries: Vec<u32> = (0..6).map(|_| 16).collect();
+        let (header_path, imgb_path) = write_synthetic_texture(&dir, 26, 1, (4, 4, 0), &entries);
+
+        let mut header_file = std::fs::File::open(&header_path).unwrap();
+        let (gtex, _) = ImgReader::new(&mut header_file).read_gtex().unwrap().unwrap();
+
+        let mut dds = std::io::Cursor::new(Vec::new());
+        ImgWriter::new(&mut dds).write_dds_header_dx10(&gtex).unwrap();
+        let mut dds = dds.into_inner();
+        assert_eq!(dds.len(), 148);
+        assert_eq!(&dds[84..88], b"DX10");
+        assert_eq!(u32::from_le_bytes([dds[136], dds[137], dds[138], dds[139]]), 0x4);
+
+        let body: Vec<u8> = (0..96u8).collect();
+        dds.extend_from_slice(&body);
+        let dds_path = dir.join("tex.dds");
+        std::fs::write(&dds_path, &dds).unwrap();
+
+        repack_img_strict(&header_path, &imgb_path, &dds_path).unwrap();
+        assert_eq!(std::fs::read(&imgb_path).unwrap(), body);
+    }
+
+    #[test]
+    fn test_regenerate_mips_from_dds() {
+        let dir = test_dir("test_img_regen_mips");
+        // 8x8 DXT1 with 4 mips; the DDS is ARGB with only the base level
+        let (header_path, imgb_path) = write_synthetic_texture(&dir, 24, 0, (8, 8, 0), &[32, 8, 8, 8]);
+        let mut dds = ddsfile::Dds::new_d3d(ddsfile::NewD3dParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: ddsfile::D3DFormat::A8R8G8B8,
+            mipmap_levels: Some(1),
+            caps2: None,
+        }).unwrap();
+        dds.data = (0..64).flat_map(|_| [0, 0, 255, 255]).collect();
+        let dds_path = dir.join("base_only.dds");
+        dds.write(&mut std::fs::File::create(&dds_path).unwrap()).unwrap();
+
+        repack_img_regenerate_mips(&header_path, &imgb_path, &dds_path, MipFilter::Box, false).unwrap();
+        let imgb = std::fs::read(&imgb_path).unwrap();
+        for (offset, size) in [(0, 8), (32, 4), (40, 2), (48, 1)] {
+            let rgba = super::decoder::decode_gtex_surface(24, &imgb[offset..], size, size, false).unwrap();
+            assert!(rgba.chunks(4).all(|p| p == [255, 0, 0, 255]), "mip at {} not regenerated", offset);
+        }
+
+        // Cubemap DDS with stale mips: faces are found past each mip chain
+        let dir = dir.join("cube");
+        let entries: Vec<u32> = (0..6).flat_map(|_| [64, 16]).collect();
+        let (header_path, imgb_path) = write_synthetic_texture(&dir, 3, 1, (4, 4, 0), &entries);
+        let faces: Vec<image::RgbaImage> = (0..6u8)
+            .map(|i| image::RgbaImage::from_pixel(4, 4, image::Rgba([i * 40, 0, 0, 255])))
+            .collect();
+        let cross_path = dir.join("cross.png");
+        super::layout::cubemap_to_cross(&faces).unwrap().save(&cross_path).unwrap();
+        repack_img_from_png(&header_path, &imgb_path, &cross_path, MipFilter::Box, false).unwrap();
+        let dds_path = dir.join("tex.dds");
+        extract_img_to_dds(&header_path, &imgb_path, &dds_path).unwrap();
+
+        let mut stale = std::fs::read(&imgb_path).unwrap();
+        for face in 0..6 {
+            stale[face * 80 + 64..face * 80 + 80].fill(0);
+        }
+        std::fs::write(&imgb_path, &stale).unwrap();
+        let mut dds_bytes = std::fs::read(&dds_path).unwrap();
+        dds_bytes[128..].copy_from_slice(&stale);
+        std::fs::write(&dds_path, &dds_bytes).unwrap();
+
+        repack_img_regenerate_mips(&header_path, &imgb_path, &dds_path, MipFilter::Box, false).unwrap();
+        let imgb = std::fs::read(&imgb_path).unwrap();
+        for face in 0..6u8 {
+            let mip1 = face as usize * 80 + 64;
+            assert_eq!(&imgb[mip1..mip1 + 4], &[0, 0, face * 40, 255]);
+        }
+
+        let png_path = dir.join("face.png");
+        faces[0].save(&png_path).unwrap();
+        assert!(repack_img_regenerate_mips(&header_path, &imgb_path, &png_path, MipFilter::Box, false).is_err());
+    }
+
+    #[test]
+    fn test_extended_formats_dds_export() {
+        let dir = test_dir("test_img_formats");
+        let gradient = image::RgbaImage::from_fn(8, 8, |x, y| {
+            image::Rgba([(x * 32) as u8, (y * 32) as u8, 128, (255 - x * 20) as u8])
+        });
+        let png_path = dir.join("gradient.png");
+        std::fs::create_dir_all(&dir).unwrap();
+        gradient.save(&png_path).unwrap();
+
+        for format in super::format::GtexPixelFormat::ALL {
+            let code = format.code();
+            let size = format.surface_size(8, 8);
+            let (header_path, imgb_path) =
+                write_synthetic_texture(&dir.join(format.name()), code, 0, (8, 8, 0), &[size]);
+            let retail = repack_img_from_png(&header_path, &imgb_path, &png_path, MipFilter::Box, false);
+            assert_eq!(retail.is_ok(), format.is_retail(), "{}", format.name());
+            repack_img_from_png(&header_path, &imgb_path, &png_path, MipFilter::Box, true).unwrap();
+
+            let mut header_file = std::fs::File::open(&header_path).unwrap();
+            let (gtex, _) = ImgReader::new(&mut header_file).read_gtex().unwrap().unwrap();
+            let mut dds = std::io::Cursor::new(Vec::new());
+            ImgWriter::new(&mut dds).allow_unverified_formats(true).write_dds_header(&gtex).unwrap();
+            let imgb = std::fs::read(&imgb_path).unwrap();
+            let mut dds = dds.into_inner();
+            dds.extend_from_slice(&imgb);
+            assert_eq!(dds.len(), 128 + size as usize);
+            let dds_path = dir.join(format!("{}.dds", format.name()));
+            std::fs::write(&dds_path, &dds).unwrap();
+
+            // The DDS preview must decode to exactly what the IMGB holds
+            let expected = super::decoder::decode_gtex_surface(code, &imgb, 8, 8, true).unwrap();
+            let out_png = dir.join(format!("{}.png", format.name()));
+            convert_dds_to_png(&dds_path, &out_png).unwrap();
+            let preview = image::open(&out_png).unwrap().to_rgba8();
+            assert_eq!(preview.as_raw(), &expected, "{}", format.name());
+        }
+    }
 
     #[test]
     fn test_img_roundtrip() {
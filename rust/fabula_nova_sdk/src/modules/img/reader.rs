@@ -27,8 +27,9 @@
 
 use std::io::{Read, Seek, SeekFrom};
 use binrw::BinReaderExt;
+use byteorder::{BigEndian, ReadBytesExt};
 use anyhow::Result;
-use super::structs::{GtexHeader, DdsHeader};
+use super::structs::{GtexHeader, DdsHeader, GtexMipEntry};
 
 /// Binary reader for texture files.
 ///
@@ -142,4 +143,22 @@ impl<R: Read + Seek> ImgReader<R> {
         let header: DdsHeader = self.reader.read_le()?;
         Ok(header)
     }
+
+    /// Reads the mipmap table of a GTEX header found at `gtex_pos`.
+    ///
+    /// The table offset is stored as a big-endian `u32` at `gtex_pos + 16`,
+    /// relative to the start of the GTEX chunk. Reads `count` entries.
+    pub fn read_mip_table(&mut self, gtex_pos: u64, count: usize) -> Result<Vec<GtexMipEntry>> {
+        self.reader.seek(SeekFrom::Start(gtex_pos + 16))?;
+        let table_offset = self.reader.read_u32::<BigEndian>()?;
+        self.reader.seek(SeekFrom::Start(gtex_pos + table_offset as u64))?;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let offset = self.reader.read_u32::<BigEndian>()?;
+            let size = self.reader.read_u32::<BigEndian>()?;
+            entries.push(GtexMipEntry { offset, size });
+        }
+        Ok(entries)
+    }
 }
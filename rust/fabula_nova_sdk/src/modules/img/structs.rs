@@ -156,6 +156,47 @@ pub struct DdsHeader {
     pub reserved2: u32,
 }
 
+impl DdsHeader {
+    /// Byte offset of the pixel data from the start of the file.
+    ///
+    /// 128 for legacy headers, 148 when a DX10 extension header follows.
+    pub fn data_offset(&self) -> u64 {
+        if self.pixel_format.flags & 0x04 != 0 && &self.pixel_format.four_cc == b"DX10" {
+            148
+        } else {
+            128
+        }
+    }
+}
+
+/// DDS DX10 extension header (20 bytes).
+///
+/// Present when the pixel format's FourCC is `"DX10"`, directly after the
+/// 124-byte header. Describes the format as a DXGI code and carries the
+/// resource dimension and cubemap flag.
+///
+/// # Resource Dimensions
+///
+/// | Value | Dimension  |
+/// |-------|------------|
+/// | 3     | Texture2D  |
+/// | 4     | Texture3D  |
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DdsHeaderDx10 {
+    /// DXGI_FORMAT value (e.g., 71 = BC1_UNORM)
+    pub dxgi_format: u32,
+    /// Resource dimension (3 = 2D, 4 = 3D)
+    pub resource_dimension: u32,
+    /// Misc flags (0x4 = TEXTURECUBE)
+    pub misc_flag: u32,
+    /// Array size (number of cubes for cubemaps)
+    pub array_size: u32,
+    /// Alpha mode flags
+    pub misc_flags2: u32,
+}
+
 /// DDS pixel format descriptor (32 bytes).
 ///
 /// Embedded within the DDS header to describe the pixel data format.
@@ -229,4 +270,173 @@ pub struct ImgData {
     pub mip_count: u8,
     /// Format name (debug representation of format code)
     pub format: String,
-}
\ No newline at end of file
+}
+
+/// One entry of the GTEX mipmap table.
+///
+/// Each entry locates the pixel data of one mip level inside the IMGB file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GtexMipEntry {
+    /// Byte offset of the level in the IMGB file
+    pub offset: u32,
+    /// Size of the level in bytes
+    pub size: u32,
+}
+
+/// Resampling filter used when generating mipmap levels.
+///
+/// See [`super::mipmap`] for how each filter behaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MipFilter {
+    /// Area average (default)
+    #[default]
+    Box,
+    /// Bilinear
+    Triangle,
+    /// Bicubic (Catmull-Rom)
+    CatmullRom,
+    /// Lanczos with a window of 3
+    Lanczos3,
+}
+
+/// One texture found in a multi-texture XGR package.
+///
+/// Describes the texture without decoding it; `imgb_ranges` lists every
+/// mipmap table entry (all faces for cubemaps) as stored in the IMGB.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XgrTexture {
+    /// Record name inside the XGR (e.g., "cs_line00")
+    pub name: String,
+    /// Record extension (e.g., "txbh")
+    pub extension: String,
+    /// Index of the record in the XGR
+    pub record_index: u32,
+    /// Texture width in pixels
+    pub width: u16,
+    /// Texture height in pixels
+    pub height: u16,
+    /// Depth (volume slices), 0 for 2D textures
+    pub depth: u16,
+    /// GTEX format code
+    pub format: u8,
+    /// Display name of the format (e.g., "DXT5")
+    pub format_name: String,
+    /// Number of mipmap levels
+    pub mip_count: u8,
+    /// GTEX image type (0/4 = 2D, 1 = cubemap, 2 = volume)
+    pub img_type: u8,
+    /// IMGB byte ranges of every mipmap table entry
+    pub imgb_ranges: Vec<GtexMipEntry>,
+}
+
+/// How two IMGB ranges of different textures relate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImgbRangeOverlapKind {
+    /// Both textures point at exactly the same bytes
+    Shared,
+    /// The ranges partially overlap
+    Overlapping,
+}
+
+/// A pair of texture ranges that point into the same IMGB bytes.
+///
+/// Editing one side of a shared or overlapping range also changes the
+/// other texture, so the UI should warn before repacking either.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImgbRangeOverlap {
+    /// Name of the first texture
+    pub first: String,
+    /// Range of the first texture
+    pub first_range: GtexMipEntry,
+    /// Name of the second texture
+    pub second: String,
+    /// Range of the second texture
+    pub second_range: GtexMipEntry,
+    /// Shared (identical) or partially overlapping
+    pub kind: ImgbRangeOverlapKind,
+}
+
+/// All textures in an XGR/IMGB pair, with range diagnostics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XgrTextureSet {
+    /// Textures in record order
+    pub textures: Vec<XgrTexture>,
+    /// Ranges shared or overlapping between different textures
+    pub overlaps: Vec<ImgbRangeOverlap>,
+    /// Names of textures with a range past the end of the IMGB
+    pub out_of_bounds: Vec<String>,
+}
+
+/// Output format for batch texture extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TextureExportFormat {
+    /// DDS with all mip levels
+    #[default]
+    Dds,
+    /// PNG of mip 0 (cubemaps as a cross, volumes one file per slice)
+    Png,
+}
+
+/// One exported texture in a batch conversion manifest.
+///
+/// Paths are relative: `header` and `imgb` to the manifest's `source_dir`,
+/// `pngs` to the directory holding the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureManifestEntry {
+    /// Header file (`.xgr`, `.txbh`, `.trb`)
+    pub header: String,
+    /// Paired IMGB file
+    pub imgb: String,
+    /// Record name inside a WPD package header, `None` for bare GTEX headers
+    pub record: Option<String>,
+    /// Exported PNGs: one for 2D textures and cubemap crosses, one per volume slice
+    pub pngs: Vec<String>,
+    /// Texture width in pixels
+    pub width: u16,
+    /// Texture height in pixels
+    pub height: u16,
+    /// GTEX format code
+    pub format: u8,
+    /// Display name of the format (e.g., "DXT5")
+    pub format_name: String,
+    /// GTEX image type (0/4 = 2D, 1 = cubemap, 2 = volume)
+    pub img_type: u8,
+}
+
+/// A texture or header that failed during a batch job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureBatchFailure {
+    /// Header file, relative to the source directory
+    pub header: String,
+    /// Record name, if the failure is specific to one record
+    pub record: Option<String>,
+    /// Error message
+    pub error: String,
+}
+
+/// Manifest written by a batch texture export.
+///
+/// Maps every PNG back to the header, record and GTEX format it came from,
+/// so edited PNGs can be imported back with the same layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureManifest {
+    /// Manifest format version
+    pub version: u32,
+    /// Absolute path of the directory that was scanned
+    pub source_dir: String,
+    /// Exported textures, sorted by header path then record order
+    pub entries: Vec<TextureManifestEntry>,
+    /// Headers or records that could not be exported
+    pub failures: Vec<TextureBatchFailure>,
+}
+
+/// Result of a batch texture import.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TextureImportReport {
+    /// PNGs written back into their IMGB
+    pub imported: Vec<String>,
+    /// Entries whose PNGs are missing and were skipped
+    pub skipped: Vec<String>,
+    /// Entries that failed to import
+    pub failures: Vec<TextureBatchFailure>,
+}
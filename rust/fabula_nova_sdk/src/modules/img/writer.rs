@@ -16,15 +16,30 @@
 //! | 25          | DXT3       | 16 bytes   | BC2 compressed       |
 //! | 26          | DXT5       | 16 bytes   | BC3 compressed       |
 //!
+//! Luminance, alpha-only, 16-bit and BC4/BC5 formats are listed in
+//! [`super::format`].
+//!
 //! ## Pitch Calculation
 //!
 //! - **Uncompressed**: `pitch = (width * bits_per_pixel + 7) / 8`
 //! - **Compressed**: `linear_size = max(1, (w+3)/4) * max(1, (h+3)/4) * block_size`
 //!
 //! The compressed formula accounts for 4x4 block alignment.
+//!
+//! ## Cubemaps and Volumes
+//!
+//! | Kind    | Header flags | caps           | caps2                  |
+//! |---------|--------------|----------------|------------------------|
+//! | Cubemap | -            | `+ COMPLEX`    | `0xFE00` (all 6 faces) |
+//! | Volume  | `+ DEPTH`    | `+ COMPLEX`    | `0x200000` (VOLUME)    |
+//!
+//! [`ImgWriter::write_dds_header_dx10`] writes the same information as a
+//! DX10 extension header for tools that expect one.
 
 use std::io::{Write, Seek};
-use super::structs::{GtexHeader, DdsHeader, DdsPixelFormat};
+use super::structs::{GtexHeader, DdsHeader, DdsHeaderDx10, DdsPixelFormat};
+use super::format::GtexPixelFormat;
+use super::layout::GtexImageKind;
 use anyhow::Result;
 
 /// Binary writer for DDS texture files.
@@ -49,12 +64,20 @@ use anyhow::Result;
 /// ```
 pub struct ImgWriter<W: Write + Seek> {
     writer: W,
+    allow_unverified: bool,
 }
 
 impl<W: Write + Seek> ImgWriter<W> {
     /// Creates a new image writer wrapping the given stream.
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self { writer, allow_unverified: false }
+    }
+
+    /// Also maps the GTEX format codes not seen in retail files (see
+    /// [`super::format`]). Without this they are written as unknown formats.
+    pub fn allow_unverified_formats(mut self, allow: bool) -> Self {
+        self.allow_unverified = allow;
+        self
     }
 
     /// Writes a DDS header based on GTEX properties.
@@ -71,6 +94,8 @@ impl<W: Write + Seek> ImgWriter<W> {
     /// | 25   | DXT3      | 0x04   | BC2, 16 bytes per 4x4 block        |
     /// | 26   | DXT5      | 0x04   | BC3, 16 bytes per 4x4 block        |
     ///
+    /// See [`super::format`] for the remaining formats.
+    ///
     /// # Caps Flags
     ///
     /// - Single level: `0x1000` (TEXTURE)
@@ -88,106 +113,7 @@ impl<W: Write + Seek> ImgWriter<W> {
     ///
     /// Based on C# DDSMethods.cs / SharedMethods.cs logic.
     pub fn write_dds_header(&mut self, gtex: &GtexHeader) -> Result<()> {
-        // Initialize DDS header with default values
-        let mut dds = DdsHeader {
-            _magic: (),
-            size: 124,  // Fixed DDS header size (excluding magic)
-            flags: 0,
-            height: gtex.height as u32,
-            width: gtex.width as u32,
-            pitch_or_linear_size: 0,
-            depth: 0,
-            mip_map_count: gtex.mip_count as u32,
-            reserved1: [0; 11],
-            pixel_format: DdsPixelFormat {
-                size: 32,  // Fixed pixel format size
-                flags: 0,
-                four_cc: [0; 4],
-                rgb_bit_count: 0,
-                r_bit_mask: 0,
-                g_bit_mask: 0,
-                b_bit_mask: 0,
-                a_bit_mask: 0,
-            },
-            caps: 0,
-            caps2: 0,
-            caps3: 0,
-            caps4: 0,
-            reserved2: 0,
-        };
-
-        // Set caps based on mipmap presence
-        // TEXTURE (0x1000) for single level
-        // TEXTURE | MIPMAP | COMPLEX (0x401008) for mipmapped
-        dds.caps = if gtex.mip_count > 1 { 0x401008 } else { 0x1000 };
-
-        let width = gtex.width as u32;
-        let height = gtex.height as u32;
-
-        // Configure pixel format based on GTEX format code
-        match gtex.format {
-            // Uncompressed RGBA (formats 3, 4)
-            3 | 4 => {
-                // Pitch = bytes per row = (width * bits + 7) / 8
-                dds.pitch_or_linear_size = (width * 32).div_ceil(8);
-
-                // RGB | ALPHA flags for uncompressed RGBA
-                dds.pixel_format.flags = 0x41;
-                dds.pixel_format.rgb_bit_count = 32;
-
-                // BGRA channel masks (standard for DDS)
-                dds.pixel_format.r_bit_mask = 0x00FF0000;
-                dds.pixel_format.g_bit_mask = 0x0000FF00;
-                dds.pixel_format.b_bit_mask = 0x000000FF;
-                dds.pixel_format.a_bit_mask = 0xFF000000;
-
-                // Header flags: basic or with mipmaps
-                dds.flags = if gtex.mip_count > 1 { 0x2100F } else { 0x100F };
-            },
-
-            // DXT1 / BC1 (format 24) - 4:1 compression, 1-bit alpha
-            24 => {
-                let block_size = 8;  // 8 bytes per 4x4 block
-                // Linear size = number of blocks * block size
-                dds.pitch_or_linear_size = std::cmp::max(1, width.div_ceil(4))
-                    * std::cmp::max(1, height.div_ceil(4))
-                    * block_size;
-
-                dds.pixel_format.flags = 0x04;  // FOURCC flag
-                dds.pixel_format.four_cc = *b"DXT1";
-
-                dds.flags = if gtex.mip_count > 1 { 0xA1007 } else { 0x81007 };
-            },
-
-            // DXT3 / BC2 (format 25) - explicit alpha
-            25 => {
-                let block_size = 16;  // 16 bytes per 4x4 block
-                dds.pitch_or_linear_size = std::cmp::max(1, width.div_ceil(4))
-                    * std::cmp::max(1, height.div_ceil(4))
-                    * block_size;
-
-                dds.pixel_format.flags = 0x04;
-                dds.pixel_format.four_cc = *b"DXT3";
-                dds.flags = if gtex.mip_count > 1 { 0xA1007 } else { 0x81007 };
-            },
-
-            // DXT5 / BC3 (format 26) - interpolated alpha
-            26 => {
-                let block_size = 16;  // 16 bytes per 4x4 block
-                dds.pitch_or_linear_size = std::cmp::max(1, width.div_ceil(4))
-                    * std::cmp::max(1, height.div_ceil(4))
-                    * block_size;
-
-                dds.pixel_format.flags = 0x04;
-                dds.pixel_format.four_cc = *b"DXT5";
-                dds.flags = if gtex.mip_count > 1 { 0xA1007 } else { 0x81007 };
-            },
-
-            // Unknown format - log warning but continue
-            _ => {
-                log::warn!("Unknown GTEX format: {}", gtex.format);
-            }
-        }
+        let dds = build_dds_header(gtex, self.allow_unverified);
 
         // Write header in little-endian format
         use binrw::BinWrite;
@@ -195,4 +121,125 @@ impl<W: Write + Seek> ImgWriter<W> {
 
         Ok(())
     }
+
+    /// Writes a DDS header followed by a DX10 extension header.
+    ///
+    /// The legacy pixel format is replaced by the `"DX10"` FourCC and the
+    /// format is expressed as a DXGI code instead. Cubemaps set the
+    /// TEXTURECUBE misc flag; volumes use the Texture3D dimension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the GTEX format has no DXGI equivalent.
+    pub fn write_dds_header_dx10(&mut self, gtex: &GtexHeader) -> Result<()> {
+        let dxgi_format = dxgi_format(gtex.format, self.allow_unverified)
+            .ok_or_else(|| anyhow::anyhow!("No DXGI format for GTEX format: {}", gtex.format))?;
+
+        let mut dds = build_dds_header(gtex, self.allow_unverified);
+        dds.pixel_format.flags = 0x04;
+        dds.pixel_format.four_cc = *b"DX10";
+        dds.pixel_format.rgb_bit_count = 0;
+        dds.pixel_format.r_bit_mask = 0;
+        dds.pixel_format.g_bit_mask = 0;
+        dds.pixel_format.b_bit_mask = 0;
+        dds.pixel_format.a_bit_mask = 0;
+
+        let kind = gtex.image_kind();
+        let dx10 = DdsHeaderDx10 {
+            dxgi_format,
+            resource_dimension: if kind == GtexImageKind::Volume { 4 } else { 3 },
+            misc_flag: if kind == GtexImageKind::Cubemap { 0x4 } else { 0 },
+            array_size: 1,
+            misc_flags2: 0,
+        };
+
+        use binrw::BinWrite;
+        dds.write_le(&mut self.writer)?;
+        dx10.write_le(&mut self.writer)?;
+
+        Ok(())
+    }
+}
+
+/// Maps a GTEX format code to its DXGI_FORMAT value.
+///
+/// Unverified codes map only if `allow_unverified` is set.
+pub fn dxgi_format(gtex_format: u8, allow_unverified: bool) -> Option<u32> {
+    GtexPixelFormat::from_code_with(gtex_format, allow_unverified).map(GtexPixelFormat::dxgi_format)
+}
+
+/// Builds a legacy DDS header from GTEX properties.
+fn build_dds_header(gtex: &GtexHeader, allow_unverified: bool) -> DdsHeader {
+    // Initialize DDS header with default values
+    let mut dds = DdsHeader {
+        _magic: (),
+        size: 124,  // Fixed DDS header size (excluding magic)
+        flags: 0,
+        height: gtex.height as u32,
+        width: gtex.width as u32,
+        pitch_or_linear_size: 0,
+        depth: 0,
+        mip_map_count: gtex.mip_count as u32,
+        reserved1: [0; 11],
+        pixel_format: DdsPixelFormat {
+            size: 32,  // Fixed pixel format size
+            flags: 0,
+            four_cc: [0; 4],
+            rgb_bit_count: 0,
+            r_bit_mask: 0,
+            g_bit_mask: 0,
+            b_bit_mask: 0,
+            a_bit_mask: 0,
+        },
+        caps: 0,
+        caps2: 0,
+        caps3: 0,
+        caps4: 0,
+        reserved2: 0,
+    };
+
+    // Set caps based on mipmap presence
+    // TEXTURE (0x1000) for single level
+    // TEXTURE | MIPMAP | COMPLEX (0x401008) for mipmapped
+    dds.caps = if gtex.mip_count > 1 { 0x401008 } else { 0x1000 };
+
+    // Configure pixel format based on GTEX format code
+    match GtexPixelFormat::from_code_with(gtex.format, allow_unverified) {
+        Some(format) => {
+            dds.pixel_format = format.dds_pixel_format();
+            dds.pitch_or_linear_size = format.pitch_or_linear_size(gtex.width as u32, gtex.height as u32);
+
+            // Uncompressed: CAPS | HEIGHT | WIDTH | PITCH | PIXELFORMAT
+            // Compressed: CAPS | HEIGHT | WIDTH | PIXELFORMAT | LINEARSIZE
+            // Either way MIPMAPCOUNT (0x20000) when there are mips
+            dds.flags = match (format.block_size().is_some(), gtex.mip_count > 1) {
+                (false, false) => 0x100F,
+                (false, true) => 0x2100F,
+                (true, false) => 0x81007,
+                (true, true) => 0xA1007,
+            };
+        }
+
+        // Unknown format - log warning but continue
+        None => {
+            log::warn!("Unknown GTEX format: {}", gtex.format);
+        }
+    }
+
+    // Cubemaps and volumes are complex surfaces with extra caps2 bits
+    match gtex.image_kind() {
+        GtexImageKind::Cubemap => {
+            dds.caps |= 0x1008;
+            dds.caps2 = 0xFE00;
+        }
+        GtexImageKind::Volume => {
+            dds.flags |= 0x800000;
+            dds.depth = gtex.layer_count();
+            dds.caps |= 0x1008;
+            dds.caps2 = 0x200000;
+        }
+        GtexImageKind::Texture2D => {}
+    }
+
+    dds
 }
@@ -0,0 +1,372 @@
+//! # XGR Texture Sets
+//!
+//! This module treats an XGR/IMGB pair as a browsable set of textures.
+//!
+//! An XGR is a WPD package whose texture records (`txbh`, `vtex`, ...) each
+//! embed a GTEX header. All of them point into one shared IMGB:
+//!
+//! ```text
+//! ui_tex.xgr (WPD)                      ui_tex.imgb
+//! ┌──────────────────────┐              ┌───────────────────────┐
+//! │ icon_a.txbh  [GTEX] ─┼─────────────▶│ icon_a mip0, mip1 ... │
+//! │ icon_b.txbh  [GTEX] ─┼─────────────▶│ icon_b mip0 ...       │
+//! │ layout.xfl   (no GTEX, skipped)     │ ...                   │
+//! └──────────────────────┘              └───────────────────────┘
+//! ```
+//!
+//! ## Functions
+//!
+//! - [`list_xgr_textures`] - Describe every texture and check IMGB ranges
+//! - [`extract_xgr_textures`] - Extract any subset to DDS/PNG in parallel
+//! - [`extract_xgr_texture_to_png_bytes`] - Decode one texture in memory
+//!
+//! Nothing is unpacked to disk to list or preview textures.
+
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use anyhow::Result;
+use image::RgbaImage;
+use rayon::prelude::*;
+use crate::modules::wpd::reader::WpdReader;
+use crate::modules::wpd::structs::WpdRecord;
+use super::api::{decode_base_layers, extract_img_to_dds_bytes, read_gtex_layout_from};
+use super::layout::{cubemap_to_cross, gtex_format_name, GtexImageKind, GtexSurface};
+use super::reader::ImgReader;
+use super::structs::{
+    GtexHeader, GtexMipEntry, ImgbRangeOverlap, ImgbRangeOverlapKind, TextureExportFormat, XgrTexture,
+    XgrTextureSet,
+};
+
+/// Lists every texture in an XGR/IMGB pair.
+///
+/// Records without a GTEX chunk are skipped. Every mipmap table entry is
+/// checked against the IMGB size and against the ranges of all other
+/// textures.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let set = list_xgr_textures("ui_tex.xgr", "ui_tex.imgb")?;
+/// for tex in &set.textures {
+///     println!("{}: {}x{} {}", tex.name, tex.width, tex.height, tex.format_name);
+/// }
+/// ```
+pub fn list_xgr_textures<P: AsRef<Path>>(xgr_path: P, imgb_path: P) -> Result<XgrTextureSet> {
+    let records = read_xgr_records(xgr_path.as_ref())?;
+    let imgb_size = std::fs::metadata(imgb_path.as_ref())?.len();
+
+    let mut textures = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        if let Some((header, ranges)) = read_texture_header(record)? {
+            textures.push(XgrTexture {
+                name: record.name.clone(),
+                extension: record.extension.clone(),
+                record_index: index as u32,
+                width: header.width,
+                height: header.height,
+                depth: header.depth,
+                format: header.format,
+                format_name: gtex_format_name(header.format),
+                mip_count: header.mip_count,
+                img_type: header.img_type,
+                imgb_ranges: ranges,
+            });
+        }
+    }
+
+    let out_of_bounds = textures
+        .iter()
+        .filter(|t| {
+            t.imgb_ranges
+                .iter()
+                .any(|r| r.offset as u64 + r.size as u64 > imgb_size)
+        })
+        .map(|t| t.name.clone())
+        .collect();
+
+    let overlaps = find_range_overlaps(&textures);
+
+    Ok(XgrTextureSet { textures, overlaps, out_of_bounds })
+}
+
+/// Extracts textures from an XGR/IMGB pair in parallel.
+///
+/// # Arguments
+///
+/// * `xgr_path` - Path to the XGR package
+/// * `imgb_path` - Path to the paired IMGB
+/// * `output_dir` - Directory for the extracted files (created if needed)
+/// * `names` - Record names to extract; empty extracts every texture
+/// * `format` - DDS (all mips) or PNG (mip 0)
+///
+/// # Returns
+///
+/// Paths of all written files, in record order.
+///
+/// # Errors
+///
+/// Returns an error if a requested name isn't a texture in the XGR, or
+/// if any extraction fails.
+pub fn extract_xgr_textures<P: AsRef<Path>>(
+    xgr_path: P,
+    imgb_path: P,
+    output_dir: P,
+    names: &[String],
+    format: TextureExportFormat,
+) -> Result<Vec<String>> {
+    let records = read_xgr_records(xgr_path.as_ref())?;
+    let imgb_path = imgb_path.as_ref();
+    let imgb_size = std::fs::metadata(imgb_path)?.len();
+    let output_dir = output_dir.as_ref();
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut selected = Vec::new();
+    for record in &records {
+        if !names.is_empty() && !names.contains(&record.name) {
+            continue;
+        }
+        if find_gtex(&record.data)?.is_some() {
+            selected.push(record);
+        }
+    }
+    for name in names {
+        if !selected.iter().any(|r| &r.name == name) {
+            anyhow::bail!("Texture not found in XGR: {}", name);
+        }
+    }
+
+    let written: Vec<Vec<String>> = selected
+        .par_iter()
+        .map(|record| extract_record(record, imgb_path, imgb_size, output_dir, format))
+        .collect::<Result<_>>()?;
+
+    Ok(written.into_iter().flatten().collect())
+}
+
+/// Decodes one texture of an XGR/IMGB pair to PNG bytes in memory.
+///
+/// Cubemaps are returned as a horizontal cross, volumes as slice 0.
+///
+/// # Returns
+///
+/// A tuple of ((width, height), png_bytes).
+pub fn extract_xgr_texture_to_png_bytes<P: AsRef<Path>>(
+    xgr_path: P,
+    imgb_path: P,
+    name: &str,
+) -> Result<((u32, u32), Vec<u8>)> {
+    let records = read_xgr_records(xgr_path.as_ref())?;
+    let record = records
+        .iter()
+        .find(|r| r.name == name && find_gtex_quiet(&r.data))
+        .ok_or_else(|| anyhow::anyhow!("Texture not found in XGR: {}", name))?;
+
+    let (_, image) = gtex_preview_images(&record.data, imgb_path.as_ref())?
+        .1
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Texture {} has no surfaces", name))?;
+
+    let mut png_data = Vec::new();
+    let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
+    image.write_with_encoder(encoder)?;
+    Ok((image.dimensions(), png_data))
+}
+
+/// Reads all records of an XGR (WPD) package.
+pub(crate) fn read_xgr_records(xgr_path: &Path) -> Result<Vec<WpdRecord>> {
+    let mut reader = WpdReader::new(BufReader::new(File::open(xgr_path)?));
+    let header = reader.read_header()?;
+    reader.read_records(&header)
+}
+
+/// Scans record data for a GTEX header.
+fn find_gtex(data: &[u8]) -> Result<Option<(GtexHeader, u64)>> {
+    ImgReader::new(Cursor::new(data)).read_gtex()
+}
+
+pub(crate) fn find_gtex_quiet(data: &[u8]) -> bool {
+    matches!(find_gtex(data), Ok(Some(_)))
+}
+
+/// Reads the GTEX header and raw mip table of a record, if it has one.
+fn read_texture_header(record: &WpdRecord) -> Result<Option<(GtexHeader, Vec<GtexMipEntry>)>> {
+    let mut reader = ImgReader::new(Cursor::new(record.data.as_slice()));
+    let Some((header, gtex_pos)) = reader.read_gtex()? else {
+        return Ok(None);
+    };
+    let ranges = reader.read_mip_table(gtex_pos, header.mip_entry_count())?;
+    Ok(Some((header, ranges)))
+}
+
+/// Finds pairs of ranges from different textures that share IMGB bytes.
+fn find_range_overlaps(textures: &[XgrTexture]) -> Vec<ImgbRangeOverlap> {
+    let mut ranges: Vec<(usize, GtexMipEntry)> = textures
+        .iter()
+        .enumerate()
+        .flat_map(|(i, t)| t.imgb_ranges.iter().filter(|r| r.size > 0).map(move |r| (i, *r)))
+        .collect();
+    ranges.sort_by_key(|(_, r)| (r.offset, r.size));
+
+    let mut overlaps = Vec::new();
+    for (a_pos, &(a_tex, a)) in ranges.iter().enumerate() {
+        let a_end = a.offset as u64 + a.size as u64;
+        for &(b_tex, b) in &ranges[a_pos + 1..] {
+            if b.offset as u64 >= a_end {
+                break;
+            }
+            if a_tex == b_tex {
+                continue;
+            }
+            let kind = if a == b { ImgbRangeOverlapKind::Shared } else { ImgbRangeOverlapKind::Overlapping };
+            overlaps.push(ImgbRangeOverlap {
+                first: textures[a_tex].name.clone(),
+                first_range: a,
+                second: textures[b_tex].name.clone(),
+                second_range: b,
+                kind,
+            });
+        }
+    }
+    overlaps
+}
+
+/// Extracts one texture record to DDS or PNG file(s).
+fn extract_record(
+    record: &WpdRecord,
+    imgb_path: &Path,
+    imgb_size: u64,
+    output_dir: &Path,
+    format: TextureExportFormat,
+) -> Result<Vec<String>> {
+    // DDS extraction copies whatever bytes exist, so check ranges up front
+    if let Some((_, ranges)) = read_texture_header(record)? {
+        if ranges.iter().any(|r| r.offset as u64 + r.size as u64 > imgb_size) {
+            anyhow::bail!("Texture {} reads past the end of the IMGB", record.name);
+        }
+    }
+
+    match format {
+        TextureExportFormat::Dds => {
+            let (_, dds_bytes) = extract_img_to_dds_bytes(&record.data, imgb_path)?;
+            let out_path = output_dir.join(format!("{}.dds", record.name));
+            std::fs::write(&out_path, dds_bytes)?;
+            Ok(vec![out_path.to_string_lossy().to_string()])
+        }
+        TextureExportFormat::Png => {
+            let mut written = Vec::new();
+            for (suffix, image) in gtex_preview_images(&record.data, imgb_path)?.1 {
+                let out_path = output_dir.join(format!("{}{}.png", record.name, suffix));
+                image.save(&out_path)?;
+                written.push(out_path.to_string_lossy().to_string());
+            }
+            Ok(written)
+        }
+    }
+}
+
+/// Decodes mip 0 of a GTEX header's texture into named preview images.
+///
+/// 2D textures give one image, cubemaps one cross, volumes one image per
+/// slice (suffixed `_sliceN`).
+pub(crate) fn gtex_preview_images(
+    header_data: &[u8],
+    imgb_path: &Path,
+) -> Result<(GtexHeader, Vec<(String, RgbaImage)>)> {
+    let (header, surfaces): (GtexHeader, Vec<GtexSurface>) = read_gtex_layout_from(Cursor::new(header_data))?;
+    let layers = decode_base_layers(&header, &surfaces, imgb_path, false)?;
+
+    let images = match header.image_kind() {
+        GtexImageKind::Texture2D => layers.into_iter().map(|l| (String::new(), l)).collect(),
+        GtexImageKind::Cubemap => vec![(String::new(), cubemap_to_cross(&layers)?)],
+        GtexImageKind::Volume => layers
+            .into_iter()
+            .enumerate()
+            .map(|(i, l)| (format!("_slice{}", i), l))
+            .collect(),
+    };
+    Ok((header, images))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::img::encoder::encode_gtex_surface;
+    use crate::modules::wpd::writer::WpdWriter;
+    use std::path::PathBuf;
+
+    /// GTEX record with one mip whose table points at `offset`/`size`.
+    fn texture_record(name: &str, format: u8, width: u16, offset: u32, size: u32) -> WpdRecord {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"GTEX");
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&[format, 1, 0, 0]);
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&24u32.to_be_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&offset.to_be_bytes());
+        data.extend_from_slice(&size.to_be_bytes());
+        WpdRecord { name: name.to_string(), extension: "txbh".to_string(), data }
+    }
+
+    #[test]
+    fn test_list_and_extract_texture_set() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target/test_img_xgr");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let red: Vec<u8> = (0..16).flat_map(|_| [255, 0, 0, 255]).collect();
+        let mut imgb = encode_gtex_surface(3, &red, 4, 4, false).unwrap();
+        imgb.extend(encode_gtex_surface(24, &red, 4, 4, false).unwrap());
+
+        let records = vec![
+            texture_record("tex_a", 3, 4, 0, 64),
+            WpdRecord { name: "layout".to_string(), extension: "xfl".to_string(), data: vec![1, 2, 3] },
+            texture_record("tex_b", 24, 4, 64, 8),
+            texture_record("tex_c", 24, 4, 64, 8),
+            texture_record("tex_d", 3, 4, 32, 64),
+        ];
+        let xgr_path = dir.join("ui.xgr");
+        let imgb_path = dir.join("ui.imgb");
+        WpdWriter::new(std::fs::File::create(&xgr_path).unwrap()).write(&records).unwrap();
+        std::fs::write(&imgb_path, &imgb).unwrap();
+
+        let set = list_xgr_textures(&xgr_path, &imgb_path).unwrap();
+        let names: Vec<&str> = set.textures.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["tex_a", "tex_b", "tex_c", "tex_d"]);
+        assert_eq!(set.textures[1].format_name, "DXT1");
+        assert_eq!(set.textures[1].record_index, 2);
+        assert_eq!(set.out_of_bounds, vec!["tex_d".to_string()]);
+
+        let shared: Vec<_> = set.overlaps.iter().filter(|o| o.kind == ImgbRangeOverlapKind::Shared).collect();
+        assert_eq!(shared.len(), 1);
+        assert_eq!((shared[0].first.as_str(), shared[0].second.as_str()), ("tex_b", "tex_c"));
+        assert!(set.overlaps.iter().any(|o| o.kind == ImgbRangeOverlapKind::Overlapping
+            && o.first == "tex_a" && o.second == "tex_d"));
+
+        let out_dir = dir.join("out");
+        let written = extract_xgr_textures(
+            &xgr_path, &imgb_path, &out_dir,
+            &["tex_a".to_string(), "tex_b".to_string()],
+            TextureExportFormat::Png,
+        ).unwrap();
+        assert_eq!(written.len(), 2);
+        let png = image::open(&written[1]).unwrap().to_rgba8();
+        assert_eq!(png.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+        let dds = extract_xgr_textures(&xgr_path, &imgb_path, &out_dir, &[], TextureExportFormat::Dds);
+        assert!(dds.is_err(), "tex_d reads past the IMGB end");
+
+        assert!(extract_xgr_textures(
+            &xgr_path, &imgb_path, &out_dir, &["layout".to_string()], TextureExportFormat::Dds
+        ).is_err());
+
+        let ((w, h), bytes) = extract_xgr_texture_to_png_bytes(&xgr_path, &imgb_path, "tex_a").unwrap();
+        assert_eq!((w, h), (4, 4));
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+}
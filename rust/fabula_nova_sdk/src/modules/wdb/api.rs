@@ -6,7 +6,10 @@
 //! ## Common Operations
 //!
 //! - [`parse_wdb`] - Load WDB file into [`WdbData`] structure
+//! - [`parse_wdb_bytes`] - Load WDB data already in memory
 //! - [`pack_wdb`] - Save [`WdbData`] to WDB binary file
+//! - [`pack_wdb_with_mode`] - Save, choosing how validation issues are handled
+//! - [`pack_wdb_bytes`] - Encode [`WdbData`] in memory, such as for `wdbpack.bin`
 //! - [`extract_wdb_to_json`] - Export WDB to JSON file
 //! - [`wdb_to_json_string`] - Convert WdbData to JSON string
 //! - [`wdb_from_json_string`] - Parse JSON string to WdbData
@@ -39,11 +42,13 @@
 use anyhow::Result;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, SeekFrom};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
 
-use super::reader::{derive_string, WdbReader, WdbVariables};
-use super::structs::{GameCode, WdbData, WdbValue};
+use super::reader::{decode_str_arrays, derive_string, WdbReader, WdbVariables};
+use super::sections::{encode_hex, SECTION_ORDER_KEY, STRING_POOL_KEY, STR_ARRAY_DATA_KEY, STR_ARRAY_LIST_KEY};
+use super::structs::{GameCode, WdbData, WdbHeader, WdbValue, WdbWriteMode};
+use super::symbolic::{symbolize_wdb_values, SCHEMA_SHEET_KEY};
 
 /// Parses a WDB file and returns structured data.
 ///
@@ -58,7 +63,7 @@ use super::structs::{GameCode, WdbData, WdbValue};
 /// # Returns
 /// A [`WdbData`] structure containing:
 /// - `header`: Metadata about the file (version, field names, etc.)
-/// - `records`: Vector of records, each a HashMap of field→value
+/// - `records`: Vector of records, each an ordered map of field→value
 ///
 /// # Errors
 /// Returns an error if:
@@ -68,7 +73,24 @@ use super::structs::{GameCode, WdbData, WdbValue};
 pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<WdbData> {
     let wdb_name = wdb_path.as_ref().file_stem().unwrap_or_default().to_string_lossy().into_owned();
     let file = BufReader::new(File::open(&wdb_path)?);
-    let mut reader = WdbReader::new(file);
+    parse_wdb_from(file, wdb_name, game_code)
+}
+
+/// Parses a WDB file held in memory, such as a record of `wdbpack.bin`.
+///
+/// # Arguments
+/// * `bytes` - The WDB file contents
+/// * `wdb_name` - File stem, used to find the sheet's schema (e.g., `item`)
+/// * `game_code` - Which FF13 game (affects parsing logic)
+///
+/// # Errors
+/// Returns an error if the data is not a valid WDB file.
+pub fn parse_wdb_bytes(bytes: &[u8], wdb_name: &str, game_code: GameCode) -> Result<WdbData> {
+    parse_wdb_from(Cursor::new(bytes), wdb_name.to_string(), game_code)
+}
+
+fn parse_wdb_from<R: Read + Seek>(source: R, wdb_name: String, game_code: GameCode) -> Result<WdbData> {
+    let mut reader = WdbReader::new(source);
     
     // 1. Read Headers
     let (file_header, sections) = reader.read_headers()?;
@@ -87,9 +109,10 @@ pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<Wdb
     let mut offsets_per_value = 0u8;
     let mut bits_per_offset = 0u8;
     let mut has_str_array = false;
+    let mut unknown_sections = Vec::new();
     
     let mut record_count = file_header.record_count;
-    let mut header_map = HashMap::new();
+    let mut header_map = WdbHeader::new();
     
     // Find sections
     for section in &sections {
@@ -134,7 +157,10 @@ pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<Wdb
             "!structitemnum" => {
                 structitemnum_data = reader.read_section_data(section)?;
             },
-            _ => {}
+            _ => {
+                log::debug!("Keeping unknown section {} as raw bytes", section.name);
+                unknown_sections.push((section.name.clone(), reader.read_section_data(section)?));
+            }
         }
     }
 
@@ -207,52 +233,54 @@ pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<Wdb
     }
 
     // Process StrArray
-    let mut str_array_dict = HashMap::new();
-    if has_str_array && !str_array_data.is_empty() && !str_array_list_data.is_empty() {
-        let str_array_offsets = WdbReader::<BufReader<File>>::parse_uint_list(&str_array_list_data);
-        let s_fields: Vec<String> = fields.iter().filter(|f| f.starts_with('s') && super::bit_helpers::derive_field_number(f) != 0).cloned().collect();
-        
-        if str_array_offsets.len() == s_fields.len() {
-            for (i, &offset) in str_array_offsets.iter().enumerate() {
-                let mut current_strings = Vec::new();
-                let mut current_pos = offset as usize;
-                
-                let end_pos = if i + 1 < str_array_offsets.len() {
-                    str_array_offsets[i+1] as usize
-                } else {
-                    str_array_data.len()
-                };
-                
-                while current_pos < end_pos {
-                    if current_pos + 4 > str_array_data.len() { break; }
-                    let val = u32::from_be_bytes(str_array_data[current_pos..current_pos+4].try_into().unwrap());
-                    let mut bit_reader = super::bit_helpers::BitReader::new(val);
-                    
-                    for _ in 0..offsets_per_value {
-                        if let Some(string_offset) = bit_reader.read_bits(bits_per_offset as usize) {
-                            let s = derive_string(&strings_data, string_offset as usize);
-                            current_strings.push(s);
-                        }
-                    }
-                    current_pos += 4;
-                }
-                str_array_dict.insert(s_fields[i].clone(), current_strings);
-            }
-        }
+    let str_array_dict = if has_str_array {
+        decode_str_arrays(&str_array_data, &str_array_list_data, &strings_data, &fields, offsets_per_value, bits_per_offset)
+    } else {
+        HashMap::new()
+    };
+
+    // Keep what records can't describe, so unmodified files repack byte for byte
+    header_map.insert(
+        SECTION_ORDER_KEY.to_string(),
+        WdbValue::StringArray(sections.iter().map(|s| s.name.clone()).collect()),
+    );
+    if !strings_data.is_empty() {
+        header_map.insert(STRING_POOL_KEY.to_string(), WdbValue::String(encode_hex(&strings_data)));
+    }
+    if has_str_array {
+        header_map.insert(STR_ARRAY_DATA_KEY.to_string(), WdbValue::String(encode_hex(&str_array_data)));
+        header_map.insert(STR_ARRAY_LIST_KEY.to_string(), WdbValue::String(encode_hex(&str_array_list_data)));
+    }
+    for (name, data) in unknown_sections {
+        header_map.insert(name, WdbValue::String(encode_hex(&data)));
     }
 
-    // FF XIII Logic: Dictionary Lookup
-    if fields.is_empty() && (game_code == GameCode::FF13_1) {
-        if let Some(sheet_name) = super::dicts::RECORD_IDS.get(wdb_name.as_str()) {
-            if let Some(dict_fields) = super::dicts::FIELD_NAMES.get(sheet_name) {
-                fields = dict_fields.iter().map(|s| s.to_string()).collect();
+    // Schema Lookup: files without !structitem take their fields from the
+    // sheet that lists them (built-in FF XIII dictionaries or user overrides)
+    let enum_fields = super::schema::with_active_schema(game_code, |schema| {
+        if fields.is_empty() {
+            if let Some(sheet) = schema.sheet_for_file(&wdb_name).filter(|s| !s.fields.is_empty()) {
+                fields = sheet.fields.iter().map(|f| f.name.clone()).collect();
                 is_known = true;
-                header_map.insert("sheetName".to_string(), WdbValue::String(sheet_name.to_string()));
+                header_map.insert("sheetName".to_string(), WdbValue::String(sheet.name.clone()));
             }
         }
-        if !is_known {
-            is_known = true;
+
+        // Remember the sheet so JSON import and the writer resolve the same enums
+        let sheet_name = match header_map.get("sheetName") {
+            Some(WdbValue::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        match schema.sheet_for_wdb(&wdb_name, sheet_name.as_deref()) {
+            Some(sheet) => {
+                header_map.insert(SCHEMA_SHEET_KEY.to_string(), WdbValue::String(sheet.name.clone()));
+                schema.enum_bindings(sheet)
+            }
+            None => HashMap::new(),
         }
+    });
+    if fields.is_empty() && game_code == GameCode::FF13_1 {
+        is_known = true;
     }
     
     header_map.insert("recordCount".to_string(), WdbValue::UInt(record_count));
@@ -319,6 +347,7 @@ pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<Wdb
         offsets_per_value,
         bits_per_offset,
         without_fields,
+        enum_fields,
     };
     
     reader.seek(SeekFrom::Start(record_start_pos))?;
@@ -329,10 +358,12 @@ pub fn parse_wdb<P: AsRef<Path>>(wdb_path: P, game_code: GameCode) -> Result<Wdb
         log::debug!("First record keys: {:?}", records[0].keys());
     }
     
-    Ok(WdbData {
+    let mut data = WdbData {
         header: header_map,
         records,
-    })
+    };
+    canonicalize_wdb(&mut data);
+    Ok(data)
 }
 
 pub fn extract_wdb_to_json<P: AsRef<Path>>(wdb_path: P, json_path: P, game_code: GameCode) -> Result<()> {
@@ -343,21 +374,140 @@ pub fn extract_wdb_to_json<P: AsRef<Path>>(wdb_path: P, json_path: P, game_code:
     Ok(())
 }
 
+/// Serializes parsed data to pretty-printed JSON.
+///
+/// Keys keep the map order, so canonical data (see [`canonicalize_wdb`])
+/// gives byte-identical output on every run and platform.
 pub fn wdb_to_json_string(data: &WdbData) -> Result<String> {
     Ok(serde_json::to_string_pretty(data)?)
 }
 
+/// Header entries in canonical order; others follow in their current order.
+const HEADER_ORDER: [&str; 15] = [
+    "gameCode",
+    "sheetName",
+    SCHEMA_SHEET_KEY,
+    "isKnown",
+    "recordCount",
+    "!!version",
+    "!!strtypelist",
+    "!!typelist",
+    "offsetsPerValue",
+    "bits_per_offset",
+    "!structitem",
+    SECTION_ORDER_KEY,
+    STRING_POOL_KEY,
+    STR_ARRAY_DATA_KEY,
+    STR_ARRAY_LIST_KEY,
+];
+
+/// Puts the header and every record into canonical key order.
+///
+/// Header entries follow a fixed order. Records list `record` first, then
+/// the `!structitem` fields in order, then any other keys as they were.
+/// Both sorts are stable, so canonicalizing twice changes nothing.
+pub fn canonicalize_wdb(data: &mut WdbData) {
+    let rank = |key: &str| HEADER_ORDER.iter().position(|k| *k == key).unwrap_or(HEADER_ORDER.len());
+    data.header.sort_by(|a, _, b, _| rank(a).cmp(&rank(b)));
+
+    let mut position: HashMap<&str, usize> = HashMap::new();
+    position.insert("record", 0);
+    if let Some(WdbValue::StringArray(fields)) = data.header.get("!structitem") {
+        for (i, field) in fields.iter().enumerate() {
+            position.entry(field.as_str()).or_insert(i + 1);
+        }
+    }
+    for record in &mut data.records {
+        record.sort_by(|a, _, b, _| {
+            let pos = |k: &str| position.get(k).copied().unwrap_or(usize::MAX);
+            pos(a).cmp(&pos(b))
+        });
+    }
+}
+
+/// Parses a JSON string produced by [`wdb_to_json_string`].
+///
+/// Enum and flag fields accept either names or numbers and are normalised
+/// to their symbolic form (see [`super::symbolic`]).
+///
+/// # Errors
+///
+/// Returns an error if the JSON is malformed or an enum field holds a name
+/// its enum doesn't define.
 pub fn wdb_from_json_string(json: &str) -> Result<WdbData> {
-    Ok(serde_json::from_str(json)?)
+    let mut data: WdbData = serde_json::from_str(json)?;
+    let game_code = game_code_from_header(&data.header);
+    symbolize_wdb_values(&mut data, game_code)?;
+    canonicalize_wdb(&mut data);
+    Ok(data)
+}
+
+/// Reads the game a parsed file came from out of its `gameCode` header entry.
+///
+/// Defaults to [`GameCode::FF13_1`] when the entry is missing.
+pub fn game_code_from_header(header: &WdbHeader) -> GameCode {
+    match header.get("gameCode") {
+        Some(WdbValue::String(s)) => match s.as_str() {
+            "FF13_2" => GameCode::FF13_2,
+            "LR" | "FF13_3" => GameCode::FF13_3,
+            _ => GameCode::FF13_1,
+        },
+        _ => GameCode::FF13_1,
+    }
 }
 
 use super::writer::WdbWriter;
 
+/// Writes [`WdbData`] to a WDB binary file, refusing data that fails validation.
+///
+/// Equivalent to [`pack_wdb_with_mode`] with [`WdbWriteMode::Strict`].
+///
+/// # Errors
+///
+/// Returns a [`WdbValidationError`](super::validate::WdbValidationError)
+/// listing every issue if [`validate_wdb`](super::validate::validate_wdb)
+/// finds any, or an error if the file can't be written.
 pub fn pack_wdb<P: AsRef<Path>>(
     data: &WdbData,
     output_path: P,
     game_code: GameCode
 ) -> Result<()> {
+    pack_wdb_with_mode(data, output_path, game_code, WdbWriteMode::Strict)
+}
+
+/// Writes [`WdbData`] to a WDB binary file after a validation pass.
+///
+/// # Arguments
+///
+/// * `data` - The data to write
+/// * `output_path` - Destination file
+/// * `game_code` - Target game
+/// * `mode` - [`WdbWriteMode::Strict`] fails on any issue;
+///   [`WdbWriteMode::Lenient`] logs them and writes what the writer can
+///
+/// # Errors
+///
+/// In strict mode, returns a
+/// [`WdbValidationError`](super::validate::WdbValidationError) before the
+/// output file is created. Downcast the error to get the issue list.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// if let Err(e) = pack_wdb_with_mode(&data, "item.wdb", GameCode::FF13_1, WdbWriteMode::Strict) {
+///     if let Some(v) = e.downcast_ref::<WdbValidationError>() {
+///         for issue in &v.issues { println!("{}", describe_issue(issue)); }
+///     }
+/// }
+/// ```
+pub fn pack_wdb_with_mode<P: AsRef<Path>>(
+    data: &WdbData,
+    output_path: P,
+    game_code: GameCode,
+    mode: WdbWriteMode,
+) -> Result<()> {
+    check_before_write(data, game_code, mode)?;
+
     let file = File::create(output_path)?;
     let mut writer = WdbWriter::new(BufWriter::new(file));
 
@@ -367,4 +517,36 @@ pub fn pack_wdb<P: AsRef<Path>>(
     // This prevents race conditions when subsequent operations read the file
     writer.into_inner().into_inner()?.sync_all()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Encodes [`WdbData`] as WDB file contents, refusing data that fails validation.
+///
+/// The in-memory counterpart of [`pack_wdb`], for WDBs stored inside
+/// another container.
+///
+/// # Errors
+///
+/// Returns a [`WdbValidationError`](super::validate::WdbValidationError)
+/// if validation finds any issue, or an error if encoding fails.
+pub fn pack_wdb_bytes(data: &WdbData, game_code: GameCode) -> Result<Vec<u8>> {
+    check_before_write(data, game_code, WdbWriteMode::Strict)?;
+    let mut writer = WdbWriter::new(Cursor::new(Vec::new()));
+    writer.write_file(data, game_code)?;
+    Ok(writer.into_inner().into_inner())
+}
+
+/// Runs the validation pass of a write and applies `mode` to its issues.
+fn check_before_write(data: &WdbData, game_code: GameCode, mode: WdbWriteMode) -> Result<()> {
+    let issues = super::validate::validate_wdb(data, game_code);
+    if !issues.is_empty() {
+        match mode {
+            WdbWriteMode::Strict => return Err(super::validate::WdbValidationError { issues }.into()),
+            WdbWriteMode::Lenient => {
+                for issue in &issues {
+                    log::warn!("{}", super::validate::describe_issue(issue));
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -161,6 +161,8 @@ pub fn sign_extend(val: u32, bits: usize) -> i32 {
 /// # Returns
 ///
 /// The original value if valid, or 0 if it exceeds the bit width.
+/// [`validate_wdb`](super::validate::validate_wdb) reports these values
+/// before packing.
 pub fn validate_uint(field_name: &str, field_bits: usize, value: u32) -> u32 {
     if field_bits == 0 || field_bits >= 32 {
         return value;
@@ -190,6 +192,8 @@ pub fn validate_uint(field_name: &str, field_bits: usize, value: u32) -> u32 {
 /// # Returns
 ///
 /// The original value if valid, or 0 if it exceeds the bit width range.
+/// [`validate_wdb`](super::validate::validate_wdb) reports these values
+/// before packing.
 pub fn validate_int(field_name: &str, field_bits: usize, value: i32) -> i32 {
     if field_bits == 0 || field_bits >= 32 {
         return value;
@@ -0,0 +1,508 @@
+//! # WDB Content Builder
+//!
+//! Adding an item, weapon or ability touches several files: a new record in
+//! the sheet, its name and help text in every language's `txtres` ZTR, and
+//! often a shop or treasure entry pointing at it. [`WdbContentBuilder`]
+//! turns high-level [`WdbContentDef`]s into one [`WdbChangeSet`] covering
+//! all of them.
+//!
+//! ## Building
+//!
+//! ```text
+//!   WdbContentDef                         WdbChangeSet
+//!   ┌───────────────────────────┐         ┌────────────────────────────────┐
+//!   │ sheet     item            │         │ ids      it_potion_2           │
+//!   │ template  it_potion       │ ──────► │ patches  item: clone + set     │
+//!   │ fields    uPrice=500      │         │          shop: set sItemLabel4 │
+//!   │ texts     sItemNameStr... │         │ texts    us/jp: $it_potion_2   │
+//!   │ links     shop.sItemLabel*│         └────────────────────────────────┘
+//!   └───────────────────────────┘
+//! ```
+//!
+//! | Step      | Rule                                                       |
+//! |-----------|------------------------------------------------------------|
+//! | Id        | The given id, or the template's id with its trailing number counted up (`wea_010` → `wea_011`, `it_potion` → `it_potion_2`) until no loaded sheet uses it |
+//! | Text keys | The template's key with its id swapped for the new one (`$it_potion_h` → `$it_potion_2_h`), or `$<id>_<field>`; `_2`, `_3`, ... added while taken |
+//! | Links     | String fields are set to the new id, string arrays get it appended, `field*` fills the first empty slot |
+//!
+//! Sheet edits are plain [`WdbPatch`]es (`clone` and `set`), so a change
+//! set can be saved, reviewed and previewed like any other patch.
+//! [`WdbContentBuilder::preview`] reports the record changes without
+//! touching anything; [`WdbContentBuilder::commit`] applies them and writes
+//! the changed WDB and ZTR files.
+//!
+//! ```rust,ignore
+//! let mut builder = WdbContentBuilder::new(GameCode::FF13_1);
+//! builder.graph_mut().load_directory("extracted/db/resident")?;
+//! builder.add_language("us", "extracted/txtres/resident/system/txtres_us.ztr")?;
+//! let changes = builder.build(&[def])?;
+//! for sheet in builder.preview(&changes)? { ... }
+//! builder.commit(&changes, "mod/out")?;
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::api::pack_wdb;
+use super::patch::{apply_wdb_patch, apply_wdb_patch_in_place};
+use super::refs::{record_id, WdbReferenceGraph};
+use super::structs::{
+    GameCode, WdbChangeSet, WdbContentDef, WdbContentLink, WdbData, WdbPatch, WdbPatchOp, WdbRecordSelector,
+    WdbSheetChanges, WdbTextAddition, WdbValue,
+};
+use super::writer::MAX_NAME_LEN;
+use crate::modules::ztr::api::{pack_ztr_from_struct_with_encoding, parse_ztr_with_encoding};
+use crate::modules::ztr::charsets::DEFAULT_ZTR_ENCODING;
+use crate::modules::ztr::structs::{ZtrData, ZtrEntry};
+
+/// Language key of a text that applies to every loaded language.
+pub const ALL_LANGUAGES: &str = "*";
+
+/// Builds change sets that add records across WDB sheets and ZTR files.
+pub struct WdbContentBuilder {
+    graph: WdbReferenceGraph,
+    /// Language -> (file name, entries)
+    languages: IndexMap<String, (String, ZtrData)>,
+    /// Language -> character table its file was read with
+    encodings: HashMap<String, String>,
+}
+
+impl WdbContentBuilder {
+    pub fn new(game_code: GameCode) -> Self {
+        Self { graph: WdbReferenceGraph::new(game_code), languages: IndexMap::new(), encodings: HashMap::new() }
+    }
+
+    /// The indexed sheets and text.
+    pub fn graph(&self) -> &WdbReferenceGraph {
+        &self.graph
+    }
+
+    /// Loads sheets (and other text) into the builder's index.
+    pub fn graph_mut(&mut self) -> &mut WdbReferenceGraph {
+        &mut self.graph
+    }
+
+    /// Loads one language's text file, which new text entries are added to.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Name used in [`WdbContentText::text`](super::structs::WdbContentText), e.g. `us`
+    /// * `ztr_path` - The language's `txtres` file; [`Self::commit`] writes it under the same name and encoding
+    pub fn add_language<P: AsRef<Path>>(&mut self, language: &str, ztr_path: P) -> Result<()> {
+        let ztr_path = ztr_path.as_ref();
+        let (data, encoding) = parse_ztr_with_encoding(ztr_path, self.graph.game_code(), None)?;
+        let file_name = ztr_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        self.add_language_texts(language, &file_name, data);
+        self.encodings.insert(language.to_string(), encoding);
+        Ok(())
+    }
+
+    /// Adds one language's text already in memory, written back as Shift-JIS.
+    pub fn add_language_texts(&mut self, language: &str, file_name: &str, data: ZtrData) {
+        self.graph.add_texts(&data);
+        self.languages.insert(language.to_string(), (file_name.to_string(), data));
+        self.encodings.remove(language);
+    }
+
+    /// Turns definitions into a change set, without modifying anything.
+    ///
+    /// Definitions are built in order, so later ones see the ids, text keys
+    /// and link slots taken by earlier ones.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first definition whose template, fields, languages or
+    /// links don't exist, or whose given id or key is already used.
+    pub fn build(&self, defs: &[WdbContentDef]) -> Result<WdbChangeSet> {
+        let mut state = BuildState {
+            sheets: IndexMap::new(),
+            operations: IndexMap::new(),
+            ids: self.graph.sheets().values().flat_map(|d| d.records.iter().filter_map(record_id)).map(str::to_string).collect(),
+            keys: self.graph.texts().keys().cloned().collect(),
+        };
+        for (_, data) in self.languages.values() {
+            state.keys.extend(data.entries.iter().map(|e| e.id.clone()));
+        }
+
+        let mut changes = WdbChangeSet::default();
+        for (i, def) in defs.iter().enumerate() {
+            self.build_def(def, &mut state, &mut changes)
+                .with_context(|| format!("Definition {} ({} from {})", i + 1, def.sheet, def.template))?;
+        }
+        changes.patches = state
+            .operations
+            .into_iter()
+            .map(|(sheet, operations)| (sheet, WdbPatch { name: None, sheet: None, operations }))
+            .collect();
+        Ok(changes)
+    }
+
+    /// The record changes a change set makes, sheet by sheet.
+    pub fn preview(&self, changes: &WdbChangeSet) -> Result<Vec<WdbSheetChanges>> {
+        changes
+            .patches
+            .iter()
+            .map(|(sheet, patch)| {
+                let result = apply_wdb_patch(self.sheet(sheet)?, patch).with_context(|| format!("Sheet {}", sheet))?;
+                Ok(WdbSheetChanges { sheet: sheet.clone(), changes: result.changes })
+            })
+            .collect()
+    }
+
+    /// Applies a change set to the builder's sheets and text in memory.
+    ///
+    /// Nothing changes if any patch or text entry fails.
+    pub fn apply(&mut self, changes: &WdbChangeSet) -> Result<()> {
+        let mut patched = Vec::with_capacity(changes.patches.len());
+        for (sheet, patch) in &changes.patches {
+            let mut data = self.sheet(sheet)?.clone();
+            apply_wdb_patch_in_place(&mut data, patch).with_context(|| format!("Sheet {}", sheet))?;
+            patched.push((sheet, data));
+        }
+        for text in &changes.texts {
+            let (_, data) = self.language(&text.language)?;
+            if data.entries.iter().any(|e| e.id == text.key) {
+                bail!("Text key '{}' already exists in language '{}'", text.key, text.language);
+            }
+        }
+
+        for (sheet, data) in patched {
+            self.graph.add_sheet(sheet, data);
+        }
+        for text in &changes.texts {
+            let entry = ZtrEntry { id: text.key.clone(), text: text.text.clone() };
+            self.graph.add_texts(&ZtrData { entries: vec![entry.clone()], mappings: Vec::new() });
+            self.languages[&text.language].1.entries.push(entry);
+        }
+        Ok(())
+    }
+
+    /// Applies a change set and writes every sheet and text file it touches.
+    ///
+    /// # Returns
+    ///
+    /// The files written: `<sheet>.wdb` and the language files' names,
+    /// under `out_dir`.
+    pub fn commit<P: AsRef<Path>>(&mut self, changes: &WdbChangeSet, out_dir: P) -> Result<Vec<PathBuf>> {
+        self.apply(changes)?;
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir)?;
+
+        let game_code = self.graph.game_code();
+        let mut written = Vec::new();
+        for (sheet, _) in &changes.patches {
+            let path = out_dir.join(format!("{}.wdb", sheet));
+            pack_wdb(self.sheet(sheet)?, &path, game_code).with_context(|| format!("Sheet {}", sheet))?;
+            written.push(path);
+        }
+        let languages: HashSet<&str> = changes.texts.iter().map(|t| t.language.as_str()).collect();
+        for (language, (file_name, data)) in &self.languages {
+            if languages.contains(language.as_str()) {
+                let path = out_dir.join(file_name);
+                let encoding = self.encodings.get(language).map_or(DEFAULT_ZTR_ENCODING, String::as_str);
+                pack_ztr_from_struct_with_encoding(data, &path, game_code, encoding)?;
+                written.push(path);
+            }
+        }
+        Ok(written)
+    }
+
+    fn build_def(&self, def: &WdbContentDef, state: &mut BuildState, changes: &mut WdbChangeSet) -> Result<()> {
+        let template = {
+            let data = state.sheet(self, &def.sheet)?;
+            data.records
+                .iter()
+                .find(|r| record_id(r) == Some(def.template.as_str()))
+                .cloned()
+                .ok_or_else(|| anyhow!("Record '{}' not found in {}", def.template, def.sheet))?
+        };
+
+        let id = match &def.id {
+            Some(id) if state.ids.contains(id) => bail!("Id '{}' is already used", id),
+            Some(id) => id.clone(),
+            None => allocate_id(&def.template, &state.ids),
+        };
+        if id.len() > MAX_NAME_LEN {
+            bail!("Id '{}' is longer than {} bytes", id, MAX_NAME_LEN);
+        }
+
+        let mut set = def.fields.clone();
+        for text in &def.texts {
+            let template_key = match template.get(&text.field) {
+                Some(WdbValue::String(s)) => s.as_str(),
+                Some(_) => bail!("{} is not a text key field", text.field),
+                None => bail!("{} has no field {}", def.sheet, text.field),
+            };
+            let key = match &text.key {
+                Some(key) if state.keys.contains(key) => bail!("Text key '{}' is already used", key),
+                Some(key) => key.clone(),
+                None => unique(&derive_key(template_key, &def.template, &id, &text.field), &state.keys),
+            };
+            for language in text.text.keys().filter(|l| *l != ALL_LANGUAGES) {
+                self.language(language)?;
+            }
+            let mut added = false;
+            for language in self.languages.keys() {
+                if let Some(value) = text.text.get(language).or_else(|| text.text.get(ALL_LANGUAGES)) {
+                    changes.texts.push(WdbTextAddition { language: language.clone(), key: key.clone(), text: value.clone() });
+                    added = true;
+                }
+            }
+            if !added {
+                bail!("No loaded language has text for {}", text.field);
+            }
+            state.keys.insert(key.clone());
+            set.insert(text.field.clone(), WdbValue::String(key));
+        }
+
+        state.stage(self, &def.sheet, WdbPatchOp::Clone { from: def.template.clone(), id: id.clone(), set })?;
+        state.ids.insert(id.clone());
+        for link in &def.links {
+            let op = link_op(state.sheet(self, &link.sheet)?, link, &id)
+                .with_context(|| format!("Link {}:{}.{}", link.sheet, link.record, link.field))?;
+            state.stage(self, &link.sheet, op)?;
+        }
+        changes.ids.push(id);
+        Ok(())
+    }
+
+    /// An indexed sheet by file stem.
+    fn sheet(&self, sheet: &str) -> Result<&WdbData> {
+        self.graph.sheets().get(&sheet.to_lowercase()).ok_or_else(|| anyhow!("Sheet '{}' is not loaded", sheet))
+    }
+
+    fn language(&self, language: &str) -> Result<&(String, ZtrData)> {
+        self.languages.get(language).ok_or_else(|| anyhow!("Language '{}' is not loaded", language))
+    }
+}
+
+/// Sheets as patched so far, and the names already taken, during a build.
+struct BuildState {
+    sheets: IndexMap<String, WdbData>,
+    operations: IndexMap<String, Vec<WdbPatchOp>>,
+    ids: HashSet<String>,
+    keys: HashSet<String>,
+}
+
+impl BuildState {
+    /// A sheet with the operations staged so far applied.
+    fn sheet(&mut self, builder: &WdbContentBuilder, sheet: &str) -> Result<&WdbData> {
+        let name = sheet.to_lowercase();
+        if !self.sheets.contains_key(&name) {
+            let data = builder.sheet(sheet)?.clone();
+            self.sheets.insert(name.clone(), data);
+        }
+        Ok(&self.sheets[&name])
+    }
+
+    /// Applies an operation to the working copy and records it.
+    fn stage(&mut self, builder: &WdbContentBuilder, sheet: &str, op: WdbPatchOp) -> Result<()> {
+        let name = sheet.to_lowercase();
+        self.sheet(builder, sheet)?;
+        let patch = WdbPatch { name: None, sheet: None, operations: vec![op] };
+        apply_wdb_patch_in_place(&mut self.sheets[&name], &patch)?;
+        self.operations.entry(name).or_default().extend(patch.operations);
+        Ok(())
+    }
+}
+
+/// The `set` operation that makes a link point at `id`.
+fn link_op(data: &WdbData, link: &WdbContentLink, id: &str) -> Result<WdbPatchOp> {
+    let record = data
+        .records
+        .iter()
+        .find(|r| record_id(r) == Some(link.record.as_str()))
+        .ok_or_else(|| anyhow!("Record '{}' not found", link.record))?;
+    let field = match link.field.strip_suffix('*') {
+        Some(prefix) => record
+            .iter()
+            .find(|(k, v)| k.starts_with(prefix) && matches!(v, WdbValue::String(s) if s.is_empty()))
+            .map(|(k, _)| k.clone())
+            .ok_or_else(|| anyhow!("No empty {} field left", link.field))?,
+        None => link.field.clone(),
+    };
+    let value = match record.get(&field) {
+        Some(WdbValue::String(_)) => WdbValue::String(id.to_string()),
+        Some(WdbValue::StringArray(ids)) => {
+            let mut ids = ids.clone();
+            if !ids.iter().any(|i| i == id) {
+                ids.push(id.to_string());
+            }
+            WdbValue::StringArray(ids)
+        }
+        Some(other) => bail!("{} is not a string field ({:?})", field, other),
+        None => bail!("No field {}", field),
+    };
+    Ok(WdbPatchOp::Set {
+        select: WdbRecordSelector { ids: vec![link.record.clone()], filter: Vec::new() },
+        fields: IndexMap::from([(field, value)]),
+    })
+}
+
+/// The first id after `template` in its numbering that isn't taken.
+fn allocate_id(template: &str, taken: &HashSet<String>) -> String {
+    let stem = template.trim_end_matches(|c: char| c.is_ascii_digit());
+    let width = template.len() - stem.len();
+    let (stem, start) = match template[stem.len()..].parse::<u64>() {
+        Ok(n) => (stem.to_string(), n),
+        Err(_) => (format!("{}_", template), 1),
+    };
+    (start + 1..)
+        .map(|n| format!("{}{:0width$}", stem, n, width = width))
+        .find(|id| !taken.contains(id))
+        .expect("unbounded range")
+}
+
+/// The text key for a new record, from the template record's key.
+fn derive_key(template_key: &str, template: &str, id: &str, field: &str) -> String {
+    if !template.is_empty() && template_key.contains(template) {
+        template_key.replacen(template, id, 1)
+    } else {
+        let sigil = if template_key.starts_with('$') { "$" } else { "" };
+        format!("{}{}_{}", sigil, id, field)
+    }
+}
+
+/// `base`, or `base_2`, `base_3`, ... if taken.
+fn unique(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+    (2..).map(|n| format!("{}_{}", base, n)).find(|k| !taken.contains(k)).expect("unbounded range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::structs::{WdbChangeKind, WdbContentText};
+    use crate::modules::wdb::tests::{data, record};
+
+    fn text(s: &str) -> WdbValue {
+        WdbValue::String(s.to_string())
+    }
+
+    fn builder() -> WdbContentBuilder {
+        let mut builder = WdbContentBuilder::new(GameCode::FF13_1);
+        let item = |id: &str, price| {
+            record(id, &[
+                ("sItemNameStringId", text(&format!("${}", id))),
+                ("sHelpStringId", text(&format!("${}_h", id))),
+                ("uPrice", WdbValue::UInt(price)),
+            ])
+        };
+        builder.graph_mut().add_sheet("item", data(vec![item("it_potion", 50), item("it_potion_2", 80)]));
+        builder.graph_mut().add_sheet(
+            "shop",
+            data(vec![record("shop_001", &[
+                ("sItemLabel1", text("it_potion")),
+                ("sItemLabel2", text("")),
+                ("sItemLabel3", text("")),
+            ])]),
+        );
+        builder.graph_mut().add_sheet(
+            "treasurebox",
+            data(vec![record("tre_001", &[("sItems", WdbValue::StringArray(vec!["it_potion".into()]))])]),
+        );
+        for language in ["us", "jp"] {
+            let entries = ["$it_potion", "$it_potion_h", "$it_potion_3_h"]
+                .iter()
+                .map(|id| ZtrEntry { id: id.to_string(), text: format!("{} {}", language, id) })
+                .collect();
+            builder.add_language_texts(language, &format!("txtres_{}.ztr", language), ZtrData { entries, mappings: Vec::new() });
+        }
+        builder
+    }
+
+    fn def() -> WdbContentDef {
+        WdbContentDef {
+            sheet: "item".to_string(),
+            template: "it_potion".to_string(),
+            id: None,
+            fields: IndexMap::from([("uPrice".to_string(), WdbValue::UInt(500))]),
+            texts: vec![
+                WdbContentText {
+                    field: "sItemNameStringId".to_string(),
+                    key: None,
+                    text: IndexMap::from([("*".to_string(), "Mega Potion".to_string()), ("jp".to_string(), "メガポーション".to_string())]),
+                },
+                WdbContentText {
+                    field: "sHelpStringId".to_string(),
+                    key: None,
+                    text: IndexMap::from([("us".to_string(), "Restores a lot of HP.".to_string())]),
+                },
+            ],
+            links: vec![
+                WdbContentLink { sheet: "shop".to_string(), record: "shop_001".to_string(), field: "sItemLabel*".to_string() },
+                WdbContentLink { sheet: "treasurebox".to_string(), record: "tre_001".to_string(), field: "sItems".to_string() },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_build_allocates_ids_keys_and_slots() {
+        let mut builder = builder();
+        let changes = builder.build(&[def(), def()]).unwrap();
+        // it_potion_2 is taken; $it_potion_3_h is taken, so the help key gets a suffix
+        assert_eq!(changes.ids, ["it_potion_3", "it_potion_4"]);
+        let keys: Vec<(&str, &str)> = changes.texts.iter().map(|t| (t.language.as_str(), t.key.as_str())).collect();
+        assert_eq!(
+            keys,
+            [
+                ("us", "$it_potion_3"),
+                ("jp", "$it_potion_3"),
+                ("us", "$it_potion_3_h_2"),
+                ("us", "$it_potion_4"),
+                ("jp", "$it_potion_4"),
+                ("us", "$it_potion_4_h"),
+            ]
+        );
+        assert_eq!(changes.texts[1].text, "メガポーション");
+
+        let preview = builder.preview(&changes).unwrap();
+        let sheets: Vec<&str> = preview.iter().map(|s| s.sheet.as_str()).collect();
+        assert_eq!(sheets, ["item", "shop", "treasurebox"]);
+        assert!(preview[0].changes.iter().all(|c| c.kind == WdbChangeKind::Added));
+        let slots: Vec<(&str, &WdbValue)> =
+            preview[1].changes.iter().map(|c| (c.field.as_deref().unwrap(), c.after.as_ref().unwrap())).collect();
+        assert_eq!(slots, [("sItemLabel2", &text("it_potion_3")), ("sItemLabel3", &text("it_potion_4"))]);
+        assert_eq!(builder.graph().record("shop", "shop_001").unwrap()["sItemLabel2"], text(""));
+
+        builder.apply(&changes).unwrap();
+        let new = builder.graph().record("item", "it_potion_4").unwrap();
+        assert_eq!(new["uPrice"], WdbValue::UInt(500));
+        assert_eq!(new["sHelpStringId"], text("$it_potion_4_h"));
+        let us = &builder.languages["us"].1.entries;
+        assert!(us.iter().any(|e| e.id == "$it_potion_4" && e.text == "Mega Potion"));
+        assert!(builder.graph().text("$it_potion_4_h").is_some());
+        assert_eq!(
+            builder.graph().record("treasurebox", "tre_001").unwrap()["sItems"],
+            WdbValue::StringArray(vec!["it_potion".into(), "it_potion_3".into(), "it_potion_4".into()])
+        );
+        // Applying twice fails without changing anything
+        assert!(builder.apply(&changes).is_err());
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let builder = builder();
+        let with = |edit: fn(&mut WdbContentDef)| {
+            let mut d = def();
+            edit(&mut d);
+            builder.build(&[d]).unwrap_err().root_cause().to_string()
+        };
+        assert!(with(|d| d.id = Some("it_potion_2".into())).contains("already used"));
+        assert!(with(|d| d.template = "it_missing".into()).contains("not found"));
+        assert!(with(|d| {
+            d.texts[0].text.insert("fr".into(), "x".into());
+        })
+        .contains("'fr' is not loaded"));
+        assert!(with(|d| d.links[0].field = "uPrice".into()).contains("No field uPrice"));
+        assert!(with(|d| d.id = Some("it_potion_with_long_id".into())).contains("longer than"));
+        // Only two free shop slots
+        let err = builder.build(&[def(), def(), def()]).unwrap_err();
+        assert!(format!("{:#}", err).contains("No empty sItemLabel* field left"), "{:#}", err);
+    }
+}
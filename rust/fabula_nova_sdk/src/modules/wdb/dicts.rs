@@ -19,6 +19,12 @@
 //!
 //! Some WDB files have platform-specific variants (win32, ps3, x360).
 //! The mappings handle these with suffix-aware keys.
+//!
+//! ## Schema Packs
+//!
+//! These tables are not read by the parser directly. They seed the built-in
+//! FF13 schema pack (see [`super::schema`]), which user schema files can
+//! extend or override at runtime.
 
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
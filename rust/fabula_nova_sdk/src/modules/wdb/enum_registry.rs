@@ -13,11 +13,19 @@
 //!
 //! ## Extensibility
 //!
-//! To add new enum mappings:
+//! Most new enums and flag sets need no code: define them in a schema pack
+//! (see [`super::schema`]) and they decode to names as plain strings. A
+//! typed Rust enum is only worth adding when Dart code needs to match on it.
+//! To add one:
 //! 1. Define the enum in [`super::enums`]
 //! 2. Add a variant to [`EnumType`]
 //! 3. Register the (sheet_name, field_name) → EnumType mapping in [`ENUM_FIELDS`]
-//! 4. Handle the conversion in [`int_to_enum_value`]
+//! 4. Handle the conversion in [`int_to_enum_value`] and give it a name in
+//!    [`EnumType::name`] so schema files can refer to it
+//!
+//! The registry only seeds the built-in schema pack (see [`super::schema`]);
+//! the reader resolves enum bindings through the active schema, so user
+//! schema files can bind existing enum types to new fields without a rebuild.
 
 use super::enums::{CrystalNodeType, CrystalRole};
 use super::structs::WdbValue;
@@ -34,6 +42,24 @@ pub enum EnumType {
     // Add more enum types here as needed
 }
 
+impl EnumType {
+    /// Every enum type, used to resolve names in schema files.
+    pub const ALL: [EnumType; 2] = [EnumType::CrystalRole, EnumType::CrystalNodeType];
+
+    /// Name used for this enum in schema files (e.g., "CrystalRole").
+    pub fn name(self) -> &'static str {
+        match self {
+            EnumType::CrystalRole => "CrystalRole",
+            EnumType::CrystalNodeType => "CrystalNodeType",
+        }
+    }
+
+    /// Looks up an enum type by its schema name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
 /// Registry mapping (sheet_name, field_name) → EnumType.
 ///
 /// This static registry tells the parser which integer fields should
@@ -76,6 +102,19 @@ pub fn int_to_enum_value(enum_type: EnumType, raw: u32) -> WdbValue {
     }
 }
 
+/// Converts a variant name (e.g., "Attacker") to the enum WdbValue variant.
+///
+/// # Returns
+///
+/// `None` if the name is not a variant of `enum_type`.
+pub fn name_to_enum_value(enum_type: EnumType, name: &str) -> Option<WdbValue> {
+    let name = serde_json::Value::String(name.to_string());
+    match enum_type {
+        EnumType::CrystalRole => serde_json::from_value(name).ok().map(WdbValue::CrystalRole),
+        EnumType::CrystalNodeType => serde_json::from_value(name).ok().map(WdbValue::CrystalNodeType),
+    }
+}
+
 /// Looks up whether a field should be converted to an enum.
 ///
 /// Performs case-insensitive matching on the sheet name to handle
@@ -145,4 +184,13 @@ mod tests {
         let val = int_to_enum_value(EnumType::CrystalNodeType, 6);
         assert_eq!(val, WdbValue::CrystalNodeType(CrystalNodeType::Ability));
     }
+
+    #[test]
+    fn test_name_to_enum_value() {
+        assert_eq!(
+            name_to_enum_value(EnumType::CrystalRole, "Healer"),
+            Some(WdbValue::CrystalRole(CrystalRole::Healer))
+        );
+        assert_eq!(name_to_enum_value(EnumType::CrystalRole, "Medic"), None);
+    }
 }
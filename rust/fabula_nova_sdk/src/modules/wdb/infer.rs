@@ -0,0 +1,823 @@
+//! # WDB Schema Inference
+//!
+//! Sheets without a `!structitem` section and without an entry in the
+//! schema pack are read with generic field names (`bitpacked-field_0`,
+//! `uint-field_1`, ...), one per `!!strtypelist` slot. This module scans
+//! many files, across all three games, and drafts a schema pack for those
+//! sheets that researchers can refine and load as an override.
+//!
+//! ## Field Names
+//!
+//! Files are grouped into sheets by their `sheetName` header (or file
+//! stem). For each unknown sheet, named files of the same sheet in any game
+//! are tried in turn. The first whose field names line up with the unknown
+//! sheet's `!!strtypelist` slots supplies the names:
+//!
+//! ```text
+//!   XIII-2 item.wdb  !structitem: sItemId  u8Kind u4Rank u1Sell  uPrice
+//!   !!strtypelist               : 2        0 ─────────────────── 3
+//!   XIII   item.wdb  !!strtypelist: 2      0                     3
+//!                                   └──── same slots, names carry over
+//! ```
+//!
+//! | Confidence | When                                                      |
+//! |------------|-----------------------------------------------------------|
+//! | High       | The sibling has the exact same `!!strtypelist`            |
+//! | Medium     | The names fit the slots, but the type lists differ or `s` bitfields had to become `u` (XIII can't write them) |
+//! | Low        | No sibling fits; placeholders like `uField0` are used     |
+//!
+//! ## Field Roles
+//!
+//! Values from every file of the sheet are pooled, and bitpacked words are
+//! split along the proposed names, so each subfield is judged on its own:
+//!
+//! | Role           | Evidence                                                 |
+//! |----------------|----------------------------------------------------------|
+//! | `Constant`     | One value everywhere                                     |
+//! | `Boolean`      | Only 0 and 1                                             |
+//! | `StringOffset` | Every non-zero value starts a string in the file's `!!string` table |
+//! | `TextKey`      | Most values (or the strings they point at) are ZTR keys  |
+//! | `Reference`    | Most values are record ids of one loaded sheet           |
+//! | `Flags`        | Values are unions of single bits that also occur alone   |
+//! | `Id`           | Nearly every record has its own value                    |
+//! | `Enum`         | At most 16 small values that repeat                      |
+//! | `Number`, `Text` | Anything else                                          |
+//!
+//! References use the same thresholds as [`super::refs`]. Fewer than
+//! eight values cap a note at low confidence.
+//!
+//! ## Output
+//!
+//! The draft is a regular [`WdbSchemaPack`]: every inferred sheet lists its
+//! files, text and record references become `ref` entries, and each field's
+//! `description` carries its note. [`write_schema_draft`] also writes the
+//! notes alone next to the pack:
+//!
+//! ```rust,ignore
+//! use fabula_nova_sdk::modules::wdb::infer::*;
+//!
+//! let mut inference = WdbSchemaInference::new();
+//! inference.load_directory("ff13/db", GameCode::FF13_1)?;
+//! inference.load_directory("ff13-2/db", GameCode::FF13_2)?;
+//! inference.load_directory("lr/db", GameCode::FF13_3)?;
+//! let draft = inference.draft(GameCode::FF13_1)?;
+//! write_schema_draft(&draft, "schemas/ff13_draft.json")?; // + ff13_draft.notes.json
+//! ```
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use super::api::parse_wdb;
+use super::bit_helpers::{derive_field_number, sign_extend, BitReader};
+use super::reader::derive_string;
+use super::refs::{record_id, INFERRED_MIN, INFERRED_SHARE};
+use super::schema::{WdbFieldSchema, WdbSchemaPack, WdbSheetSchema, WDB_SCHEMA_VERSION};
+use super::sections::{header_bytes, STRING_POOL_KEY};
+use super::structs::{
+    GameCode, WdbConfidence, WdbData, WdbFieldRole, WdbInferenceNote, WdbRecord, WdbValue,
+};
+use crate::modules::ztr::{api::parse_ztr, structs::ZtrData};
+
+/// Name prefixes of the fields the reader makes up for sheets without names.
+const GENERIC_FIELD_PREFIXES: [&str; 5] =
+    ["bitpacked-field_", "float-field_", "!!string-field_", "uint-field_", "unknown-field_"];
+
+/// Fewer values than this cap a note at low confidence.
+const MIN_SAMPLES: usize = 8;
+
+/// Most distinct values an enum is expected to have.
+const MAX_ENUM_VALUES: usize = 16;
+
+/// Largest raw value an enum is expected to use.
+const MAX_ENUM_VALUE: i64 = 64;
+
+/// A drafted schema pack and the notes behind it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WdbSchemaDraft {
+    pub pack: WdbSchemaPack,
+    pub notes: Vec<WdbInferenceNote>,
+}
+
+/// One parsed file.
+struct Sample {
+    game: GameCode,
+    stem: String,
+    /// Lowercase `sheetName` header, or the stem
+    sheet: String,
+    types: Vec<u32>,
+    fields: Vec<String>,
+    named: bool,
+    pool: Vec<u8>,
+    records: Vec<WdbRecord>,
+}
+
+/// Values seen for one field across a sheet's files.
+#[derive(Default)]
+struct Observed {
+    numbers: Vec<i64>,
+    floats: Vec<f32>,
+    strings: Vec<String>,
+    /// Strings that non-zero numbers point at, when they start one in `!!string`
+    pool_strings: Vec<String>,
+}
+
+/// Proposed names for an unknown sheet, one list per `!!strtypelist` slot.
+struct Naming {
+    slots: Vec<Vec<String>>,
+    confidence: WdbConfidence,
+    message: String,
+}
+
+/// Collects WDB files from the three games and drafts schemas for unknown sheets.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut inference = WdbSchemaInference::new();
+/// inference.add_sheet(GameCode::FF13_2, "zonelist", xiii2_zones);
+/// inference.add_sheet(GameCode::FF13_1, "zonelist", xiii_zones);
+/// for note in inference.draft(GameCode::FF13_1)?.notes {
+///     println!("{:?} {}.{:?}: {}", note.confidence, note.sheet, note.field, note.message);
+/// }
+/// ```
+#[derive(Default)]
+pub struct WdbSchemaInference {
+    samples: Vec<Sample>,
+    texts: HashMap<GameCode, HashSet<String>>,
+}
+
+impl WdbSchemaInference {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parsed file.
+    ///
+    /// `stem` is normally the WDB file stem (e.g., `item`).
+    pub fn add_sheet(&mut self, game_code: GameCode, stem: &str, data: WdbData) {
+        let header = &data.header;
+        let types = match header.get("!!strtypelist") {
+            Some(WdbValue::UIntArray(t)) => t.clone(),
+            _ => Vec::new(),
+        };
+        let fields = match header.get("!structitem") {
+            Some(WdbValue::StringArray(f)) => f.clone(),
+            _ => Vec::new(),
+        };
+        let sheet = match header.get("sheetName") {
+            Some(WdbValue::String(s)) if !s.is_empty() => s.to_lowercase(),
+            _ => stem.to_lowercase(),
+        };
+        let named = !fields.is_empty() && !fields.iter().any(|f| is_generic_field(f));
+        let pool = header_bytes(header, STRING_POOL_KEY).ok().flatten().unwrap_or_default();
+        self.samples.push(Sample {
+            game: game_code,
+            stem: stem.to_lowercase(),
+            sheet,
+            types,
+            fields,
+            named,
+            pool,
+            records: data.records,
+        });
+    }
+
+    /// Adds the keys of a ZTR file, used to spot text key fields.
+    pub fn add_texts(&mut self, game_code: GameCode, data: &ZtrData) {
+        self.texts.entry(game_code).or_default().extend(data.entries.iter().map(|e| e.id.clone()));
+    }
+
+    /// Adds every `.wdb` and `.ztr` file under a directory.
+    ///
+    /// # Returns
+    ///
+    /// The number of files added. Files that fail to parse are logged and
+    /// skipped.
+    pub fn load_directory<P: AsRef<Path>>(&mut self, dir: P, game_code: GameCode) -> Result<usize> {
+        let mut count = 0;
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let ext = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+            let result = match ext.as_deref() {
+                Some("wdb") => parse_wdb(path, game_code).map(|data| {
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                    self.add_sheet(game_code, &stem, data);
+                }),
+                Some("ztr") => parse_ztr(path, game_code).map(|data| self.add_texts(game_code, &data)),
+                _ => continue,
+            };
+            match result {
+                Ok(()) => count += 1,
+                Err(e) => log::warn!("Skipping {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Drafts a schema pack for the game's sheets that have no field names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the sheet and field if a sibling supplies a
+    /// field name with no i/u/f/s type prefix.
+    pub fn draft(&self, game_code: GameCode) -> Result<WdbSchemaDraft> {
+        let mut groups: IndexMap<&str, Vec<&Sample>> = IndexMap::new();
+        for sample in self.samples.iter().filter(|s| s.game == game_code && !s.named && !s.types.is_empty()) {
+            groups.entry(sample.sheet.as_str()).or_default().push(sample);
+        }
+
+        let ids = self.record_index(game_code);
+        let empty = HashSet::new();
+        let texts = self.texts.get(&game_code).unwrap_or(&empty);
+        let mut notes = Vec::new();
+        let mut sheets = Vec::new();
+
+        for (sheet, group) in groups {
+            let sheet_note = |confidence, message: String| WdbInferenceNote {
+                sheet: sheet.to_string(),
+                field: None,
+                role: None,
+                confidence,
+                message,
+            };
+
+            // Files of one sheet should agree; judge the rest by the first
+            let types = &group[0].types;
+            let (files, odd): (Vec<&Sample>, Vec<&Sample>) = group.iter().partition(|s| &s.types == types);
+            if !odd.is_empty() {
+                let stems: Vec<&str> = odd.iter().map(|s| s.stem.as_str()).collect();
+                notes.push(sheet_note(
+                    WdbConfidence::Low,
+                    format!("{} use a different !!strtypelist than {} and were left out", stems.join(", "), group[0].stem),
+                ));
+            }
+
+            let stems: BTreeSet<&str> = files.iter().map(|s| s.stem.as_str()).collect();
+            let naming = self.naming(game_code, sheet, &stems, types);
+            notes.push(sheet_note(naming.confidence, naming.message.clone()));
+
+            let observed = observe(&files, types, &naming.slots);
+            let mut fields = Vec::new();
+            for (name, seen) in naming.slots.iter().flatten().zip(observed) {
+                let mut field = WdbFieldSchema::from_name(name).with_context(|| format!("Sheet '{}'", sheet))?;
+                let (role, confidence, message, reference) = match name.chars().next() {
+                    Some('s') if !seen.strings.is_empty() => classify_strings(&seen.strings, texts, &ids),
+                    Some('f') if !seen.floats.is_empty() => classify_floats(&seen.floats),
+                    _ => classify_numbers(&seen, texts, &ids),
+                };
+                field.reference = reference;
+                field.description = Some(format!("[{:?}] {}", confidence, message).to_lowercase());
+                notes.push(WdbInferenceNote {
+                    sheet: sheet.to_string(),
+                    field: Some(name.clone()),
+                    role: Some(role),
+                    confidence,
+                    message,
+                });
+                fields.push(field);
+            }
+
+            sheets.push(WdbSheetSchema {
+                name: sheet.to_string(),
+                files: stems.iter().map(|s| s.to_string()).collect(),
+                fields,
+                description: Some(format!("Inferred from {} file(s): {}", files.len(), naming.message)),
+            });
+        }
+
+        Ok(WdbSchemaDraft {
+            pack: WdbSchemaPack {
+                version: WDB_SCHEMA_VERSION,
+                game: game_code,
+                enums: Vec::new(),
+                sheets,
+            },
+            notes,
+        })
+    }
+
+    /// Picks field names for an unknown sheet from its named siblings.
+    fn naming(&self, game_code: GameCode, sheet: &str, stems: &BTreeSet<&str>, types: &[u32]) -> Naming {
+        let mut siblings: Vec<&Sample> = self
+            .samples
+            .iter()
+            .filter(|s| s.named && (s.sheet == sheet || stems.contains(s.stem.as_str())))
+            .collect();
+        // Exact layouts first, then the same game, then the largest sample
+        siblings.sort_by_key(|s| (s.types != types, s.game != game_code, std::cmp::Reverse(s.records.len())));
+
+        let mut misfits = Vec::new();
+        for sibling in &siblings {
+            let fields = names_for(game_code, &sibling.fields);
+            let renamed = fields != sibling.fields;
+            let Some(slots) = assign_slots(&fields, types) else {
+                misfits.push(format!("{:?} {}", sibling.game, sibling.stem));
+                continue;
+            };
+            let exact = sibling.types == types;
+            let mut message = format!(
+                "field names from {:?} {}{}",
+                sibling.game,
+                sibling.stem,
+                if exact { " (same !!strtypelist)" } else { " (names fit, !!strtypelist differs)" }
+            );
+            if renamed {
+                message.push_str("; s bitfields renamed to u, as this game can't write them");
+            }
+            return Naming {
+                slots,
+                confidence: if exact && !renamed { WdbConfidence::High } else { WdbConfidence::Medium },
+                message,
+            };
+        }
+
+        let slots = types
+            .iter()
+            .enumerate()
+            .map(|(i, code)| vec![format!("{}Field{}", placeholder_prefix(*code), i)])
+            .collect();
+        let message = if misfits.is_empty() {
+            "no named sibling found; placeholder names".to_string()
+        } else {
+            format!("placeholder names; siblings with other layouts: {}", misfits.join(", "))
+        };
+        Naming { slots, confidence: WdbConfidence::Low, message }
+    }
+
+    /// Record id -> file stems holding a record with that id, for one game.
+    fn record_index(&self, game_code: GameCode) -> HashMap<&str, BTreeSet<&str>> {
+        let mut ids: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+        for sample in self.samples.iter().filter(|s| s.game == game_code) {
+            for id in sample.records.iter().filter_map(record_id) {
+                ids.entry(id).or_default().insert(sample.stem.as_str());
+            }
+        }
+        ids
+    }
+}
+
+/// True for the names the reader makes up for sheets without fields.
+fn is_generic_field(name: &str) -> bool {
+    GENERIC_FIELD_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// Placeholder type prefix for a `!!strtypelist` slot.
+fn placeholder_prefix(type_code: u32) -> char {
+    match type_code {
+        1 => 'f',
+        2 => 's',
+        _ => 'u',
+    }
+}
+
+/// A sibling's names as the target game can write them.
+///
+/// XIII packs no `s` bitfields, so they keep their width as `u` fields.
+fn names_for(game_code: GameCode, fields: &[String]) -> Vec<String> {
+    if game_code != GameCode::FF13_1 {
+        return fields.to_vec();
+    }
+    fields
+        .iter()
+        .map(|f| match f.strip_prefix('s') {
+            Some(rest) if derive_field_number(f) > 0 => format!("u{}", rest),
+            _ => f.clone(),
+        })
+        .collect()
+}
+
+/// Splits field names into `!!strtypelist` slots the way the reader walks them.
+///
+/// Returns `None` unless every slot gets fields of a matching type and no
+/// field is left over.
+fn assign_slots(fields: &[String], types: &[u32]) -> Option<Vec<Vec<String>>> {
+    let mut slots = Vec::with_capacity(types.len());
+    let mut f = 0;
+    for &code in types {
+        if code == 0 {
+            let mut remaining = 32;
+            let mut slot = Vec::new();
+            while let Some(field) = fields.get(f) {
+                let bits = match derive_field_number(field) {
+                    0 => 32,
+                    n => n,
+                };
+                if bits > remaining {
+                    break;
+                }
+                remaining -= bits;
+                slot.push(field.clone());
+                f += 1;
+            }
+            if slot.is_empty() {
+                return None;
+            }
+            slots.push(slot);
+        } else {
+            let field = fields.get(f)?;
+            let expected: &[char] = match code {
+                1 => &['f'],
+                2 => &['s'],
+                _ => &['u', 'i'],
+            };
+            if !field.starts_with(expected) {
+                return None;
+            }
+            slots.push(vec![field.clone()]);
+            f += 1;
+        }
+    }
+    (f == fields.len()).then_some(slots)
+}
+
+/// Pools each field's values across files, splitting bitpacked words by name.
+fn observe(files: &[&Sample], types: &[u32], slots: &[Vec<String>]) -> Vec<Observed> {
+    let mut observed: Vec<Observed> = slots.iter().flatten().map(|_| Observed::default()).collect();
+    let mut counters = [0usize; 4];
+    let mut first = 0;
+    for (code, names) in types.iter().zip(slots) {
+        let key = match code {
+            0 => format!("bitpacked-field_{}", counters[0]),
+            1 => format!("float-field_{}", counters[1]),
+            2 => format!("!!string-field_{}", counters[2]),
+            3 => format!("uint-field_{}", counters[3]),
+            _ => String::new(),
+        };
+        if let Some(c) = counters.get_mut(*code as usize) {
+            *c += 1;
+        }
+
+        for sample in files {
+            for record in &sample.records {
+                let seen = &mut observed[first..first + names.len()];
+                match record.get(&key) {
+                    Some(WdbValue::String(hex)) if *code == 0 => {
+                        let Ok(word) = u32::from_str_radix(hex.trim_start_matches("0x"), 16) else { continue };
+                        let mut bits = BitReader::new(word);
+                        for (name, seen) in names.iter().zip(seen) {
+                            let width = match derive_field_number(name) {
+                                0 => 32,
+                                n => n,
+                            };
+                            let raw = bits.read_bits(width).unwrap_or(0);
+                            let value = match name.chars().next() {
+                                Some('i') | Some('f') => sign_extend(raw, width) as i64,
+                                _ => raw as i64,
+                            };
+                            seen.push_number(value, &sample.pool);
+                        }
+                    }
+                    Some(WdbValue::Float(v)) => seen[0].floats.push(*v),
+                    Some(WdbValue::String(s)) => seen[0].strings.push(s.clone()),
+                    Some(WdbValue::UInt(v)) => {
+                        let value = if names[0].starts_with('i') { *v as i32 as i64 } else { *v as i64 };
+                        seen[0].push_number(value, &sample.pool);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        first += names.len();
+    }
+    observed
+}
+
+impl Observed {
+    fn push_number(&mut self, value: i64, pool: &[u8]) {
+        self.numbers.push(value);
+        let offset = value as usize;
+        if value > 0 && offset < pool.len() && pool[offset - 1] == 0 {
+            self.pool_strings.push(derive_string(pool, offset));
+        }
+    }
+}
+
+/// Applies [`MIN_SAMPLES`] to a confidence.
+fn sized(confidence: WdbConfidence, samples: usize) -> WdbConfidence {
+    if samples < MIN_SAMPLES {
+        WdbConfidence::Low
+    } else {
+        confidence
+    }
+}
+
+/// Whether `hits` of `total` meets the reference inference thresholds.
+fn mostly(hits: usize, total: usize) -> bool {
+    hits >= INFERRED_MIN && hits * 100 >= total * INFERRED_SHARE
+}
+
+/// Best reference target of some strings: `ztr`, a file stem, or nothing.
+fn resolve_strings(
+    values: &[&str],
+    texts: &HashSet<String>,
+    ids: &HashMap<&str, BTreeSet<&str>>,
+) -> Option<(String, usize)> {
+    let mut hits: BTreeMap<&str, usize> = BTreeMap::new();
+    for value in values {
+        if texts.contains(*value) {
+            *hits.entry("ztr").or_default() += 1;
+        }
+        for stem in ids.get(value).into_iter().flatten() {
+            *hits.entry(stem).or_default() += 1;
+        }
+    }
+    let (target, n) = hits.into_iter().max_by_key(|(_, n)| *n)?;
+    mostly(n, values.len()).then(|| (target.to_string(), n))
+}
+
+type Classified = (WdbFieldRole, WdbConfidence, String, Option<String>);
+
+fn classify_numbers(
+    seen: &Observed,
+    texts: &HashSet<String>,
+    ids: &HashMap<&str, BTreeSet<&str>>,
+) -> Classified {
+    let values = &seen.numbers;
+    let n = values.len();
+    let distinct: BTreeSet<i64> = values.iter().copied().collect();
+    let nonzero: Vec<i64> = values.iter().copied().filter(|v| *v != 0).collect();
+    let (Some(&min), Some(&max)) = (distinct.first(), distinct.last()) else {
+        return (WdbFieldRole::Number, WdbConfidence::Low, "no values seen".to_string(), None);
+    };
+    let classified = |role, confidence, message: String| (role, sized(confidence, n), message, None);
+
+    if distinct.len() == 1 {
+        return classified(WdbFieldRole::Constant, WdbConfidence::High, format!("always {} in {} records", min, n));
+    }
+    if min == 0 && max == 1 {
+        let set = values.iter().filter(|v| **v == 1).count();
+        return classified(WdbFieldRole::Boolean, WdbConfidence::High, format!("0/1, set in {} of {} records", set, n));
+    }
+
+    // Offsets into !!string: every non-zero value starts a string there
+    let nonzero_distinct = distinct.len() - distinct.contains(&0) as usize;
+    if seen.pool_strings.len() == nonzero.len() && nonzero_distinct >= 3 && max > MAX_ENUM_VALUE {
+        let strings: Vec<&str> = seen.pool_strings.iter().map(String::as_str).filter(|s| !s.is_empty()).collect();
+        let target = resolve_strings(&strings, texts, ids);
+        let message = match &target {
+            Some((t, hits)) => format!("!!string offsets; {} of {} strings resolve to {}", hits, strings.len(), t),
+            None => format!("every non-zero value starts a !!string entry ({} values)", nonzero.len()),
+        };
+        let role = match target.as_ref().map(|(t, _)| t.as_str()) {
+            Some("ztr") => WdbFieldRole::TextKey,
+            Some(_) => WdbFieldRole::Reference,
+            None => WdbFieldRole::StringOffset,
+        };
+        return classified(role, WdbConfidence::Medium, message);
+    }
+
+    // Numeric text keys
+    let as_text = nonzero.iter().filter(|v| texts.contains(&v.to_string())).count();
+    if mostly(as_text, nonzero.len()) {
+        return classified(
+            WdbFieldRole::TextKey,
+            WdbConfidence::Medium,
+            format!("{} of {} non-zero values are ZTR keys", as_text, nonzero.len()),
+        );
+    }
+
+    // Flags: every value is a union of single bits that also occur alone
+    let singles: Vec<i64> = distinct.iter().copied().filter(|v| *v > 0 && v & (v - 1) == 0).collect();
+    let single_mask = singles.iter().fold(0, |m, v| m | v);
+    let contiguous = (max - min + 1) as usize == distinct.len();
+    if min >= 0 && singles.len() >= 2 && max >= 4 && !contiguous && distinct.iter().all(|v| v & !single_mask == 0) {
+        let combos = nonzero_distinct - singles.len();
+        let confidence = if singles.len() >= 3 && combos > 0 { WdbConfidence::Medium } else { WdbConfidence::Low };
+        return classified(
+            WdbFieldRole::Flags,
+            confidence,
+            format!("bits {:#x} seen alone, {} combinations", single_mask, combos),
+        );
+    }
+
+    if distinct.len() * 10 >= n * 9 && n >= 4 {
+        let confidence = if distinct.len() == n && n >= 2 * MIN_SAMPLES { WdbConfidence::High } else { WdbConfidence::Medium };
+        return classified(
+            WdbFieldRole::Id,
+            confidence,
+            format!("{} distinct values in {} records ({}..={})", distinct.len(), n, min, max),
+        );
+    }
+
+    if distinct.len() <= MAX_ENUM_VALUES && min >= 0 && max < MAX_ENUM_VALUE && n >= 2 * distinct.len() {
+        let dense = distinct.len() * 2 > (max - min + 1) as usize;
+        let confidence = if dense && n >= 4 * distinct.len() { WdbConfidence::High } else { WdbConfidence::Medium };
+        let list: Vec<String> = distinct.iter().map(|v| v.to_string()).collect();
+        return classified(WdbFieldRole::Enum, confidence, format!("values {{{}}} over {} records", list.join(", "), n));
+    }
+
+    classified(
+        WdbFieldRole::Number,
+        WdbConfidence::Low,
+        format!("{} distinct values in {}..={}", distinct.len(), min, max),
+    )
+}
+
+fn classify_floats(values: &[f32]) -> Classified {
+    let n = values.len();
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if min == max {
+        return (WdbFieldRole::Constant, sized(WdbConfidence::High, n), format!("always {} in {} records", min, n), None);
+    }
+    (WdbFieldRole::Number, sized(WdbConfidence::Medium, n), format!("floats in {}..={}", min, max), None)
+}
+
+fn classify_strings(values: &[String], texts: &HashSet<String>, ids: &HashMap<&str, BTreeSet<&str>>) -> Classified {
+    let filled: Vec<&str> = values.iter().map(String::as_str).filter(|s| !s.is_empty()).collect();
+    let n = filled.len();
+    if n == 0 {
+        return (WdbFieldRole::Constant, sized(WdbConfidence::High, values.len()), "always empty".to_string(), None);
+    }
+
+    if let Some((target, hits)) = resolve_strings(&filled, texts, ids) {
+        let (role, message) = if target == "ztr" {
+            (WdbFieldRole::TextKey, format!("{} of {} values are ZTR keys", hits, n))
+        } else {
+            (WdbFieldRole::Reference, format!("{} of {} values are record ids in {}", hits, n, target))
+        };
+        let confidence = if hits == n { WdbConfidence::High } else { WdbConfidence::Medium };
+        return (role, sized(confidence, n), message, Some(target));
+    }
+
+    let distinct: HashSet<&str> = filled.iter().copied().collect();
+    if distinct.len() == 1 {
+        let message = format!("always \"{}\" when set ({} of {} records)", filled[0], n, values.len());
+        return (WdbFieldRole::Constant, sized(WdbConfidence::Medium, n), message, None);
+    }
+    if distinct.len() * 10 >= n * 9 {
+        let message = format!("{} distinct strings in {} records; unresolved ids or free text", distinct.len(), n);
+        return (WdbFieldRole::Id, sized(WdbConfidence::Low, n), message, None);
+    }
+    let message = format!("{} distinct strings that resolve to no loaded sheet or text", distinct.len());
+    (WdbFieldRole::Text, sized(WdbConfidence::Low, n), message, None)
+}
+
+/// Writes a draft pack and, next to it, its notes (`<name>.notes.json`).
+///
+/// The pack loads with [`super::schema::load_schema_pack`] and can be
+/// dropped into an override directory once reviewed.
+///
+/// # Errors
+///
+/// Returns an error if either file can't be written.
+pub fn write_schema_draft<P: AsRef<Path>>(draft: &WdbSchemaDraft, path: P) -> Result<()> {
+    let path = path.as_ref();
+    serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &draft.pack)?;
+    serde_json::to_writer_pretty(BufWriter::new(File::create(path.with_extension("notes.json"))?), &draft.notes)?;
+    Ok(())
+}
+
+/// Scans directories from any of the games and drafts a schema for one.
+///
+/// # Arguments
+///
+/// * `sources` - Directories with the game each one belongs to
+/// * `game_code` - Game whose unknown sheets are drafted
+/// * `out_path` - Where the draft pack is written (notes go next to it)
+///
+/// # Returns
+///
+/// The draft, also written to `out_path`.
+pub fn infer_schema_pack<P: AsRef<Path>>(
+    sources: &[(P, GameCode)],
+    game_code: GameCode,
+    out_path: P,
+) -> Result<WdbSchemaDraft> {
+    let mut inference = WdbSchemaInference::new();
+    for (dir, game) in sources {
+        inference.load_directory(dir, *game)?;
+    }
+    let draft = inference.draft(game_code)?;
+    write_schema_draft(&draft, out_path)?;
+    Ok(draft)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::schema::load_schema_pack;
+    use crate::modules::wdb::sections::encode_hex;
+    use crate::modules::wdb::structs::WdbHeader;
+    use crate::modules::ztr::structs::ZtrEntry;
+
+    fn header(types: &[u32], fields: &[&str]) -> WdbHeader {
+        let mut header = WdbHeader::new();
+        header.insert("!!strtypelist".to_string(), WdbValue::UIntArray(types.to_vec()));
+        header.insert(
+            "!structitem".to_string(),
+            WdbValue::StringArray(fields.iter().map(|f| f.to_string()).collect()),
+        );
+        header
+    }
+
+    /// XIII-2 names for a string, a bitpacked word and a uint.
+    fn sibling() -> WdbData {
+        let mut record = WdbRecord::new();
+        record.insert("record".to_string(), WdbValue::String("it_001".to_string()));
+        WdbData {
+            header: header(&[2, 0, 3], &["sName", "u4Kind", "u8Flags", "s8Group", "i8Delta", "uPrice"]),
+            records: vec![record],
+        }
+    }
+
+    /// An XIII file with the same layout and only generic fields.
+    fn unknown(rows: usize) -> WdbData {
+        let mut header = header(&[2, 0, 3], &["!!string-field_0", "bitpacked-field_0", "uint-field_0"]);
+        header.insert(STRING_POOL_KEY.to_string(), WdbValue::String(encode_hex(b"\0abc\0")));
+        let flags = [1, 2, 8, 3, 9, 0, 1, 8];
+        let records = (0..rows)
+            .map(|i| {
+                let kind = (i % 3) as u32;
+                let delta = (-(i as i32)) as u32 & 0xFF;
+                let word = kind | (flags[i % 8] << 4) | (7 << 12) | (delta << 20);
+                let mut r = WdbRecord::new();
+                r.insert("record".to_string(), WdbValue::String(format!("it_{:03}", i)));
+                r.insert("!!string-field_0".to_string(), WdbValue::String(format!("$it_{:03}", i)));
+                r.insert("bitpacked-field_0".to_string(), WdbValue::String(format!("0x{:08X}", word)));
+                r.insert("uint-field_0".to_string(), WdbValue::UInt(100 + i as u32 * 7));
+                r
+            })
+            .collect();
+        WdbData { header, records }
+    }
+
+    fn note<'a>(draft: &'a WdbSchemaDraft, field: &str) -> &'a WdbInferenceNote {
+        draft.notes.iter().find(|n| n.field.as_deref() == Some(field)).unwrap()
+    }
+
+    #[test]
+    fn test_names_from_sibling_and_roles() {
+        let mut inference = WdbSchemaInference::new();
+        inference.add_sheet(GameCode::FF13_2, "item", sibling());
+        inference.add_sheet(GameCode::FF13_1, "item", unknown(24));
+        inference.add_texts(
+            GameCode::FF13_1,
+            &ZtrData {
+                entries: (0..24).map(|i| ZtrEntry { id: format!("$it_{:03}", i), text: String::new() }).collect(),
+                mappings: Vec::new(),
+            },
+        );
+
+        let draft = inference.draft(GameCode::FF13_1).unwrap();
+        let sheet = &draft.pack.sheets[0];
+        let names: Vec<&str> = sheet.fields.iter().map(|f| f.name.as_str()).collect();
+        // XIII can't write s bitfields, so s8Group keeps its width as u8Group
+        assert_eq!(names, ["sName", "u4Kind", "u8Flags", "u8Group", "i8Delta", "uPrice"]);
+        assert_eq!(sheet.files, ["item"]);
+        assert_eq!(draft.notes[0].confidence, WdbConfidence::Medium);
+
+        assert_eq!(note(&draft, "sName").role, Some(WdbFieldRole::TextKey));
+        assert_eq!(sheet.fields[0].reference.as_deref(), Some("ztr"));
+        assert_eq!(note(&draft, "u4Kind").role, Some(WdbFieldRole::Enum));
+        assert_eq!(note(&draft, "u8Flags").role, Some(WdbFieldRole::Flags));
+        assert_eq!(note(&draft, "u8Group").role, Some(WdbFieldRole::Constant));
+        assert_eq!(note(&draft, "i8Delta").role, Some(WdbFieldRole::Id));
+        assert_eq!(note(&draft, "uPrice").role, Some(WdbFieldRole::Id));
+
+        // The draft is a loadable pack
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test_infer_schema");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_schema_draft(&draft, dir.join("draft.json")).unwrap();
+        assert_eq!(load_schema_pack(dir.join("draft.json")).unwrap(), draft.pack);
+        assert!(dir.join("draft.notes.json").exists());
+    }
+
+    #[test]
+    fn test_placeholders_without_fitting_sibling() {
+        let mut inference = WdbSchemaInference::new();
+        let mut other = sibling();
+        other.header = header(&[2, 3], &["sName", "uPrice"]);
+        inference.add_sheet(GameCode::FF13_3, "item", other);
+        inference.add_sheet(GameCode::FF13_1, "item", unknown(4));
+
+        let draft = inference.draft(GameCode::FF13_1).unwrap();
+        let names: Vec<&str> = draft.pack.sheets[0].fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["sField0", "uField1", "uField2"]);
+        assert_eq!(draft.notes[0].confidence, WdbConfidence::Low);
+        assert!(draft.notes[0].message.contains("FF13_3 item"));
+        // Too few records to trust any role
+        assert!(draft.notes.iter().all(|n| n.confidence == WdbConfidence::Low));
+        draft.pack.validate().unwrap();
+    }
+
+    #[test]
+    fn test_untyped_sibling_name_is_an_error() {
+        let mut inference = WdbSchemaInference::new();
+        let mut other = sibling();
+        // Fits the bitpacked slot, but has no type prefix
+        other.header = header(&[2, 0, 3], &["sName", "Kind", "uPrice"]);
+        inference.add_sheet(GameCode::FF13_2, "item", other);
+        inference.add_sheet(GameCode::FF13_1, "item", unknown(4));
+
+        let err = format!("{:#}", inference.draft(GameCode::FF13_1).unwrap_err());
+        assert!(err.contains("Sheet 'item'") && err.contains("'Kind'"), "{}", err);
+    }
+}
@@ -0,0 +1,400 @@
+//! # WDB Three-Way Merge
+//!
+//! This module merges two modified versions of the same WDB file against
+//! the vanilla file they both started from, so two mods editing `item.wdb`
+//! can both apply instead of the last one winning.
+//!
+//! ## Algorithm
+//!
+//! Records are matched by their `record` id, then merged field by field:
+//!
+//! ```text
+//!   base   ours   theirs      merged
+//!   ----   ----   ------      ------
+//!    A      A       A     →    A
+//!    A      B       A     →    B        (only ours changed it)
+//!    A      A       C     →    C        (only theirs changed it)
+//!    A      B       B     →    B        (same change on both sides)
+//!    A      B       C     →    conflict (ours kept until resolved)
+//! ```
+//!
+//! The same rule applies to whole records (added, deleted) and to header
+//! entries. A record deleted on one side and modified on the other is a
+//! conflict. The modified record is kept in the merge until resolved.
+//! String pool seeds (see [`super::sections`]) are the exception: they are
+//! taken together from the first side that has them.
+//!
+//! ## Value Equality
+//!
+//! Values are compared by meaning rather than by variant, because JSON
+//! import turns unsigned values into `Int` and empty arrays into
+//! `IntArray`. `UInt(5)` equals `Int(5)`, and every empty array equals every
+//! other empty array.
+//!
+//! ## Ordering
+//!
+//! Records keep the vanilla order. Records added by "ours" follow in their
+//! order, then records added only by "theirs".
+
+use anyhow::{bail, Context, Result};
+use indexmap::{IndexMap, IndexSet};
+use std::path::Path;
+
+use super::api::{canonicalize_wdb, parse_wdb};
+use super::sections::LAYOUT_HEADER_KEYS;
+use super::structs::{
+    GameCode, WdbConflictKind, WdbData, WdbHeader, WdbMergeChoice, WdbMergeConflict, WdbMergeResult, WdbRecord, WdbValue,
+};
+
+/// Header entries that are recomputed rather than merged.
+const DERIVED_HEADER_KEYS: [&str; 1] = ["recordCount"];
+
+/// Merges two edited versions of a WDB file against their common base.
+///
+/// # Arguments
+///
+/// * `base` - The vanilla data
+/// * `ours` - The first modified version (wins unresolved conflicts)
+/// * `theirs` - The second modified version
+///
+/// # Returns
+///
+/// The merged data and every conflict found. Records without a `record`
+/// id cannot be matched and are dropped, as the writer would skip them.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let result = wdb::merge_wdb(&vanilla, &mod_a, &mod_b);
+/// for conflict in &result.conflicts {
+///     println!("{:?} {:?}.{:?}", conflict.kind, conflict.record, conflict.field);
+/// }
+/// ```
+pub fn merge_wdb(base: &WdbData, ours: &WdbData, theirs: &WdbData) -> WdbMergeResult {
+    let mut conflicts = Vec::new();
+
+    // Header
+    let keys: IndexSet<&String> = ours.header.keys().chain(theirs.header.keys()).chain(base.header.keys()).collect();
+    let mut header = WdbHeader::new();
+    for key in keys {
+        if DERIVED_HEADER_KEYS.contains(&key.as_str()) || LAYOUT_HEADER_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let (b, o, t) = (base.header.get(key), ours.header.get(key), theirs.header.get(key));
+        let merged = merge_value(b, o, t).unwrap_or_else(|| {
+            conflicts.push(conflict(WdbConflictKind::Header, None, Some(key), b, o, t));
+            o
+        });
+        if let Some(value) = merged {
+            header.insert(key.clone(), value.clone());
+        }
+    }
+
+    // Layout seeds only need to be consistent, so they come from one side
+    if let Some(source) = [ours, theirs, base].into_iter().find(|d| LAYOUT_HEADER_KEYS.iter().any(|k| d.header.contains_key(*k))) {
+        for key in LAYOUT_HEADER_KEYS {
+            if let Some(value) = source.header.get(key) {
+                header.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    // Records
+    let (base_ids, ours_ids, theirs_ids) = (index(base), index(ours), index(theirs));
+    let mut ids: Vec<&str> = base_ids.keys().copied().collect();
+    ids.extend(ours_ids.keys().filter(|id| !base_ids.contains_key(*id)));
+    ids.extend(theirs_ids.keys().filter(|id| !base_ids.contains_key(*id) && !ours_ids.contains_key(*id)));
+
+    let mut records = Vec::with_capacity(ids.len());
+    for id in ids {
+        let (b, o, t) = (base_ids.get(id).copied(), ours_ids.get(id).copied(), theirs_ids.get(id).copied());
+        match (b, o, t) {
+            (_, Some(o), Some(t)) => {
+                let kind = if b.is_some() { WdbConflictKind::BothModified } else { WdbConflictKind::BothAdded };
+                records.push(merge_record(id, b, o, t, kind, &mut conflicts));
+            }
+            (None, Some(r), None) | (None, None, Some(r)) => records.push(r.clone()),
+            (Some(b), Some(kept), None) | (Some(b), None, Some(kept)) => {
+                // Deleted on one side: fine if the other side left it alone
+                if !records_equal(b, kept) {
+                    let (ours_present, theirs_present) = (o.is_some(), t.is_some());
+                    conflicts.push(WdbMergeConflict {
+                        kind: WdbConflictKind::ModifiedAndDeleted,
+                        record: Some(id.to_string()),
+                        field: None,
+                        base: None,
+                        ours: ours_present.then(|| WdbValue::String(id.to_string())),
+                        theirs: theirs_present.then(|| WdbValue::String(id.to_string())),
+                    });
+                    records.push(kept.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    header.insert("recordCount".to_string(), WdbValue::UInt(records.len() as u32));
+    let mut merged = WdbData { header, records };
+    canonicalize_wdb(&mut merged);
+    WdbMergeResult { merged, conflicts }
+}
+
+/// Parses three WDB files and merges them with [`merge_wdb`].
+pub fn merge_wdb_files<P: AsRef<Path>>(
+    base_path: P,
+    ours_path: P,
+    theirs_path: P,
+    game_code: GameCode,
+) -> Result<WdbMergeResult> {
+    let base = parse_wdb(base_path, game_code).context("Failed to parse base WDB")?;
+    let ours = parse_wdb(ours_path, game_code).context("Failed to parse first modified WDB")?;
+    let theirs = parse_wdb(theirs_path, game_code).context("Failed to parse second modified WDB")?;
+    Ok(merge_wdb(&base, &ours, &theirs))
+}
+
+/// Applies a choice for one conflict to the merged data.
+///
+/// # Errors
+///
+/// Returns an error for [`WdbConflictKind::ModifiedAndDeleted`] conflicts
+/// (keep the record, or remove it from `merged` to side with the deletion)
+/// and when the conflicting record is no longer in `merged`.
+pub fn resolve_merge_conflict(
+    merged: &mut WdbData,
+    conflict: &WdbMergeConflict,
+    choice: WdbMergeChoice,
+) -> Result<()> {
+    let value = match choice {
+        WdbMergeChoice::Base => conflict.base.clone(),
+        WdbMergeChoice::Ours => conflict.ours.clone(),
+        WdbMergeChoice::Theirs => conflict.theirs.clone(),
+        WdbMergeChoice::Value(v) => Some(v),
+    };
+
+    let (target, key) = match (conflict.kind, &conflict.record, &conflict.field) {
+        (WdbConflictKind::ModifiedAndDeleted, Some(id), _) => {
+            bail!("Record '{}' was deleted on one side: keep it or remove it from the merge", id)
+        }
+        (WdbConflictKind::Header, _, Some(key)) => (&mut merged.header, key),
+        (_, Some(id), Some(field)) => {
+            let record = merged
+                .records
+                .iter_mut()
+                .find(|r| matches!(r.get("record"), Some(WdbValue::String(s)) if s == id))
+                .with_context(|| format!("Record '{}' is not in the merged data", id))?;
+            (record, field)
+        }
+        _ => bail!("Malformed merge conflict: {:?}", conflict),
+    };
+    match value {
+        Some(v) => {
+            target.insert(key.clone(), v);
+        }
+        None => {
+            target.shift_remove(key);
+        }
+    }
+    Ok(())
+}
+
+/// Merges one record present on both modified sides.
+fn merge_record(
+    id: &str,
+    base: Option<&WdbRecord>,
+    ours: &WdbRecord,
+    theirs: &WdbRecord,
+    kind: WdbConflictKind,
+    conflicts: &mut Vec<WdbMergeConflict>,
+) -> WdbRecord {
+    let mut fields: IndexSet<&String> = ours.keys().chain(theirs.keys()).collect();
+    if let Some(b) = base {
+        fields.extend(b.keys());
+    }
+
+    let mut merged = WdbRecord::new();
+    for field in fields {
+        let b = base.and_then(|r| r.get(field));
+        let (o, t) = (ours.get(field), theirs.get(field));
+        let value = merge_value(b, o, t).unwrap_or_else(|| {
+            conflicts.push(conflict(kind, Some(id), Some(field), b, o, t));
+            o
+        });
+        if let Some(v) = value {
+            merged.insert(field.clone(), v.clone());
+        }
+    }
+    merged
+}
+
+/// Three-way merge of one value; `None` means conflict.
+fn merge_value<'a>(
+    base: Option<&'a WdbValue>,
+    ours: Option<&'a WdbValue>,
+    theirs: Option<&'a WdbValue>,
+) -> Option<Option<&'a WdbValue>> {
+    if option_equal(ours, theirs) || option_equal(theirs, base) {
+        Some(ours)
+    } else if option_equal(ours, base) {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn conflict(
+    kind: WdbConflictKind,
+    record: Option<&str>,
+    field: Option<&String>,
+    base: Option<&WdbValue>,
+    ours: Option<&WdbValue>,
+    theirs: Option<&WdbValue>,
+) -> WdbMergeConflict {
+    WdbMergeConflict {
+        kind,
+        record: record.map(str::to_string),
+        field: field.cloned(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    }
+}
+
+/// Records by id, in file order.
+pub(crate) fn index(data: &WdbData) -> IndexMap<&str, &WdbRecord> {
+    data.records
+        .iter()
+        .filter_map(|r| match r.get("record") {
+            Some(WdbValue::String(id)) if !id.is_empty() => Some((id.as_str(), r)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn records_equal(a: &WdbRecord, b: &WdbRecord) -> bool {
+    a.keys().chain(b.keys()).all(|k| option_equal(a.get(k), b.get(k)))
+}
+
+pub(crate) fn option_equal(a: Option<&WdbValue>, b: Option<&WdbValue>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => values_equal(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Compares values by meaning (see the module docs).
+pub fn values_equal(a: &WdbValue, b: &WdbValue) -> bool {
+    fn number(v: &WdbValue) -> Option<i64> {
+        match v {
+            WdbValue::Int(i) => Some(*i as i64),
+            WdbValue::UInt(u) => Some(*u as i64),
+            WdbValue::CrystalRole(r) => Some(r.to_u32() as i64),
+            WdbValue::CrystalNodeType(n) => Some(n.to_u32() as i64),
+            _ => None,
+        }
+    }
+    fn numbers(v: &WdbValue) -> Option<Vec<i64>> {
+        match v {
+            WdbValue::IntArray(a) => Some(a.iter().map(|&i| i as i64).collect()),
+            WdbValue::UIntArray(a) => Some(a.iter().map(|&u| u as i64).collect()),
+            WdbValue::StringArray(a) if a.is_empty() => Some(Vec::new()),
+            _ => None,
+        }
+    }
+
+    if let (Some(x), Some(y)) = (number(a), number(b)) {
+        return x == y;
+    }
+    if let (Some(x), Some(y)) = (numbers(a), numbers(b)) {
+        return x == y;
+    }
+    match (a, b) {
+        (WdbValue::Float(x), WdbValue::Float(y)) => x == y || (x.is_nan() && y.is_nan()),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::tests::{data, record};
+
+    fn find<'a>(data: &'a WdbData, id: &str) -> Option<&'a WdbRecord> {
+        data.records.iter().find(|r| r["record"] == WdbValue::String(id.to_string()))
+    }
+
+    #[test]
+    fn test_merge_non_overlapping_edits() {
+        let base = data(vec![
+            record("it_a", &[("uPrice", WdbValue::UInt(100)), ("uSell", WdbValue::UInt(50))]),
+            record("it_b", &[("uPrice", WdbValue::UInt(200)), ("uSell", WdbValue::UInt(80))]),
+            record("it_c", &[("uPrice", WdbValue::UInt(300)), ("uSell", WdbValue::UInt(90))]),
+        ]);
+        // ours: edit it_a price (as Int, like JSON import would), delete it_c, add it_x
+        let ours = data(vec![
+            record("it_a", &[("uPrice", WdbValue::Int(999)), ("uSell", WdbValue::Int(50))]),
+            record("it_b", &[("uPrice", WdbValue::UInt(200)), ("uSell", WdbValue::UInt(80))]),
+            record("it_x", &[("uPrice", WdbValue::UInt(1)), ("uSell", WdbValue::UInt(1))]),
+        ]);
+        // theirs: edit it_a sell price and it_b, add it_y
+        let theirs = data(vec![
+            record("it_a", &[("uPrice", WdbValue::UInt(100)), ("uSell", WdbValue::UInt(5))]),
+            record("it_b", &[("uPrice", WdbValue::UInt(250)), ("uSell", WdbValue::UInt(80))]),
+            record("it_c", &[("uPrice", WdbValue::UInt(300)), ("uSell", WdbValue::UInt(90))]),
+            record("it_y", &[("uPrice", WdbValue::UInt(2)), ("uSell", WdbValue::UInt(2))]),
+        ]);
+
+        let result = merge_wdb(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty(), "{:?}", result.conflicts);
+
+        let ids: Vec<&WdbValue> = result.merged.records.iter().map(|r| &r["record"]).collect();
+        let names: Vec<WdbValue> = ["it_a", "it_b", "it_x", "it_y"].iter().map(|s| WdbValue::String(s.to_string())).collect();
+        assert_eq!(ids, names.iter().collect::<Vec<_>>());
+
+        let a = find(&result.merged, "it_a").unwrap();
+        assert!(values_equal(&a["uPrice"], &WdbValue::UInt(999)));
+        assert!(values_equal(&a["uSell"], &WdbValue::UInt(5)));
+        assert!(values_equal(&find(&result.merged, "it_b").unwrap()["uPrice"], &WdbValue::UInt(250)));
+        assert_eq!(result.merged.header["recordCount"], WdbValue::UInt(4));
+    }
+
+    #[test]
+    fn test_merge_conflicts_and_resolution() {
+        let base = data(vec![
+            record("it_a", &[("uPrice", WdbValue::UInt(100))]),
+            record("it_b", &[("uPrice", WdbValue::UInt(200))]),
+        ]);
+        let ours = data(vec![
+            record("it_a", &[("uPrice", WdbValue::UInt(111))]),
+            record("it_n", &[("uPrice", WdbValue::UInt(1))]),
+        ]);
+        let theirs = data(vec![
+            record("it_a", &[("uPrice", WdbValue::UInt(222))]),
+            record("it_b", &[("uPrice", WdbValue::UInt(201))]),
+            record("it_n", &[("uPrice", WdbValue::UInt(2))]),
+        ]);
+
+        let mut result = merge_wdb(&base, &ours, &theirs);
+        let kinds: Vec<(WdbConflictKind, Option<&str>)> =
+            result.conflicts.iter().map(|c| (c.kind, c.record.as_deref())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (WdbConflictKind::BothModified, Some("it_a")),
+                (WdbConflictKind::ModifiedAndDeleted, Some("it_b")),
+                (WdbConflictKind::BothAdded, Some("it_n")),
+            ]
+        );
+
+        // Unresolved conflicts keep ours, deleted-vs-modified keeps the record
+        assert_eq!(find(&result.merged, "it_a").unwrap()["uPrice"], WdbValue::UInt(111));
+        assert!(find(&result.merged, "it_b").is_some());
+
+        let conflicts = result.conflicts.clone();
+        resolve_merge_conflict(&mut result.merged, &conflicts[0], WdbMergeChoice::Theirs).unwrap();
+        resolve_merge_conflict(&mut result.merged, &conflicts[2], WdbMergeChoice::Value(WdbValue::UInt(3))).unwrap();
+        assert!(resolve_merge_conflict(&mut result.merged, &conflicts[1], WdbMergeChoice::Ours).is_err());
+        assert_eq!(find(&result.merged, "it_a").unwrap()["uPrice"], WdbValue::UInt(222));
+        assert_eq!(find(&result.merged, "it_n").unwrap()["uPrice"], WdbValue::UInt(3));
+    }
+}
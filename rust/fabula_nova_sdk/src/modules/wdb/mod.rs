@@ -57,6 +57,20 @@
 //! - [`bit_helpers`] - Bit-level field reading utilities
 //! - [`dicts`] - Field name dictionaries for FF13
 //! - [`enums`] - Enum types for typed field values
+//! - [`schema`] - Runtime-loadable sheet layouts and enum bindings
+//! - [`symbolic`] - Enum/flag name conversion for JSON and the editor
+//! - [`table`] - CSV/TSV export and import for spreadsheets
+//! - [`merge`] - Three-way merge of modified WDB files
+//! - [`patch`] - Declarative record patches with dry-run diffs
+//! - [`refs`] - Cross-references between sheets and ZTR text
+//! - [`validate`] - Pre-write checks for values the writer would alter
+//! - [`sections`] - Original layout and unknown sections kept for lossless repacks
+//! - [`infer`] - Draft schemas for unknown sheets from sibling games and value statistics
+//! - [`sqlite`] - SQLite export of WDB sets and import of edited sheets
+//! - [`records`] - Typed record structs generated from the built-in FF13 schema pack
+//! - [`wdbpack`] - One-call unpack of `wdbpack.bin` to editable sheets and repack
+//! - [`content`] - Change sets that add items, weapons or abilities across sheets and text
+//! - [`randomizer`] - Seeded treasure, shop and drop randomization with logic and spoiler logs
 //!
 //! ## Usage Example
 //!
@@ -87,6 +101,20 @@ pub mod api;
 pub mod writer;
 pub mod dicts;
 pub mod enums;
+pub mod schema;
+pub mod symbolic;
+pub mod table;
+pub mod merge;
+pub mod patch;
+pub mod refs;
+pub mod validate;
+pub mod sections;
+pub mod infer;
+pub mod sqlite;
+pub mod records;
+pub mod wdbpack;
+pub mod content;
+pub mod randomizer;
 mod enum_registry;
 
 // Re-export all public items
@@ -97,6 +125,20 @@ pub use api::*;
 pub use writer::*;
 pub use dicts::*;
 pub use enums::*;
+pub use schema::*;
+pub use symbolic::*;
+pub use table::*;
+pub use merge::*;
+pub use patch::*;
+pub use refs::*;
+pub use validate::*;
+pub use sections::*;
+pub use infer::*;
+pub use sqlite::*;
+pub use records::*;
+pub use wdbpack::*;
+pub use content::*;
+pub use randomizer::*;
 
 #[cfg(test)]
 mod tests {
@@ -408,4 +450,466 @@ mod tests {
 
         println!("\n=== All comprehensive tests passed! ===");
     }
-}
\ No newline at end of file
+
+    /// A user schema pack names the fields of a file the built-in dicts don't know
+    #[test]
+    fn test_schema_override_names_fields() {
+        use super::schema::{
+            add_schema_override_dir, SchemaOverrideGuard, WdbFieldSchema, WdbSchemaPack, WdbSheetSchema,
+            WDB_SCHEMA_VERSION,
+        };
+        use super::structs::{GameCode, WdbData, WdbHeader, WdbRecord, WdbValue};
+
+        let _overrides = SchemaOverrideGuard::lock();
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_schema_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("schemas")).unwrap();
+
+        // The bitpacked word must be filled to 32 bits, as in retail files
+        let fields: Vec<String> = ["u8Level", "i8Bonus", "u16Reserved", "sName"].iter().map(|s| s.to_string()).collect();
+        let mut header = WdbHeader::new();
+        header.insert("!structitem".to_string(), WdbValue::StringArray(fields.clone()));
+        header.insert("!!strtypelist".to_string(), WdbValue::UIntArray(vec![0, 2]));
+        header.insert("!!typelist".to_string(), WdbValue::IntArray(vec![0, 2]));
+        header.insert("!!version".to_string(), WdbValue::UInt(1));
+        let mut record = WdbRecord::new();
+        record.insert("record".to_string(), WdbValue::String("lv_001".to_string()));
+        record.insert("u8Level".to_string(), WdbValue::UInt(42));
+        record.insert("i8Bonus".to_string(), WdbValue::Int(-3));
+        record.insert("u16Reserved".to_string(), WdbValue::UInt(0));
+        record.insert("sName".to_string(), WdbValue::String("name_001".to_string()));
+        let data = WdbData { header, records: vec![record] };
+
+        let wdb_path = dir.join("schema_test_levels.wdb");
+        super::api::pack_wdb(&data, &wdb_path, GameCode::FF13_1).unwrap();
+
+        // XIII files carry no !structitem, so without a schema the fields are anonymous
+        let parsed = super::api::parse_wdb(&wdb_path, GameCode::FF13_1).unwrap();
+        assert!(parsed.records[0].contains_key("bitpacked-field_0"));
+
+        let pack = WdbSchemaPack {
+            version: WDB_SCHEMA_VERSION,
+            game: GameCode::FF13_1,
+            enums: Vec::new(),
+            sheets: vec![WdbSheetSchema {
+                name: "LevelTable".to_string(),
+                files: vec!["schema_test_levels".to_string()],
+                fields: fields.iter().map(|f| WdbFieldSchema::from_name(f).unwrap()).collect(),
+                description: None,
+            }],
+        };
+        std::fs::write(dir.join("schemas/levels.json"), serde_json::to_string_pretty(&pack).unwrap()).unwrap();
+        assert_eq!(add_schema_override_dir(dir.join("schemas")).unwrap(), 1);
+
+        let parsed = super::api::parse_wdb(&wdb_path, GameCode::FF13_1).unwrap();
+        assert_eq!(parsed.header.get("sheetName"), Some(&WdbValue::String("LevelTable".to_string())));
+        let rec = &parsed.records[0];
+        assert_eq!(rec.get("u8Level"), Some(&WdbValue::UInt(42)));
+        assert_eq!(rec.get("i8Bonus"), Some(&WdbValue::Int(-3)));
+        assert_eq!(rec.get("sName"), Some(&WdbValue::String("name_001".to_string())));
+    }
+
+    /// Schema enums and flags decode to names and accept names or numbers back
+    #[test]
+    fn test_symbolic_enum_and_flag_fields() {
+        use super::api::{pack_wdb, parse_wdb, wdb_from_json_string, wdb_to_json_string};
+        use super::schema::*;
+        use super::structs::{GameCode, WdbData, WdbHeader, WdbRecord, WdbValue};
+
+        let _overrides = SchemaOverrideGuard::lock();
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_symbolic_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("schemas")).unwrap();
+
+        let field_names = ["u4Element", "u8Status", "u20Reserved", "sName"];
+        let fields: Vec<String> = field_names.iter().map(|s| s.to_string()).collect();
+        let mut header = WdbHeader::new();
+        header.insert("!structitem".to_string(), WdbValue::StringArray(fields.clone()));
+        header.insert("!!strtypelist".to_string(), WdbValue::UIntArray(vec![0, 2]));
+        header.insert("!!typelist".to_string(), WdbValue::IntArray(vec![0, 2]));
+        header.insert("!!version".to_string(), WdbValue::UInt(1));
+        let records = [("eq_001", 2, 0b101), ("eq_002", 9, 0b1000)]
+            .iter()
+            .map(|(name, element, status)| {
+                let mut record = WdbRecord::new();
+                record.insert("record".to_string(), WdbValue::String(name.to_string()));
+                record.insert("u4Element".to_string(), WdbValue::UInt(*element));
+                record.insert("u8Status".to_string(), WdbValue::UInt(*status));
+                record.insert("u20Reserved".to_string(), WdbValue::UInt(0));
+                record.insert("sName".to_string(), WdbValue::String(name.to_string()));
+                record
+            })
+            .collect();
+        let wdb_path = dir.join("symbolic_test_gear.wdb");
+        pack_wdb(&WdbData { header, records }, &wdb_path, GameCode::FF13_1).unwrap();
+
+        let value = |name: &str, value| WdbEnumValue { name: name.to_string(), value, description: None };
+        // Enum names no built-in pack or generated type uses
+        let mut element = WdbFieldSchema::from_name("u4Element").unwrap();
+        element.enum_type = Some("SymbolicTestElement".to_string());
+        let mut status = WdbFieldSchema::from_name("u8Status").unwrap();
+        status.enum_type = Some("SymbolicTestStatus".to_string());
+        let pack = WdbSchemaPack {
+            version: WDB_SCHEMA_VERSION,
+            game: GameCode::FF13_1,
+            enums: vec![
+                WdbEnumSchema {
+                    name: "SymbolicTestElement".to_string(),
+                    flags: false,
+                    values: vec![value("Fire", 1), value("Ice", 2), value("Thunder", 3)],
+                    description: None,
+                },
+                WdbEnumSchema {
+                    name: "SymbolicTestStatus".to_string(),
+                    flags: true,
+                    values: vec![value("Poison", 1), value("Slow", 2), value("Curse", 4)],
+                    description: None,
+                },
+            ],
+            sheets: vec![WdbSheetSchema {
+                name: "Gear".to_string(),
+                files: vec!["symbolic_test_gear".to_string()],
+                fields: vec![
+                    element,
+                    status,
+                    WdbFieldSchema::from_name("u20Reserved").unwrap(),
+                    WdbFieldSchema::from_name("sName").unwrap(),
+                ],
+                description: None,
+            }],
+        };
+        std::fs::write(dir.join("schemas/gear.json"), serde_json::to_string_pretty(&pack).unwrap()).unwrap();
+        add_schema_override_dir(dir.join("schemas")).unwrap();
+
+        // Known values decode to names, unnamed values and bits stay numeric
+        let data = parse_wdb(&wdb_path, GameCode::FF13_1).unwrap();
+        let names = |v: &[&str]| WdbValue::StringArray(v.iter().map(|s| s.to_string()).collect());
+        assert_eq!(data.records[0]["u4Element"], WdbValue::String("Ice".to_string()));
+        assert_eq!(data.records[0]["u8Status"], names(&["Poison", "Curse"]));
+        assert_eq!(data.records[1]["u4Element"], WdbValue::UInt(9));
+        assert_eq!(data.records[1]["u8Status"], WdbValue::UInt(8));
+
+        // JSON round trip keeps the symbolic form
+        let json = wdb_to_json_string(&data).unwrap();
+        assert!(json.contains("\"Ice\""));
+        let mut edited = wdb_from_json_string(&json).unwrap();
+        for (a, b) in edited.records.iter().zip(&data.records) {
+            assert_eq!((&a["u4Element"], &a["u8Status"]), (&b["u4Element"], &b["u8Status"]));
+        }
+
+        // Numbers are accepted and normalised, unknown names are rejected
+        let numeric = json.replacen("\"Ice\"", "3", 1);
+        assert_eq!(wdb_from_json_string(&numeric).unwrap().records[0]["u4Element"], WdbValue::String("Thunder".to_string()));
+        assert!(wdb_from_json_string(&json.replacen("\"Ice\"", "\"Water\"", 1)).is_err());
+        assert!(wdb_from_json_string(&json.replacen("\"Curse\"", "\"Doom\"", 1)).is_err());
+
+        // Edited names are written back as numbers
+        edited.records[0].insert("u4Element".to_string(), WdbValue::String("Fire".to_string()));
+        edited.records[0].insert("u8Status".to_string(), names(&["Slow"]));
+        pack_wdb(&edited, &wdb_path, GameCode::FF13_1).unwrap();
+        let reparsed = parse_wdb(&wdb_path, GameCode::FF13_1).unwrap();
+        assert_eq!(reparsed.records[0]["u4Element"], WdbValue::String("Fire".to_string()));
+        assert_eq!(reparsed.records[0]["u8Status"], names(&["Slow"]));
+        assert_eq!(reparsed.records[1]["u4Element"], WdbValue::UInt(9));
+
+        let mut bad = edited.clone();
+        bad.records[0].insert("u4Element".to_string(), WdbValue::String("Wind".to_string()));
+        assert!(pack_wdb(&bad, dir.join("bad.wdb"), GameCode::FF13_1).is_err());
+    }
+
+    /// JSON export is byte-stable and parse -> JSON -> parse -> JSON is idempotent
+    #[test]
+    fn test_json_order_is_stable() {
+        use super::api::{canonicalize_wdb, pack_wdb, parse_wdb, wdb_from_json_string, wdb_to_json_string};
+        use super::structs::{GameCode, WdbData, WdbHeader, WdbRecord, WdbValue};
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_order_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let fields = ["u8Level", "i8Bonus", "u16Reserved", "sName", "fRate"];
+        let mut header = WdbHeader::new();
+        header.insert("!!version".to_string(), WdbValue::UInt(1));
+        header.insert("!structitem".to_string(), WdbValue::StringArray(fields.iter().map(|s| s.to_string()).collect()));
+        header.insert("!!strtypelist".to_string(), WdbValue::UIntArray(vec![0, 2, 1]));
+        header.insert("!!typelist".to_string(), WdbValue::IntArray(vec![0, 2, 1]));
+        header.insert("gameCode".to_string(), WdbValue::String("FF13_2".to_string()));
+        let records: Vec<WdbRecord> = (0..20)
+            .map(|i| {
+                // Fields deliberately inserted out of schema order
+                let mut record = WdbRecord::new();
+                record.insert("fRate".to_string(), WdbValue::Float(i as f32 / 4.0));
+                record.insert("sName".to_string(), WdbValue::String(format!("name_{:03}", i)));
+                record.insert("u16Reserved".to_string(), WdbValue::UInt(0));
+                record.insert("i8Bonus".to_string(), WdbValue::Int(-i));
+                record.insert("u8Level".to_string(), WdbValue::UInt(i as u32));
+                record.insert("record".to_string(), WdbValue::String(format!("lv_{:03}", i)));
+                record
+            })
+            .collect();
+        let data = WdbData { header, records };
+        let wdb_path = dir.join("order_test.wdb");
+        pack_wdb(&data, &wdb_path, GameCode::FF13_2).unwrap();
+
+        let first = wdb_to_json_string(&parse_wdb(&wdb_path, GameCode::FF13_2).unwrap()).unwrap();
+        let again = wdb_to_json_string(&parse_wdb(&wdb_path, GameCode::FF13_2).unwrap()).unwrap();
+        assert_eq!(first, again);
+
+        let reparsed = wdb_from_json_string(&first).unwrap();
+        let keys: Vec<&str> = reparsed.records[0].keys().map(String::as_str).collect();
+        assert_eq!(keys, ["record", "u8Level", "i8Bonus", "u16Reserved", "sName", "fRate"]);
+        assert_eq!(reparsed.header.keys().next().map(String::as_str), Some("gameCode"));
+        let second = wdb_to_json_string(&reparsed).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second, wdb_to_json_string(&wdb_from_json_string(&second).unwrap()).unwrap());
+
+        // Canonical order doesn't depend on how the data was built
+        let mut built = data.clone();
+        canonicalize_wdb(&mut built);
+        let mut shuffled = data;
+        shuffled.header.reverse();
+        for record in &mut shuffled.records {
+            record.reverse();
+        }
+        canonicalize_wdb(&mut shuffled);
+        assert_eq!(wdb_to_json_string(&built).unwrap(), wdb_to_json_string(&shuffled).unwrap());
+    }
+
+    /// Lays out a WDB file the way the game's files are: section headers,
+    /// record headers, then each section's data padded to 4 bytes.
+    pub(crate) fn build_wdb(sections: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"WPD\0".to_vec();
+        out.extend((sections.len() as u32).to_be_bytes());
+        out.extend([0u8; 8]);
+        let mut offset = 16 + 32 * sections.len();
+        let mut body = Vec::new();
+        for (name, data) in sections {
+            let mut entry = name.as_bytes().to_vec();
+            entry.resize(16, 0);
+            entry.extend((offset as u32).to_be_bytes());
+            entry.extend((data.len() as u32).to_be_bytes());
+            entry.extend([0u8; 8]);
+            out.extend(entry);
+            body.extend(data);
+            body.resize(body.len().next_multiple_of(4), 0);
+            offset = 16 + 32 * sections.len() + body.len();
+        }
+        out.extend(body);
+        out
+    }
+
+    /// A record with id `id`, followed by `fields` in order.
+    pub(crate) fn record(id: &str, fields: &[(&str, super::structs::WdbValue)]) -> super::structs::WdbRecord {
+        use super::structs::{WdbRecord, WdbValue};
+
+        let mut r = WdbRecord::new();
+        r.insert("record".to_string(), WdbValue::String(id.to_string()));
+        for (k, v) in fields {
+            r.insert(k.to_string(), v.clone());
+        }
+        r
+    }
+
+    /// A sheet of `records` with only a `!!version` header.
+    pub(crate) fn data(records: Vec<super::structs::WdbRecord>) -> super::structs::WdbData {
+        use super::structs::{WdbData, WdbHeader, WdbValue};
+
+        let mut header = WdbHeader::new();
+        header.insert("!!version".to_string(), WdbValue::UInt(1));
+        WdbData { header, records }
+    }
+
+    /// Unknown sections, section order, pool layout and string array order
+    /// survive parse -> pack (and parse -> JSON -> pack) unchanged
+    #[test]
+    fn test_unknown_sections_round_trip() {
+        use super::api::{pack_wdb, parse_wdb, wdb_from_json_string, wdb_to_json_string};
+        use super::structs::{GameCode, WdbValue};
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_sections_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Pool with an unused string, and "Potion" only as the tail of "HiPotion"
+        let pool = b"\0unused\0alpha\0beta\0HiPotion\0".to_vec();
+        let (alpha, beta, potion) = (8u32, 14u32, 21u32);
+        // s8Tag lists "alpha" first although records use "beta" first
+        let str_array = ((beta << 16) | alpha).to_be_bytes().to_vec();
+        let word = |tag: u32, level: u32| (tag | (level << 8)).to_be_bytes();
+        let record = |tag, level, name: u32| [word(tag, level), name.to_be_bytes()].concat();
+        let bytes = build_wdb(&[
+            ("!!string", pool),
+            ("!!strArray", str_array),
+            ("!!strArrayInfo", vec![0, 0, 2, 16]),
+            ("!!strArrayList", vec![0, 0, 0, 0]),
+            ("!!custom", vec![1, 2, 3]),
+            ("!!strtypelist", [0u32, 2].iter().flat_map(|v| v.to_be_bytes()).collect()),
+            ("!!typelist", [0u32, 2].iter().flat_map(|v| v.to_be_bytes()).collect()),
+            ("!!version", 1u32.to_be_bytes().to_vec()),
+            ("!structitem", b"s8Tag\0u24Level\0sName\0".to_vec()),
+            ("!structitemnum", 3u32.to_be_bytes().to_vec()),
+            ("rec_001", record(1, 10, potion)),
+            ("rec_002", record(0, 20, alpha)),
+        ]);
+        let original = dir.join("sections_test.wdb");
+        std::fs::write(&original, &bytes).unwrap();
+
+        let data = parse_wdb(&original, GameCode::FF13_2).unwrap();
+        assert_eq!(data.records[0]["s8Tag"], WdbValue::String("beta".to_string()));
+        assert_eq!(data.records[0]["sName"], WdbValue::String("Potion".to_string()));
+        assert_eq!(data.header["!!custom"], WdbValue::String("010203".to_string()));
+
+        let repacked = dir.join("repacked.wdb");
+        pack_wdb(&data, &repacked, GameCode::FF13_2).unwrap();
+        assert_eq!(std::fs::read(&repacked).unwrap(), bytes);
+
+        let via_json = wdb_from_json_string(&wdb_to_json_string(&data).unwrap()).unwrap();
+        pack_wdb(&via_json, &repacked, GameCode::FF13_2).unwrap();
+        assert_eq!(std::fs::read(&repacked).unwrap(), bytes);
+
+        // Edits append new text and keep the unknown section
+        let mut edited = data.clone();
+        edited.records[1].insert("sName".to_string(), WdbValue::String("Elixir".to_string()));
+        edited.records[1].insert("s8Tag".to_string(), WdbValue::String("gamma".to_string()));
+        pack_wdb(&edited, &repacked, GameCode::FF13_2).unwrap();
+        let reparsed = parse_wdb(&repacked, GameCode::FF13_2).unwrap();
+        assert_eq!(reparsed.header["!!custom"], WdbValue::String("010203".to_string()));
+        for (a, b) in reparsed.records.iter().zip(&edited.records) {
+            assert_eq!(a, b);
+        }
+    }
+
+    /// Every WDB in Lightning Returns' wdbpack.bin repacks byte for byte
+    #[test]
+    fn test_wdbpack_repacks_byte_identical() {
+        use super::api::{pack_wdb, parse_wdb};
+        use super::structs::GameCode;
+        use crate::modules::wpd::api::unpack_wpd;
+
+        let wpd_path = PathBuf::from("/Users/hramirez/Desktop/Development/ff13-lr_data/white_img2a/db/resident/wdbpack.bin");
+        if !wpd_path.exists() {
+            eprintln!("Test file not found: {:?}", wpd_path);
+            return;
+        }
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdbpack_lossless_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let unpacked = dir.join("unpacked");
+        unpack_wpd(&wpd_path, &unpacked).expect("Failed to unpack WPD");
+
+        let mut checked = 0;
+        let mut mismatched = Vec::new();
+        for entry in std::fs::read_dir(&unpacked).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) != Some("wdb") {
+                continue;
+            }
+            let data = parse_wdb(&path, GameCode::FF13_3).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+            let repacked = dir.join(path.file_name().unwrap());
+            pack_wdb(&data, &repacked, GameCode::FF13_3).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+            if std::fs::read(&path).unwrap() != std::fs::read(&repacked).unwrap() {
+                mismatched.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            }
+            checked += 1;
+        }
+        println!("Checked {} WDB files", checked);
+        assert!(mismatched.is_empty(), "Not byte-identical: {:?}", mismatched);
+    }
+
+    /// A wdbpack of synthetic XIII and LR sheets repacks byte for byte:
+    /// optional sections, pool strings shared by tail or never used, and
+    /// the byte-sized `!!strtypelistb` of XIII-2/LR
+    #[test]
+    fn test_synthetic_wdbpack_repacks_byte_identical() {
+        use super::api::{pack_wdb_bytes, parse_wdb_bytes};
+        use super::structs::{GameCode, WdbValue};
+        use crate::modules::wpd::reader::WpdReader;
+        use crate::modules::wpd::structs::WpdRecord;
+        use crate::modules::wpd::writer::WpdWriter;
+        use std::io::Cursor;
+
+        let words = |values: &[u32]| values.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        let wdb = |name: &str, sections: &[(&str, Vec<u8>)]| WpdRecord {
+            name: name.to_string(),
+            extension: "wdb".to_string(),
+            data: build_wdb(sections),
+        };
+        let pack = |records: &[WpdRecord]| {
+            let mut buffer = Cursor::new(Vec::new());
+            WpdWriter::new(&mut buffer).write(records).unwrap();
+            buffer.into_inner()
+        };
+
+        // XIII: "unused" is never referenced, "Potion" is the tail of "HiPotion"
+        // and !structitem holds offsets of the field names in the pool
+        let pool = b"\0uPrice\0sName\0unused\0HiPotion\0".to_vec();
+        let (price, name, potion, hi_potion) = (1, 8, 23, 21);
+        let xiii = pack(&[
+            wdb("db_named", &[
+                ("!!string", pool),
+                ("!!strtypelist", words(&[3, 2])),
+                ("!!typelist", words(&[3, 2])),
+                ("!!version", words(&[1])),
+                ("!structitem", words(&[price, name])),
+                ("!structitemnum", words(&[2])),
+                ("it_001", words(&[50, potion])),
+                ("it_002", words(&[300, hi_potion])),
+            ]),
+            wdb("db_plain", &[
+                ("!!string", b"\0unused\0Ether\0".to_vec()),
+                ("!!strtypelist", words(&[3, 2])),
+                ("!!typelist", words(&[3, 2])),
+                ("!!version", words(&[1])),
+                ("it_003", words(&[7, 8])),
+            ]),
+        ]);
+
+        // XIII-2/LR: one byte per !!strtypelistb entry, with and without
+        // !!sheetname and !!typelist
+        let lr = pack(&[
+            wdb("db_goods", &[
+                ("!!sheetname", b"Goods".to_vec()),
+                ("!!string", b"\0unused\0HiPotion\0".to_vec()),
+                ("!!strtypelistb", vec![3, 2]),
+                ("!!typelist", words(&[3, 2])),
+                ("!!version", words(&[1])),
+                ("!structitem", b"uPrice\0sName\0".to_vec()),
+                ("!structitemnum", words(&[2])),
+                ("it_001", words(&[50, 10])),
+                ("it_002", words(&[300, 8])),
+            ]),
+            wdb("db_shop", &[
+                ("!!string", b"\0Ether\0".to_vec()),
+                ("!!strtypelistb", vec![2, 3]),
+                ("!!version", words(&[2])),
+                ("!structitem", b"sLabel\0uStock\0".to_vec()),
+                ("!structitemnum", words(&[2])),
+                ("shop_001", words(&[1, 99])),
+            ]),
+        ]);
+
+        let mut names = Vec::new();
+        for (bytes, game_code) in [(xiii, GameCode::FF13_1), (lr, GameCode::FF13_3)] {
+            let mut reader = WpdReader::new(Cursor::new(&bytes));
+            let header = reader.read_header().unwrap();
+            let records: Vec<WpdRecord> = reader
+                .read_records(&header)
+                .unwrap()
+                .into_iter()
+                .map(|mut record| {
+                    let data = parse_wdb_bytes(&record.data, &record.name, game_code).unwrap();
+                    let repacked = pack_wdb_bytes(&data, game_code).unwrap();
+                    assert_eq!(repacked, record.data, "{} ({:?})", record.name, game_code);
+                    names.extend(data.records.iter().filter_map(|r| r.get("sName").cloned()));
+                    record.data = repacked;
+                    record
+                })
+                .collect();
+            assert_eq!(pack(&records), bytes, "{:?}", game_code);
+        }
+        let text = |s: &str| WdbValue::String(s.to_string());
+        assert_eq!(names, [text("Potion"), text("HiPotion"), text("Potion"), text("HiPotion")]);
+    }
+}
@@ -0,0 +1,699 @@
+//! # WDB Patches
+//!
+//! This module applies declarative patches to [`WdbData`]: a short list of
+//! operations that select records by id or by field values and edit them.
+//! Patches target record ids and field names rather than byte offsets, so
+//! they keep working when a game update moves records around.
+//!
+//! ## Operations
+//!
+//! | Operation  | Effect                                                  |
+//! |------------|---------------------------------------------------------|
+//! | `set`      | Assigns field values                                    |
+//! | `add`      | Adds an amount to numeric fields                        |
+//! | `multiply` | Multiplies numeric fields (integers are rounded)        |
+//! | `clamp`    | Limits a numeric field to `min..max`                    |
+//! | `clone`    | Copies a record under a new id (appended at the end)    |
+//! | `delete`   | Removes the selected records                            |
+//!
+//! Integer results are checked against the field's bit width, and edits to
+//! fields a record doesn't have are errors, so a typo fails loudly instead
+//! of writing a new field the writer would ignore.
+//!
+//! ## Text Format
+//!
+//! One operation per line; `#` starts a comment. The selector is `*`, a
+//! comma-separated list of ids, or an id prefix ending in `*`, optionally
+//! followed by `where` predicates joined with `and`:
+//!
+//! ```text
+//! name Daily adjust scaling
+//! sheet r_btdailyadjust
+//! set * i12Day1=100 i12Day2=125 i12Day3=150
+//! multiply it_* uPrice=1.5 where uPrice>=1000 and uPrice<5000
+//! clamp * uPrice 0..65535
+//! clone it_potion as it_potion_big uPrice=500 sHelpStringId="$it_potion_big_h"
+//! delete it_test1,it_test2
+//! ```
+//!
+//! Values and predicate operands use JSON syntax (`100`, `1.5`, `"text"`,
+//! `["Poison","Slow"]`); anything that isn't valid JSON is taken as a bare
+//! string. Predicates are written without spaces (`uPrice>=1000`) and
+//! support `==`, `=`, `!=`, `<`, `<=`, `>`, `>=`.
+//!
+//! ## JSON Format
+//!
+//! The same patch serializes as [`WdbPatch`]:
+//!
+//! ```text
+//! { "sheet": "r_btdailyadjust",
+//!   "operations": [
+//!     { "op": "set", "fields": { "i12Day1": 100 } },
+//!     { "op": "multiply", "select": { "ids": ["it_*"],
+//!       "where": [{ "field": "uPrice", "op": "ge", "value": 1000 }] },
+//!       "fields": { "uPrice": 1.5 } } ] }
+//! ```
+//!
+//! ## Dry Run
+//!
+//! [`apply_wdb_patch`] never modifies its input. It returns the patched
+//! data with a field-level diff ([`WdbRecordChange`]), so the editor can
+//! show what a patch would do before anything is saved.
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexSet;
+use std::fs;
+use std::path::Path;
+
+use super::api::{pack_wdb, parse_wdb};
+use super::merge::{index, option_equal, values_equal};
+use super::structs::{
+    GameCode, WdbChangeKind, WdbCompareOp, WdbData, WdbPatch, WdbPatchOp, WdbPatchResult, WdbPredicate,
+    WdbRecord, WdbRecordChange, WdbRecordSelector, WdbValue,
+};
+use super::symbolic::SCHEMA_SHEET_KEY;
+use super::table::check_range;
+
+/// Name of the record id field.
+const RECORD_FIELD: &str = "record";
+
+/// Predicate operators in the text format, longest first.
+const COMPARE_OPS: [(&str, WdbCompareOp); 7] = [
+    ("==", WdbCompareOp::Eq),
+    ("!=", WdbCompareOp::Ne),
+    ("<=", WdbCompareOp::Le),
+    (">=", WdbCompareOp::Ge),
+    ("=", WdbCompareOp::Eq),
+    ("<", WdbCompareOp::Lt),
+    (">", WdbCompareOp::Gt),
+];
+
+// =============================================================================
+// Loading
+// =============================================================================
+
+/// Parses a patch in either the JSON or the text format.
+///
+/// Text starting with `{` is read as JSON.
+///
+/// # Errors
+///
+/// Returns an error naming the line (text format) or position (JSON) of the
+/// first syntax problem.
+pub fn parse_wdb_patch(text: &str) -> Result<WdbPatch> {
+    if text.trim_start().starts_with('{') {
+        return serde_json::from_str(text).context("Invalid JSON patch");
+    }
+
+    let mut patch = WdbPatch::default();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        parse_line(line, &mut patch).with_context(|| format!("Line {}: {}", n + 1, line))?;
+    }
+    Ok(patch)
+}
+
+/// Reads and parses a patch file.
+pub fn load_wdb_patch<P: AsRef<Path>>(path: P) -> Result<WdbPatch> {
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read patch {}", path.as_ref().display()))?;
+    parse_wdb_patch(&text)
+}
+
+fn parse_line(line: &str, patch: &mut WdbPatch) -> Result<()> {
+    let tokens = tokenize(line)?;
+    let (keyword, rest) = tokens.split_first().context("Empty line")?;
+    let (args, filter) = match rest.iter().position(|t| t == "where") {
+        Some(i) => (&rest[..i], parse_where(&rest[i + 1..])?),
+        None => (rest, Vec::new()),
+    };
+
+    match keyword.as_str() {
+        "name" | "sheet" | "clone" if !filter.is_empty() => bail!("'{}' does not take a where clause", keyword),
+        "name" => patch.name = Some(line[keyword.len()..].trim().to_string()),
+        "sheet" => match args {
+            [sheet] => patch.sheet = Some(sheet.clone()),
+            _ => bail!("Expected: sheet <name>"),
+        },
+        "clone" => match args {
+            [from, as_kw, id, assignments @ ..] if as_kw == "as" => patch.operations.push(WdbPatchOp::Clone {
+                from: from.clone(),
+                id: id.clone(),
+                set: assignments.iter().map(|a| parse_assignment(a)).collect::<Result<_>>()?,
+            }),
+            _ => bail!("Expected: clone <from> as <id> [field=value ...]"),
+        },
+        "set" | "add" | "multiply" | "mul" | "clamp" | "delete" => {
+            let (ids, args) = args.split_first().context("Missing record selector")?;
+            let select = WdbRecordSelector {
+                ids: if ids == "*" { Vec::new() } else { ids.split(',').map(str::to_string).collect() },
+                filter,
+            };
+            let op = match keyword.as_str() {
+                "set" => WdbPatchOp::Set {
+                    select,
+                    fields: args.iter().map(|a| parse_assignment(a)).collect::<Result<_>>()?,
+                },
+                "add" | "multiply" | "mul" => {
+                    let fields = args
+                        .iter()
+                        .map(|a| {
+                            let (field, value) = parse_assignment(a)?;
+                            let amount = as_number(&value).with_context(|| format!("'{}' needs a number", field))?;
+                            Ok((field, amount))
+                        })
+                        .collect::<Result<_>>()?;
+                    if keyword == "add" {
+                        WdbPatchOp::Add { select, fields }
+                    } else {
+                        WdbPatchOp::Multiply { select, fields }
+                    }
+                }
+                "clamp" => match args {
+                    [field, range] => {
+                        let (min, max) = range.split_once("..").context("Expected a range like 0..100")?;
+                        let bound = |s: &str| -> Result<Option<f64>> {
+                            if s.is_empty() {
+                                return Ok(None);
+                            }
+                            s.parse().map(Some).map_err(|_| anyhow!("'{}' is not a number", s))
+                        };
+                        WdbPatchOp::Clamp { select, field: field.clone(), min: bound(min)?, max: bound(max)? }
+                    }
+                    _ => bail!("Expected: clamp <selector> <field> <min>..<max>"),
+                },
+                _ => match args {
+                    [] => WdbPatchOp::Delete { select },
+                    _ => bail!("Expected: delete <selector> [where ...]"),
+                },
+            };
+            patch.operations.push(op);
+        }
+        other => bail!("Unknown operation '{}'", other),
+    }
+    Ok(())
+}
+
+/// Splits a line on whitespace, keeping quoted strings and `[...]` together.
+fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let (mut in_quotes, mut escaped, mut depth) = (false, false, 0usize);
+    for c in line.chars() {
+        if in_quotes {
+            current.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if in_quotes {
+        bail!("Unterminated string");
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_assignment(token: &str) -> Result<(String, WdbValue)> {
+    let (field, value) = token
+        .split_once('=')
+        .with_context(|| format!("Expected field=value, got '{}'", token))?;
+    Ok((field.to_string(), parse_value(value)))
+}
+
+fn parse_where(tokens: &[String]) -> Result<Vec<WdbPredicate>> {
+    let predicates: Vec<WdbPredicate> = tokens
+        .iter()
+        .filter(|t| *t != "and")
+        .map(|token| {
+            let pos = token
+                .find(['=', '!', '<', '>'])
+                .with_context(|| format!("Expected a comparison like uPrice>100, got '{}'", token))?;
+            let rest = &token[pos..];
+            let (symbol, op) = COMPARE_OPS
+                .iter()
+                .find(|(symbol, _)| rest.starts_with(symbol))
+                .with_context(|| format!("Unknown comparison in '{}'", token))?;
+            Ok(WdbPredicate {
+                field: token[..pos].to_string(),
+                op: *op,
+                value: parse_value(&rest[symbol.len()..]),
+            })
+        })
+        .collect::<Result<_>>()?;
+    if predicates.is_empty() {
+        bail!("Empty where clause");
+    }
+    Ok(predicates)
+}
+
+/// JSON value, or the text itself as a string.
+fn parse_value(text: &str) -> WdbValue {
+    serde_json::from_str(text).unwrap_or_else(|_| WdbValue::String(text.to_string()))
+}
+
+// =============================================================================
+// Applying
+// =============================================================================
+
+/// Applies a patch to a copy of the data and diffs the result.
+///
+/// # Arguments
+///
+/// * `data` - The data to patch (left unchanged)
+/// * `patch` - The operations to apply, in order
+///
+/// # Returns
+///
+/// The patched data and every record/field that differs from `data`.
+///
+/// # Errors
+///
+/// Returns an error, naming the operation, when the patch targets another
+/// sheet, an explicit id or field is missing, or a value doesn't fit.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let patch = wdb::parse_wdb_patch("set * i12Day1=100")?;
+/// let result = wdb::apply_wdb_patch(&data, &patch)?;
+/// for change in &result.changes {
+///     println!("{} {:?}: {:?} -> {:?}", change.record, change.field, change.before, change.after);
+/// }
+/// ```
+pub fn apply_wdb_patch(data: &WdbData, patch: &WdbPatch) -> Result<WdbPatchResult> {
+    let mut patched = data.clone();
+    apply_wdb_patch_in_place(&mut patched, patch)?;
+    let changes = diff_wdb(data, &patched);
+    Ok(WdbPatchResult { data: patched, changes })
+}
+
+/// Applies a patch directly to `data`.
+///
+/// On error, `data` may be partially patched.
+pub fn apply_wdb_patch_in_place(data: &mut WdbData, patch: &WdbPatch) -> Result<()> {
+    if let Some(sheet) = &patch.sheet {
+        let names: Vec<&String> = [SCHEMA_SHEET_KEY, "sheetName"]
+            .iter()
+            .filter_map(|key| match data.header.get(*key) {
+                Some(WdbValue::String(s)) => Some(s),
+                _ => None,
+            })
+            .collect();
+        if !names.is_empty() && !names.iter().any(|n| n.eq_ignore_ascii_case(sheet)) {
+            bail!("Patch is for sheet '{}', but the data is '{}'", sheet, names[0]);
+        }
+    }
+
+    for (i, op) in patch.operations.iter().enumerate() {
+        apply_op(data, op).with_context(|| format!("Patch operation {}", i + 1))?;
+    }
+    if data.header.contains_key("recordCount") {
+        data.header.insert("recordCount".to_string(), WdbValue::UInt(data.records.len() as u32));
+    }
+    Ok(())
+}
+
+/// Patches a WDB file, or only reports the changes when `dry_run` is set.
+///
+/// # Returns
+///
+/// The changes the patch makes. The output file is written only when
+/// `dry_run` is false.
+pub fn patch_wdb_file<P: AsRef<Path>>(
+    wdb_path: P,
+    patch_path: P,
+    output_path: P,
+    game_code: GameCode,
+    dry_run: bool,
+) -> Result<Vec<WdbRecordChange>> {
+    let data = parse_wdb(&wdb_path, game_code)?;
+    let patch = load_wdb_patch(&patch_path)?;
+    let result = apply_wdb_patch(&data, &patch)?;
+    if !dry_run {
+        pack_wdb(&result.data, output_path, game_code)?;
+    }
+    Ok(result.changes)
+}
+
+fn apply_op(data: &mut WdbData, op: &WdbPatchOp) -> Result<()> {
+    match op {
+        WdbPatchOp::Set { select, fields } => for_selected(data, select, |record| {
+            for (field, value) in fields {
+                let current = field_mut(record, field)?;
+                *current = coerce(field, current, value)?;
+            }
+            Ok(())
+        }),
+        WdbPatchOp::Add { select, fields } => for_selected(data, select, |record| {
+            for (field, amount) in fields {
+                update_number(field, field_mut(record, field)?, |x| x + amount)?;
+            }
+            Ok(())
+        }),
+        WdbPatchOp::Multiply { select, fields } => for_selected(data, select, |record| {
+            for (field, factor) in fields {
+                update_number(field, field_mut(record, field)?, |x| x * factor)?;
+            }
+            Ok(())
+        }),
+        WdbPatchOp::Clamp { select, field, min, max } => for_selected(data, select, |record| {
+            update_number(field, field_mut(record, field)?, |x| {
+                let x = min.map_or(x, |m| x.max(m));
+                max.map_or(x, |m| x.min(m))
+            })
+        }),
+        WdbPatchOp::Clone { from, id, set } => {
+            if find_record(data, id).is_some() {
+                bail!("Record '{}' already exists", id);
+            }
+            let source = find_record(data, from).with_context(|| format!("Record '{}' not found", from))?;
+            let mut record = data.records[source].clone();
+            record.insert(RECORD_FIELD.to_string(), WdbValue::String(id.clone()));
+            for (field, value) in set {
+                let current = field_mut(&mut record, field)?;
+                *current = coerce(field, current, value)?;
+            }
+            data.records.push(record);
+            Ok(())
+        }
+        WdbPatchOp::Delete { select } => {
+            let selected = select_records(data, select)?;
+            let mut i = 0;
+            data.records.retain(|_| {
+                i += 1;
+                selected.binary_search(&(i - 1)).is_err()
+            });
+            Ok(())
+        }
+    }
+}
+
+/// Runs `edit` on every selected record, naming the record on error.
+fn for_selected(
+    data: &mut WdbData,
+    select: &WdbRecordSelector,
+    mut edit: impl FnMut(&mut WdbRecord) -> Result<()>,
+) -> Result<()> {
+    for i in select_records(data, select)? {
+        let record = &mut data.records[i];
+        let id = record_id(record).unwrap_or_default().to_string();
+        edit(record).with_context(|| format!("Record '{}'", id))?;
+    }
+    Ok(())
+}
+
+/// Indices of the selected records, ascending.
+///
+/// Explicit (non-wildcard) ids must exist.
+pub(crate) fn select_records(data: &WdbData, select: &WdbRecordSelector) -> Result<Vec<usize>> {
+    for id in select.ids.iter().filter(|id| !id.ends_with('*')) {
+        if find_record(data, id).is_none() {
+            bail!("Record '{}' not found", id);
+        }
+    }
+
+    let id_matches = |record: &WdbRecord| {
+        select.ids.is_empty()
+            || record_id(record).is_some_and(|rid| {
+                select.ids.iter().any(|id| match id.strip_suffix('*') {
+                    Some(prefix) => rid.starts_with(prefix),
+                    None => rid == id,
+                })
+            })
+    };
+    Ok(data
+        .records
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| id_matches(r) && select.filter.iter().all(|p| predicate_holds(r, p)))
+        .map(|(i, _)| i)
+        .collect())
+}
+
+fn predicate_holds(record: &WdbRecord, predicate: &WdbPredicate) -> bool {
+    use std::cmp::Ordering;
+
+    let Some(value) = record.get(&predicate.field) else {
+        return false;
+    };
+    let ordering = match (as_number(value), as_number(&predicate.value)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => match (value, &predicate.value) {
+            (WdbValue::String(a), WdbValue::String(b)) => Some(a.cmp(b)),
+            _ if values_equal(value, &predicate.value) => Some(Ordering::Equal),
+            _ => None,
+        },
+    };
+    match predicate.op {
+        WdbCompareOp::Eq => ordering == Some(Ordering::Equal),
+        WdbCompareOp::Ne => ordering != Some(Ordering::Equal),
+        WdbCompareOp::Lt => ordering == Some(Ordering::Less),
+        WdbCompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        WdbCompareOp::Gt => ordering == Some(Ordering::Greater),
+        WdbCompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+fn field_mut<'a>(record: &'a mut WdbRecord, field: &str) -> Result<&'a mut WdbValue> {
+    record
+        .get_mut(field)
+        .with_context(|| format!("No field '{}'", field))
+}
+
+/// A new value for a field, converted to the field's numeric type.
+fn coerce(field: &str, current: &WdbValue, value: &WdbValue) -> Result<WdbValue> {
+    let mut coerced = current.clone();
+    match (current, as_number(value)) {
+        (WdbValue::Int(_) | WdbValue::UInt(_), Some(x)) if x.fract() != 0.0 => {
+            bail!("{} takes whole numbers, got {}", field, x)
+        }
+        (WdbValue::Int(_) | WdbValue::UInt(_) | WdbValue::Float(_), Some(x)) => {
+            update_number(field, &mut coerced, |_| x)?;
+        }
+        _ => coerced = value.clone(),
+    }
+    Ok(coerced)
+}
+
+/// Replaces a numeric value with `f(value)`, keeping its type.
+fn update_number(field: &str, value: &mut WdbValue, f: impl Fn(f64) -> f64) -> Result<()> {
+    *value = match value {
+        WdbValue::Float(x) => WdbValue::Float(f(*x as f64) as f32),
+        WdbValue::Int(n) => {
+            let n = f(*n as f64).round() as i64;
+            check_range(field, n, true).map_err(|e| anyhow!("{}: {}", field, e))?;
+            WdbValue::Int(n as i32)
+        }
+        WdbValue::UInt(n) => {
+            let n = f(*n as f64).round() as i64;
+            check_range(field, n, false).map_err(|e| anyhow!("{}: {}", field, e))?;
+            WdbValue::UInt(n as u32)
+        }
+        other => bail!("{} is not numeric ({:?})", field, other),
+    };
+    Ok(())
+}
+
+fn as_number(value: &WdbValue) -> Option<f64> {
+    match value {
+        WdbValue::Int(i) => Some(*i as f64),
+        WdbValue::UInt(u) => Some(*u as f64),
+        WdbValue::Float(f) => Some(*f as f64),
+        _ => None,
+    }
+}
+
+fn record_id(record: &WdbRecord) -> Option<&str> {
+    match record.get(RECORD_FIELD) {
+        Some(WdbValue::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+fn find_record(data: &WdbData, id: &str) -> Option<usize> {
+    data.records.iter().position(|r| record_id(r) == Some(id))
+}
+
+// =============================================================================
+// Diff
+// =============================================================================
+
+/// Lists the record and field differences between two versions of a sheet.
+///
+/// Records are matched by id. Changes come in `before` order, then added
+/// records in `after` order; fields follow the record's field order.
+pub fn diff_wdb(before: &WdbData, after: &WdbData) -> Vec<WdbRecordChange> {
+    let (old, new) = (index(before), index(after));
+    let whole = |kind, id: &str| WdbRecordChange {
+        kind,
+        record: id.to_string(),
+        field: None,
+        before: None,
+        after: None,
+    };
+
+    let mut changes = Vec::new();
+    for (id, old_record) in &old {
+        let Some(new_record) = new.get(id) else {
+            changes.push(whole(WdbChangeKind::Deleted, id));
+            continue;
+        };
+        let fields: IndexSet<&String> = old_record.keys().chain(new_record.keys()).collect();
+        for field in fields {
+            let (b, a) = (old_record.get(field), new_record.get(field));
+            if !option_equal(b, a) {
+                changes.push(WdbRecordChange {
+                    kind: WdbChangeKind::Modified,
+                    record: id.to_string(),
+                    field: Some(field.clone()),
+                    before: b.cloned(),
+                    after: a.cloned(),
+                });
+            }
+        }
+    }
+    for id in new.keys().filter(|id| !old.contains_key(*id)) {
+        changes.push(whole(WdbChangeKind::Added, id));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::structs::WdbHeader;
+
+    fn sample() -> WdbData {
+        let record = |id: &str, price: u32, day: i32| {
+            let mut r = WdbRecord::new();
+            r.insert("record".to_string(), WdbValue::String(id.to_string()));
+            r.insert("u16Price".to_string(), WdbValue::UInt(price));
+            r.insert("i12Day1".to_string(), WdbValue::Int(day));
+            r.insert("fRate".to_string(), WdbValue::Float(1.0));
+            r.insert("sName".to_string(), WdbValue::String(id.to_uppercase()));
+            r
+        };
+        let mut header = WdbHeader::new();
+        header.insert("sheetName".to_string(), WdbValue::String("item".to_string()));
+        header.insert("recordCount".to_string(), WdbValue::UInt(3));
+        WdbData {
+            header,
+            records: vec![record("it_a", 100, 1), record("it_b", 2000, 2), record("wea_c", 3000, 3)],
+        }
+    }
+
+    fn get<'a>(data: &'a WdbData, id: &str, field: &str) -> &'a WdbValue {
+        &data.records[find_record(data, id).unwrap()][field]
+    }
+
+    #[test]
+    fn test_text_patch_applies_with_diff() {
+        let patch = parse_wdb_patch(
+            r#"
+            # comment
+            name Test patch
+            sheet item
+            set * i12Day1=7
+            multiply it_* u16Price=1.5 where u16Price>=1000 and sName!="IT_A"
+            add it_a,wea_c fRate=0.25
+            clamp * u16Price ..4000
+            clone it_a as it_new sName="Big potion" u16Price=60000
+            delete wea_c
+            "#,
+        )
+        .unwrap();
+        assert_eq!(patch.name.as_deref(), Some("Test patch"));
+        assert_eq!(patch.operations.len(), 6);
+
+        let data = sample();
+        let result = apply_wdb_patch(&data, &patch).unwrap();
+        assert_eq!(get(&data, "it_b", "u16Price"), &WdbValue::UInt(2000), "input untouched");
+
+        let out = &result.data;
+        assert_eq!(get(out, "it_a", "i12Day1"), &WdbValue::Int(7));
+        assert_eq!(get(out, "it_a", "u16Price"), &WdbValue::UInt(100));
+        assert_eq!(get(out, "it_b", "u16Price"), &WdbValue::UInt(3000));
+        assert_eq!(get(out, "it_a", "fRate"), &WdbValue::Float(1.25));
+        assert_eq!(get(out, "it_new", "u16Price"), &WdbValue::UInt(60000));
+        assert_eq!(get(out, "it_new", "sName"), &WdbValue::String("Big potion".to_string()));
+        assert!(find_record(out, "wea_c").is_none());
+        assert_eq!(out.header["recordCount"], WdbValue::UInt(3));
+
+        let summary: Vec<(WdbChangeKind, &str, Option<&str>)> = result
+            .changes
+            .iter()
+            .map(|c| (c.kind, c.record.as_str(), c.field.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (WdbChangeKind::Modified, "it_a", Some("i12Day1")),
+                (WdbChangeKind::Modified, "it_a", Some("fRate")),
+                (WdbChangeKind::Modified, "it_b", Some("u16Price")),
+                (WdbChangeKind::Modified, "it_b", Some("i12Day1")),
+                (WdbChangeKind::Deleted, "wea_c", None),
+                (WdbChangeKind::Added, "it_new", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_patch_matches_text() {
+        let text = parse_wdb_patch("multiply it_* u16Price=2 where u16Price<1000").unwrap();
+        let json = parse_wdb_patch(&serde_json::to_string(&text).unwrap()).unwrap();
+        assert_eq!(text, json);
+
+        let json = parse_wdb_patch(
+            r#"{ "operations": [ { "op": "multiply",
+                 "select": { "ids": ["it_*"], "where": [ { "field": "u16Price", "op": "lt", "value": 1000 } ] },
+                 "fields": { "u16Price": 2 } } ] }"#,
+        )
+        .unwrap();
+        assert_eq!(text, json);
+        let result = apply_wdb_patch(&sample(), &json).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].after, Some(WdbValue::UInt(200)));
+    }
+
+    #[test]
+    fn test_patch_errors() {
+        let data = sample();
+        let fails = |text: &str| {
+            let patch = parse_wdb_patch(text).unwrap();
+            apply_wdb_patch(&data, &patch).unwrap_err()
+        };
+        assert!(format!("{:#}", fails("set it_zz u16Price=1")).contains("not found"));
+        assert!(format!("{:#}", fails("set * uPriceTypo=1")).contains("No field"));
+        assert!(format!("{:#}", fails("add it_a u16Price=70000")).contains("does not fit"));
+        assert!(format!("{:#}", fails("set it_a u16Price=1.5")).contains("whole numbers"));
+        assert!(format!("{:#}", fails("add it_a sName=1")).contains("not numeric"));
+        assert!(format!("{:#}", fails("sheet crystal")).contains("sheet"));
+        assert!(format!("{:#}", fails("clone it_a as it_b")).contains("already exists"));
+
+        let err = parse_wdb_patch("set *\nfrobnicate it_a").unwrap_err();
+        assert!(format!("{:#}", err).contains("Line 2"));
+    }
+}
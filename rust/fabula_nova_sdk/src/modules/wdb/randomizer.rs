@@ -0,0 +1,722 @@
+//! # WDB Randomizer
+//!
+//! Seeded randomization of item tables (`treasurebox`, `shop`, enemy drop
+//! fields) and prices. A run reads the loaded sheets, never modifies them,
+//! and returns one [`WdbPatch`] per changed sheet plus a spoiler log, so the
+//! result can be previewed, saved as patches or written as a mod folder.
+//!
+//! ## Configuration
+//!
+//! | Piece                 | Meaning                                                      |
+//! |-----------------------|--------------------------------------------------------------|
+//! | [`WdbRandomPool`]     | Slots (sheet, records, item fields) randomized together      |
+//! | [`WdbPoolMode`]       | `Shuffle` permutes the pool's own items; `Draw` picks from a list |
+//! | [`WdbLogicRule`]      | Items needed before some records' slots can be reached       |
+//! | `progression`         | Items the logic depends on                                   |
+//! | [`WdbPriceRule`]      | Random factor for a numeric field (prices, drop rates, ...)  |
+//! | [`WdbPlacementConstraint`] | Custom rule, in code, for which item may go in which slot |
+//!
+//! Empty item fields are not slots, so a shop's free entries stay free.
+//!
+//! ## Placement
+//!
+//! ```text
+//!   1. progression items, in random order, each into a random empty slot
+//!      of its own pool that is reachable assuming the items still to be
+//!      placed are collected (assumed fill; up to 16 attempts)
+//!   2. the rest of each Shuffle pool, shuffled into its remaining slots
+//!   3. each remaining Draw slot gets a random allowed item
+//!   4. price rules, in order
+//! ```
+//!
+//! Assumed fill keeps every progression item reachable from the start, so
+//! a seed never locks the player out. Progression items are only placed in
+//! `Shuffle` pools (where they already are) and are never drawn, so key
+//! items are neither duplicated nor lost.
+//!
+//! ## Determinism
+//!
+//! The same seed, configuration and sheets give the same result on every
+//! platform and release: the generator is a fixed SplitMix64 and every
+//! step walks sheets, records and fields in file order.
+//!
+//! ```rust,ignore
+//! let mut graph = WdbReferenceGraph::new(GameCode::FF13_1);
+//! graph.load_directory("extracted/db/resident")?;
+//! let mut randomizer = WdbRandomizer::new(config);
+//! randomizer.add_constraint(|slot: &WdbSlot, item: &str| !(slot.sheet == "shop" && item.starts_with("key_")));
+//! let result = randomizer.run(graph.sheets())?;
+//! print!("{}", spoiler_log(&result));
+//! write_randomizer_package(graph.sheets(), &result, "mods/random_42", GameCode::FF13_1)?;
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::api::pack_wdb;
+use super::patch::{apply_wdb_patch_in_place, select_records};
+use super::refs::record_id;
+use super::structs::{
+    GameCode, WdbData, WdbPatch, WdbPatchOp, WdbPoolMode, WdbRandomizerConfig, WdbRandomizerResult, WdbRecordSelector,
+    WdbSpoilerEntry, WdbValue,
+};
+use super::schema::WdbFieldKind;
+use super::table::check_range;
+
+/// Progression placements tried before giving up.
+const FILL_ATTEMPTS: usize = 16;
+
+/// Spoiler pool name of price rule changes.
+const PRICE_POOL: &str = "price";
+
+/// One item field of one record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WdbSlot {
+    pub pool: String,
+    pub sheet: String,
+    pub record: String,
+    pub field: String,
+    /// The item the slot held before randomizing
+    pub original: String,
+    /// Items needed to reach the slot, from the logic rules
+    pub requires: Vec<String>,
+}
+
+/// Decides whether an item may be placed in a slot.
+///
+/// Implemented for closures, so one-off rules can be added inline.
+pub trait WdbPlacementConstraint {
+    fn allows(&self, slot: &WdbSlot, item: &str) -> bool;
+}
+
+impl<F: Fn(&WdbSlot, &str) -> bool> WdbPlacementConstraint for F {
+    fn allows(&self, slot: &WdbSlot, item: &str) -> bool {
+        self(slot, item)
+    }
+}
+
+/// A randomizer configuration with its custom constraints.
+pub struct WdbRandomizer {
+    config: WdbRandomizerConfig,
+    constraints: Vec<Box<dyn WdbPlacementConstraint>>,
+}
+
+/// SplitMix64: small, and the same sequence everywhere.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Uniform in `0.0..1.0`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A slot with the index of its pool.
+struct Slot {
+    pool: usize,
+    slot: WdbSlot,
+}
+
+impl WdbRandomizer {
+    pub fn new(config: WdbRandomizerConfig) -> Self {
+        Self { config, constraints: Vec::new() }
+    }
+
+    /// Adds a rule every placement must satisfy.
+    pub fn add_constraint<C: WdbPlacementConstraint + 'static>(&mut self, constraint: C) {
+        self.constraints.push(Box::new(constraint));
+    }
+
+    /// Randomizes the configured pools and prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheets` - Sheets by lowercase file stem, e.g. [`WdbReferenceGraph::sheets`](super::refs::WdbReferenceGraph::sheets)
+    ///
+    /// # Errors
+    ///
+    /// Fails if the configuration names sheets, records or fields that
+    /// don't exist, if a logic rule needs an item that isn't progression
+    /// or isn't in any pool, or if the constraints leave a slot with no
+    /// allowed item.
+    pub fn run(&self, sheets: &IndexMap<String, WdbData>) -> Result<WdbRandomizerResult> {
+        let mut rng = Rng(self.config.seed);
+        let slots = self.collect_slots(sheets)?;
+        let progression: HashSet<&str> = self.config.progression.iter().map(String::as_str).collect();
+        for slot in &slots {
+            if let Some(item) = slot.slot.requires.iter().find(|r| !progression.contains(r.as_str())) {
+                bail!("{} requires {}, which is not a progression item", describe(&slot.slot), item);
+            }
+        }
+
+        // Progression items leave their Shuffle bags and are placed first
+        let mut bags: Vec<Vec<String>> = vec![Vec::new(); self.config.pools.len()];
+        let mut key_items: Vec<(String, usize)> = Vec::new();
+        for slot in &slots {
+            if self.config.pools[slot.pool].mode == WdbPoolMode::Shuffle {
+                let item = slot.slot.original.clone();
+                if progression.contains(item.as_str()) {
+                    key_items.push((item, slot.pool));
+                } else {
+                    bags[slot.pool].push(item);
+                }
+            }
+        }
+        for item in &self.config.progression {
+            if !key_items.iter().any(|(i, _)| i == item) {
+                bail!("Progression item {} is not in any Shuffle pool", item);
+            }
+        }
+        let mut placed = self.place_progression(&slots, &key_items, &mut rng)?;
+
+        for (p, pool) in self.config.pools.iter().enumerate() {
+            let open: Vec<usize> = (0..slots.len()).filter(|&s| slots[s].pool == p && placed[s].is_none()).collect();
+            match pool.mode {
+                WdbPoolMode::Shuffle => {
+                    let bag = &mut bags[p];
+                    rng.shuffle(bag);
+                    for s in open {
+                        let j = bag
+                            .iter()
+                            .position(|item| self.allows(&slots[s], item))
+                            .ok_or_else(|| anyhow!("No item left in pool {} fits {}", pool.name, describe(&slots[s].slot)))?;
+                        placed[s] = Some(bag.remove(j));
+                    }
+                }
+                WdbPoolMode::Draw => {
+                    let list: Vec<&String> = if pool.items.is_empty() {
+                        (0..slots.len()).filter(|&s| slots[s].pool == p).map(|s| &slots[s].slot.original).collect()
+                    } else {
+                        pool.items.iter().collect()
+                    };
+                    for s in open {
+                        let allowed: Vec<&&String> = list
+                            .iter()
+                            .filter(|item| !progression.contains(item.as_str()) && self.allows(&slots[s], item))
+                            .collect();
+                        if allowed.is_empty() {
+                            bail!("No item in pool {} fits {}", pool.name, describe(&slots[s].slot));
+                        }
+                        placed[s] = Some(allowed[rng.below(allowed.len())].to_string());
+                    }
+                }
+            }
+        }
+
+        let mut spoiler = Vec::new();
+        for (slot, item) in slots.iter().zip(placed) {
+            let item = item.expect("every slot is filled");
+            if item != slot.slot.original {
+                spoiler.push(WdbSpoilerEntry {
+                    pool: slot.slot.pool.clone(),
+                    sheet: slot.slot.sheet.clone(),
+                    record: slot.slot.record.clone(),
+                    field: slot.slot.field.clone(),
+                    before: WdbValue::String(slot.slot.original.clone()),
+                    after: WdbValue::String(item),
+                });
+            }
+        }
+        spoiler.extend(self.scale_prices(sheets, &mut rng)?);
+
+        Ok(WdbRandomizerResult { seed: self.config.seed, patches: patches_for(&spoiler), spoiler })
+    }
+
+    fn allows(&self, slot: &Slot, item: &str) -> bool {
+        !self.config.pools[slot.pool].exclude.iter().any(|e| e == item)
+            && self.constraints.iter().all(|c| c.allows(&slot.slot, item))
+    }
+
+    /// Every non-empty item field the pools select, in file order.
+    fn collect_slots(&self, sheets: &IndexMap<String, WdbData>) -> Result<Vec<Slot>> {
+        let mut slots: Vec<Slot> = Vec::new();
+        let mut seen: HashSet<(String, String, String)> = HashSet::new();
+        for (p, pool) in self.config.pools.iter().enumerate() {
+            for set in &pool.slots {
+                let data = sheet(sheets, &set.sheet)?;
+                for i in select_records(data, &set.select).with_context(|| format!("Pool {}", pool.name))? {
+                    let record = &data.records[i];
+                    let id = record_id(record).unwrap_or_default();
+                    for pattern in &set.fields {
+                        let fields: Vec<(&String, &WdbValue)> = match pattern.strip_suffix('*') {
+                            Some(prefix) => record.iter().filter(|(k, _)| k.starts_with(prefix)).collect(),
+                            None => vec![record
+                                .get_key_value(pattern)
+                                .ok_or_else(|| anyhow!("Pool {}: {} has no field {}", pool.name, id, pattern))?],
+                        };
+                        for (field, value) in fields {
+                            let original = match value {
+                                WdbValue::String(s) if s.is_empty() => continue,
+                                WdbValue::String(s) => s.clone(),
+                                _ if pattern.ends_with('*') => continue,
+                                other => bail!("Pool {}: {}.{} is not an item field ({:?})", pool.name, id, field, other),
+                            };
+                            let sheet_name = set.sheet.to_lowercase();
+                            if !seen.insert((sheet_name.clone(), id.to_string(), field.clone())) {
+                                bail!("{}:{}.{} is in more than one pool", sheet_name, id, field);
+                            }
+                            let requires = self.requirements(sheets, &sheet_name, id)?;
+                            slots.push(Slot {
+                                pool: p,
+                                slot: WdbSlot {
+                                    pool: pool.name.clone(),
+                                    sheet: sheet_name,
+                                    record: id.to_string(),
+                                    field: field.clone(),
+                                    original,
+                                    requires,
+                                },
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Items the logic rules require for one record.
+    fn requirements(&self, sheets: &IndexMap<String, WdbData>, sheet_name: &str, id: &str) -> Result<Vec<String>> {
+        let mut requires: Vec<String> = Vec::new();
+        for rule in self.config.logic.iter().filter(|r| r.sheet.eq_ignore_ascii_case(sheet_name)) {
+            let data = sheet(sheets, &rule.sheet)?;
+            let selected = select_records(data, &rule.select)?;
+            if selected.iter().any(|&i| record_id(&data.records[i]) == Some(id)) {
+                for item in &rule.requires {
+                    if !requires.contains(item) {
+                        requires.push(item.clone());
+                    }
+                }
+            }
+        }
+        Ok(requires)
+    }
+
+    /// Assumed fill of the progression items over their pools' slots.
+    fn place_progression(&self, slots: &[Slot], items: &[(String, usize)], rng: &mut Rng) -> Result<Vec<Option<String>>> {
+        'attempt: for _ in 0..FILL_ATTEMPTS {
+            let mut order = items.to_vec();
+            rng.shuffle(&mut order);
+            let mut placed: Vec<Option<String>> = vec![None; slots.len()];
+            while let Some((item, pool)) = order.pop() {
+                let assumed: Vec<&str> = order.iter().map(|(i, _)| i.as_str()).collect();
+                let reach = reachable(slots, &placed, &assumed);
+                let open: Vec<usize> = (0..slots.len())
+                    .filter(|&s| slots[s].pool == pool && placed[s].is_none() && reach[s] && self.allows(&slots[s], &item))
+                    .collect();
+                if open.is_empty() {
+                    continue 'attempt;
+                }
+                placed[open[rng.below(open.len())]] = Some(item);
+            }
+            return Ok(placed);
+        }
+        bail!("No placement keeps every progression item reachable ({} attempts)", FILL_ATTEMPTS)
+    }
+
+    /// Applies the price rules, returning what changed.
+    fn scale_prices(&self, sheets: &IndexMap<String, WdbData>, rng: &mut Rng) -> Result<Vec<WdbSpoilerEntry>> {
+        let mut changes = Vec::new();
+        for rule in &self.config.prices {
+            if rule.min > rule.max {
+                bail!("Price rule for {}.{}: min {} is above max {}", rule.sheet, rule.field, rule.min, rule.max);
+            }
+            if rule.min < 0.0 && WdbFieldKind::from_field_name(&rule.field) == Some(WdbFieldKind::UInt) {
+                bail!("Price rule for {}.{}: min {} is negative for an unsigned field", rule.sheet, rule.field, rule.min);
+            }
+            let data = sheet(sheets, &rule.sheet)?;
+            for i in select_records(data, &rule.select)? {
+                let record = &data.records[i];
+                let id = record_id(record).unwrap_or_default();
+                let before = record.get(&rule.field).ok_or_else(|| anyhow!("{} has no field {}", id, rule.field))?;
+                let factor = rule.min + rng.unit() * (rule.max - rule.min);
+                // Range-check the full result before narrowing it to the value type
+                let scale = |n: i64, signed: bool| -> Result<i64> {
+                    let scaled = (n as f64 * factor).round() as i64;
+                    check_range(&rule.field, scaled, signed)
+                        .map_err(|e| anyhow!("Price rule for {}.{}: {}", id, rule.field, e))?;
+                    Ok(scaled)
+                };
+                let after = match before {
+                    WdbValue::UInt(n) => WdbValue::UInt(u32::try_from(scale(*n as i64, false)?)?),
+                    WdbValue::Int(n) => WdbValue::Int(i32::try_from(scale(*n as i64, true)?)?),
+                    WdbValue::Float(x) => WdbValue::Float((*x as f64 * factor) as f32),
+                    other => bail!("{}.{} is not numeric ({:?})", id, rule.field, other),
+                };
+                if after != *before {
+                    changes.push(WdbSpoilerEntry {
+                        pool: PRICE_POOL.to_string(),
+                        sheet: rule.sheet.to_lowercase(),
+                        record: id.to_string(),
+                        field: rule.field.clone(),
+                        before: before.clone(),
+                        after,
+                    });
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// Which slots can be reached holding `assumed` plus every item found in
+/// reachable slots.
+fn reachable(slots: &[Slot], placed: &[Option<String>], assumed: &[&str]) -> Vec<bool> {
+    let mut have: HashSet<&str> = assumed.iter().copied().collect();
+    let mut reach = vec![false; slots.len()];
+    loop {
+        let mut changed = false;
+        for (s, slot) in slots.iter().enumerate() {
+            if !reach[s] && slot.slot.requires.iter().all(|r| have.contains(r.as_str())) {
+                reach[s] = true;
+                changed = true;
+                if let Some(item) = &placed[s] {
+                    have.insert(item);
+                }
+            }
+        }
+        if !changed {
+            return reach;
+        }
+    }
+}
+
+/// One `set` per changed record, grouped by sheet in spoiler order.
+fn patches_for(spoiler: &[WdbSpoilerEntry]) -> Vec<(String, WdbPatch)> {
+    let mut records: IndexMap<&str, IndexMap<&str, IndexMap<String, WdbValue>>> = IndexMap::new();
+    for entry in spoiler {
+        records
+            .entry(entry.sheet.as_str())
+            .or_default()
+            .entry(entry.record.as_str())
+            .or_default()
+            .insert(entry.field.clone(), entry.after.clone());
+    }
+    records
+        .into_iter()
+        .map(|(sheet, records)| {
+            let operations = records
+                .into_iter()
+                .map(|(id, fields)| WdbPatchOp::Set {
+                    select: WdbRecordSelector { ids: vec![id.to_string()], filter: Vec::new() },
+                    fields,
+                })
+                .collect();
+            (sheet.to_string(), WdbPatch { name: Some("randomizer".to_string()), sheet: None, operations })
+        })
+        .collect()
+}
+
+fn sheet<'a>(sheets: &'a IndexMap<String, WdbData>, name: &str) -> Result<&'a WdbData> {
+    sheets.get(&name.to_lowercase()).ok_or_else(|| anyhow!("Sheet '{}' is not loaded", name))
+}
+
+fn describe(slot: &WdbSlot) -> String {
+    format!("{}:{}.{}", slot.sheet, slot.record, slot.field)
+}
+
+fn show(value: &WdbValue) -> String {
+    match value {
+        WdbValue::String(s) => s.clone(),
+        WdbValue::UInt(n) => n.to_string(),
+        WdbValue::Int(n) => n.to_string(),
+        WdbValue::Float(x) => x.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Runs a configuration without custom constraints.
+pub fn randomize_wdb(sheets: &IndexMap<String, WdbData>, config: &WdbRandomizerConfig) -> Result<WdbRandomizerResult> {
+    WdbRandomizer::new(config.clone()).run(sheets)
+}
+
+/// The spoiler log as text, one change per line grouped by pool.
+///
+/// ```text
+/// Seed: 42
+///
+/// [treasure]
+///   treasurebox:tre_001.sItemResourceId  it_potion -> it_elixir
+/// ```
+pub fn spoiler_log(result: &WdbRandomizerResult) -> String {
+    let mut pools: IndexMap<&str, Vec<&WdbSpoilerEntry>> = IndexMap::new();
+    for entry in &result.spoiler {
+        pools.entry(entry.pool.as_str()).or_default().push(entry);
+    }
+    let mut out = format!("Seed: {}\n", result.seed);
+    for (pool, entries) in pools {
+        out.push_str(&format!("\n[{}]\n", pool));
+        for e in entries {
+            out.push_str(&format!(
+                "  {}:{}.{}  {} -> {}\n",
+                e.sheet,
+                e.record,
+                e.field,
+                show(&e.before),
+                show(&e.after)
+            ));
+        }
+    }
+    out
+}
+
+/// Writes a randomizer result as a mod folder.
+///
+/// The folder holds the patched `<sheet>.wdb` files, each sheet's patch as
+/// `<sheet>.patch.json`, and `spoiler.txt`.
+///
+/// # Returns
+///
+/// The files written.
+pub fn write_randomizer_package<P: AsRef<Path>>(
+    sheets: &IndexMap<String, WdbData>,
+    result: &WdbRandomizerResult,
+    out_dir: P,
+    game_code: GameCode,
+) -> Result<Vec<PathBuf>> {
+    let out_dir = out_dir.as_ref();
+    fs::create_dir_all(out_dir)?;
+    let mut written = Vec::new();
+    for (name, patch) in &result.patches {
+        let mut data = sheet(sheets, name)?.clone();
+        apply_wdb_patch_in_place(&mut data, patch).with_context(|| format!("Sheet {}", name))?;
+        let wdb_path = out_dir.join(format!("{}.wdb", name));
+        pack_wdb(&data, &wdb_path, game_code).with_context(|| format!("Sheet {}", name))?;
+        let patch_path = out_dir.join(format!("{}.patch.json", name));
+        fs::write(&patch_path, serde_json::to_string_pretty(patch)?)?;
+        written.extend([wdb_path, patch_path]);
+    }
+    let spoiler_path = out_dir.join("spoiler.txt");
+    fs::write(&spoiler_path, spoiler_log(result))?;
+    written.push(spoiler_path);
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::patch::apply_wdb_patch;
+    use crate::modules::wdb::structs::{WdbLogicRule, WdbPriceRule, WdbRandomPool, WdbSlotSet};
+    use crate::modules::wdb::tests::{data, record};
+
+    fn text(s: &str) -> WdbValue {
+        WdbValue::String(s.to_string())
+    }
+
+    /// Ten chests (two locked behind keys), a shop and an item table
+    fn sheets() -> IndexMap<String, WdbData> {
+        let chests = [
+            "it_potion", "key_gate", "it_ether", "key_tower", "it_potion",
+            "it_elixir", "it_phoenix", "it_ether", "it_potion", "it_tent",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            record(&format!("tre_{:03}", i), &[
+                ("sItemResourceId", text(item)),
+                ("uCount", WdbValue::UInt(1)),
+            ])
+        })
+        .collect();
+        let shop = vec![record("shop_001", &[
+            ("sItemLabel1", text("it_potion")),
+            ("sItemLabel2", text("it_ether")),
+            ("sItemLabel3", text("")),
+        ])];
+        let items = ["it_potion", "it_ether", "it_elixir"]
+            .iter()
+            .enumerate()
+            .map(|(i, id)| record(id, &[("u16Price", WdbValue::UInt(100 * (i as u32 + 1)))]))
+            .collect();
+        let mut sheets = IndexMap::new();
+        sheets.insert("treasurebox".to_string(), data(chests));
+        sheets.insert("shop".to_string(), data(shop));
+        sheets.insert("item".to_string(), data(items));
+        sheets
+    }
+
+    fn ids(ids: &[&str]) -> WdbRecordSelector {
+        WdbRecordSelector { ids: strings(ids), filter: Vec::new() }
+    }
+
+    fn slots(sheet: &str, field: &str) -> Vec<WdbSlotSet> {
+        vec![WdbSlotSet { sheet: sheet.to_string(), select: Default::default(), fields: vec![field.to_string()] }]
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn config(seed: u64) -> WdbRandomizerConfig {
+        WdbRandomizerConfig {
+            seed,
+            pools: vec![
+                WdbRandomPool {
+                    name: "treasure".to_string(),
+                    slots: slots("treasurebox", "sItemResourceId"),
+                    ..Default::default()
+                },
+                WdbRandomPool {
+                    name: "shop".to_string(),
+                    slots: slots("shop", "sItemLabel*"),
+                    mode: WdbPoolMode::Draw,
+                    items: strings(&["it_potion", "it_ether", "it_elixir"]),
+                    exclude: strings(&["it_elixir"]),
+                },
+            ],
+            logic: vec![
+                // Chests 5-9 are behind the gate; 8-9 are also in the tower
+                WdbLogicRule {
+                    sheet: "treasurebox".to_string(),
+                    select: ids(&["tre_005", "tre_006", "tre_007", "tre_008", "tre_009"]),
+                    requires: strings(&["key_gate"]),
+                },
+                WdbLogicRule {
+                    sheet: "treasurebox".to_string(),
+                    select: ids(&["tre_008", "tre_009"]),
+                    requires: strings(&["key_tower"]),
+                },
+            ],
+            progression: strings(&["key_gate", "key_tower"]),
+            prices: vec![WdbPriceRule {
+                sheet: "item".to_string(),
+                select: Default::default(),
+                field: "u16Price".to_string(),
+                min: 0.5,
+                max: 2.0,
+            }],
+        }
+    }
+
+    fn chest_items(data: &WdbData) -> Vec<String> {
+        data.records.iter().map(|r| show(&r["sItemResourceId"])).collect()
+    }
+
+    fn placement(sheets: &IndexMap<String, WdbData>, result: &WdbRandomizerResult) -> Vec<String> {
+        let patch = &result.patches.iter().find(|(s, _)| s == "treasurebox").unwrap().1;
+        chest_items(&apply_wdb_patch(&sheets["treasurebox"], patch).unwrap().data)
+    }
+
+    #[test]
+    fn test_seeds_are_deterministic_and_logical() {
+        let sheets = sheets();
+        let first = randomize_wdb(&sheets, &config(7)).unwrap();
+        assert_eq!(first, randomize_wdb(&sheets, &config(7)).unwrap());
+
+        let mut distinct = HashSet::new();
+        for seed in 0..64 {
+            let result = randomize_wdb(&sheets, &config(seed)).unwrap();
+            let chests = placement(&sheets, &result);
+            distinct.insert(chests.clone());
+
+            // Same items, new places
+            let mut sorted = chests.clone();
+            sorted.sort();
+            let mut original = chest_items(&sheets["treasurebox"]);
+            original.sort();
+            assert_eq!(sorted, original);
+
+            // The gate key is outside the gate, the tower key outside the tower
+            let gate = chests.iter().position(|i| i == "key_gate").unwrap();
+            let tower = chests.iter().position(|i| i == "key_tower").unwrap();
+            assert!(gate < 5, "seed {}: {:?}", seed, chests);
+            assert!(tower < 8, "seed {}: {:?}", seed, chests);
+
+            // Shop draws skip excluded items; empty slots stay empty
+            for entry in result.spoiler.iter().filter(|e| e.pool == "shop") {
+                assert_ne!(entry.after, text("it_elixir"));
+                assert_ne!(entry.field, "sItemLabel3");
+            }
+            for entry in result.spoiler.iter().filter(|e| e.pool == PRICE_POOL) {
+                let (WdbValue::UInt(before), WdbValue::UInt(after)) = (&entry.before, &entry.after) else { panic!() };
+                assert!(*after * 2 >= *before && *after <= *before * 2, "{:?}", entry);
+            }
+        }
+        assert!(distinct.len() > 32);
+
+        let log = spoiler_log(&first);
+        assert!(log.starts_with("Seed: 7\n"));
+        assert!(log.contains("[treasure]") && log.contains("[price]"));
+    }
+
+    #[test]
+    fn test_constraints_and_config_errors() {
+        let sheets = sheets();
+        let mut randomizer = WdbRandomizer::new(config(3));
+        randomizer.add_constraint(|slot: &WdbSlot, item: &str| !(slot.record == "tre_000" && item.starts_with("key_")));
+        randomizer.add_constraint(|slot: &WdbSlot, item: &str| slot.pool != "shop" || item == "it_potion");
+        for seed in 0..16 {
+            randomizer.config.seed = seed;
+            let result = randomizer.run(&sheets).unwrap();
+            assert!(!placement(&sheets, &result)[0].starts_with("key_"));
+            assert!(result.spoiler.iter().filter(|e| e.pool == "shop").all(|e| e.after == text("it_potion")));
+        }
+
+        // Every chest behind the gate leaves nowhere for its key
+        let mut locked = config(1);
+        locked.logic[0].select = WdbRecordSelector::default();
+        let err = randomize_wdb(&sheets, &locked).unwrap_err().to_string();
+        assert!(err.contains("reachable"), "{}", err);
+
+        let mut bad = config(1);
+        bad.logic[1].requires.push("it_tent".to_string());
+        assert!(randomize_wdb(&sheets, &bad).unwrap_err().to_string().contains("not a progression item"));
+
+        let mut bad = config(1);
+        bad.pools[0].slots[0].fields = vec!["uCount".to_string()];
+        assert!(randomize_wdb(&sheets, &bad).unwrap_err().to_string().contains("not an item field"));
+
+        let mut bad = config(1);
+        bad.prices[0].max = 1000.0;
+        bad.prices[0].min = 1000.0;
+        assert!(randomize_wdb(&sheets, &bad).unwrap_err().to_string().contains("does not fit"));
+
+        let mut bad = config(1);
+        bad.prices[0].min = -1.0;
+        assert!(randomize_wdb(&sheets, &bad).unwrap_err().to_string().contains("negative"));
+    }
+
+    #[test]
+    fn test_price_overflow_is_an_error() {
+        // 3e9 * 2 wraps to a plausible u32 if narrowed before the check
+        let mut sheets = sheets();
+        sheets["item"].records[0].insert("uPrice".to_string(), WdbValue::UInt(3_000_000_000));
+        let mut config = config(1);
+        config.prices[0] = WdbPriceRule { field: "uPrice".to_string(), min: 2.0, max: 2.0, ..config.prices[0].clone() };
+        let err = randomize_wdb(&sheets, &config).unwrap_err().to_string();
+        assert!(err.contains("it_potion.uPrice") && err.contains("6000000000 does not fit"), "{}", err);
+
+        // Signed fields may be scaled by a negative factor, within range
+        sheets["item"].records[0].insert("i16Bonus".to_string(), WdbValue::Int(-20_000));
+        config.prices[0] = WdbPriceRule { field: "i16Bonus".to_string(), min: -1.0, max: -1.0, ..config.prices[0].clone() };
+        config.prices[0].select = ids(&["it_potion"]);
+        let result = randomize_wdb(&sheets, &config).unwrap();
+        let entry = result.spoiler.iter().find(|e| e.pool == PRICE_POOL).unwrap();
+        assert_eq!(entry.after, WdbValue::Int(20_000));
+
+        config.prices[0].min = -2.0;
+        config.prices[0].max = -2.0;
+        assert!(randomize_wdb(&sheets, &config).unwrap_err().to_string().contains("does not fit"));
+    }
+}
@@ -27,7 +27,7 @@
 //! Field names encode bit width: `u4Role` = 4 bits, `i16Value` = 16 bits signed.
 
 use super::bit_helpers::{derive_field_number, BitReader};
-use super::enum_registry::{get_enum_type, int_to_enum_value};
+use super::schema::EnumBinding;
 use super::structs::{WdbBinaryHeader, WdbRecord, WdbSectionHeader, WdbValue};
 use anyhow::Result;
 use binrw::BinReaderExt;
@@ -54,6 +54,8 @@ pub struct WdbVariables {
     pub bits_per_offset: u8,
     /// When true, fields are unknown and should use type-based generic names
     pub without_fields: bool,
+    /// Fields that decode to typed enums, resolved from the active schema
+    pub enum_fields: HashMap<String, EnumBinding>,
 }
 
 /// Binary reader for WDB database files.
@@ -327,10 +329,15 @@ impl<R: Read + Seek> WdbReader<R> {
                             let raw_val = bit_reader.read_bits(bits_to_read).unwrap_or(0);
 
                             // Check if this field should be converted to an enum
-                            let val = if let Some(enum_type) =
-                                get_enum_type(&vars.wdb_name, field_name)
+                            let val = if let Some(binding) =
+                                vars.enum_fields.get(field_name)
                             {
-                                int_to_enum_value(enum_type, raw_val)
+                                if field_type_char == 'i' {
+                                    let signed = super::bit_helpers::sign_extend(raw_val, bits_to_read);
+                                    binding.decode(signed as i64, true)
+                                } else {
+                                    binding.decode(raw_val as i64, false)
+                                }
                             } else {
                                 match field_type_char {
                                     'i' => WdbValue::Int(super::bit_helpers::sign_extend(
@@ -408,10 +415,10 @@ impl<R: Read + Seek> WdbReader<R> {
                         if f < vars.fields.len() {
                             let field_name = &vars.fields[f];
                             // Check if this field should be an enum
-                            let val = if let Some(enum_type) =
-                                get_enum_type(&vars.wdb_name, field_name)
+                            let val = if let Some(binding) =
+                                vars.enum_fields.get(field_name)
                             {
-                                int_to_enum_value(enum_type, val_u32)
+                                binding.decode(val_u32 as i64, false)
                             } else {
                                 WdbValue::UInt(val_u32)
                             };
@@ -436,6 +443,62 @@ impl<R: Read + Seek> WdbReader<R> {
     }
 }
 
+/// Decodes the `!!strArray` sections into each string array field's values.
+///
+/// `!!strArrayList` holds one offset into `!!strArray` per `s` field with a
+/// bit width, in field order. Each 32-bit value there packs
+/// `offsets_per_value` string offsets of `bits_per_offset` bits each.
+///
+/// # Returns
+///
+/// Field name → values by index. Empty if the list doesn't match the fields.
+pub fn decode_str_arrays(
+    str_array_data: &[u8],
+    str_array_list_data: &[u8],
+    strings_data: &[u8],
+    fields: &[String],
+    offsets_per_value: u8,
+    bits_per_offset: u8,
+) -> HashMap<String, Vec<String>> {
+    let mut str_array_dict = HashMap::new();
+    if str_array_data.is_empty() || str_array_list_data.is_empty() {
+        return str_array_dict;
+    }
+    let str_array_offsets = WdbReader::<std::io::Cursor<&[u8]>>::parse_uint_list(str_array_list_data);
+    let s_fields: Vec<&String> = fields.iter().filter(|f| f.starts_with('s') && derive_field_number(f) != 0).collect();
+    if str_array_offsets.len() != s_fields.len() {
+        return str_array_dict;
+    }
+
+    for (i, &offset) in str_array_offsets.iter().enumerate() {
+        let mut current_strings = Vec::new();
+        let mut current_pos = offset as usize;
+
+        let end_pos = if i + 1 < str_array_offsets.len() {
+            str_array_offsets[i + 1] as usize
+        } else {
+            str_array_data.len()
+        };
+
+        while current_pos < end_pos {
+            if current_pos + 4 > str_array_data.len() {
+                break;
+            }
+            let val = u32::from_be_bytes(str_array_data[current_pos..current_pos + 4].try_into().unwrap());
+            let mut bit_reader = BitReader::new(val);
+
+            for _ in 0..offsets_per_value {
+                if let Some(string_offset) = bit_reader.read_bits(bits_per_offset as usize) {
+                    current_strings.push(derive_string(strings_data, string_offset as usize));
+                }
+            }
+            current_pos += 4;
+        }
+        str_array_dict.insert(s_fields[i].clone(), current_strings);
+    }
+    str_array_dict
+}
+
 /// Extracts a null-terminated string from the string pool.
 ///
 /// # Arguments
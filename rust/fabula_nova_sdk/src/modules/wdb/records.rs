@@ -0,0 +1,439 @@
+//! # Typed WDB Records
+//!
+//! One struct per sheet of the built-in FF13 schema pack (`Item`,
+//! `ItemWeapon`, `BattleCharaProp`, `Crystal`, ...), generated by `build.rs`
+//! from `schemas/ff13_1.json` when the crate is built. Renaming or
+//! retyping a field in the pack changes the struct, so code using the old
+//! name stops compiling instead of silently reading nothing.
+//!
+//! ## Generated Code
+//!
+//! Sheet names become type names (`bt_chainbonus` → `BtChainbonus`) and
+//! field names lose their type prefix (`u8NodeType` → `node_type`). Each
+//! struct implements [`WdbSheetRecord`], `TryFrom<&WdbRecord>` and
+//! `From<Struct> for WdbRecord`.
+//!
+//! | Field               | Rust Type                         |
+//! |---------------------|-----------------------------------|
+//! | `s*`                | `String`                          |
+//! | `u1*`               | `bool`                            |
+//! | `u2*`..`u8*`        | `u8`                              |
+//! | `u9*`..`u16*`       | `u16`                             |
+//! | other `u*`          | `u32`                             |
+//! | `i1*`..`i8*`, `i9*`..`i16*`, other `i*` | `i8`, `i16`, `i32` |
+//! | `f*` (no width)     | `f32`                             |
+//! | `f<n>*`             | `i32` (the reader unpacks these as ints) |
+//! | enum-bound fields   | The enum, e.g., [`CrystalRole`]   |
+//!
+//! Conversion fails when a field is missing or its value doesn't fit the
+//! type; the error names the record and field. Numbers convert between
+//! `Int` and `UInt` when they fit, and enums also accept their raw value or
+//! name.
+//!
+//! ## Schema Enums
+//!
+//! Every enum the pack defines gets a type of the same name, so fields
+//! bound to it read the symbolic values [`super::parse_wdb`] produces:
+//!
+//! | Schema Enum | Rust Type                                | Reads               |
+//! |-------------|------------------------------------------|---------------------|
+//! | plain       | `enum` with an `Other(i64)` variant      | `"Ice"` or a number |
+//! | `flags`     | bit set newtype with one const per flag  | `["Poison"]`, `"Poison"` or a number |
+//!
+//! Converting back gives the same symbolic form, and values the schema
+//! doesn't name stay numeric, as in [`super::symbolic`].
+//! The built-in pack defines no enums of its own yet, so its enum-bound
+//! fields all use the built-in types.
+//!
+//! ```rust,ignore
+//! use fabula_nova_sdk::modules::wdb::{self, records::*};
+//!
+//! let data = wdb::parse_wdb("crystal_fang.wdb", GameCode::FF13_1)?;
+//! for node in Crystal::from_wdb(&data)? {
+//!     if node.node_type == CrystalNodeType::Hp {
+//!         println!("{}: +{} HP for {} CP", node.record, node.node_val, node.cp_cost);
+//!     }
+//! }
+//! ```
+
+use anyhow::{bail, Result};
+
+use super::enums::{CrystalNodeType, CrystalRole};
+use super::structs::{WdbData, WdbRecord, WdbValue};
+
+/// A generated record struct for one sheet.
+pub trait WdbSheetRecord: for<'a> TryFrom<&'a WdbRecord, Error = anyhow::Error> + Into<WdbRecord> {
+    /// Sheet name in the field tables
+    const SHEET: &'static str;
+    /// Field names in record order, without `record`
+    const FIELDS: &'static [&'static str];
+
+    /// Converts every record of a parsed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first record that doesn't convert.
+    fn from_wdb(data: &WdbData) -> Result<Vec<Self>> {
+        data.records.iter().map(Self::try_from).collect()
+    }
+}
+
+/// A Rust type a WDB field value converts to and from.
+pub trait WdbFieldValue: Sized {
+    /// The typed value, or `None` if `value` has another kind or doesn't fit.
+    fn from_value(value: &WdbValue) -> Option<Self>;
+
+    /// The value as the reader produces it.
+    fn into_value(self) -> WdbValue;
+}
+
+/// A number of either integer kind.
+fn as_i64(value: &WdbValue) -> Option<i64> {
+    match value {
+        WdbValue::Int(i) => Some(*i as i64),
+        WdbValue::UInt(u) => Some(*u as i64),
+        _ => None,
+    }
+}
+
+macro_rules! int_field_value {
+    ($($ty:ty => $variant:ident),*) => {$(
+        impl WdbFieldValue for $ty {
+            fn from_value(value: &WdbValue) -> Option<Self> {
+                <$ty>::try_from(as_i64(value)?).ok()
+            }
+
+            fn into_value(self) -> WdbValue {
+                WdbValue::$variant(self.into())
+            }
+        }
+    )*};
+}
+
+int_field_value!(u8 => UInt, u16 => UInt, u32 => UInt, i8 => Int, i16 => Int, i32 => Int);
+
+impl WdbFieldValue for bool {
+    fn from_value(value: &WdbValue) -> Option<Self> {
+        match value {
+            WdbValue::Bool(b) => Some(*b),
+            other => match as_i64(other)? {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            },
+        }
+    }
+
+    fn into_value(self) -> WdbValue {
+        WdbValue::UInt(self as u32)
+    }
+}
+
+impl WdbFieldValue for f32 {
+    fn from_value(value: &WdbValue) -> Option<Self> {
+        match value {
+            WdbValue::Float(f) => Some(*f),
+            other => as_i64(other).map(|i| i as f32),
+        }
+    }
+
+    fn into_value(self) -> WdbValue {
+        WdbValue::Float(self)
+    }
+}
+
+impl WdbFieldValue for String {
+    fn from_value(value: &WdbValue) -> Option<Self> {
+        match value {
+            WdbValue::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> WdbValue {
+        WdbValue::String(self)
+    }
+}
+
+macro_rules! enum_field_value {
+    ($($ty:ident),*) => {$(
+        impl WdbFieldValue for $ty {
+            fn from_value(value: &WdbValue) -> Option<Self> {
+                match value {
+                    WdbValue::$ty(e) => Some(*e),
+                    WdbValue::String(s) => serde_json::from_value(serde_json::Value::String(s.clone())).ok(),
+                    other => Some($ty::from_u32(u32::try_from(as_i64(other)?).ok()?)),
+                }
+            }
+
+            fn into_value(self) -> WdbValue {
+                WdbValue::$ty(self)
+            }
+        }
+    )*};
+}
+
+enum_field_value!(CrystalRole, CrystalNodeType);
+
+// The helpers and macros below back the types generated for schema enums.
+// The built-in pack binds only the built-in enums for now, so nothing
+// outside the tests expands them.
+
+/// A number for a value the schema has no name for.
+#[allow(dead_code)]
+fn unnamed(value: i64) -> WdbValue {
+    match u32::try_from(value) {
+        Ok(u) => WdbValue::UInt(u),
+        Err(_) => WdbValue::Int(value as i32),
+    }
+}
+
+/// The raw value of a plain schema enum field: a name or a number.
+#[allow(dead_code)]
+fn enum_raw(value: &WdbValue, names: &[(&str, i64)]) -> Option<i64> {
+    match value {
+        WdbValue::String(s) => names.iter().find(|(n, _)| n == s).map(|(_, v)| *v),
+        other => as_i64(other),
+    }
+}
+
+/// The raw value of a flag set field: a list of names, one name or a number.
+#[allow(dead_code)]
+fn flags_raw(value: &WdbValue, names: &[(&str, i64)]) -> Option<i64> {
+    match value {
+        WdbValue::StringArray(list) => list.iter().try_fold(0, |bits, s| {
+            names.iter().find(|(n, _)| n == s).map(|(_, v)| bits | v)
+        }),
+        other => enum_raw(other, names),
+    }
+}
+
+/// A flag set as names, or a number if a set bit has no name.
+#[allow(dead_code)]
+fn flags_value(bits: i64, names: &[(&str, i64)]) -> WdbValue {
+    let set: Vec<String> = names.iter().filter(|(_, v)| bits & v != 0).map(|(n, _)| n.to_string()).collect();
+    let covered = names.iter().filter(|(_, v)| bits & v != 0).fold(0, |acc, (_, v)| acc | v);
+    if covered == bits {
+        WdbValue::StringArray(set)
+    } else {
+        unnamed(bits)
+    }
+}
+
+/// A plain schema enum: one variant per named value plus `Other`.
+#[allow(unused_macros)]
+macro_rules! schema_enum {
+    ($(#[$doc:meta])* $name:ident { $($variant:ident = $label:literal => $value:literal,)* }) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            /// A value the schema has no name for
+            Other(i64),
+        }
+
+        impl $name {
+            /// Name and value of every variant, in schema order.
+            pub const VALUES: &'static [(&'static str, i64)] = &[$(($label, $value)),*];
+
+            /// The variant for a raw value.
+            pub fn from_raw(value: i64) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    other => Self::Other(other),
+                }
+            }
+
+            /// The value stored in the file.
+            pub fn raw(self) -> i64 {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Other(value) => value,
+                }
+            }
+
+            /// The schema name, `None` for an unnamed value.
+            pub fn name(self) -> Option<&'static str> {
+                let raw = self.raw();
+                Self::VALUES.iter().find(|(_, v)| *v == raw).map(|(n, _)| *n)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::from_raw(0)
+            }
+        }
+
+        impl WdbFieldValue for $name {
+            fn from_value(value: &WdbValue) -> Option<Self> {
+                enum_raw(value, Self::VALUES).map(Self::from_raw)
+            }
+
+            fn into_value(self) -> WdbValue {
+                match self.name() {
+                    Some(name) => WdbValue::String(name.to_string()),
+                    None => unnamed(self.raw()),
+                }
+            }
+        }
+    };
+}
+
+/// A schema flag set: a bit set with one associated const per flag.
+#[allow(unused_macros)]
+macro_rules! schema_flags {
+    ($(#[$doc:meta])* $name:ident { $($flag:ident = $label:literal => $value:literal,)* }) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub i64);
+
+        impl $name {
+            $(pub const $flag: Self = Self($value);)*
+
+            /// Name and bit of every flag, in schema order.
+            pub const VALUES: &'static [(&'static str, i64)] = &[$(($label, $value)),*];
+
+            /// Returns true if every bit of `other` is set.
+            pub fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                Self(self.0 | other.0)
+            }
+        }
+
+        impl WdbFieldValue for $name {
+            fn from_value(value: &WdbValue) -> Option<Self> {
+                flags_raw(value, Self::VALUES).map(Self)
+            }
+
+            fn into_value(self) -> WdbValue {
+                flags_value(self.0, Self::VALUES)
+            }
+        }
+    };
+}
+
+/// Reads one field of a record as `T`.
+fn field<T: WdbFieldValue>(record: &WdbRecord, name: &str) -> Result<T> {
+    let id = || match record.get("record") {
+        Some(WdbValue::String(s)) => s.clone(),
+        _ => "?".to_string(),
+    };
+    match record.get(name) {
+        None => bail!("Record {}: missing field {}", id(), name),
+        Some(value) => match T::from_value(value) {
+            Some(v) => Ok(v),
+            None => bail!("Record {}: field {} = {:?} is not a {}", id(), name, value, std::any::type_name::<T>()),
+        },
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/wdb_records.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::dicts::FIELD_NAMES;
+
+    fn crystal_record() -> WdbRecord {
+        let mut r = WdbRecord::new();
+        r.insert("record".to_string(), WdbValue::String("cr_fa01".to_string()));
+        r.insert("uCPCost".to_string(), WdbValue::UInt(120));
+        r.insert("sAbilityID".to_string(), WdbValue::String("abi_fire".to_string()));
+        r.insert("u4Role".to_string(), WdbValue::CrystalRole(CrystalRole::Attacker));
+        r.insert("u4CrystalStage".to_string(), WdbValue::UInt(2));
+        r.insert("u8NodeType".to_string(), WdbValue::CrystalNodeType(CrystalNodeType::Ability));
+        r.insert("u16NodeVal".to_string(), WdbValue::UInt(300));
+        r
+    }
+
+    #[test]
+    fn test_typed_record_round_trip() {
+        let record = crystal_record();
+        let node = Crystal::try_from(&record).unwrap();
+        assert_eq!(node.cp_cost, 120);
+        assert_eq!(node.ability_id, "abi_fire");
+        assert_eq!(node.role, CrystalRole::Attacker);
+        assert_eq!(node.node_type, CrystalNodeType::Ability);
+        assert_eq!(node.node_val, 300u16);
+        assert_eq!(WdbRecord::from(node), record);
+
+        // Enums also accept raw numbers and names (JSON import gives strings)
+        let mut loose = record.clone();
+        loose.insert("u4Role".to_string(), WdbValue::UInt(6));
+        loose.insert("u8NodeType".to_string(), WdbValue::String("Hp".to_string()));
+        let node = Crystal::try_from(&loose).unwrap();
+        assert_eq!((node.role, node.node_type), (CrystalRole::Healer, CrystalNodeType::Hp));
+
+        let data = WdbData { header: Default::default(), records: vec![record.clone(), record] };
+        assert_eq!(Crystal::from_wdb(&data).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_typed_record_errors() {
+        let mut record = crystal_record();
+        record.insert("u16NodeVal".to_string(), WdbValue::UInt(70000));
+        let err = Crystal::try_from(&record).unwrap_err().to_string();
+        assert!(err.contains("cr_fa01") && err.contains("u16NodeVal"), "{}", err);
+
+        record.shift_remove("sAbilityID");
+        let err = Crystal::try_from(&record).unwrap_err().to_string();
+        assert!(err.contains("missing field sAbilityID"), "{}", err);
+    }
+
+    schema_enum!(
+        /// Test enum.
+        Weather {
+            Clear = "Clear" => 0,
+            Rain = "Rain" => 2,
+        }
+    );
+
+    schema_flags!(
+        /// Test flag set.
+        Ailments {
+            POISON = "Poison" => 1,
+            SLOW = "Slow" => 4,
+        }
+    );
+
+    #[test]
+    fn test_schema_enums_read_symbolic_values() {
+        // Names read and write back as names, as symbolize_wdb_values gives them
+        let rain = WdbValue::String("Rain".to_string());
+        assert_eq!(Weather::from_value(&rain), Some(Weather::Rain));
+        assert_eq!(Weather::from_value(&WdbValue::UInt(2)), Some(Weather::Rain));
+        assert_eq!(Weather::Rain.into_value(), rain);
+        assert_eq!(Weather::default(), Weather::Clear);
+
+        let set = WdbValue::StringArray(vec!["Poison".to_string(), "Slow".to_string()]);
+        let flags = Ailments::from_value(&set).unwrap();
+        assert_eq!(flags, Ailments::POISON | Ailments::SLOW);
+        assert!(flags.contains(Ailments::SLOW) && !Ailments::POISON.contains(flags));
+        assert_eq!(flags.into_value(), set);
+        assert_eq!(Ailments::from_value(&WdbValue::String("Slow".to_string())), Some(Ailments::SLOW));
+
+        // Unnamed values stay numeric both ways
+        assert_eq!(Weather::from_value(&WdbValue::UInt(200)), Some(Weather::Other(200)));
+        assert_eq!(Weather::Other(200).into_value(), WdbValue::UInt(200));
+        assert_eq!(Ailments::from_value(&WdbValue::UInt(2 | 1)), Some(Ailments(3)));
+        assert_eq!(Ailments(3).into_value(), WdbValue::UInt(3));
+        assert_eq!(Weather::from_value(&WdbValue::String("Nope".to_string())), None);
+        assert_eq!(Ailments::from_value(&WdbValue::StringArray(vec!["Nope".to_string()])), None);
+    }
+
+    #[test]
+    fn test_generated_fields_match_tables() {
+        assert_eq!(Crystal::FIELDS, FIELD_NAMES["crystal"].as_slice());
+        assert_eq!(Item::FIELDS, FIELD_NAMES["Item"].as_slice());
+        assert_eq!(BattleCharaProp::SHEET, "BattleCharaProp");
+        assert_eq!(ItemWeapon::FIELDS.len(), FIELD_NAMES["ItemWeapon"].len());
+    }
+}
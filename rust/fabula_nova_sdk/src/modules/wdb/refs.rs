@@ -0,0 +1,461 @@
+//! # WDB Cross-References
+//!
+//! String fields in WDB sheets often hold ids of other records (an item's
+//! ability, a shop's items, a treasure box's contents) or ZTR keys for
+//! names and help text. This module indexes a set of WDB and ZTR files and
+//! follows those links, so edits that leave a reference pointing nowhere
+//! are caught before the game loads them.
+//!
+//! ## Finding Reference Fields
+//!
+//! | Source     | Rule                                                         |
+//! |------------|--------------------------------------------------------------|
+//! | Schema     | The field declares `"ref": "<sheet>"` or `"ref": "ztr"`      |
+//! | Inferred   | At least 80% (and at least 2) of the field's non-empty values resolve to the same sheet or to ZTR keys |
+//!
+//! Inference runs over the indexed data itself, so a field counts as a
+//! reference only when the targets it points at are loaded. A field whose
+//! text files weren't indexed is simply not reported, never flagged as
+//! dangling wholesale. The flip side is that a field must still mostly
+//! resolve after an edit to be checked; declare it in the schema to check
+//! it unconditionally.
+//!
+//! ## Targets
+//!
+//! A record target names a sheet: it matches every indexed file whose stem
+//! or schema sheet has that name (`crystal` covers `crystal_fang`,
+//! `crystal_hope`, ...). Empty strings mean "no reference" and are skipped.
+//!
+//! ```text
+//!   item.wdb                       ability.wdb
+//!   ┌───────────┬──────────┐       ┌──────────┐
+//!   │ record    │ sAbility │ ────► │ abi_fire │
+//!   │ it_potion │ abi_fire │       └──────────┘
+//!   │ it_ether  │ abi_xxx  │ ────► (dangling)
+//!   └───────────┴──────────┘
+//! ```
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use super::api::{parse_wdb, parse_wdb_bytes};
+use super::schema::with_active_schema;
+use super::structs::{GameCode, WdbData, WdbRecord, WdbRefKind, WdbRefSource, WdbReference, WdbReferenceReport, WdbValue};
+use super::symbolic::SCHEMA_SHEET_KEY;
+use crate::modules::wpd::reader::WpdReader;
+use crate::modules::ztr::{api::parse_ztr, structs::ZtrData};
+
+/// Schema `ref` value for ZTR text keys.
+pub const TEXT_REFERENCE: &str = "ztr";
+
+/// Minimum share of resolved values (percent) for an inferred reference.
+pub(crate) const INFERRED_SHARE: usize = 80;
+
+/// Minimum number of resolved values for an inferred reference.
+pub(crate) const INFERRED_MIN: usize = 2;
+
+/// Where a reference field points.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Target {
+    Text,
+    /// Lowercase sheet name
+    Sheet(String),
+}
+
+/// An index of WDB sheets and ZTR text that resolves references between them.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut graph = WdbReferenceGraph::new(GameCode::FF13_1);
+/// graph.load_directory("extracted/db")?;
+/// graph.add_sheet("item", edited_items);
+/// for r in graph.report().dangling {
+///     println!("{}:{}.{} -> {} is missing", r.sheet, r.record, r.field, r.value);
+/// }
+/// ```
+pub struct WdbReferenceGraph {
+    game_code: GameCode,
+    sheets: IndexMap<String, WdbData>,
+    texts: HashMap<String, String>,
+}
+
+impl WdbReferenceGraph {
+    pub fn new(game_code: GameCode) -> Self {
+        Self {
+            game_code,
+            sheets: IndexMap::new(),
+            texts: HashMap::new(),
+        }
+    }
+
+    /// Adds a sheet, replacing any sheet of the same name.
+    ///
+    /// `name` is normally the WDB file stem (e.g., `item`).
+    pub fn add_sheet(&mut self, name: &str, data: WdbData) {
+        self.sheets.insert(name.to_lowercase(), data);
+    }
+
+    /// Adds the entries of a ZTR file. Later files win on duplicate keys.
+    pub fn add_texts(&mut self, data: &ZtrData) {
+        for entry in &data.entries {
+            self.texts.insert(entry.id.clone(), entry.text.clone());
+        }
+    }
+
+    /// Indexes every `.wdb` and `.ztr` file under a directory.
+    ///
+    /// # Returns
+    ///
+    /// The number of files indexed. Files that fail to parse are logged
+    /// and skipped, so one bad file doesn't hide the rest.
+    pub fn load_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize> {
+        let mut count = 0;
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let ext = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+            let result = match ext.as_deref() {
+                Some("wdb") => parse_wdb(path, self.game_code).map(|data| {
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                    self.add_sheet(&stem, data);
+                }),
+                Some("ztr") => parse_ztr(path, self.game_code).map(|data| self.add_texts(&data)),
+                _ => continue,
+            };
+            match result {
+                Ok(()) => count += 1,
+                Err(e) => log::warn!("Skipping {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Indexes every WDB record of a `wdbpack.bin`.
+    ///
+    /// # Returns
+    ///
+    /// The number of sheets indexed. Records that fail to parse are logged
+    /// and skipped.
+    pub fn load_wdbpack<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let mut reader = WpdReader::new(BufReader::new(File::open(path)?));
+        let header = reader.read_header()?;
+        let mut count = 0;
+        for record in reader.read_records(&header)? {
+            if !record.extension.eq_ignore_ascii_case("wdb") {
+                continue;
+            }
+            match parse_wdb_bytes(&record.data, &record.name, self.game_code) {
+                Ok(data) => {
+                    self.add_sheet(&record.name, data);
+                    count += 1;
+                }
+                Err(e) => log::warn!("Skipping {}.wdb: {:#}", record.name, e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// The game the indexed files belong to.
+    pub fn game_code(&self) -> GameCode {
+        self.game_code
+    }
+
+    /// Indexed sheets by file stem, in the order they were added.
+    pub fn sheets(&self) -> &IndexMap<String, WdbData> {
+        &self.sheets
+    }
+
+    /// Indexed ZTR text by key.
+    pub fn texts(&self) -> &HashMap<String, String> {
+        &self.texts
+    }
+
+    /// The record with an id in a sheet, by file stem.
+    pub fn record(&self, sheet: &str, id: &str) -> Option<&WdbRecord> {
+        self.sheets.get(&sheet.to_lowercase())?.records.iter().find(|r| record_id(r) == Some(id))
+    }
+
+    /// The text for a ZTR key.
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.texts.get(key).map(String::as_str)
+    }
+
+    /// Finds every reference field value, resolved or not.
+    pub fn report(&self) -> WdbReferenceReport {
+        let ids = self.record_index();
+        let resolve = |target: &Target, value: &str| -> Option<String> {
+            match target {
+                Target::Text => self.texts.contains_key(value).then(String::new),
+                Target::Sheet(sheet) => ids.get(value)?.iter().find(|s| self.sheet_matches(s, sheet)).cloned(),
+            }
+        };
+
+        let mut report = WdbReferenceReport::default();
+        for (sheet, data) in &self.sheets {
+            for (field, (target, source)) in self.reference_fields(sheet, data, &ids) {
+                for record in &data.records {
+                    for value in string_values(record.get(&field)) {
+                        let resolved = resolve(&target, value);
+                        let reference = WdbReference {
+                            sheet: sheet.clone(),
+                            record: record_id(record).unwrap_or_default().to_string(),
+                            field: field.clone(),
+                            value: value.to_string(),
+                            kind: match target {
+                                Target::Text => WdbRefKind::Text,
+                                Target::Sheet(_) => WdbRefKind::Record,
+                            },
+                            target_sheet: match &target {
+                                Target::Text => None,
+                                Target::Sheet(s) => Some(resolved.clone().unwrap_or_else(|| s.clone())),
+                            },
+                            source,
+                        };
+                        match resolved {
+                            Some(_) => report.references.push(reference),
+                            None => report.dangling.push(reference),
+                        }
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// References that point at one record.
+    pub fn references_to(&self, sheet: &str, id: &str) -> Vec<WdbReference> {
+        let sheet = sheet.to_lowercase();
+        self.report()
+            .references
+            .into_iter()
+            .filter(|r| r.value == id && r.target_sheet.as_deref() == Some(sheet.as_str()))
+            .collect()
+    }
+
+    /// Record id -> file stems holding a record with that id.
+    fn record_index(&self) -> HashMap<&str, Vec<String>> {
+        let mut ids: HashMap<&str, Vec<String>> = HashMap::new();
+        for (sheet, data) in &self.sheets {
+            for id in data.records.iter().filter_map(record_id) {
+                ids.entry(id).or_default().push(sheet.clone());
+            }
+        }
+        ids
+    }
+
+    /// Whether an indexed file belongs to a (lowercase) target sheet name.
+    fn sheet_matches(&self, stem: &str, target: &str) -> bool {
+        stem == target
+            || matches!(
+                self.sheets.get(stem).and_then(|d| d.header.get(SCHEMA_SHEET_KEY)),
+                Some(WdbValue::String(s)) if s.eq_ignore_ascii_case(target)
+            )
+    }
+
+    /// Reference fields of one sheet: declared ones, then inferred ones.
+    fn reference_fields(
+        &self,
+        sheet: &str,
+        data: &WdbData,
+        ids: &HashMap<&str, Vec<String>>,
+    ) -> BTreeMap<String, (Target, WdbRefSource)> {
+        let schema_sheet = match data.header.get(SCHEMA_SHEET_KEY) {
+            Some(WdbValue::String(s)) => s.clone(),
+            _ => sheet.to_string(),
+        };
+        let mut fields: BTreeMap<String, (Target, WdbRefSource)> = with_active_schema(self.game_code, |schema| {
+            schema
+                .sheet(&schema_sheet)
+                .map(|s| {
+                    s.fields
+                        .iter()
+                        .filter_map(|f| {
+                            let target = match f.reference.as_deref()? {
+                                r if r.eq_ignore_ascii_case(TEXT_REFERENCE) => Target::Text,
+                                r => Target::Sheet(r.to_lowercase()),
+                            };
+                            Some((f.name.clone(), (target, WdbRefSource::Schema)))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+
+        let string_fields: HashSet<&String> = data
+            .records
+            .iter()
+            .flat_map(|r| r.keys())
+            .filter(|k| k.starts_with('s') && !fields.contains_key(*k))
+            .collect();
+        for field in string_fields {
+            let mut total = 0;
+            let mut hits: BTreeMap<Target, usize> = BTreeMap::new();
+            for value in data.records.iter().flat_map(|r| string_values(r.get(field))) {
+                total += 1;
+                if self.texts.contains_key(value) {
+                    *hits.entry(Target::Text).or_default() += 1;
+                }
+                let sheets: HashSet<&String> = ids.get(value).into_iter().flatten().collect();
+                for s in sheets {
+                    *hits.entry(Target::Sheet(s.clone())).or_default() += 1;
+                }
+            }
+            let best = hits.into_iter().max_by_key(|(_, n)| *n);
+            if let Some((target, n)) = best {
+                if n >= INFERRED_MIN && n * 100 >= total * INFERRED_SHARE {
+                    fields.insert(field.clone(), (target, WdbRefSource::Inferred));
+                }
+            }
+        }
+        fields
+    }
+}
+
+pub(crate) fn record_id(record: &WdbRecord) -> Option<&str> {
+    match record.get("record") {
+        Some(WdbValue::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+/// Non-empty strings of a `String` or `StringArray` value.
+fn string_values(value: Option<&WdbValue>) -> Vec<&str> {
+    match value {
+        Some(WdbValue::String(s)) if !s.is_empty() => vec![s.as_str()],
+        Some(WdbValue::StringArray(a)) => a.iter().filter(|s| !s.is_empty()).map(String::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Indexes a directory and reports its references.
+///
+/// # Arguments
+///
+/// * `dir` - Directory scanned recursively for `.wdb` and `.ztr` files
+/// * `game_code` - Which game the files belong to
+/// * `edited` - In-memory sheets (by file stem) that replace the files on disk
+pub fn wdb_reference_report<P: AsRef<Path>>(
+    dir: P,
+    game_code: GameCode,
+    edited: Vec<(String, WdbData)>,
+) -> Result<WdbReferenceReport> {
+    let mut graph = WdbReferenceGraph::new(game_code);
+    graph.load_directory(dir)?;
+    for (name, data) in edited {
+        graph.add_sheet(&name, data);
+    }
+    Ok(graph.report())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::schema::{
+        add_schema_override_dir, SchemaOverrideGuard, WdbFieldSchema, WdbSchemaPack, WdbSheetSchema,
+        WDB_SCHEMA_VERSION,
+    };
+    use crate::modules::wdb::structs::WdbHeader;
+    use crate::modules::ztr::structs::ZtrEntry;
+
+    fn sheet(rows: &[(&str, &[(&str, &str)])]) -> WdbData {
+        let records = rows
+            .iter()
+            .map(|(id, fields)| {
+                let mut r = WdbRecord::new();
+                r.insert("record".to_string(), WdbValue::String(id.to_string()));
+                for (k, v) in *fields {
+                    r.insert(k.to_string(), WdbValue::String(v.to_string()));
+                }
+                r
+            })
+            .collect();
+        WdbData { header: WdbHeader::new(), records }
+    }
+
+    fn graph() -> WdbReferenceGraph {
+        let mut graph = WdbReferenceGraph::new(GameCode::FF13_2);
+        graph.add_sheet("ability", sheet(&[("abi_fire", &[]), ("abi_ice", &[]), ("abi_bolt", &[])]));
+        graph.add_sheet(
+            "item",
+            sheet(&[
+                ("it_a", &[("sAbility", "abi_fire"), ("sName", "$it_a"), ("sNote", "hello")]),
+                ("it_b", &[("sAbility", "abi_ice"), ("sName", "$it_b"), ("sNote", "")]),
+                ("it_c", &[("sAbility", "abi_bolt"), ("sName", "$it_c"), ("sNote", "abi_fire")]),
+                ("it_d", &[("sAbility", ""), ("sName", "$it_d"), ("sNote", "world")]),
+                ("it_e", &[("sAbility", "abi_ice"), ("sName", "$it_e"), ("sNote", "")]),
+                ("it_f", &[("sAbility", "abi_fire"), ("sName", "$it_f"), ("sNote", "")]),
+            ]),
+        );
+        graph.add_texts(&ZtrData {
+            entries: ["$it_a", "$it_b", "$it_c", "$it_d", "$it_e", "$it_f"]
+                .iter()
+                .map(|k| ZtrEntry { id: k.to_string(), text: k.to_uppercase() })
+                .collect(),
+            mappings: Vec::new(),
+        });
+        graph
+    }
+
+    #[test]
+    fn test_inferred_references_and_dangling() {
+        let mut graph = graph();
+        let report = graph.report();
+        assert!(report.dangling.is_empty(), "{:?}", report.dangling);
+        // sAbility and sName are inferred; sNote (1 of 3 resolves) is not a reference
+        assert_eq!(report.references.len(), 11);
+        assert!(report.references.iter().all(|r| r.field != "sNote" && r.source == WdbRefSource::Inferred));
+        assert_eq!(graph.references_to("ability", "abi_ice").len(), 2);
+        assert_eq!(graph.text("$it_b"), Some("$IT_B"));
+
+        // Rename a record other records point at
+        let mut ability = graph.sheets["ability"].clone();
+        ability.records[2].insert("record".to_string(), WdbValue::String("abi_thunder".to_string()));
+        graph.add_sheet("ability", ability);
+        let report = graph.report();
+        assert_eq!(report.dangling.len(), 1);
+        let dangling = &report.dangling[0];
+        assert_eq!((dangling.record.as_str(), dangling.value.as_str()), ("it_c", "abi_bolt"));
+        assert_eq!(dangling.kind, WdbRefKind::Record);
+        assert_eq!(dangling.target_sheet.as_deref(), Some("ability"));
+    }
+
+    #[test]
+    fn test_schema_declared_references() {
+        let _overrides = SchemaOverrideGuard::lock();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test_refs_schema");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut note = WdbFieldSchema::from_name("sNote").unwrap();
+        note.reference = Some(TEXT_REFERENCE.to_string());
+        let pack = WdbSchemaPack {
+            version: WDB_SCHEMA_VERSION,
+            game: GameCode::FF13_2,
+            enums: Vec::new(),
+            sheets: vec![WdbSheetSchema {
+                name: "refs_test_item".to_string(),
+                files: Vec::new(),
+                fields: vec![note],
+                description: None,
+            }],
+        };
+        std::fs::write(dir.join("refs.json"), serde_json::to_string(&pack).unwrap()).unwrap();
+        add_schema_override_dir(&dir).unwrap();
+
+        let mut graph = graph();
+        let item = graph.sheets["item"].clone();
+        graph.add_sheet("refs_test_item", item);
+        let report = graph.report();
+
+        // Only the sheet the schema covers declares sNote a text reference
+        let notes: Vec<&WdbReference> = report.dangling.iter().filter(|r| r.field == "sNote").collect();
+        assert_eq!(notes.len(), 3, "every non-empty sNote must be a text key");
+        assert!(notes.iter().all(|r| r.sheet == "refs_test_item"));
+        assert!(notes.iter().all(|r| r.source == WdbRefSource::Schema && r.kind == WdbRefKind::Text));
+    }
+}
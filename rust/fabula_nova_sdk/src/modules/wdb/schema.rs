@@ -0,0 +1,733 @@
+//! # WDB Schema Packs
+//!
+//! This module loads the field layouts used to decode WDB sheets. Most
+//! XIII files have no `!structitem` section, so their field names must come
+//! from somewhere else. The same goes for the fields that decode to typed
+//! enums. Both used to be compiled in. They now live in schema packs that
+//! can be loaded at runtime.
+//!
+//! ## Schema Pack Format
+//!
+//! A schema pack is a JSON file describing the sheets of one game:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "game": "FF13_1",
+//!   "sheets": [
+//!     {
+//!       "name": "crystal",
+//!       "files": ["crystal_fang", "crystal_hope"],
+//!       "fields": [
+//!         { "name": "uCPCost", "kind": "uint", "bits": 0 },
+//!         { "name": "u4Role", "kind": "uint", "bits": 4, "enum": "CrystalRole",
+//!           "description": "Role whose level the node raises" }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! | Key           | Meaning                                               |
+//! |---------------|-------------------------------------------------------|
+//! | `name`        | Field name exactly as it appears in records           |
+//! | `kind`        | `int`, `uint`, `float` or `string`                    |
+//! | `bits`        | Bitpacked width, `0` for a full 32-bit value          |
+//! | `enum`        | Optional enum type the raw value decodes to           |
+//! | `ref`         | Optional reference target: a sheet name, or `ztr`     |
+//! | `description` | Optional free text for editors                        |
+//!
+//! ## Enums and Flags
+//!
+//! A pack can define its own enums and bind them to fields by name:
+//!
+//! ```text
+//! "enums": [
+//!   { "name": "Element", "values": [
+//!       { "name": "Fire", "value": 1 }, { "name": "Ice", "value": 2 } ] },
+//!   { "name": "StatusFlags", "flags": true, "values": [
+//!       { "name": "Poison", "value": 1 }, { "name": "Slow", "value": 2 } ] }
+//! ]
+//! ```
+//!
+//! Plain enums decode to a name (`"Ice"`), flag sets to a list of names
+//! (`["Poison", "Slow"]`). Values with no name, or flag bits not covered by
+//! the enum, stay numeric so nothing is lost. See [`super::symbolic`] for
+//! how names are turned back into numbers. `CrystalRole` and
+//! `CrystalNodeType` are built in and can be bound without a definition.
+//! They are the only enums the built-in packs bind; any other table has to
+//! come from an override pack.
+//!
+//! The reader and writer still derive the type and width from the field
+//! name (`u4Role` = 4-bit unsigned). `kind` and `bits` are therefore
+//! checked against the name when a pack is loaded, so a typo fails early
+//! instead of shifting every field after it.
+//!
+//! ## Sheets and Files
+//!
+//! `files` lists the WDB file stems (e.g., `crystal_fang`) whose records
+//! use the sheet. When such a file has no `!structitem`, the sheet's fields
+//! become its field names. A sheet with no `files` only annotates fields:
+//! its enum bindings still apply to the file named after the sheet, but it
+//! never supplies field names.
+//!
+//! ## Overrides
+//!
+//! The built-in pack for each game is generated from [`super::dicts`] and
+//! the enum registry. [`add_schema_override_dir`] loads
+//! every `*.json` pack in a directory on top of it:
+//!
+//! - a sheet with the same name replaces the built-in one
+//! - a file listed by an override sheet moves to that sheet
+//! - directories are applied in the order they were added
+//!
+//! ```rust,ignore
+//! use fabula_nova_sdk::modules::wdb;
+//!
+//! wdb::export_default_schema_pack(GameCode::FF13_1, "schemas/ff13.json")?;
+//! // ... edit or add sheets ...
+//! wdb::add_schema_override_dir("schemas")?;
+//! let data = wdb::parse_wdb("zonelist.wdb", GameCode::FF13_1)?;
+//! ```
+//!
+//! A serialized copy of the built-in FF13 pack lives in
+//! `schemas/ff13_1.json`. `build.rs` generates [`super::records`] from it,
+//! and a test fails when it no longer matches [`default_schema_pack`].
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::bit_helpers::derive_field_number;
+use super::dicts::{FIELD_NAMES, RECORD_IDS};
+use super::enum_registry::{get_enum_type, EnumType, ENUM_FIELDS};
+use super::structs::GameCode;
+
+/// Schema format version written by this SDK.
+///
+/// Packs with a higher version are rejected.
+pub const WDB_SCHEMA_VERSION: u32 = 1;
+
+/// Value type of a schema field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WdbFieldKind {
+    Int,
+    UInt,
+    Float,
+    String,
+}
+
+impl WdbFieldKind {
+    /// The kind encoded by a field name's prefix (`i`, `u`, `f`, `s`).
+    pub fn from_field_name(name: &str) -> Option<Self> {
+        match name.chars().next()? {
+            'i' => Some(Self::Int),
+            'u' => Some(Self::UInt),
+            'f' => Some(Self::Float),
+            's' => Some(Self::String),
+            _ => None,
+        }
+    }
+}
+
+/// One field of a sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbFieldSchema {
+    pub name: String,
+    pub kind: WdbFieldKind,
+    #[serde(default)]
+    pub bits: u32,
+    #[serde(rename = "enum", default, skip_serializing_if = "Option::is_none")]
+    pub enum_type: Option<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl WdbFieldSchema {
+    /// Builds a field whose kind and width come from its name.
+    pub fn from_name(name: &str) -> Result<Self> {
+        let kind = WdbFieldKind::from_field_name(name)
+            .with_context(|| format!("Field '{}' has no i/u/f/s type prefix", name))?;
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            bits: derive_field_number(name) as u32,
+            enum_type: None,
+            reference: None,
+            description: None,
+        })
+    }
+}
+
+/// One sheet (record type) and the files that use it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbSheetSchema {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub fields: Vec<WdbFieldSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// One named value of an enum.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbEnumValue {
+    pub name: String,
+    pub value: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A named enum or bitflag set that fields can bind to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbEnumSchema {
+    pub name: String,
+    /// When true each value is a single bit and fields hold any combination.
+    #[serde(default)]
+    pub flags: bool,
+    pub values: Vec<WdbEnumValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl WdbEnumSchema {
+    /// Looks up the value of a name.
+    pub fn value_of(&self, name: &str) -> Option<i64> {
+        self.values.iter().find(|v| v.name == name).map(|v| v.value)
+    }
+
+    /// Looks up the name of a value.
+    pub fn name_of(&self, value: i64) -> Option<&str> {
+        self.values.iter().find(|v| v.value == value).map(|v| v.name.as_str())
+    }
+
+    /// Splits a flag set into names, or `None` if a set bit has no name.
+    pub fn flag_names(&self, value: i64) -> Option<Vec<String>> {
+        let mut names = Vec::new();
+        let mut remaining = value;
+        for v in &self.values {
+            if value & v.value != 0 {
+                names.push(v.name.clone());
+                remaining &= !v.value;
+            }
+        }
+        (remaining == 0).then_some(names)
+    }
+}
+
+/// All sheets known for one game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbSchemaPack {
+    pub version: u32,
+    pub game: GameCode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enums: Vec<WdbEnumSchema>,
+    #[serde(default)]
+    pub sheets: Vec<WdbSheetSchema>,
+}
+
+/// How a field's raw value maps to symbolic names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnumBinding {
+    /// One of the typed enums in [`super::enums`]
+    Builtin(EnumType),
+    /// An enum or flag set defined in a schema pack
+    Schema(WdbEnumSchema),
+}
+
+impl WdbSchemaPack {
+    /// Finds a sheet by name (case-insensitive).
+    pub fn sheet(&self, name: &str) -> Option<&WdbSheetSchema> {
+        self.sheets.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Finds the sheet that lists `file_stem` in its `files`.
+    pub fn sheet_for_file(&self, file_stem: &str) -> Option<&WdbSheetSchema> {
+        self.sheets.iter().find(|s| s.files.iter().any(|f| f == file_stem))
+    }
+
+    /// Finds the sheet describing a file: by its file mapping first, then by
+    /// the file's own sheet name, then by a sheet named after the file.
+    pub fn sheet_for_wdb(&self, file_stem: &str, sheet_name: Option<&str>) -> Option<&WdbSheetSchema> {
+        self.sheet_for_file(file_stem)
+            .or_else(|| sheet_name.and_then(|n| self.sheet(n)))
+            .or_else(|| self.sheet(file_stem))
+    }
+
+    /// Finds an enum defined in this pack.
+    pub fn enum_schema(&self, name: &str) -> Option<&WdbEnumSchema> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    /// Resolves an enum name to a built-in enum or one defined in this pack.
+    pub fn binding(&self, name: &str) -> Option<EnumBinding> {
+        EnumType::from_name(name)
+            .map(EnumBinding::Builtin)
+            .or_else(|| self.enum_schema(name).cloned().map(EnumBinding::Schema))
+    }
+
+    /// Checks version, field names, declared types and enum bindings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        if self.version == 0 || self.version > WDB_SCHEMA_VERSION {
+            bail!(
+                "Unsupported schema version {} (this SDK reads up to {})",
+                self.version,
+                WDB_SCHEMA_VERSION
+            );
+        }
+
+        let mut seen_enums = HashMap::new();
+        for e in &self.enums {
+            if EnumType::from_name(&e.name).is_some() {
+                bail!("Enum '{}' shadows a built-in enum", e.name);
+            }
+            if seen_enums.insert(e.name.as_str(), ()).is_some() {
+                bail!("Enum '{}' is declared twice", e.name);
+            }
+            for (i, v) in e.values.iter().enumerate() {
+                let ctx = format!("{}.{}", e.name, v.name);
+                if v.name.is_empty() || v.name.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+                    bail!("{}: value names must not be empty or start like a number", ctx);
+                }
+                if e.values[..i].iter().any(|o| o.name == v.name || o.value == v.value) {
+                    bail!("{}: duplicate name or value {}", ctx, v.value);
+                }
+                if e.flags && (v.value <= 0 || v.value > u32::MAX as i64 || v.value & (v.value - 1) != 0) {
+                    bail!("{}: flag values must be single bits, got {}", ctx, v.value);
+                }
+            }
+        }
+
+        let mut seen = HashMap::new();
+        for sheet in &self.sheets {
+            if sheet.name.is_empty() {
+                bail!("Sheet with an empty name");
+            }
+            if seen.insert(sheet.name.to_lowercase(), ()).is_some() {
+                bail!("Sheet '{}' is declared twice", sheet.name);
+            }
+
+            for field in &sheet.fields {
+                let ctx = format!("{}.{}", sheet.name, field.name);
+                let kind = WdbFieldKind::from_field_name(&field.name)
+                    .with_context(|| format!("{}: field name has no i/u/f/s type prefix", ctx))?;
+                if kind != field.kind {
+                    bail!("{}: declared as {:?} but the name encodes {:?}", ctx, field.kind, kind);
+                }
+                let bits = derive_field_number(&field.name) as u32;
+                if bits != field.bits {
+                    bail!("{}: declared as {} bits but the name encodes {}", ctx, field.bits, bits);
+                }
+                if field.reference.is_some() && field.kind != WdbFieldKind::String {
+                    bail!("{}: only string fields can hold references", ctx);
+                }
+                if let Some(enum_name) = &field.enum_type {
+                    let Some(binding) = self.binding(enum_name) else {
+                        bail!("{}: unknown enum type '{}'", ctx, enum_name);
+                    };
+                    if matches!(field.kind, WdbFieldKind::Float | WdbFieldKind::String) {
+                        bail!("{}: only integer fields can bind an enum", ctx);
+                    }
+                    if let EnumBinding::Schema(e) = binding {
+                        let (min, max) = field_range(field.kind, field.bits);
+                        let flag_total: i64 = if e.flags { e.values.iter().map(|v| v.value).sum() } else { 0 };
+                        if let Some(v) = e.values.iter().find(|v| v.value < min || v.value > max) {
+                            bail!("{}: {}.{} = {} does not fit the field", ctx, e.name, v.name, v.value);
+                        }
+                        if flag_total > max {
+                            bail!("{}: flags of {} do not fit the field", ctx, e.name);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies an override pack on top of this one.
+    ///
+    /// Sheets and enums with the same name are replaced, new ones are
+    /// appended, and files claimed by an override sheet are removed from the
+    /// others.
+    pub fn merge(&mut self, other: WdbSchemaPack) {
+        for e in other.enums {
+            match self.enums.iter_mut().find(|x| x.name == e.name) {
+                Some(existing) => *existing = e,
+                None => self.enums.push(e),
+            }
+        }
+        for sheet in other.sheets {
+            for existing in &mut self.sheets {
+                if !existing.name.eq_ignore_ascii_case(&sheet.name) {
+                    existing.files.retain(|f| !sheet.files.contains(f));
+                }
+            }
+            match self.sheets.iter_mut().find(|s| s.name.eq_ignore_ascii_case(&sheet.name)) {
+                Some(existing) => *existing = sheet,
+                None => self.sheets.push(sheet),
+            }
+        }
+    }
+
+    /// Enum bindings of a sheet, keyed by field name.
+    pub fn enum_bindings(&self, sheet: &WdbSheetSchema) -> HashMap<String, EnumBinding> {
+        sheet
+            .fields
+            .iter()
+            .filter_map(|f| Some((f.name.clone(), self.binding(f.enum_type.as_deref()?)?)))
+            .collect()
+    }
+}
+
+/// Smallest and largest value a field of this kind and width can hold.
+fn field_range(kind: WdbFieldKind, bits: u32) -> (i64, i64) {
+    let bits = if bits == 0 { 32 } else { bits.min(32) };
+    match kind {
+        WdbFieldKind::Int => (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1),
+        _ => (0, (1i64 << bits) - 1),
+    }
+}
+
+/// Builds the built-in schema pack for a game.
+///
+/// FF13 gets every sheet from [`FIELD_NAMES`], mapped to files through
+/// [`RECORD_IDS`]. XIII-2 and LR embed their field names, so their packs
+/// only carry the enum bindings.
+///
+/// # Errors
+///
+/// Returns an error naming the sheet and field if a field name in the
+/// tables has no type prefix.
+pub fn default_schema_pack(game_code: GameCode) -> Result<WdbSchemaPack> {
+    let mut sheets = Vec::new();
+
+    if game_code == GameCode::FF13_1 {
+        let mut files: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (file, sheet) in RECORD_IDS.iter() {
+            files.entry(sheet).or_default().push(file.to_string());
+        }
+
+        let sheet_names: BTreeMap<_, _> = FIELD_NAMES.iter().collect();
+        for (sheet_name, field_names) in sheet_names {
+            let mut sheet_files = files.remove(sheet_name).unwrap_or_default();
+            sheet_files.sort();
+            let fields = field_names
+                .iter()
+                .map(|name| {
+                    let mut field = WdbFieldSchema::from_name(name).with_context(|| format!("Sheet '{}'", sheet_name))?;
+                    field.enum_type = get_enum_type(sheet_name, name).map(|e| e.name().to_string());
+                    Ok(field)
+                })
+                .collect::<Result<_>>()?;
+            sheets.push(WdbSheetSchema {
+                name: sheet_name.to_string(),
+                files: sheet_files,
+                fields,
+                description: None,
+            });
+        }
+    } else {
+        let mut bindings: BTreeMap<String, BTreeMap<&str, String>> = BTreeMap::new();
+        for ((sheet, field), enum_type) in ENUM_FIELDS.iter() {
+            bindings.entry(sheet.to_lowercase()).or_default().insert(field, enum_type.name().to_string());
+        }
+        for (sheet_name, fields) in bindings {
+            let fields = fields
+                .into_iter()
+                .map(|(name, enum_type)| {
+                    let mut field = WdbFieldSchema::from_name(name).with_context(|| format!("Sheet '{}'", sheet_name))?;
+                    field.enum_type = Some(enum_type);
+                    Ok(field)
+                })
+                .collect::<Result<_>>()?;
+            sheets.push(WdbSheetSchema {
+                name: sheet_name,
+                files: Vec::new(),
+                fields,
+                description: None,
+            });
+        }
+    }
+
+    Ok(WdbSchemaPack {
+        version: WDB_SCHEMA_VERSION,
+        game: game_code,
+        enums: Vec::new(),
+        sheets,
+    })
+}
+
+/// Loads and validates a schema pack from a JSON file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, is not a schema pack, or
+/// fails [`WdbSchemaPack::validate`].
+pub fn load_schema_pack<P: AsRef<Path>>(path: P) -> Result<WdbSchemaPack> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Failed to open schema {:?}", path))?;
+    let pack: WdbSchemaPack = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse schema {:?}", path))?;
+    pack.validate().with_context(|| format!("Invalid schema {:?}", path))?;
+    Ok(pack)
+}
+
+/// Writes a game's built-in schema pack to a JSON file.
+///
+/// Useful as a starting point for an override pack.
+pub fn export_default_schema_pack<P: AsRef<Path>>(game_code: GameCode, path: P) -> Result<()> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &default_schema_pack(game_code)?)?;
+    Ok(())
+}
+
+/// Active packs plus the override directories they were built from.
+struct SchemaRegistry {
+    override_dirs: Vec<PathBuf>,
+    packs: HashMap<GameCode, WdbSchemaPack>,
+}
+
+impl SchemaRegistry {
+    /// The built-in pack of every game, so lookups never miss.
+    fn defaults() -> Result<Self> {
+        let packs = [GameCode::FF13_1, GameCode::FF13_2, GameCode::FF13_3]
+            .into_iter()
+            .map(|gc| Ok((gc, default_schema_pack(gc)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self { override_dirs: Vec::new(), packs })
+    }
+
+    /// Loads every pack in `dir` (sorted by file name) and merges it in.
+    fn apply_dir(&mut self, dir: &Path) -> Result<usize> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read schema directory {:?}", dir))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")))
+            .collect();
+        paths.sort();
+
+        // Load everything first so a bad file leaves the registry untouched.
+        let loaded = paths.iter().map(load_schema_pack).collect::<Result<Vec<_>>>()?;
+        let count = loaded.len();
+        for pack in loaded {
+            log::info!("Applying WDB schema overrides for {:?} ({} sheets)", pack.game, pack.sheets.len());
+            self.packs.get_mut(&pack.game).expect("every game has a default pack").merge(pack);
+        }
+        Ok(count)
+    }
+}
+
+static SCHEMA_REGISTRY: Lazy<RwLock<SchemaRegistry>> =
+    Lazy::new(|| RwLock::new(SchemaRegistry::defaults().expect("built-in WDB schema packs are valid")));
+
+/// Loads every `*.json` schema pack in a directory on top of the active packs.
+///
+/// The directory is remembered so [`reload_schema_overrides`] can pick up
+/// later edits.
+///
+/// # Returns
+///
+/// The number of packs loaded.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read or any pack in it is
+/// invalid. In that case no pack from the directory is applied.
+pub fn add_schema_override_dir<P: AsRef<Path>>(dir: P) -> Result<usize> {
+    let dir = dir.as_ref().to_path_buf();
+    let mut registry = SCHEMA_REGISTRY.write().unwrap();
+    let count = registry.apply_dir(&dir)?;
+    if !registry.override_dirs.contains(&dir) {
+        registry.override_dirs.push(dir);
+    }
+    Ok(count)
+}
+
+/// Rebuilds the active packs from the defaults and every override directory.
+///
+/// # Errors
+///
+/// Returns an error if a directory or pack fails to load. The previous
+/// packs stay active in that case.
+pub fn reload_schema_overrides() -> Result<()> {
+    let mut registry = SCHEMA_REGISTRY.write().unwrap();
+    let mut fresh = SchemaRegistry::defaults()?;
+    for dir in &registry.override_dirs {
+        fresh.apply_dir(dir)?;
+    }
+    fresh.override_dirs = std::mem::take(&mut registry.override_dirs);
+    *registry = fresh;
+    Ok(())
+}
+
+/// Drops every override directory and goes back to the built-in packs.
+///
+/// # Errors
+///
+/// Returns an error if a built-in pack cannot be built.
+pub fn clear_schema_overrides() -> Result<()> {
+    *SCHEMA_REGISTRY.write().unwrap() = SchemaRegistry::defaults()?;
+    Ok(())
+}
+
+/// Returns a copy of the active schema pack for a game.
+pub fn active_schema_pack(game_code: GameCode) -> WdbSchemaPack {
+    SCHEMA_REGISTRY.read().unwrap().packs[&game_code].clone()
+}
+
+/// Runs `f` against the active pack for a game without cloning it.
+pub(crate) fn with_active_schema<T>(game_code: GameCode, f: impl FnOnce(&WdbSchemaPack) -> T) -> T {
+    f(&SCHEMA_REGISTRY.read().unwrap().packs[&game_code])
+}
+
+/// Held by tests that load override packs: serializes them and drops
+/// their overrides when it goes out of scope, even if the test panics.
+#[cfg(test)]
+pub(crate) struct SchemaOverrideGuard {
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl SchemaOverrideGuard {
+    pub(crate) fn lock() -> Self {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        clear_schema_overrides().unwrap();
+        Self { _lock: guard }
+    }
+}
+
+#[cfg(test)]
+impl Drop for SchemaOverrideGuard {
+    fn drop(&mut self) {
+        let _ = clear_schema_overrides();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_packs_are_valid() {
+        for gc in [GameCode::FF13_1, GameCode::FF13_2, GameCode::FF13_3] {
+            let pack = default_schema_pack(gc).unwrap();
+            pack.validate().unwrap();
+            assert!(pack.enums.is_empty());
+        }
+
+        let pack = default_schema_pack(GameCode::FF13_1).unwrap();
+        let crystal = pack.sheet_for_file("crystal_fang").unwrap();
+        assert_eq!(crystal.name, "crystal");
+        let names: Vec<&str> = crystal.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, FIELD_NAMES["crystal"]);
+
+        let role = &crystal.fields[2];
+        assert_eq!((role.kind, role.bits), (WdbFieldKind::UInt, 4));
+        assert_eq!(role.enum_type.as_deref(), Some("CrystalRole"));
+
+        // XIII-2/LR packs only annotate, they never name a file's fields
+        let pack = default_schema_pack(GameCode::FF13_3).unwrap();
+        assert!(pack.sheets.iter().all(|s| s.files.is_empty()));
+        let crystal = pack.sheet_for_wdb("Crystal", None).unwrap();
+        assert_eq!(
+            pack.enum_bindings(crystal).get("u8NodeType"),
+            Some(&EnumBinding::Builtin(EnumType::CrystalNodeType))
+        );
+    }
+
+    #[test]
+    fn test_embedded_ff13_pack_is_current() {
+        // build.rs generates the typed records from this copy
+        let embedded: WdbSchemaPack = serde_json::from_str(include_str!("schemas/ff13_1.json")).unwrap();
+        assert!(
+            embedded == default_schema_pack(GameCode::FF13_1).unwrap(),
+            "schemas/ff13_1.json is stale, rewrite it with export_default_schema_pack(GameCode::FF13_1, ..)"
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_mismatches() {
+        let pack_with = |field: WdbFieldSchema| WdbSchemaPack {
+            version: WDB_SCHEMA_VERSION,
+            game: GameCode::FF13_1,
+            enums: Vec::new(),
+            sheets: vec![WdbSheetSchema {
+                name: "Test".to_string(),
+                files: vec!["test".to_string()],
+                fields: vec![field],
+                description: None,
+            }],
+        };
+
+        let mut field = WdbFieldSchema::from_name("u4Role").unwrap();
+        assert!(pack_with(field.clone()).validate().is_ok());
+
+        field.bits = 5;
+        assert!(pack_with(field.clone()).validate().is_err());
+
+        field.bits = 4;
+        field.kind = WdbFieldKind::Int;
+        assert!(pack_with(field.clone()).validate().is_err());
+
+        field.kind = WdbFieldKind::UInt;
+        field.enum_type = Some("NoSuchEnum".to_string());
+        assert!(pack_with(field).validate().is_err());
+
+        let mut pack = pack_with(WdbFieldSchema::from_name("u4Role").unwrap());
+        pack.version = WDB_SCHEMA_VERSION + 1;
+        assert!(pack.validate().is_err());
+
+        // Enum values must fit the bound field, flags must be single bits
+        let mut field = WdbFieldSchema::from_name("u2Kind").unwrap();
+        field.enum_type = Some("Kind".to_string());
+        let mut pack = pack_with(field);
+        let value = |name: &str, value| WdbEnumValue { name: name.to_string(), value, description: None };
+        pack.enums.push(WdbEnumSchema {
+            name: "Kind".to_string(),
+            flags: false,
+            values: vec![value("A", 0), value("B", 3)],
+            description: None,
+        });
+        assert!(pack.validate().is_ok());
+        pack.enums[0].values.push(value("C", 4));
+        assert!(pack.validate().is_err());
+        pack.enums[0].values.pop();
+        pack.enums[0].flags = true;
+        assert!(pack.validate().is_err());
+    }
+
+    #[test]
+    fn test_merge_replaces_sheets_and_moves_files() {
+        let mut pack = default_schema_pack(GameCode::FF13_1).unwrap();
+        pack.merge(WdbSchemaPack {
+            version: WDB_SCHEMA_VERSION,
+            game: GameCode::FF13_1,
+            enums: Vec::new(),
+            sheets: vec![WdbSheetSchema {
+                name: "FangCrystal".to_string(),
+                files: vec!["crystal_fang".to_string()],
+                fields: vec![WdbFieldSchema::from_name("uCPCost").unwrap()],
+                description: Some("Override".to_string()),
+            }],
+        });
+
+        assert_eq!(pack.sheet_for_file("crystal_fang").unwrap().name, "FangCrystal");
+        assert_eq!(pack.sheet_for_file("crystal_hope").unwrap().name, "crystal");
+        assert!(!pack.sheet("crystal").unwrap().files.contains(&"crystal_fang".to_string()));
+    }
+}
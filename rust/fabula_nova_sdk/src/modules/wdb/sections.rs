@@ -0,0 +1,288 @@
+//! # WDB Section Preservation
+//!
+//! The reader decodes a fixed set of sections and the writer regenerates
+//! them from records. To repack an unmodified file byte for byte, the
+//! parts of the original layout that records can't describe travel in the
+//! header:
+//!
+//! | Header Key      | Value                                                 |
+//! |-----------------|-------------------------------------------------------|
+//! | `sectionOrder`  | Every section name in file order, records included    |
+//! | `stringPool`    | Original `!!string` bytes (hex)                       |
+//! | `strArrayData`  | Original `!!strArray` bytes (hex)                     |
+//! | `strArrayList`  | Original `!!strArrayList` bytes (hex)                 |
+//! | `!!<unknown>`   | Any section the reader doesn't decode (hex), kept as-is |
+//!
+//! ## String Pool
+//!
+//! The original pool seeds the writer's pool: strings already in it keep
+//! their offsets (including strings that share another string's tail),
+//! and new strings are appended. Unused strings stay in the pool, so drop
+//! `stringPool` from the header to rebuild a compact one.
+//!
+//! ```text
+//!   original  │\0│Potion\0│Ether\0│
+//!   repacked  │\0│Potion\0│Ether\0│Elixir\0│   ← only new text appended
+//! ```
+//!
+//! String arrays are seeded the same way, so each value keeps its index.
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+use super::reader::decode_str_arrays;
+use super::structs::{WdbHeader, WdbValue};
+
+/// Header key listing every section of the original file, in order.
+pub const SECTION_ORDER_KEY: &str = "sectionOrder";
+
+/// Header key holding the original `!!string` bytes.
+pub const STRING_POOL_KEY: &str = "stringPool";
+
+/// Header key holding the original `!!strArray` bytes.
+pub const STR_ARRAY_DATA_KEY: &str = "strArrayData";
+
+/// Header key holding the original `!!strArrayList` bytes.
+pub const STR_ARRAY_LIST_KEY: &str = "strArrayList";
+
+/// Header entries that only seed the writer's layout.
+///
+/// Any consistent set works (missing strings are appended), so tools that
+/// combine headers take them together from one source.
+pub const LAYOUT_HEADER_KEYS: [&str; 3] = [STRING_POOL_KEY, STR_ARRAY_DATA_KEY, STR_ARRAY_LIST_KEY];
+
+/// Sections the reader decodes and the writer regenerates.
+pub const KNOWN_SECTIONS: [&str; 11] = [
+    "!!string",
+    "!!sheetname",
+    "!!strArray",
+    "!!strArrayInfo",
+    "!!strArrayList",
+    "!!strtypelist",
+    "!!strtypelistb",
+    "!!typelist",
+    "!!version",
+    "!structitem",
+    "!structitemnum",
+];
+
+/// Returns true for sections the writer regenerates from records.
+pub fn is_known_section(name: &str) -> bool {
+    KNOWN_SECTIONS.contains(&name)
+}
+
+/// Encodes bytes as uppercase hex.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Decodes hex produced by [`encode_hex`] (either case).
+///
+/// # Errors
+///
+/// Returns an error for odd lengths or non-hex characters.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        bail!("Hex text has odd length {}", text.len());
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| match text.get(i..i + 2).map(|h| u8::from_str_radix(h, 16)) {
+            Some(Ok(b)) => Ok(b),
+            _ => bail!("Invalid hex at position {}", i),
+        })
+        .collect()
+}
+
+/// Reads a hex header entry as bytes.
+///
+/// # Errors
+///
+/// Returns an error if the entry exists but isn't valid hex text.
+pub fn header_bytes(header: &WdbHeader, key: &str) -> Result<Option<Vec<u8>>> {
+    match header.get(key) {
+        Some(WdbValue::String(s)) => decode_hex(s)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Header entry {}: {}", key, e)),
+        Some(_) => bail!("Header entry {} must be hex text", key),
+        None => Ok(None),
+    }
+}
+
+/// Unknown sections carried in the header, in header order.
+///
+/// # Errors
+///
+/// Returns an error if a section's bytes aren't valid hex text.
+pub fn raw_sections(header: &WdbHeader) -> Result<Vec<(String, Vec<u8>)>> {
+    header
+        .iter()
+        .filter(|(key, value)| key.starts_with('!') && !is_known_section(key) && matches!(value, WdbValue::String(_)))
+        .map(|(key, _)| Ok((key.clone(), header_bytes(header, key)?.unwrap_or_default())))
+        .collect()
+}
+
+/// Puts generated and raw sections into the original file order.
+///
+/// Sections named in `sectionOrder` come first, in that order. Sections
+/// the original file didn't have follow in the order given.
+///
+/// # Errors
+///
+/// Returns an error if a raw section isn't valid hex text.
+pub fn arrange_sections(generated: Vec<(String, Vec<u8>)>, header: &WdbHeader) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut pending: IndexMap<String, Vec<u8>> = generated.into_iter().collect();
+    for (name, data) in raw_sections(header)? {
+        pending.entry(name).or_insert(data);
+    }
+
+    let mut arranged = Vec::with_capacity(pending.len());
+    if let Some(WdbValue::StringArray(order)) = header.get(SECTION_ORDER_KEY) {
+        for name in order {
+            if let Some(data) = pending.shift_remove(name) {
+                arranged.push((name.clone(), data));
+            }
+        }
+    }
+    arranged.extend(pending);
+    Ok(arranged)
+}
+
+/// Returns true if the original file had the named section.
+pub fn had_section(header: &WdbHeader, name: &str) -> bool {
+    matches!(header.get(SECTION_ORDER_KEY), Some(WdbValue::StringArray(order)) if order.iter().any(|s| s == name))
+}
+
+/// Original string array values per field, for seeding the writer.
+///
+/// Empty unless the header carries the pool and both string array sections.
+pub fn str_array_seeds(header: &WdbHeader, fields: &[String]) -> Result<HashMap<String, Vec<String>>> {
+    let (Some(pool), Some(data), Some(list)) = (
+        header_bytes(header, STRING_POOL_KEY)?,
+        header_bytes(header, STR_ARRAY_DATA_KEY)?,
+        header_bytes(header, STR_ARRAY_LIST_KEY)?,
+    ) else {
+        return Ok(HashMap::new());
+    };
+    let byte = |key| match header.get(key) {
+        Some(WdbValue::UInt(u)) => *u as u8,
+        Some(WdbValue::Int(i)) => *i as u8,
+        _ => 0,
+    };
+    Ok(decode_str_arrays(&data, &list, &pool, fields, byte("offsetsPerValue"), byte("bits_per_offset")))
+}
+
+/// A `!!string` pool that hands out offsets, appending new strings.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut pool = StringPool::new();
+/// assert_eq!(pool.offset("Potion"), 1);
+/// assert_eq!(pool.offset("Potion"), 1);
+/// assert_eq!(pool.as_bytes(), b"\0Potion\0");
+/// ```
+#[derive(Debug, Clone)]
+pub struct StringPool {
+    bytes: Vec<u8>,
+    offsets: HashMap<String, u32>,
+    /// Length of the seeded part, searched for shared tails
+    seeded_len: usize,
+}
+
+impl Default for StringPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringPool {
+    /// An empty pool holding just the empty string at offset 0.
+    pub fn new() -> Self {
+        Self { bytes: vec![0], offsets: HashMap::from([(String::new(), 0)]), seeded_len: 0 }
+    }
+
+    /// A pool that starts from an original `!!string` section.
+    ///
+    /// Each string keeps the offset of its first occurrence.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        if bytes.is_empty() {
+            return Self::new();
+        }
+        let mut offsets = HashMap::new();
+        let mut start = 0;
+        for (i, &b) in bytes.iter().enumerate() {
+            if b == 0 {
+                if let Ok(s) = std::str::from_utf8(&bytes[start..i]) {
+                    offsets.entry(s.to_string()).or_insert(start as u32);
+                }
+                start = i + 1;
+            }
+        }
+        let seeded_len = bytes.len();
+        Self { bytes, offsets, seeded_len }
+    }
+
+    /// A pool seeded from the header's `stringPool` entry, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry isn't valid hex text.
+    pub fn from_header(header: &WdbHeader) -> Result<Self> {
+        Ok(header_bytes(header, STRING_POOL_KEY)?.map(Self::from_bytes).unwrap_or_default())
+    }
+
+    /// Returns the offset of `s`, adding it to the pool if needed.
+    pub fn offset(&mut self, s: &str) -> u32 {
+        if let Some(&offset) = self.offsets.get(s) {
+            return offset;
+        }
+        let offset = self.shared_tail(s).unwrap_or_else(|| {
+            let offset = self.bytes.len();
+            self.bytes.extend_from_slice(s.as_bytes());
+            self.bytes.push(0);
+            offset
+        }) as u32;
+        self.offsets.insert(s.to_string(), offset);
+        offset
+    }
+
+    /// Finds `s` as the tail of a seeded string, as some tools share them.
+    fn shared_tail(&self, s: &str) -> Option<usize> {
+        let needle = s.as_bytes();
+        if needle.is_empty() || needle.len() >= self.seeded_len {
+            return None;
+        }
+        (0..self.seeded_len - needle.len())
+            .find(|&p| &self.bytes[p..p + needle.len()] == needle && self.bytes[p + needle.len()] == 0)
+    }
+
+    /// The pool's bytes as written to `!!string`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_pool_keeps_seeded_offsets() {
+        let mut pool = StringPool::from_bytes(b"\0Ether\0HiPotion\0Ether\0".to_vec());
+        assert_eq!(pool.offset("Ether"), 1);
+        // Tail of "HiPotion"
+        assert_eq!(pool.offset("Potion"), 9);
+        assert_eq!(pool.offset("Elixir"), 22);
+        assert_eq!(pool.as_bytes(), b"\0Ether\0HiPotion\0Ether\0Elixir\0");
+
+        let mut fresh = StringPool::new();
+        assert_eq!(fresh.offset("Potion"), 1);
+        assert_eq!(fresh.offset(""), 0);
+        assert_eq!(fresh.as_bytes(), b"\0Potion\0");
+
+        assert_eq!(decode_hex(&encode_hex(b"\x00\xffA")).unwrap(), b"\x00\xffA");
+        assert!(decode_hex("0G").is_err());
+    }
+}
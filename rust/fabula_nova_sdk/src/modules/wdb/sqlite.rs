@@ -0,0 +1,627 @@
+//! # WDB SQLite Export and Import
+//!
+//! Writes a set of WDB sheets (a directory or a `wdbpack.bin`) into one
+//! SQLite database, so game data can be queried and edited with SQL, and
+//! reads edited sheets back into [`WdbData`].
+//!
+//! ## Database Layout
+//!
+//! | Table / View         | Contents                                             |
+//! |----------------------|------------------------------------------------------|
+//! | `<sheet>`            | One row per record: `_row`, `record`, then one column per field |
+//! | `<sheet>__<field>`   | Array fields: `record`, `idx`, `value` per element   |
+//! | `<sheet>__text`      | View of `<sheet>` with a `<field>_text` column per ZTR key field |
+//! | `_ztr`               | `key`, `text` of every loaded ZTR entry              |
+//! | `_wdb_sheets`        | `sheet`, `game` and the JSON header used to repack   |
+//! | `_wdb_fields`        | Field order, value kind, storage, enum and `ref` per field |
+//!
+//! Sheets are named after their file stem. Columns are typed from the
+//! values they hold: `INTEGER` for ints, `REAL` for floats, `TEXT` for
+//! strings and enum names. A field that mixes kinds (an enum with values
+//! that have no name) gets an untyped column, so every value comes back
+//! exactly as stored. `_row` orders the records: rows inserted without one
+//! go to the end.
+//!
+//! ZTR key fields are those [`WdbReferenceGraph`] resolves to text, either
+//! declared in the schema or inferred from the loaded ZTR files.
+//!
+//! ```text
+//!   SELECT e.record, d.value AS item
+//!   FROM btsc_enemy e JOIN btsc_enemy__sDrop d ON d.record = e.record
+//!   WHERE e.u8DropRate > 10;
+//! ```
+//!
+//! ## Importing
+//!
+//! [`import_wdb_sqlite_sheet`] rebuilds a sheet from its tables. Cells keep
+//! their SQLite type (`INTEGER` → `Int`/`UInt` by the field's kind, `REAL`
+//! → `Float`, `TEXT` → `String`); `NULL` leaves the field out of the
+//! record, which [`super::validate::validate_wdb`] then reports. A child
+//! row with a `NULL` `idx` holds a single value rather than a list.
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::refs::WdbReferenceGraph;
+use super::schema::with_active_schema;
+use super::structs::{GameCode, WdbData, WdbHeader, WdbRecord, WdbRefKind, WdbValue};
+use super::symbolic::SCHEMA_SHEET_KEY;
+
+/// Table listing the exported sheets.
+pub const SHEETS_TABLE: &str = "_wdb_sheets";
+
+/// Table describing each sheet's fields.
+pub const FIELDS_TABLE: &str = "_wdb_fields";
+
+/// Table holding the loaded ZTR text.
+pub const TEXT_TABLE: &str = "_ztr";
+
+/// How an exported field is stored.
+struct FieldInfo {
+    name: String,
+    /// `WdbValue` variant most values use
+    kind: &'static str,
+    sql_type: &'static str,
+    /// Stored in a `<sheet>__<field>` child table
+    array: bool,
+}
+
+/// Quotes an SQL identifier.
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn child_table(sheet: &str, field: &str) -> String {
+    format!("{}__{}", sheet, field)
+}
+
+fn value_kind(value: &WdbValue) -> &'static str {
+    match value {
+        WdbValue::Int(_) => "Int",
+        WdbValue::UInt(_) => "UInt",
+        WdbValue::Float(_) => "Float",
+        WdbValue::Bool(_) => "Bool",
+        WdbValue::IntArray(_) => "IntArray",
+        WdbValue::UIntArray(_) => "UIntArray",
+        WdbValue::StringArray(_) => "StringArray",
+        WdbValue::String(_) | WdbValue::CrystalRole(_) | WdbValue::CrystalNodeType(_) => "String",
+        WdbValue::Unknown => "Unknown",
+    }
+}
+
+/// Column type for a set of scalar kinds; empty (untyped) when they mix.
+fn sql_type(kinds: &HashSet<&str>) -> &'static str {
+    let all = |allowed: &[&str]| kinds.iter().all(|k| allowed.contains(k));
+    if kinds.is_empty() {
+        ""
+    } else if all(&["Int", "UInt", "Bool", "IntArray", "UIntArray"]) {
+        "INTEGER"
+    } else if all(&["Float"]) {
+        "REAL"
+    } else if all(&["String", "StringArray"]) {
+        "TEXT"
+    } else {
+        ""
+    }
+}
+
+/// Field layout of a sheet, in record order.
+fn field_infos(data: &WdbData) -> Vec<FieldInfo> {
+    let mut kinds: IndexMap<&str, Vec<&'static str>> = IndexMap::new();
+    for record in &data.records {
+        for (key, value) in record.iter().filter(|(k, _)| k.as_str() != "record") {
+            kinds.entry(key.as_str()).or_default().push(value_kind(value));
+        }
+    }
+    kinds
+        .into_iter()
+        .map(|(name, seen)| {
+            let array = seen.iter().any(|k| k.ends_with("Array"));
+            let distinct: HashSet<&str> = seen.iter().copied().filter(|k| *k != "Unknown").collect();
+            let kind = seen
+                .iter()
+                .copied()
+                .find(|k| !array || k.ends_with("Array"))
+                .unwrap_or("Unknown");
+            FieldInfo { name: name.to_string(), kind, sql_type: sql_type(&distinct), array }
+        })
+        .collect()
+}
+
+fn to_sql(value: &WdbValue) -> Value {
+    match value {
+        WdbValue::Int(v) => Value::Integer(*v as i64),
+        WdbValue::UInt(v) => Value::Integer(*v as i64),
+        WdbValue::Float(v) => Value::Real(*v as f64),
+        WdbValue::Bool(v) => Value::Integer(*v as i64),
+        WdbValue::String(s) => Value::Text(s.clone()),
+        WdbValue::CrystalRole(_) | WdbValue::CrystalNodeType(_) => match serde_json::to_value(value) {
+            Ok(serde_json::Value::String(s)) => Value::Text(s),
+            _ => Value::Null,
+        },
+        _ => Value::Null,
+    }
+}
+
+/// Elements of an array value, or the value itself with no index.
+fn to_sql_elements(value: &WdbValue) -> Vec<(Option<i64>, Value)> {
+    match value {
+        WdbValue::IntArray(a) => a.iter().enumerate().map(|(i, v)| (Some(i as i64), Value::Integer(*v as i64))).collect(),
+        WdbValue::UIntArray(a) => a.iter().enumerate().map(|(i, v)| (Some(i as i64), Value::Integer(*v as i64))).collect(),
+        WdbValue::StringArray(a) => a.iter().enumerate().map(|(i, v)| (Some(i as i64), Value::Text(v.clone()))).collect(),
+        other => vec![(None, to_sql(other))],
+    }
+}
+
+/// Converts a cell back, guided by the kind the field was exported with.
+fn from_sql(value: Value, kind: &str) -> Option<WdbValue> {
+    Some(match value {
+        Value::Null | Value::Blob(_) => return None,
+        Value::Integer(i) => match kind {
+            "Int" | "IntArray" => WdbValue::Int(i as i32),
+            "Float" => WdbValue::Float(i as f32),
+            "Bool" => WdbValue::Bool(i != 0),
+            _ if (0..=u32::MAX as i64).contains(&i) => WdbValue::UInt(i as u32),
+            _ => WdbValue::Int(i as i32),
+        },
+        Value::Real(f) => WdbValue::Float(f as f32),
+        Value::Text(s) => WdbValue::String(s),
+    })
+}
+
+/// Builds an array of the exported kind from child rows.
+fn array_value(kind: &str, elements: Vec<WdbValue>) -> WdbValue {
+    match kind {
+        "IntArray" => WdbValue::IntArray(
+            elements.into_iter().map(|v| match v {
+                WdbValue::Int(i) => i,
+                WdbValue::UInt(u) => u as i32,
+                _ => 0,
+            }).collect(),
+        ),
+        "UIntArray" => WdbValue::UIntArray(
+            elements.into_iter().map(|v| match v {
+                WdbValue::UInt(u) => u,
+                WdbValue::Int(i) => i as u32,
+                _ => 0,
+            }).collect(),
+        ),
+        _ => WdbValue::StringArray(
+            elements.into_iter().map(|v| match v {
+                WdbValue::String(s) => s,
+                other => lossy_text(to_sql(&other)),
+            }).collect(),
+        ),
+    }
+}
+
+/// A cell as text, for string arrays holding other kinds.
+fn lossy_text(value: Value) -> String {
+    match value {
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(s) => s,
+        Value::Null | Value::Blob(_) => String::new(),
+    }
+}
+
+/// Writes every sheet and text entry of a graph to a new SQLite database.
+///
+/// An existing file at `db_path` is replaced.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut graph = WdbReferenceGraph::new(GameCode::FF13_3);
+/// graph.load_wdbpack("db/resident/wdbpack.bin")?;
+/// graph.load_directory("txtres")?;
+/// write_wdb_sqlite(&graph, "lr.sqlite")?;
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the database can't be created or written.
+pub fn write_wdb_sqlite<P: AsRef<Path>>(graph: &WdbReferenceGraph, db_path: P) -> Result<()> {
+    let db_path = db_path.as_ref();
+    if db_path.exists() {
+        std::fs::remove_file(db_path).with_context(|| format!("Failed to replace {:?}", db_path))?;
+    }
+    let mut conn = Connection::open(db_path).with_context(|| format!("Failed to create {:?}", db_path))?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(&format!(
+        "CREATE TABLE {s} (sheet TEXT PRIMARY KEY, game TEXT, header TEXT);
+         CREATE TABLE {f} (sheet TEXT, position INTEGER, field TEXT, kind TEXT, sql_type TEXT,
+                           storage TEXT, enum TEXT, ref TEXT, PRIMARY KEY (sheet, field));
+         CREATE TABLE {t} (key TEXT PRIMARY KEY, text TEXT);",
+        s = ident(SHEETS_TABLE),
+        f = ident(FIELDS_TABLE),
+        t = ident(TEXT_TABLE),
+    ))?;
+
+    {
+        let mut insert = tx.prepare(&format!("INSERT INTO {} VALUES (?1, ?2)", ident(TEXT_TABLE)))?;
+        for (key, text) in graph.texts() {
+            insert.execute(params![key, text])?;
+        }
+    }
+
+    // Scalar ZTR key fields per sheet, for the text views
+    let mut text_fields: HashMap<String, Vec<String>> = HashMap::new();
+    for reference in graph.report().references.into_iter().filter(|r| r.kind == WdbRefKind::Text) {
+        let fields = text_fields.entry(reference.sheet).or_default();
+        if !fields.contains(&reference.field) {
+            fields.push(reference.field);
+        }
+    }
+
+    let game = format!("{:?}", graph.game_code());
+    for (sheet, data) in graph.sheets() {
+        let fields = field_infos(data);
+        write_sheet(&tx, sheet, data, &fields)?;
+        tx.execute(
+            &format!("INSERT INTO {} VALUES (?1, ?2, ?3)", ident(SHEETS_TABLE)),
+            params![sheet, game, serde_json::to_string(&data.header)?],
+        )?;
+
+        let schema_sheet = match data.header.get(SCHEMA_SHEET_KEY) {
+            Some(WdbValue::String(s)) => s.clone(),
+            _ => sheet.clone(),
+        };
+        let declared: HashMap<String, (Option<String>, Option<String>)> = with_active_schema(graph.game_code(), |schema| {
+            schema
+                .sheet(&schema_sheet)
+                .map(|s| s.fields.iter().map(|f| (f.name.clone(), (f.enum_type.clone(), f.reference.clone()))).collect())
+                .unwrap_or_default()
+        });
+        let mut insert = tx.prepare(&format!("INSERT INTO {} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", ident(FIELDS_TABLE)))?;
+        for (position, field) in fields.iter().enumerate() {
+            let (enum_type, reference) = declared.get(&field.name).cloned().unwrap_or_default();
+            insert.execute(params![
+                sheet,
+                position as i64,
+                field.name,
+                field.kind,
+                field.sql_type,
+                if field.array { "table" } else { "column" },
+                enum_type,
+                reference,
+            ])?;
+        }
+
+        let keys: Vec<&String> = text_fields
+            .get(sheet)
+            .into_iter()
+            .flatten()
+            .filter(|k| fields.iter().any(|f| &f.name == *k && !f.array))
+            .collect();
+        if !keys.is_empty() {
+            let columns: Vec<String> = keys
+                .iter()
+                .enumerate()
+                .map(|(i, k)| format!("z{}.text AS {}", i, ident(&format!("{}_text", k))))
+                .collect();
+            let joins: Vec<String> = keys
+                .iter()
+                .enumerate()
+                .map(|(i, k)| format!("LEFT JOIN {} z{i} ON z{i}.key = t.{}", ident(TEXT_TABLE), ident(k)))
+                .collect();
+            tx.execute_batch(&format!(
+                "CREATE VIEW {} AS SELECT t.*, {} FROM {} t {};",
+                ident(&format!("{}__text", sheet)),
+                columns.join(", "),
+                ident(sheet),
+                joins.join(" ")
+            ))?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Creates and fills a sheet's table and its array tables.
+fn write_sheet(conn: &Connection, sheet: &str, data: &WdbData, fields: &[FieldInfo]) -> Result<()> {
+    let columns: Vec<&FieldInfo> = fields.iter().filter(|f| !f.array).collect();
+    let mut ddl = vec!["\"_row\" INTEGER PRIMARY KEY".to_string(), "\"record\" TEXT".to_string()];
+    ddl.extend(columns.iter().map(|f| format!("{} {}", ident(&f.name), f.sql_type).trim_end().to_string()));
+    conn.execute_batch(&format!(
+        "CREATE TABLE {t} ({}); CREATE INDEX {} ON {t} (\"record\");",
+        ddl.join(", "),
+        ident(&format!("{}__record", sheet)),
+        t = ident(sheet),
+    ))?;
+    for field in fields.iter().filter(|f| f.array) {
+        let table = child_table(sheet, &field.name);
+        let value_type = sql_type(&HashSet::from([field.kind]));
+        conn.execute_batch(&format!(
+            "CREATE TABLE {t} (\"record\" TEXT NOT NULL, \"idx\" INTEGER, \"value\" {}); CREATE INDEX {} ON {t} (\"record\");",
+            value_type,
+            ident(&format!("{}__record", table)),
+            t = ident(&table),
+        ))?;
+    }
+
+    let placeholders: Vec<String> = (1..=columns.len() + 2).map(|i| format!("?{}", i)).collect();
+    let mut insert = conn.prepare(&format!("INSERT INTO {} VALUES ({})", ident(sheet), placeholders.join(", ")))?;
+    let mut children: Vec<_> = fields
+        .iter()
+        .filter(|f| f.array)
+        .map(|f| {
+            let sql = format!("INSERT INTO {} VALUES (?1, ?2, ?3)", ident(&child_table(sheet, &f.name)));
+            conn.prepare(&sql).map(|stmt| (f.name.as_str(), stmt))
+        })
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (row, record) in data.records.iter().enumerate() {
+        let id = record.get("record").map(to_sql).unwrap_or(Value::Null);
+        let mut values = vec![Value::Integer(row as i64), id.clone()];
+        values.extend(columns.iter().map(|f| record.get(&f.name).map(to_sql).unwrap_or(Value::Null)));
+        insert.execute(params_from_iter(values))?;
+
+        for (name, stmt) in &mut children {
+            for (idx, value) in record.get(*name).map(to_sql_elements).unwrap_or_default() {
+                stmt.execute(params![id, idx, value])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exports a directory or `wdbpack.bin` of WDB files to a new SQLite database.
+///
+/// # Arguments
+///
+/// * `source` - A directory (scanned recursively for `.wdb` and `.ztr`) or a `wdbpack.bin`
+/// * `text_dir` - Optional directory of `.ztr` files for the text views
+/// * `db_path` - Database to create (replaced if it exists)
+/// * `game_code` - Which game the files belong to
+///
+/// # Returns
+///
+/// The number of sheets exported.
+pub fn export_wdb_sqlite<P: AsRef<Path>>(
+    source: P,
+    text_dir: Option<P>,
+    db_path: P,
+    game_code: GameCode,
+) -> Result<usize> {
+    let source = source.as_ref();
+    let mut graph = WdbReferenceGraph::new(game_code);
+    if source.is_dir() {
+        graph.load_directory(source)?;
+    } else {
+        graph.load_wdbpack(source)?;
+    }
+    if let Some(dir) = text_dir {
+        graph.load_directory(dir)?;
+    }
+    write_wdb_sqlite(&graph, db_path)?;
+    Ok(graph.sheets().len())
+}
+
+/// Reads one sheet back from a database written by [`write_wdb_sqlite`].
+///
+/// # Errors
+///
+/// Returns an error if the database has no such sheet or its tables don't
+/// match `_wdb_fields`.
+pub fn import_wdb_sqlite_sheet<P: AsRef<Path>>(db_path: P, sheet: &str) -> Result<WdbData> {
+    let conn = Connection::open(db_path.as_ref()).with_context(|| format!("Failed to open {:?}", db_path.as_ref()))?;
+    read_sheet(&conn, sheet)
+}
+
+/// Reads every sheet back from a database written by [`write_wdb_sqlite`].
+///
+/// # Returns
+///
+/// `(sheet, data)` pairs in export order, ready for [`super::api::pack_wdb`].
+pub fn import_wdb_sqlite<P: AsRef<Path>>(db_path: P) -> Result<Vec<(String, WdbData)>> {
+    let conn = Connection::open(db_path.as_ref()).with_context(|| format!("Failed to open {:?}", db_path.as_ref()))?;
+    let sheets: Vec<String> = conn
+        .prepare(&format!("SELECT sheet FROM {} ORDER BY rowid", ident(SHEETS_TABLE)))?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    sheets
+        .into_iter()
+        .map(|sheet| read_sheet(&conn, &sheet).map(|data| (sheet, data)))
+        .collect()
+}
+
+fn read_sheet(conn: &Connection, sheet: &str) -> Result<WdbData> {
+    let header: Option<String> = conn
+        .query_row(
+            &format!("SELECT header FROM {} WHERE sheet = ?1", ident(SHEETS_TABLE)),
+            params![sheet],
+            |row| row.get(0),
+        )
+        .ok();
+    let Some(header) = header else {
+        bail!("No sheet '{}' in the database", sheet);
+    };
+    let mut header: WdbHeader = serde_json::from_str(&header).with_context(|| format!("Bad header for sheet '{}'", sheet))?;
+
+    let fields: Vec<(String, String, bool)> = conn
+        .prepare(&format!("SELECT field, kind, storage FROM {} WHERE sheet = ?1 ORDER BY position", ident(FIELDS_TABLE)))?
+        .query_map(params![sheet], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, String>(2)? == "table")))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let columns: Vec<&(String, String, bool)> = fields.iter().filter(|f| !f.2).collect();
+    let mut select = vec!["\"record\"".to_string()];
+    select.extend(columns.iter().map(|f| ident(&f.0)));
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM {} ORDER BY \"_row\"", select.join(", "), ident(sheet)))?;
+    let mut rows = stmt.query([])?;
+    let mut records = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values: HashMap<&str, WdbValue> = HashMap::new();
+        for (i, (name, kind, _)) in columns.iter().enumerate() {
+            if let Some(value) = from_sql(row.get(i + 1)?, kind) {
+                values.insert(name.as_str(), value);
+            }
+        }
+        let mut record = WdbRecord::new();
+        if let Some(id) = from_sql(row.get(0)?, "String") {
+            record.insert("record".to_string(), id);
+        }
+        for (name, kind, array) in &fields {
+            if *array {
+                record.insert(name.clone(), array_value(kind, Vec::new()));
+            } else if let Some(value) = values.remove(name.as_str()) {
+                record.insert(name.clone(), value);
+            }
+        }
+        records.push(record);
+    }
+
+    // First record with each id, for the child rows
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    for (i, record) in records.iter().enumerate() {
+        if let Some(WdbValue::String(id)) = record.get("record") {
+            by_id.entry(id.clone()).or_insert(i);
+        }
+    }
+    for (name, kind, _) in fields.iter().filter(|f| f.2) {
+        let sql = format!(
+            "SELECT \"record\", \"idx\", \"value\" FROM {} ORDER BY \"record\", \"idx\"",
+            ident(&child_table(sheet, name))
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+        let mut elements: HashMap<usize, (Vec<WdbValue>, Option<WdbValue>)> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let Some(&index) = by_id.get(&id) else {
+                log::warn!("{}.{}: no record '{}' for array element", sheet, name, id);
+                continue;
+            };
+            let idx: Option<i64> = row.get(1)?;
+            let entry = elements.entry(index).or_default();
+            match (idx, from_sql(row.get(2)?, kind.trim_end_matches("Array"))) {
+                (Some(_), Some(value)) => entry.0.push(value),
+                (None, Some(value)) => entry.1 = Some(value),
+                _ => {}
+            }
+        }
+        for (index, (list, scalar)) in elements {
+            let value = scalar.unwrap_or_else(|| array_value(kind, list));
+            records[index].insert(name.clone(), value);
+        }
+    }
+
+    if header.contains_key("recordCount") {
+        header.insert("recordCount".to_string(), WdbValue::UInt(records.len() as u32));
+    }
+    Ok(WdbData { header, records })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::ztr::structs::{ZtrData, ZtrEntry};
+
+    fn items() -> WdbData {
+        let mut header = WdbHeader::new();
+        header.insert("recordCount".to_string(), WdbValue::UInt(3));
+        let records = (0..3)
+            .map(|i| {
+                let mut r = WdbRecord::new();
+                r.insert("record".to_string(), WdbValue::String(format!("it_{}", i)));
+                r.insert("sName".to_string(), WdbValue::String(format!("$it_{}", i)));
+                r.insert("uPrice".to_string(), WdbValue::UInt(100 * i));
+                r.insert("iDelta".to_string(), WdbValue::Int(-(i as i32)));
+                r.insert("fRate".to_string(), WdbValue::Float(0.5 + i as f32));
+                // An enum whose last value has no name
+                let kind = if i == 2 { WdbValue::UInt(9) } else { WdbValue::String(format!("Kind{}", i)) };
+                r.insert("u4Kind".to_string(), kind);
+                r.insert("sTags".to_string(), WdbValue::StringArray((0..i).map(|t| format!("tag{}", t)).collect()));
+                r
+            })
+            .collect();
+        WdbData { header, records }
+    }
+
+    fn graph() -> WdbReferenceGraph {
+        let mut graph = WdbReferenceGraph::new(GameCode::FF13_2);
+        graph.add_sheet("db_item", items());
+        graph.add_texts(&ZtrData {
+            entries: (0..3).map(|i| ZtrEntry { id: format!("$it_{}", i), text: format!("Item {}", i) }).collect(),
+            mappings: Vec::new(),
+        });
+        graph
+    }
+
+    fn db_path(name: &str) -> std::path::PathBuf {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test_wdb_sqlite");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let path = db_path("round_trip.sqlite");
+        write_wdb_sqlite(&graph(), &path).unwrap();
+
+        let imported = import_wdb_sqlite(&path).unwrap();
+        assert_eq!(imported.len(), 1);
+        let (sheet, data) = &imported[0];
+        assert_eq!(sheet, "db_item");
+        assert_eq!(data.records, items().records);
+        assert_eq!(data.header, items().header);
+
+        let conn = Connection::open(&path).unwrap();
+        let name: String = conn
+            .query_row("SELECT sName_text FROM db_item__text WHERE record = 'it_1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(name, "Item 1");
+        let tags: i64 = conn.query_row("SELECT COUNT(*) FROM db_item__sTags", [], |r| r.get(0)).unwrap();
+        assert_eq!(tags, 3);
+    }
+
+    #[test]
+    fn test_sqlite_edits_apply() {
+        let path = db_path("edits.sqlite");
+        write_wdb_sqlite(&graph(), &path).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "UPDATE db_item SET uPrice = uPrice + 5, u4Kind = 'Kind1' WHERE record = 'it_2';
+             DELETE FROM db_item WHERE record = 'it_0';
+             INSERT INTO db_item (record, sName, uPrice, iDelta, fRate, u4Kind) VALUES ('it_9', '', 7, -3, 2, 3);
+             INSERT INTO db_item__sTags VALUES ('it_9', 0, 'new');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let data = import_wdb_sqlite_sheet(&path, "db_item").unwrap();
+        let ids: Vec<&WdbValue> = data.records.iter().map(|r| &r["record"]).collect();
+        assert_eq!(ids, [&WdbValue::String("it_1".into()), &WdbValue::String("it_2".into()), &WdbValue::String("it_9".into())]);
+        assert_eq!(data.records[1]["uPrice"], WdbValue::UInt(205));
+        assert_eq!(data.records[1]["u4Kind"], WdbValue::String("Kind1".into()));
+        assert_eq!(data.records[2]["iDelta"], WdbValue::Int(-3));
+        assert_eq!(data.records[2]["fRate"], WdbValue::Float(2.0));
+        assert_eq!(data.records[2]["u4Kind"], WdbValue::UInt(3));
+        assert_eq!(data.records[2]["sTags"], WdbValue::StringArray(vec!["new".into()]));
+        assert_eq!(data.header["recordCount"], WdbValue::UInt(3));
+        assert!(import_wdb_sqlite_sheet(&path, "missing").is_err());
+    }
+
+    /// Every sheet of Lightning Returns' wdbpack.bin comes back unchanged
+    #[test]
+    fn test_wdbpack_sqlite_round_trip() {
+        let wpd_path = Path::new("/Users/hramirez/Desktop/Development/ff13-lr_data/white_img2a/db/resident/wdbpack.bin");
+        if !wpd_path.exists() {
+            eprintln!("Test file not found: {:?}", wpd_path);
+            return;
+        }
+
+        let mut graph = WdbReferenceGraph::new(GameCode::FF13_3);
+        graph.load_wdbpack(wpd_path).unwrap();
+        let path = db_path("wdbpack.sqlite");
+        write_wdb_sqlite(&graph, &path).unwrap();
+        for (sheet, data) in import_wdb_sqlite(&path).unwrap() {
+            assert_eq!(data.records, graph.sheets()[&sheet].records, "{}", sheet);
+        }
+    }
+}
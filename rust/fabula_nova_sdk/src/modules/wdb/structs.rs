@@ -11,7 +11,7 @@
 //!
 //! 2. **High-Level Rust/Dart Structures** - For application use
 //!    - [`WdbData`] - Complete parsed WDB file
-//!    - [`WdbRecord`] - Single record (HashMap of field names to values)
+//!    - [`WdbRecord`] - Single record (ordered map of field names to values)
 //!    - [`WdbValue`] - Typed field value (int, float, string, etc.)
 //!
 //! 3. **C FFI Structures** - For legacy C interop
@@ -29,10 +29,19 @@
 //! - `UIntArray(Vec<u32>)` - Array of unsigned integers
 //! - `StringArray(Vec<String>)` - Array of strings
 //! - `CrystalRole`, `CrystalNodeType` - Enum types for Crystarium
+//!
+//! Fields bound to a schema enum hold its value name as a `String`, and
+//! flag fields the set names as a `StringArray` (see [`super::symbolic`]).
+//!
+//! ## Ordering
+//!
+//! Records and the header are insertion-ordered maps. Records list
+//! `record` first, then the fields in `!structitem` (schema) order, so JSON
+//! exports are byte-stable and diff cleanly (see [`super::canonicalize_wdb`]).
 
 use binrw::BinRead;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 // Re-export GameCode for convenience
 pub use crate::core::utils::GameCode;
@@ -89,14 +98,546 @@ pub enum WdbValue {
     Unknown,
 }
 
-pub type WdbRecord = HashMap<String, WdbValue>;
+pub type WdbRecord = IndexMap<String, WdbValue>;
+
+/// Header entries (`!!version`, `!structitem`, `sheetName`, ...) in file order.
+pub type WdbHeader = IndexMap<String, WdbValue>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WdbData {
-    pub header: HashMap<String, WdbValue>,
+    pub header: WdbHeader,
     pub records: Vec<WdbRecord>,
 }
 
+/// Delimiter used for spreadsheet export/import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WdbTableFormat {
+    /// Comma-separated values
+    #[default]
+    Csv,
+    /// Tab-separated values
+    Tsv,
+}
+
+/// A cell that could not be imported from a spreadsheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbCellError {
+    /// 1-based row number in the file (the header row is row 1)
+    pub row: usize,
+    /// Field name of the column (empty for row-level problems)
+    pub column: String,
+    /// The offending cell text
+    pub value: String,
+    pub message: String,
+}
+
+/// Result of importing a spreadsheet.
+///
+/// `data` is only meant to be packed when `errors` is empty; cells with
+/// errors are left out of their records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WdbTableImport {
+    pub data: WdbData,
+    pub errors: Vec<WdbCellError>,
+}
+
+/// Kind of conflict found by a three-way merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbConflictKind {
+    /// Both sides changed the same field of an existing record differently
+    BothModified,
+    /// Both sides added the same record id with a different field value
+    BothAdded,
+    /// One side deleted a record the other side modified
+    ModifiedAndDeleted,
+    /// Both sides changed the same header entry differently
+    Header,
+}
+
+/// A change the merge could not reconcile on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbMergeConflict {
+    pub kind: WdbConflictKind,
+    /// Record id (`None` for header conflicts)
+    pub record: Option<String>,
+    /// Field or header key (`None` for record-level conflicts)
+    pub field: Option<String>,
+    /// Value in the vanilla data (`None` = absent)
+    pub base: Option<WdbValue>,
+    /// Value on the first modified side (`None` = absent or deleted)
+    pub ours: Option<WdbValue>,
+    /// Value on the second modified side (`None` = absent or deleted)
+    pub theirs: Option<WdbValue>,
+}
+
+/// Which value to keep when resolving a merge conflict.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WdbMergeChoice {
+    Base,
+    Ours,
+    Theirs,
+    Value(WdbValue),
+}
+
+/// Result of a three-way merge.
+///
+/// `merged` already holds every non-conflicting change. Conflicting fields
+/// hold the "ours" value until resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WdbMergeResult {
+    pub merged: WdbData,
+    pub conflicts: Vec<WdbMergeConflict>,
+}
+
+/// Comparison used by a patch predicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WdbCompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A condition on one field of a record, e.g. `uPrice > 1000`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbPredicate {
+    pub field: String,
+    pub op: WdbCompareOp,
+    pub value: WdbValue,
+}
+
+/// Which records a patch operation applies to.
+///
+/// `ids` entries ending in `*` match by prefix; an empty list selects every
+/// record. All `filter` predicates must hold for a record to be selected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbRecordSelector {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<String>,
+    #[serde(default, rename = "where", skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<WdbPredicate>,
+}
+
+/// One step of a WDB patch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum WdbPatchOp {
+    /// Assigns field values
+    Set {
+        #[serde(default)]
+        select: WdbRecordSelector,
+        fields: IndexMap<String, WdbValue>,
+    },
+    /// Adds an amount to numeric fields
+    Add {
+        #[serde(default)]
+        select: WdbRecordSelector,
+        fields: IndexMap<String, f64>,
+    },
+    /// Multiplies numeric fields (integer fields are rounded)
+    Multiply {
+        #[serde(default)]
+        select: WdbRecordSelector,
+        fields: IndexMap<String, f64>,
+    },
+    /// Limits a numeric field to a range
+    Clamp {
+        #[serde(default)]
+        select: WdbRecordSelector,
+        field: String,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// Copies a record under a new id, then assigns `set`
+    Clone {
+        from: String,
+        id: String,
+        #[serde(default)]
+        set: IndexMap<String, WdbValue>,
+    },
+    /// Removes records
+    Delete {
+        #[serde(default)]
+        select: WdbRecordSelector,
+    },
+}
+
+/// A declarative list of edits to a WDB sheet.
+///
+/// `sheet`, when set, must match the file's schema sheet or `sheetName`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    pub operations: Vec<WdbPatchOp>,
+}
+
+/// Kind of difference between two versions of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbChangeKind {
+    Added,
+    Deleted,
+    Modified,
+}
+
+/// One difference reported by a patch dry run.
+///
+/// `Modified` changes name the field; `Added` and `Deleted` cover the whole
+/// record and leave `field`, `before` and `after` empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbRecordChange {
+    pub kind: WdbChangeKind,
+    pub record: String,
+    pub field: Option<String>,
+    pub before: Option<WdbValue>,
+    pub after: Option<WdbValue>,
+}
+
+/// Patched data together with its diff against the input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WdbPatchResult {
+    pub data: WdbData,
+    pub changes: Vec<WdbRecordChange>,
+}
+
+/// What a reference field points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbRefKind {
+    /// A record id in another (or the same) sheet
+    Record,
+    /// A ZTR text key
+    Text,
+}
+
+/// Why a field was treated as a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbRefSource {
+    /// Declared with `ref` in the schema pack
+    Schema,
+    /// Inferred because most of the field's values resolve to one target
+    Inferred,
+}
+
+/// A field value that points at a record or text entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbReference {
+    pub sheet: String,
+    pub record: String,
+    pub field: String,
+    pub value: String,
+    pub kind: WdbRefKind,
+    /// Sheet holding the target record (the declared target when dangling);
+    /// `None` for text references
+    pub target_sheet: Option<String>,
+    pub source: WdbRefSource,
+}
+
+/// Resolved and dangling references across a set of sheets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WdbReferenceReport {
+    pub references: Vec<WdbReference>,
+    pub dangling: Vec<WdbReference>,
+}
+
+/// Category of a problem found by WDB validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbIssueKind {
+    /// A number doesn't fit the field's bit width
+    OutOfRange,
+    /// The value's type doesn't match the field or its `!!strtypelist` slot
+    TypeMismatch,
+    /// A record lacks a `!structitem` field
+    MissingField,
+    /// An enum field holds a name its enum doesn't define
+    UnknownEnumValue,
+    /// String offsets don't fit `bits_per_offset`
+    StringTable,
+    /// A string array field has more distinct values than its index can hold
+    ArrayLength,
+    /// `!structitem`, `!!strtypelist` or the bitpacked layout is inconsistent
+    Layout,
+    /// A record has no id, or shares its id with another record
+    RecordId,
+}
+
+/// One problem that would make the writer drop or alter data.
+///
+/// `record` and `field` are `None` for problems that concern the whole
+/// file or every record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbValidationIssue {
+    pub kind: WdbIssueKind,
+    pub record: Option<String>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// How packing treats validation issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WdbWriteMode {
+    /// Refuse to write when validation finds any issue
+    #[default]
+    Strict,
+    /// Log issues and write anyway (out-of-range values become 0)
+    Lenient,
+}
+
+/// What schema inference thinks a field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WdbFieldRole {
+    /// Same value in every record
+    Constant,
+    /// Only 0 and 1
+    Boolean,
+    /// A few small values that repeat
+    Enum,
+    /// Combinations of single bits
+    Flags,
+    /// A different value in (almost) every record
+    Id,
+    /// A plain quantity
+    Number,
+    /// A ZTR text key
+    TextKey,
+    /// A record id in another (or the same) sheet
+    Reference,
+    /// An offset into the file's `!!string` table
+    StringOffset,
+    /// Free text that resolves to nothing
+    Text,
+}
+
+/// How far an inference note can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WdbConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// One finding of schema inference, for researchers to confirm or refine.
+///
+/// `field` is `None` for notes about the whole sheet (where its field
+/// names came from, files that disagree on the layout).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbInferenceNote {
+    pub sheet: String,
+    pub field: Option<String>,
+    pub role: Option<WdbFieldRole>,
+    pub confidence: WdbConfidence,
+    pub message: String,
+}
+
+/// File format of the sheets [`super::wdbpack::unpack_wdbpack`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WdbPackFormat {
+    /// One JSON file per sheet, as [`super::api::wdb_to_json_string`] writes it
+    #[default]
+    Json,
+    /// Comma-separated values
+    Csv,
+    /// Tab-separated values
+    Tsv,
+}
+
+/// One record of an unpacked wdbpack, in package order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbPackEntry {
+    /// Record name (file stem)
+    pub name: String,
+    /// Record extension, empty if none
+    pub extension: String,
+    /// Editable sheet file; `None` for records that aren't WDBs or didn't parse
+    pub file: Option<String>,
+    /// Copy of the original record bytes
+    pub raw: String,
+    /// Hash of `file` as written, to tell which sheets were edited
+    pub hash: Option<String>,
+}
+
+/// Manifest of an unpacked wdbpack (`!!WDBPACK.json`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbPackManifest {
+    pub game_code: GameCode,
+    pub format: WdbPackFormat,
+    pub entries: Vec<WdbPackEntry>,
+}
+
+/// What [`super::wdbpack::repack_wdbpack`] did with each sheet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbPackSummary {
+    /// Sheets rebuilt from their edited file
+    pub reencoded: Vec<String>,
+    /// Records copied from their original bytes
+    pub copied: u32,
+}
+
+/// Text for a new record's ZTR key field, per language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbContentText {
+    /// Field holding the ZTR key, e.g. `sItemNameStringId`
+    pub field: String,
+    /// Key to use; derived from the template's key and the new id when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Text by language; `*` covers every loaded language not listed
+    pub text: IndexMap<String, String>,
+}
+
+/// A record in another sheet that should point at a new record.
+///
+/// A `field` ending in `*` picks the first empty string field with that
+/// prefix (e.g. the first free `sItemLabel*` slot of a shop). String array
+/// fields get the new id appended; string fields are overwritten.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbContentLink {
+    pub sheet: String,
+    pub record: String,
+    pub field: String,
+}
+
+/// A high-level definition of a new item, weapon, ability, ...
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbContentDef {
+    /// Sheet (file stem) holding the template, e.g. `item`
+    pub sheet: String,
+    /// Id of the record to clone
+    pub template: String,
+    /// Id for the new record; a free id is derived from the template when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Field values to assign after cloning
+    #[serde(default)]
+    pub fields: IndexMap<String, WdbValue>,
+    #[serde(default)]
+    pub texts: Vec<WdbContentText>,
+    #[serde(default)]
+    pub links: Vec<WdbContentLink>,
+}
+
+/// A ZTR entry to append to one language's text file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbTextAddition {
+    pub language: String,
+    pub key: String,
+    pub text: String,
+}
+
+/// Every edit needed to add a set of [`WdbContentDef`]s.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbChangeSet {
+    /// Ids given to the definitions, in order
+    pub ids: Vec<String>,
+    /// One patch per touched sheet (by file stem), in the order first touched
+    pub patches: Vec<(String, WdbPatch)>,
+    pub texts: Vec<WdbTextAddition>,
+}
+
+/// Record changes a change set makes to one sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbSheetChanges {
+    pub sheet: String,
+    pub changes: Vec<WdbRecordChange>,
+}
+
+/// Fields of some records of one sheet that hold item ids.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbSlotSet {
+    /// Sheet (file stem), e.g. `treasurebox`
+    pub sheet: String,
+    #[serde(default)]
+    pub select: WdbRecordSelector,
+    /// Item fields; entries ending in `*` match by prefix (`sItemLabel*`)
+    pub fields: Vec<String>,
+}
+
+/// How a pool's slots get their new items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WdbPoolMode {
+    /// Permute the items already in the pool's slots
+    #[default]
+    Shuffle,
+    /// Draw each slot's item from the pool's item list
+    Draw,
+}
+
+/// A group of slots randomized together.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbRandomPool {
+    pub name: String,
+    pub slots: Vec<WdbSlotSet>,
+    #[serde(default)]
+    pub mode: WdbPoolMode,
+    /// Items to draw from in `Draw` mode; the pool's current items when empty
+    #[serde(default)]
+    pub items: Vec<String>,
+    /// Items never placed in this pool
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Items the player needs before the selected records' slots can be reached.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbLogicRule {
+    pub sheet: String,
+    #[serde(default)]
+    pub select: WdbRecordSelector,
+    pub requires: Vec<String>,
+}
+
+/// Scales a numeric field by a random factor in `min..=max`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbPriceRule {
+    pub sheet: String,
+    #[serde(default)]
+    pub select: WdbRecordSelector,
+    pub field: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Everything a randomizer run needs besides the sheets themselves.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbRandomizerConfig {
+    pub seed: u64,
+    pub pools: Vec<WdbRandomPool>,
+    #[serde(default)]
+    pub logic: Vec<WdbLogicRule>,
+    /// Items the logic depends on; placed first so each stays reachable
+    #[serde(default)]
+    pub progression: Vec<String>,
+    #[serde(default)]
+    pub prices: Vec<WdbPriceRule>,
+}
+
+/// One slot or price the randomizer changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WdbSpoilerEntry {
+    /// Pool name, or `price` for price rules
+    pub pool: String,
+    pub sheet: String,
+    pub record: String,
+    pub field: String,
+    pub before: WdbValue,
+    pub after: WdbValue,
+}
+
+/// Patches and spoiler log of a randomizer run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WdbRandomizerResult {
+    pub seed: u64,
+    /// One patch per changed sheet (by file stem)
+    pub patches: Vec<(String, WdbPatch)>,
+    pub spoiler: Vec<WdbSpoilerEntry>,
+}
+
 // Legacy C Structs
 #[repr(C)]
 pub enum WDBValueTypeC {
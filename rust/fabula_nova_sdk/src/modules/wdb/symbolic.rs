@@ -0,0 +1,198 @@
+//! # WDB Symbolic Values
+//!
+//! This module converts enum and flag fields between the numbers stored in
+//! WDB files and the names shown in JSON and the editor.
+//!
+//! ## Representations
+//!
+//! | Binding          | Decoded value                  | Accepted on input               |
+//! |------------------|--------------------------------|---------------------------------|
+//! | Built-in enum    | Typed variant (`"Attacker"`)   | Variant name or number          |
+//! | Schema enum      | `String` name (`"Ice"`)        | Name or number                  |
+//! | Schema flag set  | `StringArray` (`["Poison"]`)   | List of names, one name, number |
+//!
+//! A value with no name (or a flag bit with no name) stays a number in both
+//! directions, so files with values the schema doesn't know still round-trip.
+//! An unknown *name* is an error: it is almost always a typo, and writing 0
+//! in its place would silently corrupt the record.
+//!
+//! ## Where Bindings Come From
+//!
+//! [`super::parse_wdb`] records the schema sheet it matched under the
+//! [`SCHEMA_SHEET_KEY`] header entry. JSON import and the writer look the
+//! bindings up again from that entry (or `sheetName`) in the active schema
+//! pack, so they always agree with the reader.
+
+use anyhow::{bail, Context, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use super::enum_registry::{int_to_enum_value, name_to_enum_value};
+use super::schema::{with_active_schema, EnumBinding};
+use super::structs::{GameCode, WdbData, WdbValue};
+
+/// Header entry naming the schema sheet a file was decoded with.
+pub const SCHEMA_SHEET_KEY: &str = "schemaSheet";
+
+impl EnumBinding {
+    /// Name of the bound enum, for error messages.
+    pub fn name(&self) -> &str {
+        match self {
+            EnumBinding::Builtin(t) => t.name(),
+            EnumBinding::Schema(e) => &e.name,
+        }
+    }
+
+    /// Decodes a raw field value into its symbolic form.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The field value (already sign-extended for `i` fields)
+    /// * `signed` - Whether the field is signed, used when it stays numeric
+    pub fn decode(&self, value: i64, signed: bool) -> WdbValue {
+        match self {
+            EnumBinding::Builtin(t) => int_to_enum_value(*t, value as u32),
+            EnumBinding::Schema(e) if e.flags => e
+                .flag_names(value)
+                .map(WdbValue::StringArray)
+                .unwrap_or_else(|| numeric(value, signed)),
+            EnumBinding::Schema(e) => e
+                .name_of(value)
+                .map(|n| WdbValue::String(n.to_string()))
+                .unwrap_or_else(|| numeric(value, signed)),
+        }
+    }
+
+    /// Resolves a symbolic or numeric value to the number stored in the file.
+    ///
+    /// # Returns
+    ///
+    /// `None` for values that are neither names nor numbers (left untouched).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for names the enum doesn't define, and for lists of
+    /// names bound to a plain (non-flag) enum.
+    pub fn encode(&self, value: &WdbValue) -> Result<Option<i64>> {
+        let n = match (self, value) {
+            (_, WdbValue::Int(i)) => *i as i64,
+            (_, WdbValue::UInt(u)) => *u as i64,
+            (_, WdbValue::CrystalRole(r)) => r.to_u32() as i64,
+            (_, WdbValue::CrystalNodeType(n)) => n.to_u32() as i64,
+            (EnumBinding::Builtin(t), WdbValue::String(s)) => {
+                match name_to_enum_value(*t, s) {
+                    Some(WdbValue::CrystalRole(r)) => r.to_u32() as i64,
+                    Some(WdbValue::CrystalNodeType(n)) => n.to_u32() as i64,
+                    _ => bail!("Unknown {} value '{}'", t.name(), s),
+                }
+            }
+            (EnumBinding::Schema(e), WdbValue::String(s)) => match e.value_of(s) {
+                Some(v) => v,
+                None => bail!("Unknown {} value '{}'", e.name, s),
+            },
+            (EnumBinding::Schema(e), WdbValue::StringArray(names)) if e.flags => {
+                let mut bits = 0;
+                for name in names {
+                    match e.value_of(name) {
+                        Some(v) => bits |= v,
+                        None => bail!("Unknown {} flag '{}'", e.name, name),
+                    }
+                }
+                bits
+            }
+            (EnumBinding::Schema(e), WdbValue::IntArray(a)) if e.flags && a.is_empty() => 0,
+            (EnumBinding::Schema(e), WdbValue::UIntArray(a)) if e.flags && a.is_empty() => 0,
+            (_, WdbValue::StringArray(_)) => {
+                bail!("{} is not a flag set and takes a single name", self.name())
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(n))
+    }
+}
+
+/// A numeric WdbValue of the field's signedness.
+fn numeric(value: i64, signed: bool) -> WdbValue {
+    if signed {
+        WdbValue::Int(value as i32)
+    } else {
+        WdbValue::UInt(value as u32)
+    }
+}
+
+/// Enum bindings for a parsed file, keyed by field name.
+///
+/// The sheet is taken from the [`SCHEMA_SHEET_KEY`] header entry, falling
+/// back to `sheetName`.
+pub fn wdb_enum_bindings(data: &WdbData, game_code: GameCode) -> HashMap<String, EnumBinding> {
+    let sheet_name = [SCHEMA_SHEET_KEY, "sheetName"]
+        .iter()
+        .find_map(|key| match data.header.get(*key) {
+            Some(WdbValue::String(s)) => Some(s.clone()),
+            _ => None,
+        });
+    let Some(sheet_name) = sheet_name else {
+        return HashMap::new();
+    };
+    with_active_schema(game_code, |schema| {
+        schema
+            .sheet(&sheet_name)
+            .map(|sheet| schema.enum_bindings(sheet))
+            .unwrap_or_default()
+    })
+}
+
+/// Rewrites every enum field into its canonical symbolic form.
+///
+/// Used after JSON import: numbers become names where the enum has one, and
+/// names are checked against the enum.
+///
+/// # Errors
+///
+/// Returns an error naming the record and field of the first unknown name.
+pub fn symbolize_wdb_values(data: &mut WdbData, game_code: GameCode) -> Result<()> {
+    let bindings = wdb_enum_bindings(data, game_code);
+    map_enum_fields(data, &bindings, |binding, n, signed| binding.decode(n, signed))
+}
+
+/// Returns the data with every enum field resolved to a plain number.
+///
+/// Used by the writer. Borrows the input unchanged when the sheet has no
+/// enum bindings.
+///
+/// # Errors
+///
+/// Returns an error naming the record and field of the first unknown name.
+pub fn resolve_wdb_values(data: &WdbData, game_code: GameCode) -> Result<Cow<'_, WdbData>> {
+    let bindings = wdb_enum_bindings(data, game_code);
+    if bindings.is_empty() {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut resolved = data.clone();
+    map_enum_fields(&mut resolved, &bindings, |_, n, signed| numeric(n, signed))?;
+    Ok(Cow::Owned(resolved))
+}
+
+/// Encodes every bound field and replaces it with `rebuild(binding, number, signed)`.
+fn map_enum_fields(
+    data: &mut WdbData,
+    bindings: &HashMap<String, EnumBinding>,
+    rebuild: impl Fn(&EnumBinding, i64, bool) -> WdbValue,
+) -> Result<()> {
+    for (i, record) in data.records.iter_mut().enumerate() {
+        let name = match record.get("record") {
+            Some(WdbValue::String(s)) => s.clone(),
+            _ => i.to_string(),
+        };
+        for (field, binding) in bindings {
+            let Some(value) = record.get_mut(field) else { continue };
+            let encoded = binding
+                .encode(value)
+                .with_context(|| format!("Record {}, field {}", name, field))?;
+            if let Some(n) = encoded {
+                *value = rebuild(binding, n, field.starts_with('i'));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,665 @@
+//! # WDB Spreadsheet Export/Import
+//!
+//! This module converts [`WdbData`] records to and from CSV/TSV so sheets
+//! can be balanced in a spreadsheet and imported back.
+//!
+//! ## Layout
+//!
+//! One row per record, one column per field. The first column is always
+//! `record`; the others follow the `!structitem` field order, so columns
+//! stay put across exports. Each header cell carries a type hint:
+//!
+//! ```text
+//! record,u4Role:enum(CrystalRole),u8Status:flags(Status),sAbilityID:string,iVal:int[]
+//! cr_001,Attacker,Poison|Slow,abi_fire,1|-2|3
+//! ```
+//!
+//! ## Cell Encoding
+//!
+//! | Hint            | Cell                                                  |
+//! |-----------------|-------------------------------------------------------|
+//! | `int`, `uint`   | Decimal number, checked against the field's bit width |
+//! | `float`         | Decimal number                                        |
+//! | `string`        | Text as is                                            |
+//! | `bool`          | `true` / `false`                                      |
+//! | `int[]`, `uint[]`, `string[]` | Elements separated by `\|`; `\|` and `\\` escape inside strings; empty cell = empty array |
+//! | `enum(Name)`    | Value name, or a number for values without one        |
+//! | `flags(Name)`   | Flag names separated by `\|`, or a number; empty = none |
+//!
+//! Quoting follows RFC 4180 for both delimiters: cells containing the
+//! delimiter, a quote or a line break are wrapped in `"` with quotes doubled.
+//!
+//! ## Import
+//!
+//! The spreadsheet only holds records. The WDB header (type lists, version,
+//! sheet name) comes from a base [`WdbData`], normally the file the sheet
+//! was exported from. Columns deleted from the sheet keep the base record's
+//! values, and rows may be added, removed or reordered.
+//!
+//! Every cell is checked, and all problems are returned together as
+//! [`WdbCellError`]s (row, column, text, reason) instead of stopping at the
+//! first one. Only structural problems, such as a missing `record` column
+//! or a column that isn't a field, fail the whole import.
+
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::api::{canonicalize_wdb, game_code_from_header, pack_wdb, parse_wdb};
+use super::bit_helpers::derive_field_number;
+use super::schema::EnumBinding;
+use super::structs::{GameCode, WdbCellError, WdbData, WdbRecord, WdbTableFormat, WdbTableImport, WdbValue};
+use super::symbolic::wdb_enum_bindings;
+
+/// Name of the record id column.
+const RECORD_COLUMN: &str = "record";
+
+/// Separator between array elements and flag names inside one cell.
+const ARRAY_SEPARATOR: char = '|';
+
+/// UTF-8 byte order mark, so spreadsheet apps detect the encoding.
+const UTF8_BOM: &str = "\u{FEFF}";
+
+impl WdbTableFormat {
+    /// The cell delimiter.
+    pub fn delimiter(self) -> char {
+        match self {
+            WdbTableFormat::Csv => ',',
+            WdbTableFormat::Tsv => '\t',
+        }
+    }
+
+    /// Picks the format from a file extension (`.tsv`/`.tab` = TSV, else CSV).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("tsv") || e.eq_ignore_ascii_case("tab") => WdbTableFormat::Tsv,
+            _ => WdbTableFormat::Csv,
+        }
+    }
+}
+
+/// Type of a column, written as the header hint.
+#[derive(Debug, Clone, PartialEq)]
+enum CellType {
+    Int,
+    UInt,
+    Float,
+    String,
+    Bool,
+    IntArray,
+    UIntArray,
+    StringArray,
+    Enum(String),
+    Flags(String),
+}
+
+impl CellType {
+    fn hint(&self) -> String {
+        match self {
+            CellType::Int => "int".to_string(),
+            CellType::UInt => "uint".to_string(),
+            CellType::Float => "float".to_string(),
+            CellType::String => "string".to_string(),
+            CellType::Bool => "bool".to_string(),
+            CellType::IntArray => "int[]".to_string(),
+            CellType::UIntArray => "uint[]".to_string(),
+            CellType::StringArray => "string[]".to_string(),
+            CellType::Enum(name) => format!("enum({})", name),
+            CellType::Flags(name) => format!("flags({})", name),
+        }
+    }
+
+    fn parse_hint(hint: &str) -> Option<Self> {
+        let named = |prefix: &str| {
+            hint.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(str::to_string)
+        };
+        Some(match hint {
+            "int" => CellType::Int,
+            "uint" => CellType::UInt,
+            "float" => CellType::Float,
+            "string" => CellType::String,
+            "bool" => CellType::Bool,
+            "int[]" => CellType::IntArray,
+            "uint[]" => CellType::UIntArray,
+            "string[]" => CellType::StringArray,
+            _ => {
+                if let Some(name) = named("enum(") {
+                    CellType::Enum(name)
+                } else {
+                    CellType::Flags(named("flags(")?)
+                }
+            }
+        })
+    }
+
+    /// Column type for a field, from its enum binding, its values, or its name.
+    fn for_field(field: &str, binding: Option<&EnumBinding>, records: &[WdbRecord]) -> Self {
+        match binding {
+            Some(EnumBinding::Schema(e)) if e.flags => return CellType::Flags(e.name.clone()),
+            Some(b) => return CellType::Enum(b.name().to_string()),
+            None => {}
+        }
+        let from_values = records.iter().find_map(|r| match r.get(field)? {
+            // Unsigned fields come back from JSON as Int, so trust the name for those
+            WdbValue::Int(_) | WdbValue::UInt(_) => Some(Self::from_field_name(field)),
+            WdbValue::Float(_) => Some(CellType::Float),
+            WdbValue::String(_) => Some(CellType::String),
+            WdbValue::Bool(_) => Some(CellType::Bool),
+            WdbValue::IntArray(_) => Some(CellType::IntArray),
+            WdbValue::UIntArray(_) => Some(CellType::UIntArray),
+            WdbValue::StringArray(_) => Some(CellType::StringArray),
+            WdbValue::CrystalRole(_) => Some(CellType::Enum("CrystalRole".to_string())),
+            WdbValue::CrystalNodeType(_) => Some(CellType::Enum("CrystalNodeType".to_string())),
+            WdbValue::Unknown => None,
+        });
+        from_values.unwrap_or_else(|| Self::from_field_name(field))
+    }
+
+    fn from_field_name(field: &str) -> Self {
+        match field.chars().next() {
+            Some('i') => CellType::Int,
+            Some('u') => CellType::UInt,
+            Some('f') if derive_field_number(field) == 0 => CellType::Float,
+            // Bitpacked floats are stored as ints
+            Some('f') => CellType::Int,
+            _ => CellType::String,
+        }
+    }
+}
+
+// =============================================================================
+// Export
+// =============================================================================
+
+/// Converts records to CSV or TSV text.
+///
+/// Enum columns are resolved with the active schema for the game recorded
+/// in the header.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let data = wdb::parse_wdb("item.wdb", GameCode::FF13_1)?;
+/// let csv = wdb::wdb_to_table_string(&data, WdbTableFormat::Csv)?;
+/// ```
+pub fn wdb_to_table_string(data: &WdbData, format: WdbTableFormat) -> Result<String> {
+    let bindings = wdb_enum_bindings(data, game_code_from_header(&data.header));
+    let columns = table_columns(data);
+
+    let mut rows = Vec::with_capacity(data.records.len() + 1);
+    let types: Vec<CellType> = columns
+        .iter()
+        .map(|c| CellType::for_field(c, bindings.get(c), &data.records))
+        .collect();
+
+    let mut header = vec![RECORD_COLUMN.to_string()];
+    header.extend(columns.iter().zip(&types).map(|(c, t)| format!("{}:{}", c, t.hint())));
+    rows.push(header);
+
+    for record in &data.records {
+        let mut row = vec![match record.get(RECORD_COLUMN) {
+            Some(WdbValue::String(s)) => s.clone(),
+            _ => String::new(),
+        }];
+        row.extend(columns.iter().map(|c| record.get(c).map(format_cell).unwrap_or_default()));
+        rows.push(row);
+    }
+
+    Ok(write_table(&rows, format.delimiter()))
+}
+
+/// Field columns in `!structitem` order, then any other record keys sorted.
+fn table_columns(data: &WdbData) -> Vec<String> {
+    let mut columns: Vec<String> = match data.header.get("!structitem") {
+        Some(WdbValue::StringArray(fields)) => fields.clone(),
+        _ => Vec::new(),
+    };
+    let known: HashSet<String> = columns.iter().cloned().collect();
+    let mut extra: Vec<String> = data
+        .records
+        .iter()
+        .flat_map(|r| r.keys())
+        .filter(|k| *k != RECORD_COLUMN && !known.contains(*k))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    extra.sort();
+    columns.extend(extra);
+    columns
+}
+
+fn format_cell(value: &WdbValue) -> String {
+    let join = |items: Vec<String>| items.join(&ARRAY_SEPARATOR.to_string());
+    match value {
+        WdbValue::Int(i) => i.to_string(),
+        WdbValue::UInt(u) => u.to_string(),
+        WdbValue::Float(f) => f.to_string(),
+        WdbValue::String(s) => s.clone(),
+        WdbValue::Bool(b) => b.to_string(),
+        WdbValue::IntArray(a) => join(a.iter().map(|v| v.to_string()).collect()),
+        WdbValue::UIntArray(a) => join(a.iter().map(|v| v.to_string()).collect()),
+        WdbValue::StringArray(a) => join(a.iter().map(|s| escape_element(s)).collect()),
+        WdbValue::CrystalRole(r) => format!("{:?}", r),
+        WdbValue::CrystalNodeType(n) => format!("{:?}", n),
+        WdbValue::Unknown => String::new(),
+    }
+}
+
+fn escape_element(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(ARRAY_SEPARATOR, "\\|")
+}
+
+/// Splits an array cell on unescaped separators.
+fn split_elements(cell: &str) -> Vec<String> {
+    if cell.is_empty() {
+        return Vec::new();
+    }
+    let mut items = vec![String::new()];
+    let mut chars = cell.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    items.last_mut().unwrap().push(next);
+                }
+            }
+            ARRAY_SEPARATOR => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(c),
+        }
+    }
+    items
+}
+
+fn write_table(rows: &[Vec<String>], delimiter: char) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| {
+                if cell.contains([delimiter, '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell.clone()
+                }
+            })
+            .collect();
+        out.push_str(&cells.join(&delimiter.to_string()));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+// =============================================================================
+// Import
+// =============================================================================
+
+/// Parses CSV/TSV text into rows of cells.
+///
+/// Handles quoted cells with doubled quotes and embedded line breaks, LF or
+/// CRLF line endings, and a leading byte order mark.
+fn read_table(text: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+    let text = text.strip_prefix(UTF8_BOM).unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                cell.push(c);
+            }
+        } else if c == '"' && cell.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            row.push(std::mem::take(&mut cell));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            row.push(std::mem::take(&mut cell));
+            rows.push(std::mem::take(&mut row));
+        } else {
+            cell.push(c);
+        }
+    }
+    if in_quotes {
+        bail!("Unterminated quoted cell at end of file");
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    // Spreadsheet apps like to append blank lines
+    while rows.last().is_some_and(|r| r.iter().all(|c| c.is_empty())) {
+        rows.pop();
+    }
+    Ok(rows)
+}
+
+/// Imports CSV or TSV text on top of a base file.
+///
+/// # Arguments
+///
+/// * `text` - The spreadsheet contents
+/// * `format` - CSV or TSV
+/// * `base` - The data the sheet was exported from; supplies the header and
+///   the values of columns missing from the sheet
+///
+/// # Returns
+///
+/// The imported data plus every cell error found. Pack `data` only when
+/// `errors` is empty.
+///
+/// # Errors
+///
+/// Returns an error if the text is malformed, the header has no `record`
+/// column, or a column is not a field of the base sheet.
+pub fn wdb_from_table_string(text: &str, format: WdbTableFormat, base: &WdbData) -> Result<WdbTableImport> {
+    let rows = read_table(text, format.delimiter())?;
+    let Some((header, body)) = rows.split_first() else {
+        bail!("The table is empty");
+    };
+
+    let bindings = wdb_enum_bindings(base, game_code_from_header(&base.header));
+    let fields: HashSet<String> = table_columns(base).into_iter().collect();
+
+    // Resolve header cells to (field, type)
+    let mut record_idx = None;
+    let mut columns: Vec<(String, CellType)> = Vec::with_capacity(header.len());
+    let mut seen = HashSet::new();
+    for (i, cell) in header.iter().enumerate() {
+        let (name, hint) = match cell.split_once(':') {
+            Some((name, hint)) => (name.trim(), Some(hint.trim())),
+            None => (cell.trim(), None),
+        };
+        if !seen.insert(name.to_string()) {
+            bail!("Column '{}' appears twice", name);
+        }
+        if name == RECORD_COLUMN {
+            record_idx = Some(i);
+        } else if !fields.contains(name) {
+            bail!("Column '{}' is not a field of this sheet", name);
+        }
+        let cell_type = match hint {
+            Some(h) => CellType::parse_hint(h)
+                .ok_or_else(|| anyhow::anyhow!("Column '{}' has unknown type hint '{}'", name, h))?,
+            None => CellType::for_field(name, bindings.get(name), &base.records),
+        };
+        columns.push((name.to_string(), cell_type));
+    }
+    let Some(record_idx) = record_idx else {
+        bail!("The table has no '{}' column", RECORD_COLUMN);
+    };
+
+    let base_records: HashMap<&str, &WdbRecord> = base
+        .records
+        .iter()
+        .filter_map(|r| match r.get(RECORD_COLUMN) {
+            Some(WdbValue::String(id)) => Some((id.as_str(), r)),
+            _ => None,
+        })
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut records = Vec::with_capacity(body.len());
+    let mut ids = HashSet::new();
+    for (i, row) in body.iter().enumerate() {
+        let row_no = i + 2;
+        let mut error = |column: &str, value: &str, message: String| {
+            errors.push(WdbCellError {
+                row: row_no,
+                column: column.to_string(),
+                value: value.to_string(),
+                message,
+            })
+        };
+
+        if row.len() != columns.len() {
+            error("", "", format!("Expected {} cells, found {}", columns.len(), row.len()));
+            continue;
+        }
+        let id = &row[record_idx];
+        if id.is_empty() {
+            error(RECORD_COLUMN, id, "Record id is empty".to_string());
+            continue;
+        }
+        if !ids.insert(id.clone()) {
+            error(RECORD_COLUMN, id, "Duplicate record id".to_string());
+            continue;
+        }
+
+        let mut record = base_records.get(id.as_str()).map(|r| (*r).clone()).unwrap_or_default();
+        for ((field, cell_type), cell) in columns.iter().zip(row) {
+            if field == RECORD_COLUMN {
+                continue;
+            }
+            match parse_cell(field, cell_type, cell, bindings.get(field)) {
+                Ok(value) => {
+                    record.insert(field.clone(), value);
+                }
+                Err(message) => {
+                    record.shift_remove(field);
+                    error(field, cell, message);
+                }
+            }
+        }
+        record.insert(RECORD_COLUMN.to_string(), WdbValue::String(id.clone()));
+        records.push(record);
+    }
+
+    let mut header_map = base.header.clone();
+    header_map.insert("recordCount".to_string(), WdbValue::UInt(records.len() as u32));
+    let mut data = WdbData { header: header_map, records };
+    canonicalize_wdb(&mut data);
+    Ok(WdbTableImport { data, errors })
+}
+
+/// Parses one cell, returning a message for the cell error on failure.
+fn parse_cell(
+    field: &str,
+    cell_type: &CellType,
+    cell: &str,
+    binding: Option<&EnumBinding>,
+) -> std::result::Result<WdbValue, String> {
+    let signed = field.starts_with('i') || (field.starts_with('f') && derive_field_number(field) != 0);
+    let number = |s: &str| -> std::result::Result<i64, String> {
+        let n: i64 = s.trim().parse().map_err(|_| format!("'{}' is not a whole number", s))?;
+        check_range(field, n, signed)?;
+        Ok(n)
+    };
+
+    Ok(match cell_type {
+        CellType::Int => WdbValue::Int(number(cell)? as i32),
+        CellType::UInt => WdbValue::UInt(number(cell)? as u32),
+        CellType::Float => WdbValue::Float(
+            cell.trim().parse().map_err(|_| format!("'{}' is not a number", cell))?,
+        ),
+        CellType::String => WdbValue::String(cell.to_string()),
+        CellType::Bool => match cell.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" => WdbValue::Bool(true),
+            "false" | "0" => WdbValue::Bool(false),
+            _ => return Err(format!("'{}' is not true or false", cell)),
+        },
+        CellType::IntArray => WdbValue::IntArray(
+            split_elements(cell)
+                .iter()
+                .map(|s| s.trim().parse().map_err(|_| format!("'{}' is not a signed 32-bit number", s)))
+                .collect::<std::result::Result<_, _>>()?,
+        ),
+        CellType::UIntArray => WdbValue::UIntArray(
+            split_elements(cell)
+                .iter()
+                .map(|s| s.trim().parse().map_err(|_| format!("'{}' is not an unsigned 32-bit number", s)))
+                .collect::<std::result::Result<_, _>>()?,
+        ),
+        CellType::StringArray => WdbValue::StringArray(split_elements(cell)),
+        CellType::Enum(name) | CellType::Flags(name) => {
+            let binding = binding.ok_or_else(|| {
+                format!("The schema no longer binds this field to {}", name)
+            })?;
+            let raw = match cell.trim().parse::<i64>() {
+                Ok(n) => WdbValue::Int(n as i32),
+                Err(_) if matches!(cell_type, CellType::Flags(_)) => WdbValue::StringArray(split_elements(cell.trim())),
+                Err(_) => WdbValue::String(cell.trim().to_string()),
+            };
+            let n = binding
+                .encode(&raw)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("'{}' is not a {} value", cell, name))?;
+            check_range(field, n, signed)?;
+            binding.decode(n, signed)
+        }
+    })
+}
+
+/// Checks that a value fits the field's bit width.
+pub(crate) fn check_range(field: &str, n: i64, signed: bool) -> std::result::Result<(), String> {
+    let bits = match derive_field_number(field) {
+        0 => 32,
+        b => b.min(32),
+    };
+    let (min, max) = if signed {
+        (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1)
+    } else {
+        (0, (1i64 << bits) - 1)
+    };
+    if n < min || n > max {
+        let kind = if signed { "signed" } else { "unsigned" };
+        return Err(format!("{} does not fit the {}-bit {} field ({}..={})", n, bits, kind, min, max));
+    }
+    Ok(())
+}
+
+// =============================================================================
+// File helpers
+// =============================================================================
+
+/// Exports a WDB file to CSV or TSV (picked from the output extension).
+///
+/// The file starts with a UTF-8 byte order mark so spreadsheet apps show
+/// Japanese text correctly.
+pub fn export_wdb_table<P: AsRef<Path>>(wdb_path: P, table_path: P, game_code: GameCode) -> Result<()> {
+    let data = parse_wdb(&wdb_path, game_code)?;
+    let text = wdb_to_table_string(&data, WdbTableFormat::from_path(&table_path))?;
+    fs::write(table_path, format!("{}{}", UTF8_BOM, text))?;
+    Ok(())
+}
+
+/// Imports a CSV or TSV file onto a WDB file and writes the result.
+///
+/// # Arguments
+///
+/// * `wdb_path` - The original WDB file (supplies the header)
+/// * `table_path` - The edited spreadsheet
+/// * `output_path` - Where to write the new WDB file
+/// * `game_code` - Which game the WDB belongs to
+///
+/// # Returns
+///
+/// The cell errors found. Nothing is written unless the list is empty.
+pub fn import_wdb_table<P: AsRef<Path>>(
+    wdb_path: P,
+    table_path: P,
+    output_path: P,
+    game_code: GameCode,
+) -> Result<Vec<WdbCellError>> {
+    let base = parse_wdb(&wdb_path, game_code)?;
+    let text = fs::read_to_string(&table_path)?;
+    let import = wdb_from_table_string(&text, WdbTableFormat::from_path(&table_path), &base)?;
+    if import.errors.is_empty() {
+        pack_wdb(&import.data, output_path, game_code)?;
+    }
+    Ok(import.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::structs::WdbHeader;
+
+    fn sample() -> WdbData {
+        let fields: Vec<String> = ["u4Role", "i8Bonus", "u20Reserved", "sName", "fRate", "iList"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut header = WdbHeader::new();
+        header.insert("!structitem".to_string(), WdbValue::StringArray(fields));
+        header.insert("sheetName".to_string(), WdbValue::String("crystal".to_string()));
+        header.insert("gameCode".to_string(), WdbValue::String("FF13_2".to_string()));
+
+        let record = |id: &str, role, bonus, name: &str| {
+            let mut r = WdbRecord::new();
+            r.insert("record".to_string(), WdbValue::String(id.to_string()));
+            r.insert("u4Role".to_string(), super::super::enum_registry::int_to_enum_value(
+                super::super::enum_registry::EnumType::CrystalRole,
+                role,
+            ));
+            r.insert("i8Bonus".to_string(), WdbValue::Int(bonus));
+            r.insert("u20Reserved".to_string(), WdbValue::UInt(0));
+            r.insert("sName".to_string(), WdbValue::String(name.to_string()));
+            r.insert("fRate".to_string(), WdbValue::Float(0.25));
+            r.insert("iList".to_string(), WdbValue::IntArray(vec![1, -2]));
+            r
+        };
+        WdbData {
+            header,
+            records: vec![
+                record("cr_001", 2, -5, "Plain"),
+                record("cr_002", 6, 7, "Comma, \"quoted\"\nand a | pipe"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_table_roundtrip() {
+        let data = sample();
+        for format in [WdbTableFormat::Csv, WdbTableFormat::Tsv] {
+            let text = wdb_to_table_string(&data, format).unwrap();
+            let first_line = text.lines().next().unwrap();
+            assert!(first_line.starts_with("record"));
+            assert!(first_line.contains("u4Role:enum(CrystalRole)"));
+            assert!(first_line.contains("iList:int[]"));
+
+            let import = wdb_from_table_string(&text, format, &data).unwrap();
+            assert!(import.errors.is_empty(), "{:?}", import.errors);
+            assert_eq!(import.data.records, data.records);
+        }
+    }
+
+    #[test]
+    fn test_table_reports_cell_errors() {
+        let data = sample();
+        let text = "record,u4Role,i8Bonus:int,iList:int[]\r\n\
+                    cr_001,Healer,200,1|x\r\n\
+                    cr_002,Medic,-128,\r\n\
+                    cr_003,3,1\r\n";
+        let import = wdb_from_table_string(text, WdbTableFormat::Csv, &data).unwrap();
+
+        let found: Vec<(usize, &str)> = import.errors.iter().map(|e| (e.row, e.column.as_str())).collect();
+        assert_eq!(found, vec![(2, "i8Bonus"), (2, "iList"), (3, "u4Role"), (4, "")]);
+        assert!(import.errors[0].message.contains("8-bit signed"));
+
+        // Valid cells still apply, columns missing from the sheet keep their base values
+        let r = &import.data.records[1];
+        assert_eq!(r["i8Bonus"], WdbValue::Int(-128));
+        assert_eq!(r["iList"], WdbValue::IntArray(vec![]));
+        assert_eq!(r["sName"], data.records[1]["sName"]);
+
+        assert!(wdb_from_table_string("record,uMissing\r\n", WdbTableFormat::Csv, &data).is_err());
+        assert!(wdb_from_table_string("u4Role\r\n", WdbTableFormat::Csv, &data).is_err());
+    }
+}
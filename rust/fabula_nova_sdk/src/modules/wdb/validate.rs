@@ -0,0 +1,634 @@
+//! # WDB Validation
+//!
+//! The writer never fails on bad values: numbers that overflow their bit
+//! width become 0, missing fields become 0, values of the wrong type become
+//! 0 or an empty string, and records without an id are dropped. This module
+//! walks the same layout as the writer and reports every such problem up
+//! front, so [`pack_wdb`](super::api::pack_wdb) can refuse to write a file
+//! that wouldn't round-trip.
+//!
+//! ## Checks
+//!
+//! | Kind                 | Problem                                                     |
+//! |----------------------|-------------------------------------------------------------|
+//! | `Layout`             | `!structitem`/`!!strtypelist` missing or not lining up      |
+//! | `TypeMismatch`       | Field prefix or value type doesn't match its slot           |
+//! | `OutOfRange`         | Number doesn't fit the field's bit width                    |
+//! | `UnknownEnumValue`   | Enum field holds a name its enum doesn't define             |
+//! | `MissingField`       | Record lacks a field (would be written as 0)                |
+//! | `ArrayLength`        | More distinct strings than a string array index can hold    |
+//! | `StringTable`        | String array offsets overflow `bits_per_offset`             |
+//! | `RecordId`           | Record has no id, repeats another's, or exceeds 16 bytes    |
+//!
+//! ## Slots
+//!
+//! Each `!!strtypelist` entry is one 32-bit slot of a record:
+//!
+//! ```text
+//!   type 0  bitpacked   u4 u4 i8 f16 ... (packed until the next field won't fit)
+//!   type 1  float       one f32 field
+//!   type 2  string      one offset into !!string
+//!   type 3  uint        one full u32 field
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use super::bit_helpers::derive_field_number;
+use super::structs::{GameCode, WdbData, WdbIssueKind, WdbValidationIssue, WdbValue};
+use super::schema::EnumBinding;
+use super::sections::{raw_sections, str_array_seeds, StringPool};
+use super::symbolic::wdb_enum_bindings;
+use super::table::check_range;
+use super::writer::MAX_NAME_LEN;
+
+/// Error returned by strict packing, carrying every issue found.
+///
+/// Callers that want the structured list can downcast the
+/// [`anyhow::Error`] to this type.
+#[derive(Debug, Clone)]
+pub struct WdbValidationError {
+    pub issues: Vec<WdbValidationIssue>,
+}
+
+impl fmt::Display for WdbValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WDB validation found {} issue(s)", self.issues.len())?;
+        for issue in self.issues.iter().take(5) {
+            write!(f, "\n  {}", describe_issue(issue))?;
+        }
+        if self.issues.len() > 5 {
+            write!(f, "\n  ... and {} more", self.issues.len() - 5)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WdbValidationError {}
+
+/// One-line description of an issue, prefixed with its record and field.
+pub fn describe_issue(issue: &WdbValidationIssue) -> String {
+    match (&issue.record, &issue.field) {
+        (Some(r), Some(f)) => format!("{}.{}: {}", r, f, issue.message),
+        (Some(r), None) => format!("{}: {}", r, issue.message),
+        (None, Some(f)) => format!("{}: {}", f, issue.message),
+        (None, None) => issue.message.clone(),
+    }
+}
+
+/// Where a field lives in a record.
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    /// Packed into a type 0 word
+    Bits,
+    /// Type 1 float
+    Float,
+    /// Type 2 string offset
+    String,
+    /// Type 3 full 32-bit number
+    Number,
+}
+
+/// Checks WDB data against the layout the writer will use.
+///
+/// # Arguments
+///
+/// * `data` - The data to check
+/// * `game_code` - Target game (XIII has no string arrays)
+///
+/// # Returns
+///
+/// Every issue found, in record order. Empty means the data packs without
+/// losing or altering values.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// for issue in validate_wdb(&data, GameCode::FF13_1) {
+///     println!("{:?} {}", issue.kind, describe_issue(&issue));
+/// }
+/// ```
+pub fn validate_wdb(data: &WdbData, game_code: GameCode) -> Vec<WdbValidationIssue> {
+    let mut issues = Vec::new();
+
+    let fields = match data.header.get("!structitem") {
+        Some(WdbValue::StringArray(f)) => f,
+        _ => {
+            issues.push(issue(WdbIssueKind::Layout, None, None, "Missing !structitem in header".into()));
+            return issues;
+        }
+    };
+    // JSON import reads small non-negative arrays back as IntArray
+    let strtypelist: Vec<u32> = match data.header.get("!!strtypelist") {
+        Some(WdbValue::UIntArray(arr)) => arr.clone(),
+        Some(WdbValue::IntArray(arr)) => arr.iter().map(|&v| v as u32).collect(),
+        _ => Vec::new(),
+    };
+
+    let slots = layout(fields, &strtypelist, game_code, &mut issues);
+    let bindings = wdb_enum_bindings(data, game_code);
+
+    // Raw sections and layout seeds must decode for the writer to use them
+    if let Err(e) = raw_sections(&data.header) {
+        issues.push(issue(WdbIssueKind::Layout, None, None, e.to_string()));
+    }
+    let mut seeds = str_array_seeds(&data.header, fields).unwrap_or_else(|e| {
+        issues.push(issue(WdbIssueKind::Layout, None, None, e.to_string()));
+        Default::default()
+    });
+
+    let mut seen_ids = HashSet::new();
+    // The writer collects string array values per field, in field order,
+    // after the values the original file listed
+    let mut str_arrays: IndexMap<&str, Vec<String>> = fields
+        .iter()
+        .zip(&slots)
+        .filter(|(f, slot)| matches!(slot, Some(Slot::Bits)) && f.starts_with('s'))
+        .map(|(f, _)| (f.as_str(), seeds.remove(f).unwrap_or_default()))
+        .collect();
+
+    for (i, record) in data.records.iter().enumerate() {
+        let id = match record.get("record") {
+            Some(WdbValue::String(s)) if !s.is_empty() => s.clone(),
+            _ => {
+                issues.push(issue(
+                    WdbIssueKind::RecordId,
+                    Some(format!("#{}", i + 1)),
+                    None,
+                    "Record has no id and would be skipped".into(),
+                ));
+                continue;
+            }
+        };
+        if !seen_ids.insert(id.clone()) {
+            issues.push(issue(
+                WdbIssueKind::RecordId,
+                Some(id.clone()),
+                None,
+                "Duplicate record id; only the last copy would be written".into(),
+            ));
+        }
+        if id.len() > MAX_NAME_LEN {
+            issues.push(issue(
+                WdbIssueKind::RecordId,
+                Some(id.clone()),
+                None,
+                format!("Record id is {} bytes; ids are stored in {}-byte names", id.len(), MAX_NAME_LEN),
+            ));
+        }
+
+        for (field, slot) in fields.iter().zip(&slots) {
+            let Some(slot) = slot else { continue };
+            let Some(value) = record.get(field) else {
+                issues.push(issue(
+                    WdbIssueKind::MissingField,
+                    Some(id.clone()),
+                    Some(field.clone()),
+                    "Field is missing and would be written as 0".into(),
+                ));
+                continue;
+            };
+            if let Err((kind, message)) = check_value(field, *slot, value, bindings.get(field)) {
+                issues.push(issue(kind, Some(id.clone()), Some(field.clone()), message));
+            }
+            if let (Some(list), WdbValue::String(s)) = (str_arrays.get_mut(field.as_str()), value) {
+                if !list.contains(s) {
+                    list.push(s.clone());
+                }
+            }
+        }
+    }
+
+    check_str_arrays(data, &str_arrays, &mut issues);
+    issues
+}
+
+/// Assigns each field its slot, mirroring the writer's `!!strtypelist` walk.
+///
+/// Fields the writer can't place or would skip get `None` and a
+/// layout or type issue.
+fn layout(
+    fields: &[String],
+    strtypelist: &[u32],
+    game_code: GameCode,
+    issues: &mut Vec<WdbValidationIssue>,
+) -> Vec<Option<Slot>> {
+    let mut slots = vec![None; fields.len()];
+    if strtypelist.is_empty() {
+        issues.push(issue(WdbIssueKind::Layout, None, None, "Missing or empty !!strtypelist in header".into()));
+        return slots;
+    }
+
+    let mut f = 0;
+    for (entry, &type_code) in strtypelist.iter().enumerate() {
+        if f >= fields.len() {
+            issues.push(issue(
+                WdbIssueKind::Layout,
+                None,
+                None,
+                format!(
+                    "!!strtypelist has {} entries but the fields run out at entry {}",
+                    strtypelist.len(),
+                    entry
+                ),
+            ));
+            break;
+        }
+        match type_code {
+            0 => {
+                let mut used = 0;
+                while f < fields.len() {
+                    let field = &fields[f];
+                    let bits = match derive_field_number(field) {
+                        0 => 32,
+                        b => b,
+                    };
+                    if bits > 32 {
+                        issues.push(field_issue(
+                            WdbIssueKind::Layout,
+                            field,
+                            format!("{}-bit field can't be packed into a 32-bit word", bits),
+                        ));
+                        return slots;
+                    }
+                    if used + bits > 32 {
+                        break;
+                    }
+                    match field.chars().next() {
+                        Some('i' | 'u' | 'f') => slots[f] = Some(Slot::Bits),
+                        Some('s') if game_code != GameCode::FF13_1 => slots[f] = Some(Slot::Bits),
+                        Some('s') => issues.push(field_issue(
+                            WdbIssueKind::TypeMismatch,
+                            field,
+                            "XIII files have no string arrays; a bitpacked string field is written as 0".into(),
+                        )),
+                        _ => issues.push(field_issue(
+                            WdbIssueKind::TypeMismatch,
+                            field,
+                            "Bitpacked field needs an i/u/f/s type prefix; it would be written as 0".into(),
+                        )),
+                    }
+                    used += bits;
+                    f += 1;
+                    if used == 32 {
+                        break;
+                    }
+                }
+            }
+            1 => {
+                slots[f] = Some(Slot::Float);
+                f += 1;
+            }
+            2 => {
+                slots[f] = Some(Slot::String);
+                f += 1;
+            }
+            3 => {
+                slots[f] = Some(Slot::Number);
+                f += 1;
+            }
+            other => {
+                issues.push(issue(
+                    WdbIssueKind::Layout,
+                    None,
+                    None,
+                    format!("!!strtypelist entry {} has unknown type {}", entry, other),
+                ));
+            }
+        }
+    }
+
+    if f < fields.len() {
+        issues.push(issue(
+            WdbIssueKind::Layout,
+            None,
+            None,
+            format!(
+                "!!strtypelist has no slot for {} field(s) from '{}' on; they would not be written",
+                fields.len() - f,
+                fields[f]
+            ),
+        ));
+    }
+    slots
+}
+
+/// Checks one value against its slot and enum binding.
+fn check_value(
+    field: &str,
+    slot: Slot,
+    value: &WdbValue,
+    binding: Option<&EnumBinding>,
+) -> Result<(), (WdbIssueKind, String)> {
+    let prefix = field.chars().next().unwrap_or('?');
+    let number = match binding {
+        Some(b) => b.encode(value).map_err(|e| (WdbIssueKind::UnknownEnumValue, e.to_string()))?,
+        None => as_number(value),
+    };
+
+    match slot {
+        Slot::Bits if prefix == 's' => match value {
+            WdbValue::String(_) => Ok(()),
+            other => Err(mismatch("a string", other)),
+        },
+        Slot::Bits => {
+            let n = match (number, value) {
+                (Some(n), _) => n,
+                (None, WdbValue::Float(v)) if prefix == 'f' => {
+                    if v.fract() != 0.0 {
+                        return Err((
+                            WdbIssueKind::TypeMismatch,
+                            format!("Bitpacked float field stores whole numbers; {} would be truncated", v),
+                        ));
+                    }
+                    *v as i64
+                }
+                (None, other) => return Err(mismatch("a number", other)),
+            };
+            check_range(field, n, prefix != 'u').map_err(|m| (WdbIssueKind::OutOfRange, m))
+        }
+        Slot::Float => match value {
+            WdbValue::Float(_) | WdbValue::Int(_) | WdbValue::UInt(_) => Ok(()),
+            other => Err(mismatch("a float", other)),
+        },
+        Slot::String => match value {
+            WdbValue::String(_) => Ok(()),
+            other => Err(mismatch("a string", other)),
+        },
+        Slot::Number => match number {
+            // Negative values are stored as their two's complement
+            Some(n) if (i32::MIN as i64..=u32::MAX as i64).contains(&n) => Ok(()),
+            Some(n) => Err((WdbIssueKind::OutOfRange, format!("{} does not fit a 32-bit field", n))),
+            None => Err(mismatch("a number", value)),
+        },
+    }
+}
+
+/// Checks string array index widths and offset widths.
+fn check_str_arrays(
+    data: &WdbData,
+    str_arrays: &IndexMap<&str, Vec<String>>,
+    issues: &mut Vec<WdbValidationIssue>,
+) {
+    if str_arrays.is_empty() {
+        return;
+    }
+
+    // Same defaults as the writer
+    let offsets_per_value = match data.header.get("offsetsPerValue") {
+        Some(WdbValue::UInt(u)) => *u as usize,
+        Some(WdbValue::Int(i)) => *i as usize,
+        _ => 2,
+    };
+    let bits_per_offset = match data.header.get("bits_per_offset") {
+        Some(WdbValue::UInt(u)) => *u as usize,
+        Some(WdbValue::Int(i)) => *i as usize,
+        _ => 16,
+    };
+    if bits_per_offset == 0 || offsets_per_value * bits_per_offset > 32 {
+        issues.push(issue(
+            WdbIssueKind::Layout,
+            None,
+            None,
+            format!(
+                "{} offsets of {} bits don't fit a 32-bit string array value",
+                offsets_per_value, bits_per_offset
+            ),
+        ));
+        return;
+    }
+
+    for (field, strings) in str_arrays {
+        let max_index = strings.len() as i64 - 1;
+        if check_range(field, max_index, false).is_err() {
+            let bits = derive_field_number(field);
+            issues.push(field_issue(
+                WdbIssueKind::ArrayLength,
+                field,
+                format!(
+                    "{} distinct values, but a {}-bit index holds at most {}",
+                    strings.len(),
+                    bits,
+                    1u64 << bits
+                ),
+            ));
+        }
+    }
+
+    // String array text goes first in !!string, after the original pool
+    let mut pool = match StringPool::from_header(&data.header) {
+        Ok(pool) => pool,
+        Err(e) => {
+            issues.push(issue(WdbIssueKind::Layout, None, None, e.to_string()));
+            return;
+        }
+    };
+    let max_offset = str_arrays.values().flatten().map(|s| pool.offset(s) as usize).max().unwrap_or(0);
+    let limit = if bits_per_offset >= 32 { u32::MAX as usize } else { (1usize << bits_per_offset) - 1 };
+    if max_offset > limit {
+        issues.push(issue(
+            WdbIssueKind::StringTable,
+            None,
+            None,
+            format!(
+                "String array text needs offsets up to {}, but {} bits_per_offset hold at most {}",
+                max_offset, bits_per_offset, limit
+            ),
+        ));
+    }
+}
+
+/// The numeric form of a value, if it has one.
+fn as_number(value: &WdbValue) -> Option<i64> {
+    match value {
+        WdbValue::Int(i) => Some(*i as i64),
+        WdbValue::UInt(u) => Some(*u as i64),
+        WdbValue::CrystalRole(r) => Some(r.to_u32() as i64),
+        WdbValue::CrystalNodeType(n) => Some(n.to_u32() as i64),
+        _ => None,
+    }
+}
+
+fn mismatch(expected: &str, value: &WdbValue) -> (WdbIssueKind, String) {
+    let found = match value {
+        WdbValue::Int(_) => "an int",
+        WdbValue::UInt(_) => "a uint",
+        WdbValue::Float(_) => "a float",
+        WdbValue::String(_) => "a string",
+        WdbValue::Bool(_) => "a bool",
+        WdbValue::IntArray(_) | WdbValue::UIntArray(_) => "a number list",
+        WdbValue::StringArray(_) => "a string list",
+        WdbValue::CrystalRole(_) | WdbValue::CrystalNodeType(_) => "an enum",
+        WdbValue::Unknown => "an unknown value",
+    };
+    (WdbIssueKind::TypeMismatch, format!("Expected {}, found {}", expected, found))
+}
+
+fn issue(kind: WdbIssueKind, record: Option<String>, field: Option<String>, message: String) -> WdbValidationIssue {
+    WdbValidationIssue { kind, record, field, message }
+}
+
+fn field_issue(kind: WdbIssueKind, field: &str, message: String) -> WdbValidationIssue {
+    issue(kind, None, Some(field.to_string()), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::api::{pack_wdb, pack_wdb_with_mode, parse_wdb};
+    use crate::modules::wdb::structs::{WdbHeader, WdbRecord, WdbWriteMode};
+    use crate::modules::wdb::tests::record;
+    use std::path::PathBuf;
+
+    fn data(fields: &[&str], strtypelist: &[u32], records: Vec<WdbRecord>) -> WdbData {
+        let mut header = WdbHeader::new();
+        header.insert("!structitem".to_string(), WdbValue::StringArray(fields.iter().map(|f| f.to_string()).collect()));
+        header.insert("!!strtypelist".to_string(), WdbValue::UIntArray(strtypelist.to_vec()));
+        header.insert("!!typelist".to_string(), WdbValue::IntArray(strtypelist.iter().map(|&t| t as i32).collect()));
+        header.insert("!!version".to_string(), WdbValue::UInt(1));
+        WdbData { header, records }
+    }
+
+    fn kinds(issues: &[WdbValidationIssue]) -> Vec<(WdbIssueKind, Option<&str>, Option<&str>)> {
+        issues.iter().map(|i| (i.kind, i.record.as_deref(), i.field.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_validate_reports_per_record_issues() {
+        let fields = ["u8Level", "i8Bonus", "u16Reserved", "sName", "fRate"];
+        let valid = |id| {
+            record(id, &[
+                ("u8Level", WdbValue::UInt(1)),
+                ("i8Bonus", WdbValue::Int(-1)),
+                ("u16Reserved", WdbValue::UInt(0)),
+                ("sName", WdbValue::String(id.to_string())),
+                ("fRate", WdbValue::Float(0.5)),
+            ])
+        };
+        let mut overflow = valid("lv_002");
+        overflow.insert("u8Level".to_string(), WdbValue::UInt(256));
+        overflow.insert("i8Bonus".to_string(), WdbValue::Int(-129));
+        let mut wrong_type = valid("lv_003");
+        wrong_type.insert("sName".to_string(), WdbValue::UInt(7));
+        wrong_type.shift_remove("fRate");
+        let data = data(&fields, &[0, 2, 1], vec![valid("lv_001"), overflow, wrong_type, valid("lv_001")]);
+
+        assert_eq!(
+            kinds(&validate_wdb(&data, GameCode::FF13_1)),
+            vec![
+                (WdbIssueKind::OutOfRange, Some("lv_002"), Some("u8Level")),
+                (WdbIssueKind::OutOfRange, Some("lv_002"), Some("i8Bonus")),
+                (WdbIssueKind::TypeMismatch, Some("lv_003"), Some("sName")),
+                (WdbIssueKind::MissingField, Some("lv_003"), Some("fRate")),
+                (WdbIssueKind::RecordId, Some("lv_001"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_checks_strtypelist_and_enums() {
+        // sName sits in a bitpacked word on XIII, and a float slot gets a string
+        let data = data(
+            &["u16Level", "s16Name", "fRate"],
+            &[0, 1],
+            vec![record("lv_001", &[
+                ("u16Level", WdbValue::UInt(1)),
+                ("s16Name", WdbValue::String("a".to_string())),
+                ("fRate", WdbValue::String("fast".to_string())),
+            ])],
+        );
+        assert_eq!(
+            kinds(&validate_wdb(&data, GameCode::FF13_1)),
+            vec![
+                (WdbIssueKind::TypeMismatch, None, Some("s16Name")),
+                (WdbIssueKind::TypeMismatch, Some("lv_001"), Some("fRate")),
+            ]
+        );
+        // XIII-2 has string arrays, so only the float slot is wrong
+        assert_eq!(
+            kinds(&validate_wdb(&data, GameCode::FF13_2)),
+            vec![(WdbIssueKind::TypeMismatch, Some("lv_001"), Some("fRate"))]
+        );
+
+        let short = self::data(&["u16Level", "u16Pad", "uCount"], &[0], vec![]);
+        assert_eq!(kinds(&validate_wdb(&short, GameCode::FF13_1)), vec![(WdbIssueKind::Layout, None, None)]);
+
+        // The built-in crystal sheet binds u4Role to CrystalRole
+        let mut crystal = self::data(
+            &["u4Role", "u28Pad"],
+            &[0],
+            vec![
+                record("cr_001", &[("u4Role", WdbValue::String("Attacker".to_string())), ("u28Pad", WdbValue::UInt(0))]),
+                record("cr_002", &[("u4Role", WdbValue::String("Bard".to_string())), ("u28Pad", WdbValue::UInt(0))]),
+            ],
+        );
+        crystal.header.insert("sheetName".to_string(), WdbValue::String("crystal".to_string()));
+        let issues = validate_wdb(&crystal, GameCode::FF13_1);
+        assert_eq!(kinds(&issues), vec![(WdbIssueKind::UnknownEnumValue, Some("cr_002"), Some("u4Role"))]);
+    }
+
+    #[test]
+    fn test_validate_string_array_limits() {
+        let records = (0..5)
+            .map(|i| record(&format!("it_{:03}", i), &[("s2Tag", WdbValue::String(format!("tag_{}", i))), ("u30Pad", WdbValue::UInt(0))]))
+            .collect();
+        let mut data = data(&["s2Tag", "u30Pad"], &[0], records);
+        data.header.insert("offsetsPerValue".to_string(), WdbValue::UInt(4));
+        data.header.insert("bits_per_offset".to_string(), WdbValue::UInt(4));
+        let issues = validate_wdb(&data, GameCode::FF13_2);
+        assert_eq!(
+            kinds(&issues),
+            vec![(WdbIssueKind::ArrayLength, None, Some("s2Tag")), (WdbIssueKind::StringTable, None, None)]
+        );
+    }
+
+    #[test]
+    fn test_pack_strict_fails_and_lenient_writes() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_validate_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let data = data(
+            &["u8Level", "u24Pad"],
+            &[0],
+            vec![
+                record("lv_001", &[("u8Level", WdbValue::UInt(300)), ("u24Pad", WdbValue::UInt(0))]),
+                record("lv_002", &[("u8Level", WdbValue::UInt(9)), ("u24Pad", WdbValue::UInt(0))]),
+            ],
+        );
+
+        let strict = dir.join("strict.wdb");
+        let err = pack_wdb(&data, &strict, GameCode::FF13_1).unwrap_err();
+        let validation = err.downcast_ref::<WdbValidationError>().unwrap();
+        assert_eq!(validation.issues.len(), 1);
+        assert_eq!(validation.issues[0].kind, WdbIssueKind::OutOfRange);
+        assert!(!strict.exists());
+
+        let lenient = dir.join("lenient.wdb");
+        pack_wdb_with_mode(&data, &lenient, GameCode::FF13_1, WdbWriteMode::Lenient).unwrap();
+        let parsed = parse_wdb(&lenient, GameCode::FF13_1).unwrap();
+        assert_eq!(parsed.records.len(), 2);
+    }
+
+    #[test]
+    fn test_long_record_ids_are_rejected() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/wdb_validate_ids_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 16 bytes fill the name slot exactly, 17 don't fit
+        let row = |id| record(id, &[("u8Level", WdbValue::UInt(1)), ("u24Pad", WdbValue::UInt(0))]);
+        let data = data(&["u8Level", "u24Pad"], &[0], vec![row("lv_0123456789abc"), row("lv_0123456789abcd")]);
+        assert_eq!(
+            kinds(&validate_wdb(&data, GameCode::FF13_1)),
+            vec![(WdbIssueKind::RecordId, Some("lv_0123456789abcd"), None)]
+        );
+
+        assert!(pack_wdb(&data, dir.join("strict.wdb"), GameCode::FF13_1).is_err());
+        let err = pack_wdb_with_mode(&data, dir.join("lenient.wdb"), GameCode::FF13_1, WdbWriteMode::Lenient)
+            .unwrap_err();
+        assert!(err.to_string().contains("lv_0123456789abcd"), "{}", err);
+    }
+}
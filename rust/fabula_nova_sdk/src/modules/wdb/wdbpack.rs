@@ -0,0 +1,377 @@
+//! # wdbpack Unpack and Repack
+//!
+//! `db/resident/wdbpack.bin` is a WPD package of WDB sheets. Instead of
+//! unpacking the WPD and then opening each WDB, [`unpack_wdbpack`] writes
+//! every sheet as an editable JSON, CSV or TSV file in one call, and
+//! [`repack_wdbpack`] rebuilds the WDBs and the package.
+//!
+//! ## Folder Layout
+//!
+//! ```text
+//! wdbpack/
+//! ├── !!WDBPACK.json      manifest: game, format, records in package order
+//! ├── item.json           one editable file per sheet
+//! ├── item_weapon.json
+//! └── _raw/
+//!     ├── item.wdb        original bytes of every record
+//!     └── ...
+//! ```
+//!
+//! ## Repacking
+//!
+//! The manifest keeps a hash of each sheet file as written. On repack,
+//! sheets whose file still has that hash are copied from `_raw` unchanged;
+//! only edited sheets are parsed and encoded again. Records that aren't
+//! WDBs, or that failed to parse, always come from `_raw`.
+//!
+//! CSV/TSV files only hold records, so an edited sheet takes its header
+//! from the raw copy (see [`super::table`]). Every edited sheet is checked
+//! before anything is written: cell errors and validation issues of all
+//! sheets are reported together and the package is left untouched.
+//!
+//! ```rust,ignore
+//! use fabula_nova_sdk::modules::wdb::{self, WdbPackFormat};
+//!
+//! wdb::unpack_wdbpack("wdbpack.bin", "wdbpack", GameCode::FF13_3, WdbPackFormat::Csv)?;
+//! // ... edit wdbpack/item.csv ...
+//! let summary = wdb::repack_wdbpack("wdbpack", "wdbpack.bin")?;
+//! println!("Re-encoded {:?}", summary.reencoded);
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use super::api::{pack_wdb_bytes, parse_wdb_bytes, wdb_from_json_string, wdb_to_json_string};
+use super::structs::{
+    GameCode, WdbData, WdbPackEntry, WdbPackFormat, WdbPackManifest, WdbPackSummary, WdbTableFormat,
+};
+use super::table::{wdb_from_table_string, wdb_to_table_string};
+use super::validate::{describe_issue, WdbValidationError};
+use crate::modules::wpd::reader::WpdReader;
+use crate::modules::wpd::structs::WpdRecord;
+use crate::modules::wpd::writer::WpdWriter;
+
+/// Manifest file name inside an unpacked folder.
+pub const WDBPACK_MANIFEST: &str = "!!WDBPACK.json";
+
+/// Folder holding the original record bytes.
+const RAW_DIR: &str = "_raw";
+
+/// UTF-8 byte order mark, as [`super::table::export_wdb_table`] writes it.
+const UTF8_BOM: &str = "\u{FEFF}";
+
+impl WdbPackFormat {
+    /// Extension of the sheet files.
+    pub fn extension(self) -> &'static str {
+        match self {
+            WdbPackFormat::Json => "json",
+            WdbPackFormat::Csv => "csv",
+            WdbPackFormat::Tsv => "tsv",
+        }
+    }
+
+    /// The spreadsheet format, or `None` for JSON.
+    fn table(self) -> Option<WdbTableFormat> {
+        match self {
+            WdbPackFormat::Json => None,
+            WdbPackFormat::Csv => Some(WdbTableFormat::Csv),
+            WdbPackFormat::Tsv => Some(WdbTableFormat::Tsv),
+        }
+    }
+}
+
+/// Unpacks a wdbpack-style WPD into a folder of editable sheets.
+///
+/// # Arguments
+///
+/// * `wpd_path` - The package, e.g. `db/resident/wdbpack.bin`
+/// * `output_dir` - Folder to write; created if missing
+/// * `game_code` - Which game the sheets belong to
+/// * `format` - File format of the sheets
+///
+/// # Returns
+///
+/// The manifest written to `!!WDBPACK.json`. Records that fail to parse are
+/// logged and kept only as raw bytes (`file` is `None`).
+///
+/// # Errors
+///
+/// Returns an error if the package can't be read or a file can't be written.
+pub fn unpack_wdbpack<P: AsRef<Path>>(
+    wpd_path: P,
+    output_dir: P,
+    game_code: GameCode,
+    format: WdbPackFormat,
+) -> Result<WdbPackManifest> {
+    let output_dir = output_dir.as_ref();
+    fs::create_dir_all(output_dir.join(RAW_DIR))?;
+
+    let mut reader = WpdReader::new(BufReader::new(File::open(&wpd_path)?));
+    let header = reader.read_header()?;
+    let mut entries = Vec::new();
+    for record in reader.read_records(&header)? {
+        let raw = format!("{}/{}", RAW_DIR, file_name(&record.name, &record.extension));
+        fs::write(output_dir.join(&raw), &record.data)?;
+
+        let mut entry = WdbPackEntry {
+            name: record.name.clone(),
+            extension: record.extension.clone(),
+            file: None,
+            raw,
+            hash: None,
+        };
+        if record.extension.eq_ignore_ascii_case("wdb") {
+            match sheet_text(&record, game_code, format) {
+                Ok(text) => {
+                    let file = file_name(&record.name, format.extension());
+                    fs::write(output_dir.join(&file), &text)?;
+                    entry.hash = Some(text_hash(&text));
+                    entry.file = Some(file);
+                }
+                Err(e) => log::warn!("Keeping {}.wdb raw: {:#}", record.name, e),
+            }
+        }
+        entries.push(entry);
+    }
+
+    let manifest = WdbPackManifest { game_code, format, entries };
+    let file = File::create(output_dir.join(WDBPACK_MANIFEST))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &manifest)?;
+    Ok(manifest)
+}
+
+/// Rebuilds a wdbpack-style WPD from a folder written by [`unpack_wdbpack`].
+///
+/// # Arguments
+///
+/// * `input_dir` - The unpacked folder
+/// * `wpd_path` - The package to write
+///
+/// # Returns
+///
+/// Which sheets were encoded again and how many records were copied as is.
+///
+/// # Errors
+///
+/// Returns an error naming every edited sheet that fails to import or
+/// validate; nothing is written in that case. Also fails if the manifest
+/// or a file it lists is missing.
+pub fn repack_wdbpack<P: AsRef<Path>>(input_dir: P, wpd_path: P) -> Result<WdbPackSummary> {
+    let input_dir = input_dir.as_ref();
+    let manifest_path = input_dir.join(WDBPACK_MANIFEST);
+    let manifest: WdbPackManifest = serde_json::from_reader(BufReader::new(
+        File::open(&manifest_path).with_context(|| format!("Missing {:?}", manifest_path))?,
+    ))?;
+
+    let mut summary = WdbPackSummary::default();
+    let mut records = Vec::with_capacity(manifest.entries.len());
+    let mut failures = Vec::new();
+    for entry in &manifest.entries {
+        let raw_path = input_dir.join(&entry.raw);
+        let mut data = fs::read(&raw_path).with_context(|| format!("Missing {:?}", raw_path))?;
+        if let (Some(file), Some(hash)) = (&entry.file, &entry.hash) {
+            let path = input_dir.join(file);
+            let text = fs::read_to_string(&path).with_context(|| format!("Missing {:?}", path))?;
+            if text_hash(&text) != *hash {
+                match encode_sheet(&text, &data, &entry.name, manifest.game_code, manifest.format) {
+                    Ok(bytes) => {
+                        data = bytes;
+                        summary.reencoded.push(entry.name.clone());
+                    }
+                    Err(e) => failures.push(format!("{}: {:#}", file, e)),
+                }
+            } else {
+                summary.copied += 1;
+            }
+        } else {
+            summary.copied += 1;
+        }
+        records.push(WpdRecord { name: entry.name.clone(), extension: entry.extension.clone(), data });
+    }
+    if !failures.is_empty() {
+        bail!("{} sheet(s) could not be packed:\n{}", failures.len(), failures.join("\n"));
+    }
+
+    let file = File::create(wpd_path)?;
+    WpdWriter::new(BufWriter::new(file)).write(&records)?;
+    Ok(summary)
+}
+
+/// `name.ext`, or `name` when there is no extension.
+fn file_name(name: &str, extension: &str) -> String {
+    if extension.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+/// A WDB record as sheet file text.
+fn sheet_text(record: &WpdRecord, game_code: GameCode, format: WdbPackFormat) -> Result<String> {
+    let data = parse_wdb_bytes(&record.data, &record.name, game_code)?;
+    match format.table() {
+        None => wdb_to_json_string(&data),
+        Some(table) => Ok(format!("{}{}", UTF8_BOM, wdb_to_table_string(&data, table)?)),
+    }
+}
+
+/// Parses an edited sheet file and encodes it as WDB bytes.
+fn encode_sheet(text: &str, raw: &[u8], name: &str, game_code: GameCode, format: WdbPackFormat) -> Result<Vec<u8>> {
+    let data: WdbData = match format.table() {
+        None => wdb_from_json_string(text)?,
+        Some(table) => {
+            let base = parse_wdb_bytes(raw, name, game_code)?;
+            let import = wdb_from_table_string(text, table, &base)?;
+            if let Some(first) = import.errors.first() {
+                return Err(anyhow!(
+                    "{} cell error(s), first at row {} column {}: {}",
+                    import.errors.len(),
+                    first.row,
+                    first.column,
+                    first.message
+                ));
+            }
+            import.data
+        }
+    };
+    pack_wdb_bytes(&data, game_code).map_err(|e| match e.downcast::<WdbValidationError>() {
+        Ok(v) => {
+            let issues: Vec<String> = v.issues.iter().map(describe_issue).collect();
+            anyhow!("{}", issues.join("; "))
+        }
+        Err(e) => e,
+    })
+}
+
+/// FNV-1a hash of a sheet file, stable across runs and platforms.
+fn text_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::wdb::api::parse_wdb;
+    use crate::modules::wdb::structs::WdbValue;
+    use crate::modules::wdb::tests::build_wdb;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn sheet_bytes() -> Vec<u8> {
+        let words = |values: &[u32]| values.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        build_wdb(&[
+            ("!!string", b"\0Potion\0Ether\0".to_vec()),
+            ("!!strtypelist", words(&[3, 2])),
+            ("!!typelist", words(&[3, 2])),
+            ("!!version", words(&[1])),
+            ("!structitem", b"uPrice\0sName\0".to_vec()),
+            ("!structitemnum", words(&[2])),
+            ("it_001", words(&[50, 1])),
+            ("it_002", words(&[300, 8])),
+        ])
+    }
+
+    fn write_pack(path: &Path) -> Vec<WpdRecord> {
+        let records = vec![
+            WpdRecord { name: "db_goods".to_string(), extension: "wdb".to_string(), data: sheet_bytes() },
+            WpdRecord { name: "db_shop".to_string(), extension: "wdb".to_string(), data: sheet_bytes() },
+            WpdRecord { name: "readme".to_string(), extension: "txt".to_string(), data: b"keep me".to_vec() },
+        ];
+        let mut buffer = Cursor::new(Vec::new());
+        WpdWriter::new(&mut buffer).write(&records).unwrap();
+        fs::write(path, buffer.into_inner()).unwrap();
+        records
+    }
+
+    fn read_pack(path: &Path) -> Vec<WpdRecord> {
+        let mut reader = WpdReader::new(BufReader::new(File::open(path).unwrap()));
+        let header = reader.read_header().unwrap();
+        reader.read_records(&header).unwrap()
+    }
+
+    #[test]
+    fn test_unpack_repack_only_edited_sheets() {
+        for format in [WdbPackFormat::Json, WdbPackFormat::Csv] {
+            let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("target/test_wdbpack_{:?}", format));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let pack = dir.join("wdbpack.bin");
+            let original = write_pack(&pack);
+            let folder = dir.join("unpacked");
+
+            let manifest = unpack_wdbpack(&pack, &folder, GameCode::FF13_2, format).unwrap();
+            assert_eq!(manifest.entries.len(), 3);
+            assert_eq!(manifest.entries[0].file.as_deref(), Some(format!("db_goods.{}", format.extension()).as_str()));
+            assert!(manifest.entries[2].file.is_none());
+
+            // Untouched folder: everything is copied byte for byte
+            let rebuilt = dir.join("rebuilt.bin");
+            let summary = repack_wdbpack(&folder, &rebuilt).unwrap();
+            assert!(summary.reencoded.is_empty());
+            assert_eq!(summary.copied, 3);
+            assert_eq!(fs::read(&rebuilt).unwrap(), fs::read(&pack).unwrap());
+
+            // Edit one sheet
+            let path = folder.join(manifest.entries[0].file.as_ref().unwrap());
+            let text = fs::read_to_string(&path).unwrap().replace("Potion", "Elixir");
+            fs::write(&path, text).unwrap();
+            let summary = repack_wdbpack(&folder, &rebuilt).unwrap();
+            assert_eq!(summary.reencoded, ["db_goods"]);
+            let records = read_pack(&rebuilt);
+            assert_eq!(records[1].data, original[1].data);
+            assert_eq!(records[2].data, original[2].data);
+            let item = folder.join("db_goods_check.wdb");
+            fs::write(&item, &records[0].data).unwrap();
+            let data = parse_wdb(&item, GameCode::FF13_2).unwrap();
+            assert_eq!(data.records[0]["sName"], WdbValue::String("Elixir".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_repack_reports_bad_sheets() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/test_wdbpack_errors");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let pack = dir.join("wdbpack.bin");
+        write_pack(&pack);
+        let folder = dir.join("unpacked");
+        unpack_wdbpack(&pack, &folder, GameCode::FF13_2, WdbPackFormat::Csv).unwrap();
+
+        for name in ["db_goods.csv", "db_shop.csv"] {
+            let path = folder.join(name);
+            let text = fs::read_to_string(&path).unwrap().replace(",300,", ",oops,");
+            fs::write(&path, text).unwrap();
+        }
+        let rebuilt = dir.join("rebuilt.bin");
+        let err = repack_wdbpack(&folder, &rebuilt).unwrap_err().to_string();
+        assert!(err.contains("2 sheet(s)") && err.contains("db_goods.csv") && err.contains("db_shop.csv"), "{}", err);
+        assert!(!rebuilt.exists());
+    }
+
+    /// Every record of Lightning Returns' wdbpack.bin survives unpack -> repack
+    #[test]
+    fn test_retail_wdbpack_round_trip() {
+        let wpd_path = PathBuf::from("/Users/hramirez/Desktop/Development/ff13-lr_data/white_img2a/db/resident/wdbpack.bin");
+        if !wpd_path.exists() {
+            eprintln!("Test file not found: {:?}", wpd_path);
+            return;
+        }
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/test_wdbpack_retail");
+        let _ = fs::remove_dir_all(&dir);
+        let folder = dir.join("unpacked");
+        let manifest = unpack_wdbpack(&wpd_path, &folder, GameCode::FF13_3, WdbPackFormat::Json).unwrap();
+        assert!(manifest.entries.iter().any(|e| e.file.is_some()));
+        let rebuilt = dir.join("wdbpack.bin");
+        let summary = repack_wdbpack(&folder, &rebuilt).unwrap();
+        assert!(summary.reencoded.is_empty());
+        let before = read_pack(&wpd_path);
+        let after = read_pack(&rebuilt);
+        assert_eq!(before.len(), after.len());
+        for (a, b) in before.iter().zip(&after) {
+            assert_eq!((&a.name, &a.data), (&b.name, &b.data));
+        }
+    }
+}
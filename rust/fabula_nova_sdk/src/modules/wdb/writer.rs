@@ -19,53 +19,35 @@
 //! | FF13-1    | !!string, !!strtypelist, !!typelist, !!version       |
 //! | FF13-2/LR | + !!strArray, !!strArrayInfo, !structitem, etc.      |
 //!
+//! When the header carries the original layout (see [`super::sections`]),
+//! sections keep their original order, unknown sections are written back
+//! unchanged, and the string pool and string arrays start from the
+//! original ones, so unmodified files repack byte for byte.
+//!
 //! ## Bitpacking Algorithm
 //!
-//! For type 0 fields, values are packed into 32-bit words:
+//! For type 0 fields, values are packed into 32-bit words with
+//! [`BitWriter`], lowest bits first, then written big-endian:
+//!
+//! ```text
+//!   fields   u4Role=3   i8Bonus=-1   u20Pad=0
+//!   word     0000 0000 0000 0000 0000 1111 1111 0011
+//!            └──────── u20Pad ───────┘└ i8Bonus┘└u4┘
+//! ```
 //!
-//! 1. Convert each value to binary with appropriate width
-//! 2. Reverse bit order for each value
-//! 3. Concatenate all reversed values
-//! 4. Reverse the entire collected string
-//! 5. Parse as u32 and write big-endian
+//! Values are masked to their width. Values that don't fit are reported by
+//! [`validate_wdb`](super::validate::validate_wdb) and written as 0.
 
 use std::io::{Write, Seek, SeekFrom};
-use std::collections::BTreeMap;
 use byteorder::{BigEndian, WriteBytesExt};
 use anyhow::Result;
 use indexmap::IndexMap;
-use super::structs::{WdbValue, WdbData, GameCode};
-use super::bit_helpers::{derive_field_number, validate_int, validate_uint};
-
-/// Converts an unsigned integer to a fixed-width binary string.
-///
-/// Zero-pads on the left to reach the specified width.
-/// Equivalent to C# `UIntToBinaryFixed`.
-fn uint_to_binary_fixed(val: u32, width: usize) -> String {
-    format!("{:0>width$b}", val, width = width)
-}
+use super::structs::{WdbValue, WdbData, WdbHeader, GameCode};
+use super::bit_helpers::{derive_field_number, validate_int, validate_uint, BitWriter};
+use super::sections::{arrange_sections, had_section, str_array_seeds, StringPool};
 
-/// Converts a signed integer to a fixed-width binary string.
-///
-/// For negative numbers, uses two's complement representation and
-/// extracts the last `width` bits.
-/// Equivalent to C# `IntToBinaryFixed`.
-fn int_to_binary_fixed(val: i32, width: usize) -> String {
-    if val < 0 {
-        // For negative numbers, get 32-bit two's complement and take last `width` characters
-        let full_binary = format!("{:032b}", val as u32);
-        full_binary[32 - width..].to_string()
-    } else {
-        format!("{:0>width$b}", val, width = width)
-    }
-}
-
-/// Reverses a binary string.
-///
-/// Used in the bitpacking algorithm to handle the LSB-first encoding.
-fn reverse_binary(s: &str) -> String {
-    s.chars().rev().collect()
-}
+/// Section names and record ids are stored in 16-byte header slots.
+pub(crate) const MAX_NAME_LEN: usize = 16;
 
 /// Binary writer for WDB database files.
 ///
@@ -105,14 +87,19 @@ impl<W: Write + Seek> WdbWriter<W> {
     ///
     /// * `data` - The WDB data structure with header and records
     /// * `game_code` - Target game (affects file format)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `!structitem` is missing or an enum field holds a
+    /// name its enum doesn't define.
     pub fn write_file(&mut self, data: &WdbData, game_code: GameCode) -> Result<()> {
+        // Enum names from JSON or the editor become the numbers stored on disk
+        let data = super::symbolic::resolve_wdb_values(data, game_code)?;
         let records = &data.records;
         let header_map = &data.header;
 
         log::info!("Writing WDB file for {:?}", game_code);
 
-        let record_count = records.len() as u32;
-
         // Fields definition
         let fields: Vec<String> = if let Some(WdbValue::StringArray(f)) = header_map.get("!structitem") {
             f.clone()
@@ -122,8 +109,10 @@ impl<W: Write + Seek> WdbWriter<W> {
         let field_count = fields.len();
 
         // Get strtypelist values
+        // JSON import reads small non-negative arrays back as IntArray
         let strtypelist_values: Vec<u32> = match header_map.get("!!strtypelist") {
             Some(WdbValue::UIntArray(arr)) => arr.clone(),
+            Some(WdbValue::IntArray(arr)) => arr.iter().map(|&v| v as u32).collect(),
             _ => Vec::new(),
         };
 
@@ -156,10 +145,10 @@ impl<W: Write + Seek> WdbWriter<W> {
 
         if game_code == GameCode::FF13_1 {
             // FF13-1 (XIII) specific logic
-            self.write_xiii(header_map, &fields, field_count, &strtypelist_values, &records_data_dict, record_count)?;
+            self.write_xiii(header_map, &fields, field_count, &strtypelist_values, &records_data_dict)?;
         } else {
             // FF13-2 (XIII-2) and FF13-3 (Lightning Returns) specific logic
-            self.write_xiii2lr(header_map, &fields, field_count, &strtypelist_values, &records_data_dict, record_count, game_code)?;
+            self.write_xiii2lr(header_map, &fields, field_count, &strtypelist_values, &records_data_dict, game_code)?;
         }
 
         Ok(())
@@ -171,16 +160,12 @@ impl<W: Write + Seek> WdbWriter<W> {
     /// `!!string`, `!!strtypelist`, `!!typelist`, `!!version`
     fn write_xiii(
         &mut self,
-        header_map: &std::collections::HashMap<String, WdbValue>,
+        header_map: &WdbHeader,
         fields: &[String],
         field_count: usize,
         strtypelist_values: &[u32],
         records_data_dict: &IndexMap<String, Vec<WdbValue>>,
-        record_count: u32,
     ) -> Result<()> {
-        // XIII ALWAYS has 4 sections: !!string, !!strtypelist, !!typelist, !!version
-        let record_count_with_sections = record_count + 4;
-
         // Convert strtypelist values to big-endian bytes (4 bytes per value)
         let strtypelist_data: Vec<u8> = strtypelist_values.iter()
             .flat_map(|&v| v.to_be_bytes())
@@ -196,6 +181,7 @@ impl<W: Write + Seek> WdbWriter<W> {
         // Convert version to big-endian bytes
         let version_data: Vec<u8> = match header_map.get("!!version") {
             Some(WdbValue::UInt(v)) => v.to_be_bytes().to_vec(),
+            Some(WdbValue::Int(v)) => v.to_be_bytes().to_vec(),
             _ => vec![0, 0, 0, 0],
         };
 
@@ -203,307 +189,41 @@ impl<W: Write + Seek> WdbWriter<W> {
         // CONVERT RECORDS (C# RecordsConversion.ConvertRecordsWithFields)
         // ============================================================
 
-        let mut string_pos: u32 = 1;
-        // Use IndexMap to preserve insertion order like C# Dictionary
-        let mut processed_strings_dict: IndexMap<String, u32> = IndexMap::new();
-        processed_strings_dict.insert(String::new(), 0); // Empty string at offset 0
-
-        let out_per_record_size = strtypelist_values.len() * 4;
-        let mut out_per_record_data: IndexMap<String, Vec<u8>> = IndexMap::new();
+        // Starts from the original pool when the header carries one
+        let mut pool = StringPool::from_header(header_map)?;
 
-        for (record_name, record_values) in records_data_dict {
-            let mut current_out_data = vec![0u8; out_per_record_size];
-            let mut data_index: usize = 0;
-            let mut strtypelist_index: usize = 0;
-            let mut f: usize = 0;
-
-            while f < field_count {
-                if strtypelist_index >= strtypelist_values.len() {
-                    break;
-                }
-
-                match strtypelist_values[strtypelist_index] {
-                    // Type 0: bitpacked values
-                    0 => {
-                        let mut field_bits_to_process: i32 = 32;
-                        let mut collected_binary = String::new();
-
-                        while field_bits_to_process != 0 && f < field_count {
-                            let field_name = &fields[f];
-                            let field_type = field_name.chars().next().unwrap_or('u');
-                            let field_num = derive_field_number(field_name);
-                            let actual_field_num = if field_num == 0 { 32 } else { field_num };
-
-                            if actual_field_num as i32 > field_bits_to_process {
-                                // Field doesn't fit, decrement f and exit loop
-                                // (f will be incremented at end of outer loop, so net effect is f stays same)
-                                f = f.saturating_sub(1);
-                                field_bits_to_process = 0;
-                                continue;
-                            }
-
-                            match field_type {
-                                'i' => {
-                                    // Signed int
-                                    let i_val: i32 = match &record_values[f] {
-                                        WdbValue::Int(i) => *i,
-                                        WdbValue::UInt(u) => *u as i32,
-                                        WdbValue::CrystalRole(r) => r.to_u32() as i32,
-                                        WdbValue::CrystalNodeType(n) => n.to_u32() as i32,
-                                        _ => 0,
-                                    };
-                                    let i_val = validate_int(field_name, actual_field_num, i_val);
-
-                                    let mut binary_str = int_to_binary_fixed(i_val, actual_field_num);
-                                    if binary_str.len() > actual_field_num {
-                                        binary_str = binary_str[binary_str.len() - actual_field_num..].to_string();
-                                    }
-                                    binary_str = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&binary_str);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 {
-                                        f += 1;
-                                    }
-                                },
-                                'u' => {
-                                    // Unsigned int
-                                    let u_val: u32 = match &record_values[f] {
-                                        WdbValue::UInt(u) => *u,
-                                        WdbValue::Int(i) => *i as u32,
-                                        WdbValue::CrystalRole(r) => r.to_u32(),
-                                        WdbValue::CrystalNodeType(n) => n.to_u32(),
-                                        _ => 0,
-                                    };
-                                    let u_val = validate_uint(field_name, actual_field_num, u_val);
-
-                                    let binary_str = uint_to_binary_fixed(u_val, actual_field_num);
-                                    let reversed = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&reversed);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 {
-                                        f += 1;
-                                    }
-                                },
-                                'f' => {
-                                    // Float (bitpacked as int)
-                                    let f_val: i32 = match &record_values[f] {
-                                        WdbValue::Int(i) => *i,
-                                        WdbValue::UInt(u) => *u as i32,
-                                        WdbValue::Float(fv) => *fv as i32,
-                                        WdbValue::CrystalRole(r) => r.to_u32() as i32,
-                                        WdbValue::CrystalNodeType(n) => n.to_u32() as i32,
-                                        _ => 0,
-                                    };
-                                    let f_val = validate_int(field_name, actual_field_num, f_val);
-
-                                    let mut binary_str = int_to_binary_fixed(f_val, actual_field_num);
-                                    if binary_str.len() > actual_field_num {
-                                        binary_str = binary_str[binary_str.len() - actual_field_num..].to_string();
-                                    }
-                                    binary_str = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&binary_str);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 {
-                                        f += 1;
-                                    }
-                                },
-                                _ => {
-                                    // Unknown field type, skip
-                                    if field_bits_to_process != 0 {
-                                        f += 1;
-                                    }
-                                }
-                            }
-                        }
-
-                        // Reverse collected binary and convert to u32
-                        let final_binary = reverse_binary(&collected_binary);
-                        let collective_val = if final_binary.is_empty() {
-                            0u32
-                        } else {
-                            u32::from_str_radix(&final_binary, 2).unwrap_or(0)
-                        };
-
-                        // Write as big-endian
-                        current_out_data[data_index] = (collective_val >> 24) as u8;
-                        current_out_data[data_index + 1] = (collective_val >> 16) as u8;
-                        current_out_data[data_index + 2] = (collective_val >> 8) as u8;
-                        current_out_data[data_index + 3] = collective_val as u8;
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 1: float value
-                    1 => {
-                        let float_val: f32 = match &record_values[f] {
-                            WdbValue::Float(fv) => *fv,
-                            WdbValue::Int(i) => *i as f32,
-                            WdbValue::UInt(u) => *u as f32,
-                            _ => 0.0,
-                        };
-
-                        let float_bytes = float_val.to_be_bytes();
-                        current_out_data[data_index..data_index + 4].copy_from_slice(&float_bytes);
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 2: string section offset
-                    2 => {
-                        let string_val = match &record_values[f] {
-                            WdbValue::String(s) => s.clone(),
-                            _ => String::new(),
-                        };
-
-                        if !string_val.is_empty() {
-                            let mut added_string = false;
-                            if !processed_strings_dict.contains_key(&string_val) {
-                                processed_strings_dict.insert(string_val.clone(), string_pos);
-                                added_string = true;
-                            }
-
-                            let offset = *processed_strings_dict.get(&string_val).unwrap();
-
-                            // Write offset as big-endian
-                            current_out_data[data_index] = (offset >> 24) as u8;
-                            current_out_data[data_index + 1] = (offset >> 16) as u8;
-                            current_out_data[data_index + 2] = (offset >> 8) as u8;
-                            current_out_data[data_index + 3] = offset as u8;
-
-                            if added_string {
-                                // C# uses UTF8.GetByteCount(stringVal + "\0")
-                                string_pos += string_val.len() as u32 + 1;
-                            }
-                        }
-                        // If string is empty, currentOutData stays 0 (points to offset 0 = empty string)
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 3: uint value
-                    3 => {
-                        let uint_val: u32 = match &record_values[f] {
-                            WdbValue::UInt(u) => *u,
-                            WdbValue::Int(i) => *i as u32,
-                            WdbValue::CrystalRole(r) => r.to_u32(),
-                            WdbValue::CrystalNodeType(n) => n.to_u32(),
-                            _ => 0,
-                        };
-
-                        // Write as big-endian
-                        current_out_data[data_index] = (uint_val >> 24) as u8;
-                        current_out_data[data_index + 1] = (uint_val >> 16) as u8;
-                        current_out_data[data_index + 2] = (uint_val >> 8) as u8;
-                        current_out_data[data_index + 3] = uint_val as u8;
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    _ => {
-                        strtypelist_index += 1;
-                    }
-                }
-
-                f += 1;
-            }
+        // XIII has no string arrays, so bitpacked `s` fields are skipped
+        let out_per_record_data: IndexMap<String, Vec<u8>> = records_data_dict
+            .iter()
+            .map(|(record_name, record_values)| {
+                let data = encode_record(fields, strtypelist_values, record_values, &mut pool, None);
+                (record_name.clone(), data)
+            })
+            .collect();
 
-            out_per_record_data.insert(record_name.clone(), current_out_data);
+        // XIII files whose fields were stored keep their !structitem,
+        // which points into the string pool by offset
+        let mut sections: Vec<(String, Vec<u8>)> = Vec::new();
+        if had_section(header_map, "!structitem") {
+            let offsets: Vec<u8> = fields.iter().flat_map(|f| pool.offset(f).to_be_bytes()).collect();
+            sections.push(("!structitem".to_string(), offsets));
+        }
+        if had_section(header_map, "!structitemnum") {
+            sections.push(("!structitemnum".to_string(), (field_count as u32).to_be_bytes().to_vec()));
         }
-
-        // Determine if we have a string section
-        let has_string_section = processed_strings_dict.len() > 1 ||
-            !processed_strings_dict.is_empty();
 
         // ============================================================
         // BUILD WDB FILE (C# WDBbuilder.BuildWDB)
         // ============================================================
 
-        // Write file header
-        self.writer.write_all(b"WPD\0")?;
-        self.writer.write_u32::<BigEndian>(record_count_with_sections)?;
-        self.writer.write_all(&[0u8; 8])?; // Padding
-
-        // Write section headers (names only, offset/size will be updated later)
-        // Section 1: !!string
-        self.write_section_name("!!string", 8)?;
-        // Section 2: !!strtypelist
-        self.write_section_name("!!strtypelist", 13)?;
-        // Section 3: !!typelist
-        self.write_section_name("!!typelist", 10)?;
-        // Section 4: !!version
-        self.write_section_name("!!version", 9)?;
-
-        // Write record headers
-        for record_name in out_per_record_data.keys() {
-            self.write_section_name(record_name, record_name.len())?;
-        }
-
-        // Now write data and update offsets
-        let mut offset_update_pos: u64 = 32; // After file header (16 bytes) + first 16 bytes of first section header
-
-        // Section 1: !!string
-        let sec_pos = self.writer.stream_position()? as u32;
-        let mut string_section_size: u32 = 0;
-
-        if has_string_section {
-            // Write strings in insertion order (IndexMap preserves this)
-            for (string_key, _) in &processed_strings_dict {
-                if string_key.is_empty() {
-                    self.writer.write_u8(0)?;
-                    string_section_size += 1;
-                } else {
-                    let string_bytes = string_key.as_bytes();
-                    self.writer.write_all(string_bytes)?;
-                    self.writer.write_u8(0)?;
-                    string_section_size += string_bytes.len() as u32 + 1;
-                }
-            }
-        } else {
-            self.writer.write_u8(0)?;
-            string_section_size = 1;
-        }
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, string_section_size)?;
-        offset_update_pos += 32;
-
-        // Section 2: !!strtypelist
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&strtypelist_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, strtypelist_data.len() as u32)?;
-        offset_update_pos += 32;
-
-        // Section 3: !!typelist
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&typelist_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, typelist_data.len() as u32)?;
-        offset_update_pos += 32;
-
-        // Section 4: !!version
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&version_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, version_data.len() as u32)?;
-        offset_update_pos += 32;
-
-        // Records
-        for (_, record_data) in &out_per_record_data {
-            let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(record_data)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, record_data.len() as u32)?;
-            offset_update_pos += 32;
-        }
-
-        Ok(())
+        // XIII has at least 4 sections: !!string, !!strtypelist, !!typelist, !!version
+        sections.splice(0..0, [
+            ("!!string".to_string(), pool.as_bytes().to_vec()),
+            ("!!strtypelist".to_string(), strtypelist_data),
+            ("!!typelist".to_string(), typelist_data),
+            ("!!version".to_string(), version_data),
+        ]);
+        self.write_sections(sections, header_map, &out_per_record_data)
     }
 
     /// Writes FF13-2/LR (XIII-2/Lightning Returns) format WDB file.
@@ -516,12 +236,11 @@ impl<W: Write + Seek> WdbWriter<W> {
     #[allow(clippy::too_many_arguments)]
     fn write_xiii2lr(
         &mut self,
-        header_map: &std::collections::HashMap<String, WdbValue>,
+        header_map: &WdbHeader,
         fields: &[String],
         field_count: usize,
         strtypelist_values: &[u32],
         records_data_dict: &IndexMap<String, Vec<WdbValue>>,
-        record_count: u32,
         _game_code: GameCode,
     ) -> Result<()> {
         // Determine which sections are present
@@ -535,8 +254,9 @@ impl<W: Write + Seek> WdbWriter<W> {
             f.starts_with('s') && derive_field_number(f) != 0
         });
 
-        // Check for string fields (type 2 in strtypelist)
-        let has_string_section = strtypelist_values.contains(&2);
+        // Check for string fields (type 2 in strtypelist); string arrays
+        // point into the same pool
+        let has_string_section = strtypelist_values.contains(&2) || has_str_array_section;
 
         // Check for !!strtypelistb vs !!strtypelist
         let parse_strtypelist_as_v1 = !header_map.contains_key("!!strtypelistb");
@@ -544,18 +264,6 @@ impl<W: Write + Seek> WdbWriter<W> {
         // Check for !!typelist
         let has_typelist_section = header_map.contains_key("!!typelist");
 
-        // Calculate section count
-        let mut section_count: u32 = 0;
-        if sheet_name.is_some() { section_count += 1; }
-        if has_str_array_section { section_count += 3; } // strArray, strArrayInfo, strArrayList
-        if has_string_section { section_count += 1; }
-        section_count += 1; // strtypelist/strtypelistb (always)
-        if has_typelist_section { section_count += 1; }
-        section_count += 1; // version (always)
-        section_count += 2; // structitem, structitemnum (always for XIII-2/LR)
-
-        let record_count_with_sections = record_count + section_count;
-
         // Prepare section data
 
         // strtypelist data
@@ -577,6 +285,7 @@ impl<W: Write + Seek> WdbWriter<W> {
         // version data
         let version_data: Vec<u8> = match header_map.get("!!version") {
             Some(WdbValue::UInt(v)) => v.to_be_bytes().to_vec(),
+            Some(WdbValue::Int(v)) => v.to_be_bytes().to_vec(),
             _ => vec![0, 0, 0, 0],
         };
 
@@ -596,18 +305,26 @@ impl<W: Write + Seek> WdbWriter<W> {
         // STRARRAY HANDLING (if present)
         // ============================================================
 
+        // JSON import reads these back as Int
         let offsets_per_value = match header_map.get("offsetsPerValue") {
             Some(WdbValue::UInt(u)) => *u as u8,
+            Some(WdbValue::Int(i)) => *i as u8,
             _ => 2,
         };
         let bits_per_offset = match header_map.get("bits_per_offset") {
             Some(WdbValue::UInt(u)) => *u as u8,
+            Some(WdbValue::Int(i)) => *i as u8,
             _ => 16,
         };
 
-        // Build strArrayDataDict (field -> list of unique strings)
-        let mut str_array_data_dict: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        // Build strArrayDataDict (field -> list of unique strings), in field
+        // order as the reader expects, starting from the original lists
+        let mut str_array_data_dict: IndexMap<String, Vec<String>> = IndexMap::new();
         if has_str_array_section {
+            let mut seeds = str_array_seeds(header_map, fields)?;
+            for field in fields.iter().filter(|f| f.starts_with('s') && derive_field_number(f) != 0) {
+                str_array_data_dict.insert(field.clone(), seeds.remove(field).unwrap_or_default());
+            }
             for (_, record_values) in records_data_dict {
                 for (f_idx, field) in fields.iter().enumerate() {
                     if field.starts_with('s') && derive_field_number(field) != 0 {
@@ -629,9 +346,8 @@ impl<W: Write + Seek> WdbWriter<W> {
         // CONVERT RECORDS
         // ============================================================
 
-        let mut string_pos: u32 = 1;
-        let mut processed_strings_dict: IndexMap<String, u32> = IndexMap::new();
-        processed_strings_dict.insert(String::new(), 0);
+        // Starts from the original pool when the header carries one
+        let mut pool = StringPool::from_header(header_map)?;
 
         // Build strArray sections if needed
         let mut str_array_data = Vec::new();
@@ -644,33 +360,9 @@ impl<W: Write + Seek> WdbWriter<W> {
             for (current_array_name, current_array_list) in &str_array_data_dict {
                 str_array_val_dict.insert(current_array_name.clone(), Vec::new());
 
-                let mut s = 0;
-                while s < current_array_list.len() {
-                    let mut current_val_binary_list: Vec<String> = Vec::new();
-
-                    for _ in 0..offsets_per_value {
-                        if s >= current_array_list.len() {
-                            break;
-                        }
-
-                        let current_string_item = &current_array_list[s];
-
-                        if !processed_strings_dict.contains_key(current_string_item) {
-                            processed_strings_dict.insert(current_string_item.clone(), string_pos);
-                            string_pos += current_string_item.len() as u32 + 1;
-                        }
-
-                        let string_item_pos = *processed_strings_dict.get(current_string_item).unwrap();
-                        let current_offset_val = uint_to_binary_fixed(string_item_pos, bits_per_offset as usize);
-                        current_val_binary_list.push(current_offset_val);
-
-                        s += 1;
-                    }
-
-                    current_val_binary_list.reverse();
-                    let current_val_binary = current_val_binary_list.join("");
-                    let packed_value = u32::from_str_radix(&current_val_binary, 2).unwrap_or(0);
-
+                for chunk in current_array_list.chunks(offsets_per_value.max(1) as usize) {
+                    let offsets: Vec<u32> = chunk.iter().map(|s| pool.offset(s)).collect();
+                    let packed_value = pack_offsets(&offsets, bits_per_offset as usize);
                     str_array_val_dict.get_mut(current_array_name).unwrap().push(packed_value);
                 }
             }
@@ -699,348 +391,73 @@ impl<W: Write + Seek> WdbWriter<W> {
         }
 
         // Now convert records
-        let out_per_record_size = strtypelist_values.len() * 4;
-        let mut out_per_record_data: IndexMap<String, Vec<u8>> = IndexMap::new();
-
-        for (record_name, record_values) in records_data_dict {
-            let mut current_out_data = vec![0u8; out_per_record_size];
-            let mut data_index: usize = 0;
-            let mut strtypelist_index: usize = 0;
-            let mut f: usize = 0;
-
-            while f < field_count {
-                if strtypelist_index >= strtypelist_values.len() {
-                    break;
-                }
-
-                match strtypelist_values[strtypelist_index] {
-                    // Type 0: bitpacked values
-                    0 => {
-                        let mut field_bits_to_process: i32 = 32;
-                        let mut collected_binary = String::new();
-
-                        while field_bits_to_process != 0 && f < field_count {
-                            let field_name = &fields[f];
-                            let field_type = field_name.chars().next().unwrap_or('u');
-                            let field_num = derive_field_number(field_name);
-                            let actual_field_num = if field_num == 0 { 32 } else { field_num };
-
-                            if actual_field_num as i32 > field_bits_to_process {
-                                f = f.saturating_sub(1);
-                                field_bits_to_process = 0;
-                                continue;
-                            }
-
-                            match field_type {
-                                'i' => {
-                                    let i_val: i32 = match &record_values[f] {
-                                        WdbValue::Int(i) => *i,
-                                        WdbValue::UInt(u) => *u as i32,
-                                        WdbValue::CrystalRole(r) => r.to_u32() as i32,
-                                        WdbValue::CrystalNodeType(n) => n.to_u32() as i32,
-                                        _ => 0,
-                                    };
-                                    let i_val = validate_int(field_name, actual_field_num, i_val);
-
-                                    let mut binary_str = int_to_binary_fixed(i_val, actual_field_num);
-                                    if binary_str.len() > actual_field_num {
-                                        binary_str = binary_str[binary_str.len() - actual_field_num..].to_string();
-                                    }
-                                    binary_str = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&binary_str);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 { f += 1; }
-                                },
-                                'u' => {
-                                    let u_val: u32 = match &record_values[f] {
-                                        WdbValue::UInt(u) => *u,
-                                        WdbValue::Int(i) => *i as u32,
-                                        WdbValue::CrystalRole(r) => r.to_u32(),
-                                        WdbValue::CrystalNodeType(n) => n.to_u32(),
-                                        _ => 0,
-                                    };
-                                    let u_val = validate_uint(field_name, actual_field_num, u_val);
-
-                                    let binary_str = uint_to_binary_fixed(u_val, actual_field_num);
-                                    let reversed = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&reversed);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 { f += 1; }
-                                },
-                                'f' => {
-                                    let f_val: i32 = match &record_values[f] {
-                                        WdbValue::Int(i) => *i,
-                                        WdbValue::UInt(u) => *u as i32,
-                                        WdbValue::Float(fv) => *fv as i32,
-                                        WdbValue::CrystalRole(r) => r.to_u32() as i32,
-                                        WdbValue::CrystalNodeType(n) => n.to_u32() as i32,
-                                        _ => 0,
-                                    };
-                                    let f_val = validate_int(field_name, actual_field_num, f_val);
-
-                                    let mut binary_str = int_to_binary_fixed(f_val, actual_field_num);
-                                    if binary_str.len() > actual_field_num {
-                                        binary_str = binary_str[binary_str.len() - actual_field_num..].to_string();
-                                    }
-                                    binary_str = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&binary_str);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 { f += 1; }
-                                },
-                                's' => {
-                                    // strArray item index
-                                    let string_item = match &record_values[f] {
-                                        WdbValue::String(s) => s.clone(),
-                                        _ => String::new(),
-                                    };
-                                    let s_val = str_array_data_dict.get(field_name)
-                                        .and_then(|list| list.iter().position(|s| s == &string_item))
-                                        .unwrap_or(0) as u32;
-                                    let s_val = validate_uint(field_name, actual_field_num, s_val);
-
-                                    let binary_str = uint_to_binary_fixed(s_val, actual_field_num);
-                                    let reversed = reverse_binary(&binary_str);
-                                    collected_binary.push_str(&reversed);
-
-                                    field_bits_to_process -= actual_field_num as i32;
-                                    if field_bits_to_process != 0 { f += 1; }
-                                },
-                                _ => {
-                                    if field_bits_to_process != 0 { f += 1; }
-                                }
-                            }
-                        }
-
-                        let final_binary = reverse_binary(&collected_binary);
-                        let collective_val = if final_binary.is_empty() {
-                            0u32
-                        } else {
-                            u32::from_str_radix(&final_binary, 2).unwrap_or(0)
-                        };
-
-                        current_out_data[data_index] = (collective_val >> 24) as u8;
-                        current_out_data[data_index + 1] = (collective_val >> 16) as u8;
-                        current_out_data[data_index + 2] = (collective_val >> 8) as u8;
-                        current_out_data[data_index + 3] = collective_val as u8;
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 1: float value
-                    1 => {
-                        let float_val: f32 = match &record_values[f] {
-                            WdbValue::Float(fv) => *fv,
-                            WdbValue::Int(i) => *i as f32,
-                            WdbValue::UInt(u) => *u as f32,
-                            _ => 0.0,
-                        };
-
-                        let float_bytes = float_val.to_be_bytes();
-                        current_out_data[data_index..data_index + 4].copy_from_slice(&float_bytes);
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 2: string section offset
-                    2 => {
-                        let string_val = match &record_values[f] {
-                            WdbValue::String(s) => s.clone(),
-                            _ => String::new(),
-                        };
-
-                        if !string_val.is_empty() {
-                            if !processed_strings_dict.contains_key(&string_val) {
-                                processed_strings_dict.insert(string_val.clone(), string_pos);
-                                string_pos += string_val.len() as u32 + 1;
-                            }
-
-                            let offset = *processed_strings_dict.get(&string_val).unwrap();
-
-                            current_out_data[data_index] = (offset >> 24) as u8;
-                            current_out_data[data_index + 1] = (offset >> 16) as u8;
-                            current_out_data[data_index + 2] = (offset >> 8) as u8;
-                            current_out_data[data_index + 3] = offset as u8;
-                        }
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    // Type 3: uint value
-                    3 => {
-                        let uint_val: u32 = match &record_values[f] {
-                            WdbValue::UInt(u) => *u,
-                            WdbValue::Int(i) => *i as u32,
-                            WdbValue::CrystalRole(r) => r.to_u32(),
-                            WdbValue::CrystalNodeType(n) => n.to_u32(),
-                            _ => 0,
-                        };
-
-                        current_out_data[data_index] = (uint_val >> 24) as u8;
-                        current_out_data[data_index + 1] = (uint_val >> 16) as u8;
-                        current_out_data[data_index + 2] = (uint_val >> 8) as u8;
-                        current_out_data[data_index + 3] = uint_val as u8;
-
-                        strtypelist_index += 1;
-                        data_index += 4;
-                    },
-
-                    _ => {
-                        strtypelist_index += 1;
-                    }
-                }
-
-                f += 1;
-            }
-
-            out_per_record_data.insert(record_name.clone(), current_out_data);
-        }
+        let out_per_record_data: IndexMap<String, Vec<u8>> = records_data_dict
+            .iter()
+            .map(|(record_name, record_values)| {
+                let data = encode_record(fields, strtypelist_values, record_values, &mut pool, Some(&str_array_data_dict));
+                (record_name.clone(), data)
+            })
+            .collect();
 
         // ============================================================
         // BUILD WDB FILE
         // ============================================================
 
-        // Write file header
-        self.writer.write_all(b"WPD\0")?;
-        self.writer.write_u32::<BigEndian>(record_count_with_sections)?;
-        self.writer.write_all(&[0u8; 8])?;
-
-        // Write section headers
-        if sheet_name.is_some() {
-            self.write_section_name("!!sheetname", 11)?;
+        let mut sections: Vec<(String, Vec<u8>)> = Vec::new();
+        if let Some(name) = sheet_name {
+            sections.push(("!!sheetname".to_string(), name.into_bytes()));
         }
         if has_str_array_section {
-            self.write_section_name("!!strArray", 10)?;
-            self.write_section_name("!!strArrayInfo", 14)?;
-            self.write_section_name("!!strArrayList", 14)?;
+            sections.push(("!!strArray".to_string(), str_array_data));
+            sections.push(("!!strArrayInfo".to_string(), str_array_info_data));
+            sections.push(("!!strArrayList".to_string(), str_array_list_data));
         }
         if has_string_section {
-            self.write_section_name("!!string", 8)?;
-        }
-        if parse_strtypelist_as_v1 {
-            self.write_section_name("!!strtypelist", 13)?;
-        } else {
-            self.write_section_name("!!strtypelistb", 14)?;
+            sections.push(("!!string".to_string(), pool.as_bytes().to_vec()));
         }
+        let strtypelist_name = if parse_strtypelist_as_v1 { "!!strtypelist" } else { "!!strtypelistb" };
+        sections.push((strtypelist_name.to_string(), strtypelist_data));
         if has_typelist_section {
-            self.write_section_name("!!typelist", 10)?;
-        }
-        self.write_section_name("!!version", 9)?;
-        self.write_section_name("!structitem", 11)?;
-        self.write_section_name("!structitemnum", 14)?;
-
-        // Record headers
-        for record_name in out_per_record_data.keys() {
-            self.write_section_name(record_name, record_name.len())?;
-        }
-
-        // Write data and update offsets
-        let mut offset_update_pos: u64 = 32;
-
-        // sheetname
-        if let Some(ref name) = sheet_name {
-            let sec_pos = self.writer.stream_position()? as u32;
-            let name_bytes = name.as_bytes();
-            self.writer.write_all(name_bytes)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, name_bytes.len() as u32)?;
-            offset_update_pos += 32;
+            sections.push(("!!typelist".to_string(), typelist_data));
         }
+        sections.push(("!!version".to_string(), version_data));
+        sections.push(("!structitem".to_string(), struct_item_data));
+        sections.push(("!structitemnum".to_string(), struct_item_num_data));
 
-        // strArray sections
-        if has_str_array_section {
-            // strArray
-            let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(&str_array_data)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, str_array_data.len() as u32)?;
-            offset_update_pos += 32;
-
-            // strArrayInfo
-            let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(&str_array_info_data)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, str_array_info_data.len() as u32)?;
-            offset_update_pos += 32;
-
-            // strArrayList
-            let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(&str_array_list_data)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, str_array_list_data.len() as u32)?;
-            offset_update_pos += 32;
-        }
-
-        // string
-        if has_string_section {
-            let sec_pos = self.writer.stream_position()? as u32;
-            let mut string_section_size: u32 = 0;
-
-            for (string_key, _) in &processed_strings_dict {
-                if string_key.is_empty() {
-                    self.writer.write_u8(0)?;
-                    string_section_size += 1;
-                } else {
-                    let string_bytes = string_key.as_bytes();
-                    self.writer.write_all(string_bytes)?;
-                    self.writer.write_u8(0)?;
-                    string_section_size += string_bytes.len() as u32 + 1;
-                }
-            }
+        self.write_sections(sections, header_map, &out_per_record_data)
+    }
 
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, string_section_size)?;
-            offset_update_pos += 32;
-        }
+    /// Writes the file header, section headers, section data and records.
+    ///
+    /// Sections follow the original file's order when the header records
+    /// one (see [`super::sections`]), and unknown sections kept from the
+    /// original are written back unchanged. Each section is padded to 4
+    /// bytes.
+    fn write_sections(
+        &mut self,
+        sections: Vec<(String, Vec<u8>)>,
+        header_map: &WdbHeader,
+        records: &IndexMap<String, Vec<u8>>,
+    ) -> Result<()> {
+        let sections = arrange_sections(sections, header_map)?;
 
-        // strtypelist
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&strtypelist_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, strtypelist_data.len() as u32)?;
-        offset_update_pos += 32;
+        // Write file header
+        self.writer.write_all(b"WPD\0")?;
+        self.writer.write_u32::<BigEndian>((sections.len() + records.len()) as u32)?;
+        self.writer.write_all(&[0u8; 8])?; // Padding
 
-        // typelist
-        if has_typelist_section {
-            let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(&typelist_data)?;
-            self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, typelist_data.len() as u32)?;
-            offset_update_pos += 32;
+        // Write section and record headers (offset/size are updated later)
+        for name in sections.iter().map(|(name, _)| name).chain(records.keys()) {
+            self.write_section_name(name)?;
         }
 
-        // version
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&version_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, version_data.len() as u32)?;
-        offset_update_pos += 32;
-
-        // structitem
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&struct_item_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, struct_item_data.len() as u32)?;
-        offset_update_pos += 32;
-
-        // structitemnum
-        let sec_pos = self.writer.stream_position()? as u32;
-        self.writer.write_all(&struct_item_num_data)?;
-        self.pad_bytes_after_section()?;
-        self.update_offsets(offset_update_pos, sec_pos, 4)?;
-        offset_update_pos += 32;
-
-        // records
-        for (_, record_data) in &out_per_record_data {
+        // Now write data and update offsets
+        let mut offset_update_pos: u64 = 32; // After file header (16 bytes) + first 16 bytes of first section header
+        for data in sections.iter().map(|(_, data)| data).chain(records.values()) {
             let sec_pos = self.writer.stream_position()? as u32;
-            self.writer.write_all(record_data)?;
+            self.writer.write_all(data)?;
             self.pad_bytes_after_section()?;
-            self.update_offsets(offset_update_pos, sec_pos, record_data.len() as u32)?;
+            self.update_offsets(offset_update_pos, sec_pos, data.len() as u32)?;
             offset_update_pos += 32;
         }
 
@@ -1051,13 +468,19 @@ impl<W: Write + Seek> WdbWriter<W> {
     ///
     /// Format: 16 bytes name (null-padded) + 16 bytes for offset/size/reserved.
     /// The offset and size are written later via `update_offsets`.
-    fn write_section_name(&mut self, name: &str, name_length: usize) -> Result<()> {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name doesn't fit in 16 bytes.
+    fn write_section_name(&mut self, name: &str) -> Result<()> {
         let name_bytes = name.as_bytes();
+        if name_bytes.len() > MAX_NAME_LEN {
+            anyhow::bail!("Name '{}' is {} bytes, longer than {}", name, name_bytes.len(), MAX_NAME_LEN);
+        }
         self.writer.write_all(name_bytes)?;
 
         // Pad to 16 bytes
-        let padding = 16 - name_length;
-        for _ in 0..padding {
+        for _ in name_bytes.len()..MAX_NAME_LEN {
             self.writer.write_u8(0)?;
         }
 
@@ -1099,3 +522,391 @@ impl<W: Write + Seek> WdbWriter<W> {
         Ok(())
     }
 }
+
+/// Encodes one record's values into its `!!strtypelist` slots.
+///
+/// # Arguments
+///
+/// * `fields` - Field names in `!structitem` order
+/// * `strtypelist_values` - Slot type codes
+/// * `record_values` - The record's values in field order
+/// * `pool` - String pool for type 2 slots
+/// * `str_arrays` - String array values per field; `None` for XIII, whose
+///   bitpacked `s` fields take no bits
+///
+/// # Returns
+///
+/// Four bytes per slot, big-endian. Slots left after the fields run out
+/// are zero.
+fn encode_record(
+    fields: &[String],
+    strtypelist_values: &[u32],
+    record_values: &[WdbValue],
+    pool: &mut StringPool,
+    str_arrays: Option<&IndexMap<String, Vec<String>>>,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(strtypelist_values.len() * 4);
+    let mut f = 0;
+
+    for &type_code in strtypelist_values {
+        if f >= fields.len() {
+            break;
+        }
+        match type_code {
+            // Type 0: bitpacked values
+            0 => {
+                let (word, next) = pack_bitpacked_word(fields, record_values, f, str_arrays);
+                out.extend_from_slice(&word.to_be_bytes());
+                f = next;
+            }
+            // Type 1: float value
+            1 => {
+                let float_val: f32 = match &record_values[f] {
+                    WdbValue::Float(fv) => *fv,
+                    WdbValue::Int(i) => *i as f32,
+                    WdbValue::UInt(u) => *u as f32,
+                    _ => 0.0,
+                };
+                out.extend_from_slice(&float_val.to_be_bytes());
+                f += 1;
+            }
+            // Type 2: string section offset (empty strings point at offset 0)
+            2 => {
+                let offset = match &record_values[f] {
+                    WdbValue::String(s) if !s.is_empty() => pool.offset(s),
+                    _ => 0,
+                };
+                out.extend_from_slice(&offset.to_be_bytes());
+                f += 1;
+            }
+            // Type 3: uint value
+            3 => {
+                out.extend_from_slice(&uint_value(&record_values[f]).to_be_bytes());
+                f += 1;
+            }
+            // Unknown slot types hold no data but still consume a field
+            _ => f += 1,
+        }
+    }
+
+    out.resize(strtypelist_values.len() * 4, 0);
+    out
+}
+
+/// Packs type 0 fields from index `f` on into one 32-bit word.
+///
+/// Fields are added lowest bits first until the next one doesn't fit.
+/// Fields without an `i`/`u`/`f` prefix (or `s` when `str_arrays` is
+/// given) take no bits and are skipped.
+///
+/// # Returns
+///
+/// The packed word and the index of the first field for the next slot.
+fn pack_bitpacked_word(
+    fields: &[String],
+    record_values: &[WdbValue],
+    mut f: usize,
+    str_arrays: Option<&IndexMap<String, Vec<String>>>,
+) -> (u32, usize) {
+    let mut word = BitWriter::new();
+
+    while f < fields.len() {
+        let field_name = &fields[f];
+        let bits = match derive_field_number(field_name) {
+            0 => 32,
+            n => n,
+        };
+        if word.bits_consumed + bits > 32 {
+            break;
+        }
+
+        let value = &record_values[f];
+        let raw = match (field_name.chars().next().unwrap_or('u'), str_arrays) {
+            ('i', _) => Some(validate_int(field_name, bits, int_value(value)) as u32),
+            ('u', _) => Some(validate_uint(field_name, bits, uint_value(value))),
+            // Float packed as int, as the game stores it
+            ('f', _) => {
+                let f_val = match value {
+                    WdbValue::Float(fv) => *fv as i32,
+                    other => int_value(other),
+                };
+                Some(validate_int(field_name, bits, f_val) as u32)
+            }
+            // strArray item index
+            ('s', Some(lists)) => {
+                let item = match value {
+                    WdbValue::String(s) => s.as_str(),
+                    _ => "",
+                };
+                let index = lists
+                    .get(field_name)
+                    .and_then(|list| list.iter().position(|s| s == item))
+                    .unwrap_or(0);
+                Some(validate_uint(field_name, bits, index as u32))
+            }
+            _ => None,
+        };
+        if let Some(raw) = raw {
+            word.write_bits(raw, bits);
+        }
+        f += 1;
+    }
+
+    (word.value, f)
+}
+
+/// Packs string offsets into one strArray value, first offset lowest.
+///
+/// Returns 0 if the offsets don't fit in 32 bits together.
+fn pack_offsets(offsets: &[u32], bits_per_offset: usize) -> u32 {
+    if offsets.len() * bits_per_offset > 32 {
+        return 0;
+    }
+    let mut word = BitWriter::new();
+    for &offset in offsets {
+        word.write_bits(offset, bits_per_offset);
+    }
+    word.value
+}
+
+/// Signed view of a numeric value; non-numbers are 0.
+fn int_value(value: &WdbValue) -> i32 {
+    match value {
+        WdbValue::Int(i) => *i,
+        WdbValue::UInt(u) => *u as i32,
+        WdbValue::CrystalRole(r) => r.to_u32() as i32,
+        WdbValue::CrystalNodeType(n) => n.to_u32() as i32,
+        _ => 0,
+    }
+}
+
+/// Unsigned view of a numeric value; non-numbers are 0.
+fn uint_value(value: &WdbValue) -> u32 {
+    match value {
+        WdbValue::UInt(u) => *u,
+        WdbValue::Int(i) => *i as u32,
+        WdbValue::CrystalRole(r) => r.to_u32(),
+        WdbValue::CrystalNodeType(n) => n.to_u32(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    // =========================================================================
+    // Reference: the original string-based packing (C# port), kept to prove
+    // the integer packing writes the same words
+    // =========================================================================
+
+    fn uint_to_binary_fixed(val: u32, width: usize) -> String {
+        format!("{:0>width$b}", val, width = width)
+    }
+
+    fn int_to_binary_fixed(val: i32, width: usize) -> String {
+        if val < 0 {
+            let full_binary = format!("{:032b}", val as u32);
+            full_binary[32 - width..].to_string()
+        } else {
+            format!("{:0>width$b}", val, width = width)
+        }
+    }
+
+    fn reverse_binary(s: &str) -> String {
+        s.chars().rev().collect()
+    }
+
+    /// One type 0 slot as the old writer packed it; returns the word and
+    /// the field index after the outer loop's increment.
+    fn legacy_pack_word(
+        fields: &[String],
+        record_values: &[WdbValue],
+        mut f: usize,
+        str_arrays: Option<&IndexMap<String, Vec<String>>>,
+    ) -> (u32, usize) {
+        let field_count = fields.len();
+        let mut field_bits_to_process: i32 = 32;
+        let mut collected_binary = String::new();
+
+        while field_bits_to_process != 0 && f < field_count {
+            let field_name = &fields[f];
+            let field_type = field_name.chars().next().unwrap_or('u');
+            let field_num = derive_field_number(field_name);
+            let actual_field_num = if field_num == 0 { 32 } else { field_num };
+
+            if actual_field_num as i32 > field_bits_to_process {
+                f = f.saturating_sub(1);
+                field_bits_to_process = 0;
+                continue;
+            }
+
+            let binary_str = match (field_type, str_arrays) {
+                ('i', _) | ('f', _) => {
+                    let val: i32 = match &record_values[f] {
+                        WdbValue::Float(fv) if field_type == 'f' => *fv as i32,
+                        other => int_value(other),
+                    };
+                    let val = validate_int(field_name, actual_field_num, val);
+                    let mut binary_str = int_to_binary_fixed(val, actual_field_num);
+                    if binary_str.len() > actual_field_num {
+                        binary_str = binary_str[binary_str.len() - actual_field_num..].to_string();
+                    }
+                    Some(reverse_binary(&binary_str))
+                }
+                ('u', _) => {
+                    let val = validate_uint(field_name, actual_field_num, uint_value(&record_values[f]));
+                    Some(reverse_binary(&uint_to_binary_fixed(val, actual_field_num)))
+                }
+                ('s', Some(lists)) => {
+                    let string_item = match &record_values[f] {
+                        WdbValue::String(s) => s.clone(),
+                        _ => String::new(),
+                    };
+                    let s_val = lists
+                        .get(field_name)
+                        .and_then(|list| list.iter().position(|s| s == &string_item))
+                        .unwrap_or(0) as u32;
+                    let s_val = validate_uint(field_name, actual_field_num, s_val);
+                    Some(reverse_binary(&uint_to_binary_fixed(s_val, actual_field_num)))
+                }
+                _ => None,
+            };
+            if let Some(binary_str) = binary_str {
+                collected_binary.push_str(&binary_str);
+                field_bits_to_process -= actual_field_num as i32;
+            }
+            if field_bits_to_process != 0 {
+                f += 1;
+            }
+        }
+
+        let final_binary = reverse_binary(&collected_binary);
+        let word = if final_binary.is_empty() { 0 } else { u32::from_str_radix(&final_binary, 2).unwrap_or(0) };
+        (word, f + 1)
+    }
+
+    fn legacy_pack_offsets(offsets: &[u32], bits_per_offset: usize) -> u32 {
+        let mut list: Vec<String> = offsets.iter().map(|&o| uint_to_binary_fixed(o, bits_per_offset)).collect();
+        list.reverse();
+        u32::from_str_radix(&list.join(""), 2).unwrap_or(0)
+    }
+
+    type PackWord = fn(&[String], &[WdbValue], usize, Option<&IndexMap<String, Vec<String>>>) -> (u32, usize);
+
+    /// Small deterministic generator so layouts are reproducible
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    /// A random layout with values, string arrays for `s` fields included.
+    fn random_layout(rng: &mut XorShift) -> (Vec<String>, Vec<WdbValue>, IndexMap<String, Vec<String>>) {
+        let mut fields = Vec::new();
+        let mut values = Vec::new();
+        let mut lists = IndexMap::new();
+        for i in 0..1 + rng.below(12) {
+            // Mostly narrow fields, sometimes full words
+            let bits = if rng.below(6) == 0 { 32 } else { 1 + rng.below(16) as usize };
+            let prefix = ['i', 'u', 'f', 's', 'x'][rng.below(5) as usize];
+            let name = format!("{}{}Field{}", prefix, bits, i);
+            // Occasionally one past the range, which both writers zero
+            let span = if bits >= 32 { u32::MAX as u64 } else { (1u64 << bits) + 1 };
+            let value = match prefix {
+                'i' => WdbValue::Int((rng.below(span) as i64 - (span as i64 / 2)) as i32),
+                'u' => WdbValue::UInt(rng.below(span) as u32),
+                'f' if rng.below(2) == 0 => WdbValue::Float(rng.below(span) as f32 - (span / 2) as f32),
+                'f' => WdbValue::Int(rng.below(span) as i32),
+                's' => {
+                    let list: Vec<String> = (0..1 + rng.below(6)).map(|n| format!("text_{}", n)).collect();
+                    let value = list[rng.below(list.len() as u64) as usize].clone();
+                    lists.insert(name.clone(), list);
+                    WdbValue::String(value)
+                }
+                _ => WdbValue::UInt(rng.below(span) as u32),
+            };
+            fields.push(name);
+            values.push(value);
+        }
+        (fields, values, lists)
+    }
+
+    #[test]
+    fn test_bitpacking_matches_string_packing() {
+        let mut rng = XorShift(0x5EED_1234_ABCD_0001);
+        for _ in 0..5000 {
+            let (fields, values, lists) = random_layout(&mut rng);
+            for str_arrays in [None, Some(&lists)] {
+                // Pack the whole layout word by word, as a record would be
+                let (mut f_old, mut f_new) = (0, 0);
+                while f_old < fields.len() {
+                    let (old_word, old_next) = legacy_pack_word(&fields, &values, f_old, str_arrays);
+                    let (new_word, new_next) = pack_bitpacked_word(&fields, &values, f_new, str_arrays);
+                    assert_eq!(old_word, new_word, "{:?} {:?} from field {}", fields, values, f_old);
+                    assert_eq!(old_next.min(fields.len()), new_next, "{:?}", fields);
+                    (f_old, f_new) = (old_next, new_next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_offset_packing_matches_string_packing() {
+        let mut rng = XorShift(0x0FF5_E75E_ED00_0002);
+        for _ in 0..5000 {
+            let bits_per_offset = [8, 10, 16, 32][rng.below(4) as usize];
+            let count = 1 + rng.below((32 / bits_per_offset) as u64) as usize;
+            let offsets: Vec<u32> = (0..count).map(|_| rng.below(1u64 << bits_per_offset) as u32).collect();
+            assert_eq!(legacy_pack_offsets(&offsets, bits_per_offset), pack_offsets(&offsets, bits_per_offset));
+        }
+    }
+
+    /// Compares old and new packing speed on an LR battle-table sized sheet.
+    ///
+    /// Run with `cargo test --release bench_bitpacking -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_bitpacking() {
+        let mut rng = XorShift(0xBE4C_0000_0000_0003);
+        let layouts: Vec<_> = (0..64).map(|_| random_layout(&mut rng)).collect();
+        let rounds = 2000;
+
+        let pack_all = |pack: PackWord| {
+            let start = Instant::now();
+            let mut checksum = 0u32;
+            for _ in 0..rounds {
+                for (fields, values, lists) in &layouts {
+                    let mut f = 0;
+                    while f < fields.len() {
+                        let (word, next) = pack(fields, values, f, Some(lists));
+                        checksum = checksum.wrapping_add(word);
+                        f = next;
+                    }
+                }
+            }
+            (start.elapsed(), checksum)
+        };
+
+        let (old_time, old_sum) = pack_all(legacy_pack_word);
+        let (new_time, new_sum) = pack_all(pack_bitpacked_word);
+        assert_eq!(old_sum, new_sum);
+        println!(
+            "{} records: string packing {:?}, integer packing {:?} ({:.1}x)",
+            rounds * layouts.len(),
+            old_time,
+            new_time,
+            old_time.as_secs_f64() / new_time.as_secs_f64()
+        );
+    }
+}
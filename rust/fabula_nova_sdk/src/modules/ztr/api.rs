@@ -11,6 +11,13 @@
 //! - [`parse_ztr`] - Load ZTR into memory as [`ZtrData`]
 //! - [`pack_ztr_from_struct`] - Save [`ZtrData`] to ZTR binary
 //!
+//! ## Encodings
+//!
+//! The plain functions read and write Shift-JIS, so a parse → pack round
+//! trip never changes a file's bytes. The `_with_encoding` variants take a
+//! character table (see [`super::charsets`]); reading with `None` detects
+//! it and reports the table used, which must then be passed to packing.
+//!
 //! ## Text File Format
 //!
 //! The text format uses ` |:| ` as delimiter between ID and text:
@@ -42,12 +49,13 @@ use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 use walkdir::WalkDir;
 
+use super::charsets::{detect_ztr_encoding, resolve_char_table, DEFAULT_ZTR_ENCODING};
 use super::key_dicts::GameCode;
 use super::reader::ZtrReader;
 use super::structs::{
     ZtrData, ZtrDirectoryResult, ZtrEntry, ZtrEntryWithSource, ZtrFileError, ZtrParseProgress,
 };
-use super::text_decoder::decode_ztr_line;
+use super::text_decoder::decode_with_table;
 use super::writer::ZtrWriter;
 
 /// Extracts a ZTR file to a human-readable text file.
@@ -73,20 +81,68 @@ pub fn extract_ztr_to_text<P: AsRef<Path>>(
     txt_path: P,
     game_code: GameCode,
 ) -> Result<()> {
+    extract_ztr_to_text_with_encoding(ztr_path, txt_path, game_code, Some(DEFAULT_ZTR_ENCODING))?;
+    Ok(())
+}
+
+/// Extracts a ZTR file to a text file with a given or detected encoding.
+///
+/// # Arguments
+/// * `encoding` - Character table name, or `None` to detect it
+///
+/// # Returns
+/// The name of the character table used.
+///
+/// # Errors
+/// Same as [`extract_ztr_to_text`], plus an unknown `encoding`.
+pub fn extract_ztr_to_text_with_encoding<P: AsRef<Path>>(
+    ztr_path: P,
+    txt_path: P,
+    game_code: GameCode,
+    encoding: Option<&str>,
+) -> Result<String> {
+    let ztr_path = ztr_path.as_ref();
     let file = File::open(ztr_path)?;
     let mut reader = ZtrReader::new(BufReader::new(file));
     let entries = reader.read()?;
 
     // Convert to decoded entries
-    let mut decoded_entries = Vec::new();
-    for (id, data) in entries {
-        let text = decode_ztr_line(&data, game_code, "Shift-JIS");
-        decoded_entries.push((id, text));
-    }
+    let (decoded_entries, encoding) = decode_entries(entries, game_code, encoding, file_name(ztr_path))?;
+    log::info!("Extracting {:?} as {}", ztr_path, encoding);
 
     write_ztr_text_file(&decoded_entries, txt_path)?;
 
-    Ok(())
+    Ok(encoding)
+}
+
+/// Detects the character table of a ZTR file without keeping its text.
+pub fn detect_ztr_file_encoding<P: AsRef<Path>>(ztr_path: P, game_code: GameCode) -> Result<String> {
+    let ztr_path = ztr_path.as_ref();
+    let mut reader = ZtrReader::new(BufReader::new(File::open(ztr_path)?));
+    let entries = reader.read()?;
+    Ok(detect_ztr_encoding(&entries, game_code, file_name(ztr_path)))
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|n| n.to_str())
+}
+
+/// Decodes raw entries with the named table, or the detected one.
+fn decode_entries(
+    entries: Vec<(String, Vec<u8>)>,
+    game_code: GameCode,
+    encoding: Option<&str>,
+    file_name: Option<&str>,
+) -> Result<(Vec<(String, String)>, String)> {
+    let table = match encoding {
+        Some(name) => resolve_char_table(name)?,
+        None => resolve_char_table(&detect_ztr_encoding(&entries, game_code, file_name))?,
+    };
+    let decoded = entries
+        .into_iter()
+        .map(|(id, data)| (id, decode_with_table(&data, game_code, &table).0))
+        .collect();
+    Ok((decoded, table.name().to_string()))
 }
 
 /// Helper to write entries to text file (for FFI dump).
@@ -107,8 +163,23 @@ pub fn pack_ztr_from_memory<P: AsRef<Path>>(
     ztr_path: P,
     game_code: GameCode,
 ) -> Result<()> {
+    pack_ztr_from_memory_with_encoding(entries, ztr_path, game_code, DEFAULT_ZTR_ENCODING)
+}
+
+/// Packs in-memory entries to ZTR with a character table, e.g. "Big5".
+///
+/// # Errors
+/// Returns an error for an unknown `encoding` (before creating the file)
+/// or if writing fails.
+pub fn pack_ztr_from_memory_with_encoding<P: AsRef<Path>>(
+    entries: &[(String, String)],
+    ztr_path: P,
+    game_code: GameCode,
+    encoding: &str,
+) -> Result<()> {
+    let table = resolve_char_table(encoding)?;
     let mut out_file = File::create(ztr_path)?;
-    let mut writer = ZtrWriter::new(&mut out_file, game_code);
+    let mut writer = ZtrWriter::new(&mut out_file, game_code).with_encoding(table.name());
     writer.write(entries)?;
     Ok(())
 }
@@ -117,13 +188,23 @@ pub fn pack_ztr_from_struct<P: AsRef<Path>>(
     data: &ZtrData,
     ztr_path: P,
     game_code: GameCode,
+) -> Result<()> {
+    pack_ztr_from_struct_with_encoding(data, ztr_path, game_code, DEFAULT_ZTR_ENCODING)
+}
+
+/// Saves [`ZtrData`] to ZTR with a character table, e.g. "Big5".
+pub fn pack_ztr_from_struct_with_encoding<P: AsRef<Path>>(
+    data: &ZtrData,
+    ztr_path: P,
+    game_code: GameCode,
+    encoding: &str,
 ) -> Result<()> {
     let entries: Vec<(String, String)> = data
         .entries
         .iter()
         .map(|e| (e.id.clone(), e.text.clone()))
         .collect();
-    pack_ztr_from_memory(&entries, ztr_path, game_code)
+    pack_ztr_from_memory_with_encoding(&entries, ztr_path, game_code, encoding)
 }
 
 /// High-level API to pack text file to ZTR.
@@ -131,6 +212,16 @@ pub fn pack_text_to_ztr<P: AsRef<Path>>(
     txt_path: P,
     ztr_path: P,
     game_code: GameCode,
+) -> Result<()> {
+    pack_text_to_ztr_with_encoding(txt_path, ztr_path, game_code, DEFAULT_ZTR_ENCODING)
+}
+
+/// Packs a text file to ZTR with a character table, e.g. "Big5".
+pub fn pack_text_to_ztr_with_encoding<P: AsRef<Path>>(
+    txt_path: P,
+    ztr_path: P,
+    game_code: GameCode,
+    encoding: &str,
 ) -> Result<()> {
     let file = File::open(txt_path)?;
     let reader = std::io::BufReader::new(file);
@@ -162,21 +253,37 @@ pub fn pack_text_to_ztr<P: AsRef<Path>>(
         entries.push((current_id, current_text));
     }
 
-    pack_ztr_from_memory(&entries, ztr_path, game_code)
+    pack_ztr_from_memory_with_encoding(&entries, ztr_path, game_code, encoding)
 }
 
 /// Parse ZTR file and return structured data (for Flutter).
+///
+/// Decodes as Shift-JIS, matching [`pack_ztr_from_struct`]; use
+/// [`parse_ztr_with_encoding`] to read Big5 or UHC text.
 pub fn parse_ztr<P: AsRef<Path>>(ztr_path: P, game_code: GameCode) -> Result<ZtrData> {
+    Ok(parse_ztr_with_encoding(ztr_path, game_code, Some(DEFAULT_ZTR_ENCODING))?.0)
+}
+
+/// Parses a ZTR file with a given character table, or detects it.
+///
+/// # Returns
+/// The parsed data and the name of the table used, which packing needs
+/// to write the text back unchanged.
+///
+/// # Errors
+/// Returns an error if the file can't be read or `encoding` is unknown.
+pub fn parse_ztr_with_encoding<P: AsRef<Path>>(
+    ztr_path: P,
+    game_code: GameCode,
+    encoding: Option<&str>,
+) -> Result<(ZtrData, String)> {
+    let ztr_path = ztr_path.as_ref();
     let file = File::open(ztr_path)?;
     let mut reader = ZtrReader::new(BufReader::new(file));
     let raw_entries = reader.read()?;
 
-    let mut entries = Vec::with_capacity(raw_entries.len());
-
-    for (id, data) in raw_entries {
-        let text = decode_ztr_line(&data, game_code, "Shift-JIS");
-        entries.push(ZtrEntry { id, text });
-    }
+    let (decoded, encoding) = decode_entries(raw_entries, game_code, encoding, file_name(ztr_path))?;
+    let entries = decoded.into_iter().map(|(id, text)| ZtrEntry { id, text }).collect();
 
     // Mappings: Currently Reader does not extract "LastUsedDict" explicitly in a way that matches C# "Mappings" output.
     // In C#, `LastUsedDict` is populated during `Finalize` in `DecoderBase`.
@@ -193,21 +300,21 @@ pub fn parse_ztr<P: AsRef<Path>>(ztr_path: P, game_code: GameCode) -> Result<Ztr
     // C# seems to accumulate them.
     // Since I don't track usage yet, I'll return empty. This should be sufficient for viewing text.
 
-    Ok(ZtrData {
-        entries,
-        mappings: Vec::new(),
-    })
+    Ok((
+        ZtrData {
+            entries,
+            mappings: Vec::new(),
+        },
+        encoding,
+    ))
 }
 
 pub fn parse_ztr_from_memory(data: &[u8], game_code: GameCode) -> Result<ZtrData> {
     let mut reader = ZtrReader::new(std::io::Cursor::new(data));
     let raw_entries = reader.read()?;
 
-    let mut entries = Vec::with_capacity(raw_entries.len());
-    for (id, data) in raw_entries {
-        let text = decode_ztr_line(&data, game_code, "Shift-JIS");
-        entries.push(ZtrEntry { id, text });
-    }
+    let (decoded, _) = decode_entries(raw_entries, game_code, Some(DEFAULT_ZTR_ENCODING), None)?;
+    let entries = decoded.into_iter().map(|(id, text)| ZtrEntry { id, text }).collect();
 
     Ok(ZtrData {
         entries,
@@ -0,0 +1,418 @@
+//! # ZTR Character Tables
+//!
+//! Every language release stores its text with the same control codes but
+//! its own code page. A [`ZtrCharTable`] describes one of them: the base
+//! code page, whether the European `0x85 XX` row is in use, and any custom
+//! glyphs a font hack added. The `encoding` argument of
+//! [`decode_ztr_line`](super::decode_ztr_line) and
+//! [`encode_ztr_line`](super::encode_ztr_line) names the table to use.
+//!
+//! ## Built-in Tables
+//!
+//! | Name        | Base      | Used By                                     |
+//! |-------------|-----------|---------------------------------------------|
+//! | `Shift-JIS` | Shift-JIS | `jp`, `us` and the European files (default) |
+//! | `Latin`     | Shift-JIS | European files, accents as plain letters    |
+//! | `UHC`       | UHC       | `kr` (Korean)                               |
+//! | `Big5`      | Big5      | `ch` (Traditional Chinese)                  |
+//! | `GBK`       | GBK       | Simplified Chinese fan translations         |
+//!
+//! `Shift-JIS` decodes the European row as tags (`{é}`); `Latin` decodes
+//! the same bytes as `é`, so translators can type accents directly.
+//!
+//! ## Decoding Order
+//!
+//! ```text
+//! single-byte keys → color/icon/button keys → two-byte glyphs
+//!   → European row (latin_keys) → special keys → one-byte glyphs
+//!   → base code page → {XX}
+//! ```
+//!
+//! ## Detection
+//!
+//! [`detect_ztr_encoding`] decodes a file with every table and keeps the
+//! one that leaves the fewest bytes as `{XX}`. Ties go to the table the
+//! file name suggests (`txtres_ch.ztr` → `Big5`), then to the one that
+//! reads the most characters from its common block (kana, frequent hanzi
+//! or KS X 1001 hangul), then to the earliest registered table. `Latin`
+//! is never picked by detection.
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::key_dicts::{GameCode, BASE_CHARA_KEYS, SINGLE_KEYS};
+use super::structs::{ZtrBaseEncoding, ZtrCharTable};
+use super::text_decoder::decode_with_table;
+
+/// Table used when none is given: what every release before this used.
+pub const DEFAULT_ZTR_ENCODING: &str = "Shift-JIS";
+
+/// The European table; only chosen explicitly.
+const LATIN_ENCODING: &str = "Latin";
+
+impl ZtrBaseEncoding {
+    fn encoding(self) -> &'static encoding_rs::Encoding {
+        match self {
+            ZtrBaseEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            ZtrBaseEncoding::Big5 => encoding_rs::BIG5,
+            ZtrBaseEncoding::Gbk => encoding_rs::GBK,
+            ZtrBaseEncoding::Uhc => encoding_rs::EUC_KR,
+        }
+    }
+
+    /// Whether `b` starts a two-byte character.
+    fn is_lead(self, b: u8) -> bool {
+        match self {
+            // C# Logic from KeysDecoderLJ.cs (ShiftJISCharaCheck): the
+            // 0x85/0x86 rows and 0xF0-0xF9 belong to the control codes.
+            ZtrBaseEncoding::ShiftJis => matches!(b, 0x81..=0x84 | 0x87..=0x9F | 0xE0..=0xEA | 0xFA..=0xFC),
+            _ => (0x81..=0xFE).contains(&b),
+        }
+    }
+
+    /// Whether a two-byte character lies in the code page's common block:
+    /// kana and level 1 kanji, the frequent hanzi, or KS X 1001 hangul.
+    /// Other code pages tend to read these bytes as rare characters.
+    fn is_common(self, b1: u8, b2: u8) -> bool {
+        match self {
+            ZtrBaseEncoding::ShiftJis => matches!(b1, 0x82..=0x83 | 0x88..=0x98),
+            ZtrBaseEncoding::Big5 => (0xA4..=0xC6).contains(&b1),
+            ZtrBaseEncoding::Gbk => (0xB0..=0xD7).contains(&b1) && b2 >= 0xA1,
+            ZtrBaseEncoding::Uhc => (0xB0..=0xC8).contains(&b1) && b2 >= 0xA1,
+        }
+    }
+}
+
+/// A [`ZtrCharTable`] ready for decoding and encoding.
+#[derive(Debug)]
+pub(crate) struct CharTable {
+    source: ZtrCharTable,
+    glyphs: HashMap<Vec<u8>, char>,
+    rev_glyphs: HashMap<char, Vec<u8>>,
+}
+
+impl CharTable {
+    fn compile(source: ZtrCharTable) -> Result<Self> {
+        if source.name.trim().is_empty() {
+            bail!("Character table has no name");
+        }
+        let mut glyphs = HashMap::new();
+        let mut rev_glyphs = HashMap::new();
+        for (hex, text) in &source.glyphs {
+            let bytes = parse_hex(hex).with_context(|| format!("Glyph {:?}", hex))?;
+            if SINGLE_KEYS.contains_key(&bytes[0]) {
+                bail!("Glyph {:?} starts with control byte {:02X}", hex, bytes[0]);
+            }
+            let mut chars = text.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                bail!("Glyph {:?} must be exactly one character, got {:?}", hex, text);
+            };
+            if let Some(other) = rev_glyphs.insert(c, bytes.clone()) {
+                bail!("Character {:?} is mapped twice ({:02X?} and {:02X?})", c, other, bytes);
+            }
+            glyphs.insert(bytes, c);
+        }
+        Ok(Self { source, glyphs, rev_glyphs })
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.source.name
+    }
+
+    pub(crate) fn latin_keys(&self) -> bool {
+        self.source.latin_keys
+    }
+
+    /// The glyph drawn by exactly these bytes.
+    pub(crate) fn glyph(&self, bytes: &[u8]) -> Option<char> {
+        self.glyphs.get(bytes).copied()
+    }
+
+    /// Whether a decoded pair is a common character (for detection).
+    pub(crate) fn is_common(&self, b1: u8, b2: u8) -> bool {
+        self.source.base.is_common(b1, b2)
+    }
+
+    /// A two-byte character of the base code page.
+    pub(crate) fn decode_pair(&self, b1: u8, b2: u8) -> Option<String> {
+        if !self.source.base.is_lead(b1) {
+            return None;
+        }
+        let pair = [b1, b2];
+        let (text, had_errors) = self.source.base.encoding().decode_without_bom_handling(&pair);
+        (!had_errors).then(|| text.into_owned())
+    }
+
+    /// Appends `c` as a glyph or in the base code page.
+    ///
+    /// Characters the code page lacks come out as `&#NNNN;`, as before.
+    pub(crate) fn encode_char(&self, c: char, out: &mut Vec<u8>) {
+        if let Some(bytes) = self.rev_glyphs.get(&c) {
+            out.extend_from_slice(bytes);
+            return;
+        }
+        let mut buf = [0u8; 4];
+        let (res, _, _) = self.source.base.encoding().encode(c.encode_utf8(&mut buf));
+        out.extend_from_slice(&res);
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    if !matches!(hex.len(), 2 | 4) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("expected one or two bytes in hex, e.g. \"A0\" or \"8640\"");
+    }
+    Ok((0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect())
+}
+
+/// Names compare without case or punctuation: `shift_jis` finds `Shift-JIS`.
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+fn builtin_tables() -> Vec<ZtrCharTable> {
+    let table = |name: &str, base, latin_keys| ZtrCharTable {
+        name: name.to_string(),
+        base,
+        latin_keys,
+        glyphs: IndexMap::new(),
+    };
+
+    // Latin: the European row as plain letters, except where Shift-JIS has
+    // the character itself (×, °, ...) so both spellings keep their bytes.
+    let mut latin = table(LATIN_ENCODING, ZtrBaseEncoding::ShiftJis, true);
+    let mut row: Vec<_> = BASE_CHARA_KEYS.iter().collect();
+    row.sort();
+    for (&(b1, b2), tag) in row {
+        let c = tag.trim_start_matches('{').trim_end_matches('}');
+        let (_, _, unmappable) = encoding_rs::SHIFT_JIS.encode(c);
+        if !c.is_ascii() && unmappable {
+            latin.glyphs.insert(format!("{:02X}{:02X}", b1, b2), c.to_string());
+        }
+    }
+
+    vec![
+        table(DEFAULT_ZTR_ENCODING, ZtrBaseEncoding::ShiftJis, true),
+        latin,
+        table("UHC", ZtrBaseEncoding::Uhc, false),
+        table("Big5", ZtrBaseEncoding::Big5, false),
+        table("GBK", ZtrBaseEncoding::Gbk, false),
+    ]
+}
+
+/// Registered tables in detection order.
+static CHAR_TABLES: Lazy<RwLock<Vec<Arc<CharTable>>>> = Lazy::new(|| {
+    let tables = builtin_tables().into_iter().map(|t| Arc::new(CharTable::compile(t).unwrap())).collect();
+    RwLock::new(tables)
+});
+
+fn find_table(name: &str) -> Option<Arc<CharTable>> {
+    let key = normalize(name);
+    CHAR_TABLES.read().unwrap().iter().find(|t| normalize(t.name()) == key).cloned()
+}
+
+/// Looks up a table by name.
+///
+/// # Errors
+///
+/// Returns an error naming the known tables if there is no such table.
+pub(crate) fn resolve_char_table(name: &str) -> Result<Arc<CharTable>> {
+    match find_table(name) {
+        Some(table) => Ok(table),
+        None => bail!("Unknown ZTR encoding {:?} (known: {})", name, ztr_encodings().join(", ")),
+    }
+}
+
+/// Looks up a table by name, falling back to Shift-JIS for unknown names.
+pub(crate) fn char_table(name: &str) -> Arc<CharTable> {
+    find_table(name).unwrap_or_else(|| {
+        log::warn!("Unknown ZTR encoding {:?}, using {}", name, DEFAULT_ZTR_ENCODING);
+        find_table(DEFAULT_ZTR_ENCODING).expect("built-in table")
+    })
+}
+
+/// Names of every registered table, in detection order.
+pub fn ztr_encodings() -> Vec<String> {
+    CHAR_TABLES.read().unwrap().iter().map(|t| t.name().to_string()).collect()
+}
+
+/// Returns a copy of a registered table, e.g. to start a custom one from.
+pub fn ztr_char_table(name: &str) -> Option<ZtrCharTable> {
+    find_table(name).map(|t| t.source.clone())
+}
+
+/// Adds a table, replacing any table with the same name.
+///
+/// New tables are tried last by [`detect_ztr_encoding`]; a replaced table
+/// keeps its place.
+///
+/// # Errors
+///
+/// Returns an error if the table has no name, a glyph key isn't one or
+/// two bytes of hex, a glyph starts with a single-byte control code, or
+/// a glyph isn't exactly one character or is used twice.
+pub fn register_ztr_char_table(table: ZtrCharTable) -> Result<()> {
+    let table = Arc::new(CharTable::compile(table)?);
+    let mut tables = CHAR_TABLES.write().unwrap();
+    let key = normalize(table.name());
+    match tables.iter().position(|t| normalize(t.name()) == key) {
+        Some(i) => tables[i] = table,
+        None => tables.push(table),
+    }
+    Ok(())
+}
+
+/// Loads a table from a JSON file and registers it.
+///
+/// # Returns
+///
+/// The table's name, for use as an `encoding`.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or parsed, or the table is
+/// rejected by [`register_ztr_char_table`].
+pub fn load_ztr_char_table<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Failed to open character table {:?}", path))?;
+    let table: ZtrCharTable = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse character table {:?}", path))?;
+    let name = table.name.clone();
+    register_ztr_char_table(table).with_context(|| format!("Invalid character table {:?}", path))?;
+    Ok(name)
+}
+
+/// The table a file name suggests, from its language suffix.
+fn language_hint(file_name: &str) -> Option<&'static str> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?.to_ascii_lowercase();
+    match stem.rsplit('_').next()? {
+        "ch" => Some("Big5"),
+        "kr" => Some("UHC"),
+        "cn" | "zh" => Some("GBK"),
+        _ => None,
+    }
+}
+
+/// Picks the registered table that decodes `lines` best.
+///
+/// # Arguments
+///
+/// * `lines` - Raw entries as returned by [`ZtrReader::read`](super::ZtrReader::read)
+/// * `game_code` - Game whose control codes to recognize
+/// * `file_name` - Optional file name, used as a tie-breaker (see the module docs)
+///
+/// # Returns
+///
+/// The table's name, [`DEFAULT_ZTR_ENCODING`] for plain ASCII.
+pub fn detect_ztr_encoding(lines: &[(String, Vec<u8>)], game_code: GameCode, file_name: Option<&str>) -> String {
+    let hint = file_name.and_then(language_hint).map(normalize);
+    let tables = CHAR_TABLES.read().unwrap().clone();
+
+    let best = tables
+        .iter()
+        .filter(|t| t.name() != LATIN_ENCODING)
+        .min_by_key(|table| {
+            let (mut unknown, mut common) = (0, 0);
+            for (_, data) in lines {
+                let stats = decode_with_table(data, game_code, table).1;
+                unknown += stats.hex_tags;
+                common += stats.common;
+            }
+            let hinted = hint.as_deref() == Some(normalize(table.name()).as_str());
+            (unknown, !hinted, Reverse(common))
+        });
+    let name = best.map_or(DEFAULT_ZTR_ENCODING, |t| t.name()).to_string();
+    log::debug!("Detected ZTR encoding {} for {:?}", name, file_name);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::ztr::text_decoder::decode_with_table;
+    use crate::modules::ztr::text_encoder::encode_with_table;
+    use crate::modules::ztr::{decode_ztr_line, encode_ztr_line};
+
+    fn lines(texts: &[&str], encoding: &str) -> Vec<(String, Vec<u8>)> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (format!("txt_{:03}", i), encode_ztr_line(t, GameCode::FF13_3, encoding)))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_every_encoding() {
+        let samples: &[(&str, &[&str])] = &[
+            ("Shift-JIS", &["{Color Gold}ライトニング{Color White}が仲間になった", "Press {Btn A} {é}"]),
+            ("Latin", &["{Color Gold}Élan{Color White} à la forêt", "Größe: 5 × 2 {Btn A}"]),
+            ("Big5", &["{Color Gold}雷光{Color White}加入了隊伍", "按下{Btn A}繼續"]),
+            ("UHC", &["{Color Gold}라이트닝{Color White}이 동료가 되었다", "{Btn A} 버튼을 누르세요"]),
+            ("GBK", &["{Color Gold}雷光{Color White}加入了队伍", "按下{Btn A}继续"]),
+        ];
+        for (encoding, texts) in samples {
+            for (_, data) in lines(texts, encoding) {
+                let text = decode_ztr_line(&data, GameCode::FF13_3, encoding);
+                assert!(texts.contains(&text.as_str()), "{}: {:?} from {:02X?}", encoding, text, data);
+                assert_eq!(encode_ztr_line(&text, GameCode::FF13_3, encoding), data, "{}", encoding);
+            }
+        }
+
+        // Latin and Shift-JIS write accents to the same European row
+        assert_eq!(encode_ztr_line("é", GameCode::FF13_1, "Latin"), vec![0x85, 0xC8]);
+        assert_eq!(decode_ztr_line(&[0x85, 0xC8], GameCode::FF13_1, "Shift-JIS"), "{é}");
+        assert_eq!(decode_ztr_line(&[0x85, 0xC8], GameCode::FF13_1, "latin"), "é");
+        // ...but CJK tables read those bytes as their own characters
+        assert_ne!(decode_ztr_line(&[0x85, 0xC8], GameCode::FF13_1, "UHC"), "{é}");
+    }
+
+    #[test]
+    fn test_detection() {
+        let gc = GameCode::FF13_3;
+        let jp = lines(&["ライトニングが仲間になった"], "Shift-JIS");
+        let ch = lines(&["雷光加入了隊伍", "按下{Btn A}繼續"], "Big5");
+        let kr = lines(&["라이트닝이 동료가 되었다", "버튼을 누르세요"], "UHC");
+        let us = lines(&["Press {Btn A} to continue {é}"], "Shift-JIS");
+        assert_eq!(detect_ztr_encoding(&jp, gc, Some("txtres_jp.ztr")), "Shift-JIS");
+        assert_eq!(detect_ztr_encoding(&ch, gc, None), "Big5");
+        assert_eq!(detect_ztr_encoding(&kr, gc, None), "UHC");
+        assert_eq!(detect_ztr_encoding(&us, gc, Some("txtres_fr.ztr")), "Shift-JIS");
+
+        // Big5 and GBK both read most hanzi; the file name breaks the tie
+        let cn = lines(&["雷光加入"], "GBK");
+        assert_eq!(detect_ztr_encoding(&cn, gc, Some("txtres_cn.ztr")), "GBK");
+    }
+
+    #[test]
+    fn test_custom_char_table() {
+        // Compiled locally: a registered table would join detection in other tests
+        let mut table = ztr_char_table("Shift-JIS").unwrap();
+        table.name = "test-cyrillic".to_string();
+        table.glyphs.insert("8640".to_string(), "Б".to_string());
+        table.glyphs.insert("A0".to_string(), "Ж".to_string());
+        let compiled = CharTable::compile(table.clone()).unwrap();
+        assert_eq!(normalize("Test_Cyrillic"), normalize(compiled.name()));
+
+        let data = encode_with_table("Жук {Color Gold}Б", GameCode::FF13_1, &compiled);
+        let mut expected = vec![0xA0];
+        expected.extend(encode_ztr_line("ук ", GameCode::FF13_1, "Shift-JIS"));
+        expected.extend([0xF9, 0x42, 0x86, 0x40]);
+        assert_eq!(data, expected);
+        assert_eq!(decode_with_table(&data, GameCode::FF13_1, &compiled).0, "Жук {Color Gold}Б");
+        assert!(!ztr_encodings().contains(&"test-cyrillic".to_string()));
+
+        let mut bad = table.clone();
+        bad.glyphs.insert("0140".to_string(), "Д".to_string());
+        assert!(CharTable::compile(bad).unwrap_err().to_string().contains("control byte"));
+        let mut bad = table.clone();
+        bad.glyphs.insert("86".to_string(), "Б".to_string());
+        assert!(CharTable::compile(bad).is_err());
+        assert!(resolve_char_table("no-such-table").unwrap_err().to_string().contains("Big5"));
+    }
+}
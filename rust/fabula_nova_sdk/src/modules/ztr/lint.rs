@@ -0,0 +1,325 @@
+//! # ZTR Linter
+//!
+//! Checks entry text for control-code mistakes before it is packed. The
+//! encoder never fails: a tag it can't find in any dictionary is written as
+//! literal text, so a typo like `{Colour Gold}` shows up in game as
+//! "{Colour Gold}" instead of gold text.
+//!
+//! ## Checks
+//!
+//! | Kind               | Severity | Example                                        |
+//! |--------------------|----------|------------------------------------------------|
+//! | `UnknownTag`       | Error    | `{Colour Gold}` (did you mean `{Color Gold}`?) |
+//! | `WrongGameTag`     | Error    | An FF13-2 icon in an FF13 file                 |
+//! | `UnclosedBrace`    | Error    | `{Color Gold text`                             |
+//! | `UnbalancedColor`  | Warning  | `{Color Gold}Gil` with no `{Color White}`      |
+//! | `UnbalancedItalic` | Warning  | An odd number of `{Italic}` toggles            |
+//! | `RoundTrip`        | Warning  | `é` comes back as `{é}`; `€` as `?`            |
+//!
+//! Spans are character offsets into the entry's text, so editors can
+//! underline the tag directly.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::api::parse_ztr_with_encoding;
+use super::key_dicts::{
+    GameCode, KeyDictionaries, REV_BASE_CHARA_KEYS, REV_EX_CHARA_KEYS, REV_SINGLE_KEYS,
+    REV_SPECIAL_KEYS, REV_UNK2_KEYS, REV_UNK_KEYS,
+};
+use super::structs::{ZtrData, ZtrEntry, ZtrLintDiagnostic, ZtrLintKind, ZtrLintSeverity};
+use super::text_decoder::decode_ztr_line;
+use super::text_encoder::encode_ztr_line;
+
+/// Tag that resets the text colour.
+const COLOR_RESET: &str = "{Color White}";
+
+/// Tag that toggles italics.
+const ITALIC: &str = "{Italic}";
+
+/// Largest edit distance for a "did you mean" suggestion.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+const ALL_GAMES: [GameCode; 3] = [GameCode::FF13_1, GameCode::FF13_2, GameCode::FF13_3];
+
+/// Whether the encoder turns `tag` into a control code or character.
+fn is_known_tag(tag: &str, dicts: &KeyDictionaries) -> bool {
+    let inner = &tag[1..tag.len() - 1];
+    REV_SINGLE_KEYS.contains_key(tag)
+        || dicts.rev_color_keys.contains_key(tag)
+        || dicts.rev_icon_keys.contains_key(tag)
+        || dicts.rev_btn_keys.contains_key(tag)
+        || REV_BASE_CHARA_KEYS.contains_key(tag)
+        || REV_SPECIAL_KEYS.contains_key(tag)
+        || REV_EX_CHARA_KEYS.contains_key(tag)
+        || REV_UNK_KEYS.contains_key(tag)
+        || REV_UNK2_KEYS.contains_key(tag)
+        || (inner.len() == 2 && inner.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Games other than `game_code` whose colour, icon or button keys have `tag`.
+fn games_with_tag(tag: &str, game_code: GameCode) -> Vec<GameCode> {
+    ALL_GAMES
+        .into_iter()
+        .filter(|&gc| gc != game_code)
+        .filter(|&gc| {
+            let dicts = KeyDictionaries::get(gc);
+            dicts.rev_color_keys.contains_key(tag)
+                || dicts.rev_icon_keys.contains_key(tag)
+                || dicts.rev_btn_keys.contains_key(tag)
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// The control tag of this game closest to an unknown tag, if close enough.
+fn suggest_tag(tag: &str, dicts: &KeyDictionaries) -> Option<&'static str> {
+    let lower = tag.to_lowercase();
+    REV_SINGLE_KEYS
+        .keys()
+        .chain(dicts.rev_color_keys.keys())
+        .chain(dicts.rev_icon_keys.keys())
+        .chain(dicts.rev_btn_keys.keys())
+        .chain(REV_SPECIAL_KEYS.keys())
+        .chain(REV_UNK_KEYS.keys())
+        .chain(REV_UNK2_KEYS.keys())
+        .map(|&known| (edit_distance(&lower, &known.to_lowercase()), known))
+        .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE)
+        // Ties broken by name so the suggestion doesn't depend on hash order
+        .min()
+        .map(|(_, known)| known)
+}
+
+/// Lints the text of one entry.
+///
+/// # Arguments
+///
+/// * `entry` - Entry to check
+/// * `game_code` - Game whose dictionaries the text is checked against
+/// * `encoding` - Character table the file will be packed with, e.g. "Shift-JIS"
+///
+/// # Returns
+///
+/// Diagnostics in text order, with the round-trip check last.
+pub fn lint_ztr_entry(entry: &ZtrEntry, game_code: GameCode, encoding: &str) -> Vec<ZtrLintDiagnostic> {
+    let dicts = KeyDictionaries::get(game_code);
+    let chars: Vec<char> = entry.text.chars().collect();
+    let mut out = Vec::new();
+    let mut report = |kind, severity, start: usize, end: usize, message: String| {
+        out.push(ZtrLintDiagnostic {
+            id: entry.id.clone(),
+            kind,
+            severity,
+            start: start as u32,
+            end: end as u32,
+            message,
+        });
+    };
+
+    // Last colour tag that wasn't a reset, and the open {Italic}
+    let mut color: Option<(usize, usize, String)> = None;
+    let mut italic: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '{' {
+            i += 1;
+            continue;
+        }
+        let close = chars[i + 1..].iter().position(|&c| c == '}').map(|p| i + 1 + p);
+        let reopen = chars[i + 1..].iter().position(|&c| c == '{').map(|p| i + 1 + p);
+        let end = match (close, reopen) {
+            (Some(close), Some(reopen)) if reopen < close => {
+                report(
+                    ZtrLintKind::UnclosedBrace,
+                    ZtrLintSeverity::Error,
+                    i,
+                    reopen,
+                    "'{' is not closed before the next tag".to_string(),
+                );
+                i = reopen;
+                continue;
+            }
+            (Some(close), _) => close + 1,
+            (None, _) => {
+                report(
+                    ZtrLintKind::UnclosedBrace,
+                    ZtrLintSeverity::Error,
+                    i,
+                    chars.len(),
+                    "'{' is never closed".to_string(),
+                );
+                break;
+            }
+        };
+        let tag: String = chars[i..end].iter().collect();
+
+        if is_known_tag(&tag, dicts) {
+            if tag == COLOR_RESET {
+                color = None;
+            } else if dicts.rev_color_keys.contains_key(tag.as_str()) {
+                color = Some((i, end, tag));
+            } else if tag == ITALIC {
+                italic = match italic {
+                    Some(_) => None,
+                    None => Some((i, end)),
+                };
+            }
+        } else {
+            let games = games_with_tag(&tag, game_code);
+            if !games.is_empty() {
+                let names: Vec<String> = games.iter().map(|gc| format!("{:?}", gc)).collect();
+                report(
+                    ZtrLintKind::WrongGameTag,
+                    ZtrLintSeverity::Error,
+                    i,
+                    end,
+                    format!("{} is not a {:?} tag (only {})", tag, game_code, names.join(", ")),
+                );
+            } else {
+                let message = match suggest_tag(&tag, dicts) {
+                    Some(known) => format!("Unknown tag {}; did you mean {}?", tag, known),
+                    None => format!("Unknown tag {} would be written as text", tag),
+                };
+                report(ZtrLintKind::UnknownTag, ZtrLintSeverity::Error, i, end, message);
+            }
+        }
+        i = end;
+    }
+
+    if let Some((start, end, tag)) = color {
+        report(
+            ZtrLintKind::UnbalancedColor,
+            ZtrLintSeverity::Warning,
+            start,
+            end,
+            format!("Line ends in {}; add {} after the coloured text", tag, COLOR_RESET),
+        );
+    }
+    if let Some((start, end)) = italic {
+        report(
+            ZtrLintKind::UnbalancedItalic,
+            ZtrLintSeverity::Warning,
+            start,
+            end,
+            format!("{} is never turned off", ITALIC),
+        );
+    }
+
+    let decoded = decode_ztr_line(&encode_ztr_line(&entry.text, game_code, encoding), game_code, encoding);
+    if decoded != entry.text {
+        let decoded: Vec<char> = decoded.chars().collect();
+        let prefix = chars.iter().zip(&decoded).take_while(|(a, b)| a == b).count();
+        let suffix = chars[prefix..]
+            .iter()
+            .rev()
+            .zip(decoded[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (end, decoded_end) = (chars.len() - suffix, decoded.len() - suffix);
+        let changed: String = decoded[prefix..decoded_end].iter().collect();
+        report(
+            ZtrLintKind::RoundTrip,
+            ZtrLintSeverity::Warning,
+            prefix,
+            end.max(prefix),
+            format!("Reads back as {:?} after packing with {}", changed, encoding),
+        );
+    }
+    out
+}
+
+/// Lints every entry of a ZTR file held in memory.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// for d in lint_ztr(&data, GameCode::FF13_1, "Shift-JIS") {
+///     println!("{} [{}..{}] {:?}: {}", d.id, d.start, d.end, d.kind, d.message);
+/// }
+/// ```
+pub fn lint_ztr(data: &ZtrData, game_code: GameCode, encoding: &str) -> Vec<ZtrLintDiagnostic> {
+    data.entries
+        .iter()
+        .flat_map(|entry| lint_ztr_entry(entry, game_code, encoding))
+        .collect()
+}
+
+/// Lints a ZTR file using the character table detected for it.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be parsed.
+pub fn lint_ztr_file<P: AsRef<Path>>(ztr_path: P, game_code: GameCode) -> Result<Vec<ZtrLintDiagnostic>> {
+    let (data, encoding) = parse_ztr_with_encoding(ztr_path, game_code, None)?;
+    Ok(lint_ztr(&data, game_code, &encoding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(text: &str, game_code: GameCode) -> Vec<ZtrLintDiagnostic> {
+        let entry = ZtrEntry { id: "txt_001".to_string(), text: text.to_string() };
+        lint_ztr_entry(&entry, game_code, "Shift-JIS")
+    }
+
+    #[test]
+    fn test_clean_text() {
+        let text = "Press {Btn A} for {Color Gold}{Italic}100{Italic} Gil{Color White}.{Text NewLine}{F9}{é}";
+        assert_eq!(lint(text, GameCode::FF13_1), Vec::new());
+    }
+
+    #[test]
+    fn test_tag_problems() {
+        let diags = lint("A {Colour Gold}b{Color Gold c{Btn A}", GameCode::FF13_1);
+        let found: Vec<_> = diags.iter().map(|d| (d.kind, d.start, d.end)).collect();
+        assert_eq!(
+            found,
+            vec![(ZtrLintKind::UnknownTag, 2, 15), (ZtrLintKind::UnclosedBrace, 16, 29)]
+        );
+        assert!(diags[0].message.contains("did you mean {Color Gold}?"), "{}", diags[0].message);
+
+        let diags = lint("{Color Gold}{Italic}text", GameCode::FF13_1);
+        let found: Vec<_> = diags.iter().map(|d| (d.kind, d.severity, d.start, d.end)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (ZtrLintKind::UnbalancedColor, ZtrLintSeverity::Warning, 0, 12),
+                (ZtrLintKind::UnbalancedItalic, ZtrLintSeverity::Warning, 12, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_game_tag() {
+        // A colour only FF13-2 and Lightning Returns have
+        let diags = lint("{Color Lavender}x{Color White}", GameCode::FF13_1);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].kind, ZtrLintKind::WrongGameTag);
+        assert!(diags[0].message.contains("FF13_2"), "{}", diags[0].message);
+        assert!(lint("{Color Lavender}x{Color White}", GameCode::FF13_2).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Plain é is written with its {é} tag; € has no Shift-JIS code
+        let diags = lint("Caf\u{e9} 5\u{20ac}", GameCode::FF13_1);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert_eq!(diags[0].kind, ZtrLintKind::RoundTrip);
+        assert_eq!((diags[0].start, diags[0].end), (3, 7));
+    }
+}
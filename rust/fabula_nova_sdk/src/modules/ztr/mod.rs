@@ -42,6 +42,10 @@
 //! - **Two-byte control codes**: Colors, icons, button prompts
 //! - **Standard Shift-JIS**: Japanese and ASCII characters
 //!
+//! The Chinese and Korean releases keep the control codes but store text
+//! in Big5 and UHC instead; [`charsets`] holds one character table per
+//! release and detects which one a file uses.
+//!
 //! When decoded, control codes are converted to human-readable tags:
 //! ```text
 //! Raw:     0xF9 0x40 "Hello" 0x00 0x00
@@ -57,6 +61,9 @@
 //! - [`text_encoder`] - Converts human-readable text back to binary
 //! - [`compression`] - Dictionary-based compression algorithm
 //! - [`key_dicts`] - Game-specific control code dictionaries
+//! - [`charsets`] - Character tables for each language release
+//! - [`translation`] - XLIFF and PO export/import for translators
+//! - [`lint`] - Control-code checks before packing
 //! - [`api`] - High-level public API functions
 //!
 //! ## Usage Example
@@ -81,21 +88,27 @@
 pub mod structs;
 pub mod reader;
 pub mod key_dicts;
+pub mod charsets;
 pub mod text_decoder;
 pub mod text_encoder;
 pub mod compression;
 pub mod writer;
 pub mod api;
+pub mod translation;
+pub mod lint;
 
 // Re-export all public items for convenient access
 pub use structs::*;
 pub use reader::*;
 pub use key_dicts::*;
+pub use charsets::*;
 pub use text_decoder::*;
 pub use text_encoder::*;
 pub use compression::*;
 pub use writer::*;
 pub use api::*;
+pub use translation::*;
+pub use lint::*;
 
 #[cfg(test)]
 mod tests {
@@ -254,4 +267,49 @@ mod tests {
         // They should be identical in content
         assert_eq!(s1, s2);
     }
+
+    /// Parsing and packing a Big5 or UHC file again gives the same bytes.
+    #[test]
+    fn test_cjk_parse_pack_is_byte_identical() {
+        use super::api::*;
+        use super::structs::{ZtrData, ZtrEntry};
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/test_ztr_cjk");
+        std::fs::create_dir_all(&dir).unwrap();
+        let gc = GameCode::FF13_3;
+        let samples = [
+            ("Big5", ["{Color Gold}雷光{Color White}加入了隊伍", "按下{Btn A}繼續"]),
+            ("UHC", ["{Color Gold}라이트닝{Color White}이 동료가 되었다", "{Btn A} 버튼을 누르세요"]),
+        ];
+        for (encoding, texts) in samples {
+            let data = ZtrData {
+                entries: texts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| ZtrEntry { id: format!("$txt_{:03}", i), text: t.to_string() })
+                    .collect(),
+                mappings: Vec::new(),
+            };
+            let original = dir.join(format!("{}.ztr", encoding));
+            pack_ztr_from_struct_with_encoding(&data, &original, gc, encoding).unwrap();
+            let bytes = std::fs::read(&original).unwrap();
+
+            // Detected table, handed back to packing
+            let (parsed, detected) = parse_ztr_with_encoding(&original, gc, None).unwrap();
+            assert_eq!(detected, encoding);
+            let texts_of = |d: &ZtrData| d.entries.iter().map(|e| e.text.clone()).collect::<Vec<_>>();
+            assert_eq!(texts_of(&parsed), texts);
+            let repacked = dir.join(format!("{}_detected.ztr", encoding));
+            pack_ztr_from_struct_with_encoding(&parsed, &repacked, gc, &detected).unwrap();
+            assert_eq!(std::fs::read(&repacked).unwrap(), bytes, "{} (detected)", encoding);
+
+            // The plain pair stays on Shift-JIS both ways
+            let legacy = dir.join(format!("{}_legacy.ztr", encoding));
+            pack_ztr_from_struct(&parse_ztr(&original, gc).unwrap(), &legacy, gc).unwrap();
+            assert_eq!(std::fs::read(&legacy).unwrap(), bytes, "{} (parse_ztr)", encoding);
+            let from_memory = parse_ztr_from_memory(&bytes, gc).unwrap();
+            pack_ztr_from_struct(&from_memory, &legacy, gc).unwrap();
+            assert_eq!(std::fs::read(&legacy).unwrap(), bytes, "{} (parse_ztr_from_memory)", encoding);
+        }
+    }
 }
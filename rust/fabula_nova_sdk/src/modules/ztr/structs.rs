@@ -13,11 +13,15 @@
 //!    - [`ZtrData`] - Complete parsed ZTR file
 //!    - [`ZtrEntry`] - Single text entry (ID + text)
 //!    - [`ZtrMapEntry`] - Key-value mapping for control codes
+//!    - [`ZtrCharTable`] - Text encoding of one language release
+//!    - [`ZtrTranslationOptions`], [`ZtrTranslationImport`] - CAT tool exchange
+//!    - [`ZtrLintDiagnostic`] - Control-code problem in an entry
 //!
 //! 3. **C FFI Structures** - For legacy C interop
 //!    - [`ZtrEntryC`], [`ZtrKeyMappingC`], [`ZtrResultDataC`]
 
 use binrw::binrw;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 // =============================================================================
@@ -196,6 +200,163 @@ pub struct ZtrData {
     pub mappings: Vec<ZtrMapEntry>,
 }
 
+// =============================================================================
+// Character Tables
+// =============================================================================
+
+/// Code page a [`ZtrCharTable`] decodes ordinary text with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ZtrBaseEncoding {
+    /// Japanese, English and the European releases (code page 932)
+    #[default]
+    #[serde(rename = "Shift-JIS")]
+    ShiftJis,
+    /// Traditional Chinese (code page 950)
+    Big5,
+    /// Simplified Chinese (code page 936)
+    #[serde(rename = "GBK")]
+    Gbk,
+    /// Korean (code page 949, a superset of EUC-KR)
+    #[serde(rename = "UHC")]
+    Uhc,
+}
+
+/// A text encoding for one language release.
+///
+/// Control codes (`{Color ...}`, `{Btn ...}`, ...) are shared by every
+/// table; the table decides how the remaining bytes become characters.
+///
+/// # JSON Format
+/// ```text
+/// {
+///   "name": "ru-fan",
+///   "base": "Shift-JIS",
+///   "latin_keys": true,
+///   "glyphs": { "8640": "Б", "A0": "Ж" }
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZtrCharTable {
+    /// Name passed as the `encoding` of decode/encode calls, e.g. "Big5"
+    pub name: String,
+
+    /// Code page for bytes no glyph or control code claims.
+    #[serde(default)]
+    pub base: ZtrBaseEncoding,
+
+    /// Whether `0x85 XX` is the European character row (`{é}`, `{ß}`, ...).
+    /// The CJK code pages use these bytes for their own characters.
+    #[serde(default)]
+    pub latin_keys: bool,
+
+    /// Custom glyphs: one or two bytes in hex → the character they draw.
+    /// Glyphs win over the base code page and the European row.
+    #[serde(default)]
+    pub glyphs: IndexMap<String, String>,
+}
+
+// =============================================================================
+// Translation Exchange
+// =============================================================================
+
+/// File format for translation exchange with CAT tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ZtrTranslationFormat {
+    /// XLIFF 1.2, control codes as `<ph>` elements
+    #[default]
+    Xliff12,
+    /// XLIFF 2.0, control codes as `<ph>` elements with `<originalData>`
+    Xliff20,
+    /// gettext PO, control codes left in the text
+    Po,
+}
+
+/// Options for exporting a ZTR pair for translation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZtrTranslationOptions {
+    /// Output format
+    pub format: ZtrTranslationFormat,
+
+    /// Language code of the source file, e.g. "en"
+    pub source_language: String,
+
+    /// Language code of the target file, e.g. "fr"
+    pub target_language: String,
+
+    /// Name of the source ZTR file, recorded as the file's origin
+    pub original: String,
+}
+
+/// A translated entry that was not imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZtrTranslationIssue {
+    /// Entry ID
+    pub id: String,
+
+    /// What is wrong, e.g. "missing {Btn A}"
+    pub message: String,
+}
+
+/// Result of importing a translation file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZtrTranslationImport {
+    /// The base file with every valid translation applied.
+    pub data: ZtrData,
+
+    /// Entries whose text was replaced or added.
+    pub translated: u32,
+
+    /// Entries with no target text, left as they were.
+    pub untranslated: u32,
+
+    /// Entries whose control codes don't match the source.
+    pub issues: Vec<ZtrTranslationIssue>,
+}
+
+// =============================================================================
+// Linting
+// =============================================================================
+
+/// Category of a problem found by the ZTR linter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZtrLintKind {
+    /// A `{...}` tag no dictionary knows; it would be written as literal text
+    UnknownTag,
+    /// A control tag that only exists in another game's dictionaries
+    WrongGameTag,
+    /// A `{` with no matching `}`
+    UnclosedBrace,
+    /// The line ends in a colour other than `{Color White}`
+    UnbalancedColor,
+    /// The line ends with `{Italic}` still on
+    UnbalancedItalic,
+    /// Encoding and decoding the text gives different text
+    RoundTrip,
+}
+
+/// How serious a lint diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ZtrLintSeverity {
+    /// The text may be displayed differently from what was intended
+    Warning,
+    /// The text will not be written as intended
+    Error,
+}
+
+/// One problem found in an entry's text.
+///
+/// `start` and `end` are character (not byte) offsets into the text,
+/// end exclusive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZtrLintDiagnostic {
+    pub id: String,
+    pub kind: ZtrLintKind,
+    pub severity: ZtrLintSeverity,
+    pub start: u32,
+    pub end: u32,
+    pub message: String,
+}
+
 // =============================================================================
 // Batch/Directory Loading Structures
 // =============================================================================
@@ -14,12 +14,14 @@
 //! | Special Keys  | 0xF4-0xF7 XX  | `{Entity 1}`, `{Text NewLine}` |
 //! | Character Keys| 0x85 XX       | Extended Latin characters    |
 //!
-//! ## Shift-JIS Handling
+//! ## Character Handling
 //!
-//! Non-control bytes are decoded as Shift-JIS (Japanese encoding).
-//! The decoder automatically detects Shift-JIS lead bytes and decodes
-//! two-byte sequences appropriately.
+//! Non-control bytes are decoded with the character table named by the
+//! `encoding` argument (see [`super::charsets`]): Shift-JIS by default,
+//! or Big5, UHC, GBK and custom glyph tables for other releases. Bytes
+//! the table can't read come out as `{XX}`.
 
+use super::charsets::{char_table, CharTable};
 use super::key_dicts::{
     GameCode, KeyDictionaries, BASE_CHARA_KEYS, EX_CHARA_KEYS, SINGLE_KEYS, SPECIAL_KEYS,
     UNK2_KEYS, UNK_KEYS,
@@ -33,7 +35,8 @@ use super::key_dicts::{
 /// # Arguments
 /// * `data` - Raw binary text data from ZTR file
 /// * `game_code` - Which FF13 game (affects color/icon dictionaries)
-/// * `_encoding` - Encoding hint (currently ignored, Shift-JIS assumed)
+/// * `encoding` - Character table name, e.g. "Shift-JIS" or "Big5"
+///   (unknown names fall back to Shift-JIS)
 ///
 /// # Returns
 /// A UTF-8 string with control codes as `{Tag}` placeholders.
@@ -50,15 +53,30 @@ use super::key_dicts::{
 /// # Algorithm
 /// 1. Check if current byte is a single-byte control code
 /// 2. Check if current + next byte form a two-byte control code
-/// 3. Check if bytes are valid Shift-JIS sequence
+/// 3. Check the character table's glyphs and code page
 /// 4. Fall back to hex representation `{XX}` for unknown bytes
 pub fn decode_ztr_line(
     data: &[u8], 
     game_code: GameCode, 
-    _encoding: &str // "Shift-JIS", etc. For now we assume Shift-JIS / UTF-8 hybrid as per C# logic
+    encoding: &str
 ) -> String {
+    decode_with_table(data, game_code, &char_table(encoding)).0
+}
+
+/// How well a table fit a line, for encoding detection.
+#[derive(Debug, Default)]
+pub(crate) struct DecodeStats {
+    /// Bytes that fell back to `{XX}`
+    pub hex_tags: usize,
+    /// Two-byte characters from the code page's common block
+    pub common: usize,
+}
+
+/// Decodes a line with a resolved table.
+pub(crate) fn decode_with_table(data: &[u8], game_code: GameCode, table: &CharTable) -> (String, DecodeStats) {
     let dicts = KeyDictionaries::get(game_code);
     let mut result = Vec::new();
+    let mut stats = DecodeStats::default();
     let mut i = 0;
     
     while i < data.len() {
@@ -98,7 +116,12 @@ pub fn decode_ztr_line(
                 i += 2;
                 continue;
             }
-            if let Some(key) = BASE_CHARA_KEYS.get(&pair) {
+            if let Some(c) = table.glyph(&data[i..i + 2]) {
+                result.extend_from_slice(c.to_string().as_bytes());
+                i += 2;
+                continue;
+            }
+            if let Some(key) = BASE_CHARA_KEYS.get(&pair).filter(|_| table.latin_keys()) {
                 result.extend_from_slice(key.as_bytes());
                 i += 2;
                 continue;
@@ -108,7 +131,7 @@ pub fn decode_ztr_line(
                 i += 2;
                 continue;
             }
-            if let Some(key) = EX_CHARA_KEYS.get(&pair) {
+            if let Some(key) = EX_CHARA_KEYS.get(&pair).filter(|_| table.latin_keys()) {
                 result.extend_from_slice(key.as_bytes());
                 i += 2;
                 continue;
@@ -125,24 +148,18 @@ pub fn decode_ztr_line(
             }
         }
         
+        if let Some(c) = table.glyph(&data[i..i + 1]) {
+            result.extend_from_slice(c.to_string().as_bytes());
+            i += 1;
+            continue;
+        }
+
         // Default Decoding
-        // In C#, it checks for ShiftJIS range.
-        // Rust's encoding_rs can handle ShiftJIS.
-        // But we need to handle mixed content.
-        
-        // Simple fallback: if valid ASCII, write it. If not, write as byte?
-        // Or strictly follow C# ShiftJISCharaCheck.
-        
-        if is_shift_jis_start(b1) && i + 1 < data.len() {
-             // Let encoding_rs handle the pair?
-             // Or just pass raw bytes for now?
-             // result.push(b1);
-             // result.push(data[i+1]);
-             
-             // Decoding Shift-JIS to UTF-8
-             let chunk = &data[i..i+2];
-             let (res, _, _) = encoding_rs::SHIFT_JIS.decode(chunk);
-             result.extend_from_slice(res.as_bytes());
+        if let Some((b2, text)) = data.get(i + 1).and_then(|&b2| Some((b2, table.decode_pair(b1, b2)?))) {
+             result.extend_from_slice(text.as_bytes());
+             if table.is_common(b1, b2) {
+                 stats.common += 1;
+             }
              i += 2;
         } else if b1 < 0x80 {
              result.push(b1);
@@ -151,42 +168,10 @@ pub fn decode_ztr_line(
              // Unknown or unmapped control code
              let hex = format!("{{{:02X}}}", b1);
              result.extend_from_slice(hex.as_bytes());
+             stats.hex_tags += 1;
              i += 1;
         }
     }
     
-    String::from_utf8_lossy(&result).into_owned()
-}
-
-fn is_shift_jis_start(b: u8) -> bool {
-    // C# Logic from KeysDecoderLJ.cs (ShiftJISCharaCheck)
-    // It only allows specific ranges.
-    // Range 1: 0x81 .. 0x84, 0x87, 0x88, 0x98, 0xEA, 0xFA..0xFC
-    // Range 2: 0x89..0x97
-    // Range 3: 0x99..0x9F
-    // Range 4: 0xE0..0xE9
-    
-    // Notably, it DOES NOT include 0xF0..0xF9 (except specific cases?)
-    // ColorKeys: 0xF9
-    // IconKeys: 0xF0, 0xF2
-    // BtnKeys: 0xF1
-    // SpecialKeys: 0xF4, 0xF6, 0xF7
-    // BaseCharaKeys: 0x85
-    // UnkKeys: 0xFF, 0x81, 0xFA
-    
-    // My previous check `(b >= 0xE0 && b <= 0xFC)` included F0-FC.
-    // I need to exclude the key ranges.
-    
-    match b {
-        0x81..=0x84 => true,
-        0x87 => true,
-        0x88 => true,
-        0x98 => true,
-        0xEA => true,
-        0xFA..=0xFC => true, // Wait, 0xFA is used for UnkKeys? But also SJIS? C# checks ranges of 2nd byte.
-        0x89..=0x97 => true,
-        0x99..=0x9F => true,
-        0xE0..=0xE9 => true,
-        _ => false,
-    }
+    (String::from_utf8_lossy(&result).into_owned(), stats)
 }
@@ -7,7 +7,8 @@
 //!
 //! - **Control Tags**: `{Color White}`, `{Btn A}`, `{Icon Gil}`, etc.
 //! - **Hex Tags**: `{XX}` for arbitrary byte values (e.g., `{F9}`)
-//! - **Literal Text**: Regular characters encoded as Shift-JIS
+//! - **Literal Text**: Regular characters encoded with the character table
+//!   named by `encoding` (Shift-JIS by default, see [`super::charsets`])
 //!
 //! ## Encoding Process
 //!
@@ -16,12 +17,13 @@
 //! 3. Look up tag in dictionaries (reverse mappings)
 //! 4. If not found, check for hex format `{XX}`
 //! 5. If still not found, treat as literal text
-//! 6. For regular characters, encode as Shift-JIS
+//! 6. For regular characters, use the table's glyphs, then its code page
 //!
 //! ## Note
 //! The encoder does NOT add the double-null terminator (0x00 0x00).
 //! That is handled by the ZtrWriter.
 
+use super::charsets::{char_table, CharTable};
 use super::key_dicts::{
     GameCode, KeyDictionaries, REV_BASE_CHARA_KEYS, REV_EX_CHARA_KEYS, REV_SINGLE_KEYS,
     REV_SPECIAL_KEYS, REV_UNK2_KEYS, REV_UNK_KEYS,
@@ -35,7 +37,8 @@ use super::key_dicts::{
 /// # Arguments
 /// * `text` - Human-readable text with `{Tag}` placeholders
 /// * `game_code` - Which FF13 game (affects control code mappings)
-/// * `_encoding` - Encoding hint (currently ignored, Shift-JIS assumed)
+/// * `encoding` - Character table name, e.g. "Shift-JIS" or "Big5"
+///   (unknown names fall back to Shift-JIS)
 ///
 /// # Returns
 /// A vector of bytes in ZTR binary format.
@@ -59,12 +62,17 @@ use super::key_dicts::{
 /// 7. Extended character keys
 /// 8. Unknown keys
 /// 9. Hex format `{XX}`
-/// 10. Literal text (as Shift-JIS)
+/// 10. Literal text (glyphs, then the table's code page)
 pub fn encode_ztr_line(
     text: &str, 
     game_code: GameCode, 
-    _encoding: &str
+    encoding: &str
 ) -> Vec<u8> {
+    encode_with_table(text, game_code, &char_table(encoding))
+}
+
+/// Encodes a line with a resolved table.
+pub(crate) fn encode_with_table(text: &str, game_code: GameCode, table: &CharTable) -> Vec<u8> {
     let dicts = KeyDictionaries::get(game_code);
     let mut result = Vec::new();
     
@@ -148,10 +156,7 @@ pub fn encode_ztr_line(
             }
         } else {
             // Normal char
-            // Use shift-jis encoder
-            let s = c.to_string();
-            let (res, _, _) = encoding_rs::SHIFT_JIS.encode(&s);
-            result.extend_from_slice(&res);
+            table.encode_char(c, &mut result);
         }
     }
     
@@ -0,0 +1,871 @@
+//! # ZTR Translation Exchange
+//!
+//! Exports a source-language ZTR, optionally paired with a target-language
+//! ZTR, to the formats CAT tools read, and imports the translated file back.
+//! Entries are paired by ID; entries with empty source text are skipped.
+//!
+//! ## Formats
+//!
+//! | Format     | Entry ID              | Control Codes                          |
+//! |------------|-----------------------|----------------------------------------|
+//! | XLIFF 1.2  | `trans-unit/@resname` | `<ph id="1">{Btn A}</ph>`              |
+//! | XLIFF 2.0  | `unit/@name`          | `<ph id="1" dataRef="d1"/>` + `<data>` |
+//! | gettext PO | `msgctxt`             | Left in the text, listed in a `#.` note |
+//!
+//! ## Placeholders
+//!
+//! Control codes (`{Color Gold}`, `{Btn A}`, `{Text NewLine}`, `{F9}`, ...)
+//! become inline placeholders so CAT tools protect them. European character
+//! tags (`{é}`) are exported as plain letters and turned back into tags on
+//! import; other braces are ordinary text.
+//!
+//! ```text
+//! ZTR:     Press {Btn A} to {Color Gold}continue
+//! XLIFF:   Press <ph id="1">{Btn A}</ph> to <ph id="2">{Color Gold}</ph>continue
+//! ```
+//!
+//! ## Import
+//!
+//! Translations are applied to a base file: the existing target-language
+//! ZTR, or the source ZTR when starting a new language. A translation is
+//! only applied if it has exactly the control codes of the source ZTR's
+//! entry with its ID (in any order); the source text inside the translation
+//! file is not trusted, since CAT tools let translators edit it. The others
+//! are reported as [`ZtrTranslationIssue`]s and [`import_ztr_translation`]
+//! refuses to pack the file while any remain.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
+use xml::reader::{ParserConfig, XmlEvent};
+
+use super::api::{pack_ztr_from_struct_with_encoding, parse_ztr_with_encoding};
+use super::charsets::ztr_char_table;
+use super::key_dicts::{
+    GameCode, KeyDictionaries, REV_SINGLE_KEYS, REV_SPECIAL_KEYS, REV_UNK2_KEYS, REV_UNK_KEYS,
+};
+use super::structs::{
+    ZtrData, ZtrEntry, ZtrTranslationFormat, ZtrTranslationImport, ZtrTranslationIssue,
+    ZtrTranslationOptions,
+};
+
+/// Issues listed in the error when an import is refused.
+const MAX_REPORTED_ISSUES: usize = 10;
+
+/// European letters that have a `{c}` tag (the `Latin` table's glyphs).
+static LATIN_CHARS: Lazy<HashSet<char>> = Lazy::new(|| {
+    ztr_char_table("Latin")
+        .map(|t| t.glyphs.values().filter_map(|g| g.chars().next()).collect())
+        .unwrap_or_default()
+});
+
+/// Part of an entry's text.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    /// A control code, e.g. `{Btn A}`
+    Code(String),
+}
+
+/// Whether `tag` (with braces) is a control code the encoder understands.
+fn is_control_tag(tag: &str, dicts: &KeyDictionaries) -> bool {
+    let inner = &tag[1..tag.len() - 1];
+    REV_SINGLE_KEYS.contains_key(tag)
+        || dicts.rev_color_keys.contains_key(tag)
+        || dicts.rev_icon_keys.contains_key(tag)
+        || dicts.rev_btn_keys.contains_key(tag)
+        || REV_SPECIAL_KEYS.contains_key(tag)
+        || REV_UNK_KEYS.contains_key(tag)
+        || REV_UNK2_KEYS.contains_key(tag)
+        || (inner.len() == 2 && inner.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Splits text into literal text and control codes.
+///
+/// Character tags become their letter, and raw control characters become
+/// `{XX}` codes (XML can't carry them).
+fn segments(text: &str, game_code: GameCode) -> Vec<Segment> {
+    let dicts = KeyDictionaries::get(game_code);
+    let mut out = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let tag = match c {
+            '{' => rest.find('}').map(|end| &rest[..=end]).filter(|t| !t[1..].contains('{')),
+            _ => None,
+        };
+        let code = match tag {
+            Some(tag) if is_control_tag(tag, dicts) => Some(tag.to_string()),
+            Some(tag) => {
+                let mut chars = tag[1..tag.len() - 1].chars();
+                match (chars.next(), chars.next()) {
+                    (Some(letter), None) if LATIN_CHARS.contains(&letter) => literal.push(letter),
+                    _ => literal.push_str(tag),
+                }
+                rest = &rest[tag.len()..];
+                continue;
+            }
+            None if c.is_control() && c != '\n' && c != '\t' => Some(format!("{{{:02X}}}", c as u32)),
+            None => None,
+        };
+        match code {
+            Some(code) => {
+                if !literal.is_empty() {
+                    out.push(Segment::Text(std::mem::take(&mut literal)));
+                }
+                rest = &rest[tag.map_or(c.len_utf8(), str::len)..];
+                out.push(Segment::Code(code));
+            }
+            None => {
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !literal.is_empty() {
+        out.push(Segment::Text(literal));
+    }
+    out
+}
+
+/// The control codes of a text, sorted (order may change in translation).
+fn control_codes(text: &str, game_code: GameCode) -> Vec<String> {
+    let mut codes: Vec<String> = segments(text, game_code)
+        .into_iter()
+        .filter_map(|s| match s {
+            Segment::Code(code) => Some(code),
+            Segment::Text(_) => None,
+        })
+        .collect();
+    codes.sort();
+    codes
+}
+
+/// Turns European letters back into the tags the text was exported from.
+fn retag_chars(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if LATIN_CHARS.contains(&c) {
+            out.push('{');
+            out.push(c);
+            out.push('}');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Compares a translation's control codes with its source's.
+fn check_codes(source: &str, target: &str, game_code: GameCode) -> Option<String> {
+    let mut expected = control_codes(source, game_code);
+    let mut unexpected = Vec::new();
+    for code in control_codes(target, game_code) {
+        match expected.iter().position(|c| *c == code) {
+            Some(i) => {
+                expected.remove(i);
+            }
+            None => unexpected.push(code),
+        }
+    }
+    let mut problems = Vec::new();
+    if !expected.is_empty() {
+        problems.push(format!("missing {}", expected.join(" ")));
+    }
+    if !unexpected.is_empty() {
+        problems.push(format!("unexpected {}", unexpected.join(" ")));
+    }
+    (!problems.is_empty()).then(|| problems.join("; "))
+}
+
+/// An entry to translate.
+struct Unit<'a> {
+    id: &'a str,
+    source: &'a str,
+    target: Option<&'a str>,
+}
+
+fn pair_units<'a>(source: &'a ZtrData, target: Option<&'a ZtrData>) -> Vec<Unit<'a>> {
+    let targets: HashMap<&str, &str> = target
+        .map(|t| t.entries.iter().map(|e| (e.id.as_str(), e.text.as_str())).collect())
+        .unwrap_or_default();
+    source
+        .entries
+        .iter()
+        .filter(|e| !e.text.is_empty())
+        .map(|e| Unit { id: &e.id, source: &e.text, target: targets.get(e.id.as_str()).copied() })
+        .collect()
+}
+
+/// Renders a source ZTR (and its translation so far) in an exchange format.
+///
+/// # Arguments
+///
+/// * `source` - Source-language entries
+/// * `target` - Existing target-language entries, paired by ID
+/// * `options` - Format, language codes and original file name
+/// * `game_code` - Game whose control codes to protect
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = ZtrTranslationOptions {
+///     format: ZtrTranslationFormat::Xliff12,
+///     source_language: "en".into(),
+///     target_language: "fr".into(),
+///     original: "txtres_us.ztr".into(),
+/// };
+/// let xliff = write_ztr_translation(&us, Some(&fr), &options, GameCode::FF13_1);
+/// ```
+pub fn write_ztr_translation(
+    source: &ZtrData,
+    target: Option<&ZtrData>,
+    options: &ZtrTranslationOptions,
+    game_code: GameCode,
+) -> String {
+    let units = pair_units(source, target);
+    match options.format {
+        ZtrTranslationFormat::Xliff12 => write_xliff12(&units, options, game_code),
+        ZtrTranslationFormat::Xliff20 => write_xliff20(&units, options, game_code),
+        ZtrTranslationFormat::Po => write_po(&units, options, game_code),
+    }
+}
+
+/// Inline XLIFF for a text. `ids` numbers the codes, shared between a
+/// unit's source and target so matching codes get matching ids.
+fn xliff_inline(text: &str, game_code: GameCode, ids: &mut PlaceholderIds, ph: impl Fn(usize, &str) -> String) -> String {
+    let mut out = String::new();
+    ids.start_text();
+    for segment in segments(text, game_code) {
+        match segment {
+            Segment::Text(t) => out.push_str(&escape_str_pcdata(&t)),
+            Segment::Code(code) => out.push_str(&ph(ids.id_for(&code), &code)),
+        }
+    }
+    out
+}
+
+/// Placeholder ids within one unit.
+#[derive(Default)]
+struct PlaceholderIds {
+    /// Codes by id, as first seen
+    codes: Vec<String>,
+    /// Ids already used by the current text
+    used: HashSet<usize>,
+}
+
+impl PlaceholderIds {
+    fn start_text(&mut self) {
+        self.used.clear();
+    }
+
+    /// The first id with this code not used yet in the current text.
+    fn id_for(&mut self, code: &str) -> usize {
+        let found = (1..=self.codes.len()).find(|id| self.codes[id - 1] == code && !self.used.contains(id));
+        let id = found.unwrap_or_else(|| {
+            self.codes.push(code.to_string());
+            self.codes.len()
+        });
+        self.used.insert(id);
+        id
+    }
+}
+
+fn write_xliff12(units: &[Unit], options: &ZtrTranslationOptions, game_code: GameCode) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n");
+    let _ = writeln!(
+        out,
+        "  <file original=\"{}\" source-language=\"{}\" target-language=\"{}\" datatype=\"plaintext\">",
+        escape_str_attribute(&options.original),
+        escape_str_attribute(&options.source_language),
+        escape_str_attribute(&options.target_language)
+    );
+    out.push_str("    <body>\n");
+    for (n, unit) in units.iter().enumerate() {
+        let mut ids = PlaceholderIds::default();
+        let ph = |id: usize, code: &str| format!("<ph id=\"{}\">{}</ph>", id, escape_str_pcdata(code));
+        let _ = writeln!(
+            out,
+            "      <trans-unit id=\"{}\" resname=\"{}\" xml:space=\"preserve\">",
+            n + 1,
+            escape_str_attribute(unit.id)
+        );
+        let _ = writeln!(out, "        <source>{}</source>", xliff_inline(unit.source, game_code, &mut ids, ph));
+        if let Some(target) = unit.target {
+            let _ = writeln!(out, "        <target>{}</target>", xliff_inline(target, game_code, &mut ids, ph));
+        }
+        out.push_str("      </trans-unit>\n");
+    }
+    out.push_str("    </body>\n  </file>\n</xliff>\n");
+    out
+}
+
+fn write_xliff20(units: &[Unit], options: &ZtrTranslationOptions, game_code: GameCode) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = write!(
+        out,
+        "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\"",
+        escape_str_attribute(&options.source_language)
+    );
+    if !options.target_language.is_empty() {
+        let _ = write!(out, " trgLang=\"{}\"", escape_str_attribute(&options.target_language));
+    }
+    out.push_str(">\n");
+    let _ = writeln!(out, "  <file id=\"f1\" original=\"{}\">", escape_str_attribute(&options.original));
+    for (n, unit) in units.iter().enumerate() {
+        let mut ids = PlaceholderIds::default();
+        let ph = |id: usize, _: &str| format!("<ph id=\"{}\" dataRef=\"d{}\"/>", id, id);
+        let source = xliff_inline(unit.source, game_code, &mut ids, ph);
+        let target = unit.target.map(|t| xliff_inline(t, game_code, &mut ids, ph));
+
+        let _ = writeln!(out, "    <unit id=\"u{}\" name=\"{}\">", n + 1, escape_str_attribute(unit.id));
+        if !ids.codes.is_empty() {
+            out.push_str("      <originalData>\n");
+            for (i, code) in ids.codes.iter().enumerate() {
+                let _ = writeln!(out, "        <data id=\"d{}\">{}</data>", i + 1, escape_str_pcdata(code));
+            }
+            out.push_str("      </originalData>\n");
+        }
+        out.push_str("      <segment>\n");
+        let _ = writeln!(out, "        <source xml:space=\"preserve\">{}</source>", source);
+        if let Some(target) = target {
+            let _ = writeln!(out, "        <target xml:space=\"preserve\">{}</target>", target);
+        }
+        out.push_str("      </segment>\n    </unit>\n");
+    }
+    out.push_str("  </file>\n</xliff>\n");
+    out
+}
+
+/// Quotes a PO string, one line per `\n` for multi-line text.
+fn po_string(keyword: &str, text: &str) -> String {
+    let escape = |s: &str| {
+        s.replace('\\', "\\\\").replace('"', "\\\"").replace('\t', "\\t").replace('\r', "\\r").replace('\n', "\\n")
+    };
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        return format!("{} \"{}\"\n", keyword, escape(text));
+    }
+    let mut out = format!("{} \"\"\n", keyword);
+    for line in lines {
+        let _ = writeln!(out, "\"{}\"", escape(line));
+    }
+    out
+}
+
+fn write_po(units: &[Unit], options: &ZtrTranslationOptions, game_code: GameCode) -> String {
+    let mut out = String::new();
+    out.push_str("msgid \"\"\nmsgstr \"\"\n");
+    out.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    let _ = writeln!(out, "\"Language: {}\\n\"", options.target_language);
+    let _ = writeln!(out, "\"X-Source-Language: {}\\n\"", options.source_language);
+    for unit in units {
+        // Character tags as letters, like the XLIFF formats
+        let flatten = |text: &str| {
+            segments(text, game_code)
+                .into_iter()
+                .map(|s| match s {
+                    Segment::Text(t) | Segment::Code(t) => t,
+                })
+                .collect::<String>()
+        };
+        out.push('\n');
+        let mut codes = control_codes(unit.source, game_code);
+        codes.dedup();
+        if !codes.is_empty() {
+            let _ = writeln!(out, "#. Keep control codes: {}", codes.join(" "));
+        }
+        if !options.original.is_empty() {
+            let _ = writeln!(out, "#: {}", options.original);
+        }
+        out.push_str(&po_string("msgctxt", unit.id));
+        out.push_str(&po_string("msgid", &flatten(unit.source)));
+        out.push_str(&po_string("msgstr", &unit.target.map(flatten).unwrap_or_default()));
+    }
+    out
+}
+
+/// A unit read back from a translation file, codes restored.
+///
+/// Its source text is skipped: imports check against the source ZTR.
+#[derive(Debug, Default)]
+struct ParsedUnit {
+    id: String,
+    target: Option<String>,
+}
+
+/// Which text of a unit is being read.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Source,
+    Target,
+}
+
+/// Reads the units of an XLIFF 1.2 or 2.0 file.
+fn parse_xliff(text: &str) -> Result<Vec<ParsedUnit>> {
+    let reader = ParserConfig::new()
+        .whitespace_to_characters(true)
+        .cdata_to_characters(true)
+        .create_reader(text.as_bytes());
+
+    let mut units = Vec::new();
+    let mut unit: Option<ParsedUnit> = None;
+    let mut stack: Vec<String> = Vec::new();
+    let mut side: Option<Side> = None;
+    // XLIFF 1.2: codes of the source's <ph> by id; 2.0: <data> by id
+    let mut source_codes: HashMap<String, String> = HashMap::new();
+    let mut original_data: HashMap<String, String> = HashMap::new();
+    // Open code element (1.2 <ph>/<bpt>/<ept>/<it>, 2.0 <data>) and its text
+    let mut code: Option<(String, String)> = None;
+
+    for event in reader {
+        match event.context("Invalid XLIFF")? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let local = name.local_name;
+                let attr = |key: &str| attributes.iter().find(|a| a.name.local_name == key).map(|a| a.value.clone());
+                let parent = stack.last().map(String::as_str);
+                match local.as_str() {
+                    "xliff" => {
+                        let version = attr("version").unwrap_or_default();
+                        if !version.starts_with("1.") && !version.starts_with("2.") {
+                            bail!("Unsupported XLIFF version {:?}", version);
+                        }
+                    }
+                    "trans-unit" | "unit" => {
+                        let id = attr("resname").or_else(|| attr("name")).or_else(|| attr("id")).unwrap_or_default();
+                        unit = Some(ParsedUnit { id, ..Default::default() });
+                        source_codes.clear();
+                        original_data.clear();
+                    }
+                    "source" | "target" if matches!(parent, Some("trans-unit" | "segment" | "ignorable")) => {
+                        side = Some(if local == "source" { Side::Source } else { Side::Target });
+                        if let (Some(Side::Target), Some(u)) = (side, unit.as_mut()) {
+                            u.target.get_or_insert_with(String::new);
+                        }
+                    }
+                    "data" if parent == Some("originalData") => {
+                        code = Some((attr("id").unwrap_or_default(), String::new()));
+                    }
+                    "ph" | "sc" | "ec" if side.is_some() && attr("dataRef").is_some() => {
+                        // XLIFF 2.0: the code lives in <originalData>
+                        let data_ref = attr("dataRef").unwrap_or_default();
+                        let id = attr("id").unwrap_or_default();
+                        let value = original_data.get(&data_ref).or_else(|| source_codes.get(&id)).cloned();
+                        let Some(value) = value else {
+                            bail!("Unit {:?}: placeholder {} refers to missing data {:?}", unit_id(&unit), id, data_ref);
+                        };
+                        push_text(&mut unit, side, &value);
+                    }
+                    "ph" | "bpt" | "ept" | "it" if side.is_some() => {
+                        code = Some((attr("id").unwrap_or_default(), String::new()));
+                    }
+                    _ => {}
+                }
+                stack.push(local);
+            }
+            XmlEvent::EndElement { .. } => {
+                let local = stack.pop().unwrap_or_default();
+                match local.as_str() {
+                    "trans-unit" | "unit" => units.extend(unit.take()),
+                    "source" | "target" if side.is_some() && matches!(stack.last().map(String::as_str), Some("trans-unit" | "segment" | "ignorable")) => {
+                        side = None;
+                    }
+                    "data" => {
+                        if let Some((id, value)) = code.take() {
+                            original_data.insert(id, value);
+                        }
+                    }
+                    "ph" | "bpt" | "ept" | "it" if code.is_some() => {
+                        let (id, mut value) = code.take().unwrap_or_default();
+                        if side == Some(Side::Source) {
+                            source_codes.insert(id, value.clone());
+                        } else if value.is_empty() {
+                            // Some tools empty the element; the source still has the code
+                            value = source_codes.get(&id).cloned().unwrap_or_default();
+                        }
+                        push_text(&mut unit, side, &value);
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::Characters(chars) => match code.as_mut() {
+                Some((_, value)) => value.push_str(&chars),
+                None => push_text(&mut unit, side, &chars),
+            },
+            _ => {}
+        }
+    }
+    Ok(units)
+}
+
+fn unit_id(unit: &Option<ParsedUnit>) -> &str {
+    unit.as_ref().map_or("", |u| u.id.as_str())
+}
+
+fn push_text(unit: &mut Option<ParsedUnit>, side: Option<Side>, text: &str) {
+    if let (Some(u), Some(Side::Target)) = (unit.as_mut(), side) {
+        u.target.get_or_insert_with(String::new).push_str(text);
+    }
+}
+
+/// Unquotes one PO string literal.
+fn po_unquote(literal: &str, line: usize) -> Result<String> {
+    let inner = literal
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .with_context(|| format!("Line {}: expected a quoted string", line))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(c @ ('"' | '\\')) => out.push(c),
+            other => bail!("Line {}: unsupported escape \\{}", line, other.map(String::from).unwrap_or_default()),
+        }
+    }
+    Ok(out)
+}
+
+/// Reads the units of a PO file. Entries need a `msgctxt` (the entry ID).
+fn parse_po(text: &str) -> Result<Vec<ParsedUnit>> {
+    #[derive(Default)]
+    struct Entry {
+        ctxt: Option<String>,
+        id: Option<String>,
+        str: Option<String>,
+        line: usize,
+    }
+    fn finish(entry: Entry, units: &mut Vec<ParsedUnit>) -> Result<()> {
+        match (entry.ctxt, entry.id) {
+            // The header
+            (None, Some(id)) if id.is_empty() => Ok(()),
+            (Some(ctxt), Some(_)) => {
+                units.push(ParsedUnit { id: ctxt, target: entry.str });
+                Ok(())
+            }
+            (None, Some(_)) => bail!("Line {}: entry has no msgctxt (the ZTR entry ID)", entry.line),
+            _ => Ok(()),
+        }
+    }
+
+    let mut units = Vec::new();
+    let mut entry = Entry::default();
+    let mut field: Option<fn(&mut Entry) -> &mut Option<String>> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            field = None;
+            continue;
+        }
+        let (keyword, literal) = match line.split_once(' ') {
+            Some((k, rest)) if !k.starts_with('"') => (k, rest.trim()),
+            _ => ("", line),
+        };
+        let select: Option<fn(&mut Entry) -> &mut Option<String>> = match keyword {
+            "msgctxt" => Some(|e| &mut e.ctxt),
+            "msgid" => Some(|e| &mut e.id),
+            "msgstr" | "msgstr[0]" => Some(|e| &mut e.str),
+            // Plural forms don't occur in ZTR files
+            "msgid_plural" => None,
+            k if k.starts_with("msgstr[") => None,
+            "" => {
+                let Some(field) = field else {
+                    bail!("Line {}: string continuation without a keyword", line_no);
+                };
+                let value = po_unquote(literal, line_no)?;
+                field(&mut entry).get_or_insert_with(String::new).push_str(&value);
+                continue;
+            }
+            other => bail!("Line {}: unknown keyword {:?}", line_no, other),
+        };
+        let Some(select) = select else {
+            field = None;
+            continue;
+        };
+        // A new msgctxt/msgid after a msgstr starts the next entry
+        if keyword != "msgstr" && keyword != "msgstr[0]" && entry.str.is_some() {
+            finish(std::mem::take(&mut entry), &mut units)?;
+        }
+        if entry.ctxt.is_none() && entry.id.is_none() {
+            entry.line = line_no;
+        }
+        *select(&mut entry) = Some(po_unquote(literal, line_no)?);
+        field = Some(select);
+    }
+    finish(entry, &mut units)?;
+    Ok(units)
+}
+
+/// Applies a translation file to a base ZTR.
+///
+/// The base is the existing target-language file, or `source` when starting
+/// a new language. Each target's control codes are checked against the
+/// `source` entry with the same ID. Units with no target text are counted as
+/// untranslated; units whose IDs the base lacks are appended.
+///
+/// # Errors
+///
+/// Returns an error if the file is neither XLIFF nor PO or is malformed.
+/// Code mismatches and IDs missing from `source` are reported in
+/// [`ZtrTranslationImport::issues`].
+pub fn read_ztr_translation(
+    text: &str,
+    source: &ZtrData,
+    base: &ZtrData,
+    game_code: GameCode,
+) -> Result<ZtrTranslationImport> {
+    let text = text.trim_start_matches('\u{feff}');
+    let units = if text.trim_start().starts_with('<') { parse_xliff(text)? } else { parse_po(text)? };
+
+    let sources: HashMap<&str, &str> = source.entries.iter().map(|e| (e.id.as_str(), e.text.as_str())).collect();
+    let mut data = base.clone();
+    let index: HashMap<String, usize> = data.entries.iter().enumerate().map(|(i, e)| (e.id.clone(), i)).collect();
+    let (mut translated, mut untranslated, mut issues) = (0, 0, Vec::new());
+    for unit in units {
+        let target = match unit.target {
+            Some(target) if !target.is_empty() => retag_chars(&target),
+            _ => {
+                untranslated += 1;
+                continue;
+            }
+        };
+        let Some(source_text) = sources.get(unit.id.as_str()) else {
+            issues.push(ZtrTranslationIssue { id: unit.id, message: "not in the source file".to_string() });
+            continue;
+        };
+        if let Some(message) = check_codes(source_text, &target, game_code) {
+            issues.push(ZtrTranslationIssue { id: unit.id, message });
+            continue;
+        }
+        match index.get(&unit.id) {
+            Some(&i) => data.entries[i].text = target,
+            None => data.entries.push(ZtrEntry { id: unit.id, text: target }),
+        }
+        translated += 1;
+    }
+    Ok(ZtrTranslationImport { data, translated, untranslated, issues })
+}
+
+/// Exports a ZTR file, paired with an optional translation, for a CAT tool.
+///
+/// # Returns
+///
+/// The number of units written.
+///
+/// # Errors
+///
+/// Returns an error if a ZTR file can't be parsed or the output written.
+pub fn export_ztr_translation<P: AsRef<Path>>(
+    source_ztr: P,
+    target_ztr: Option<P>,
+    out_path: P,
+    options: &ZtrTranslationOptions,
+    game_code: GameCode,
+) -> Result<usize> {
+    let (source, _) = parse_ztr_with_encoding(source_ztr, game_code, None)?;
+    let target = target_ztr.map(|p| parse_ztr_with_encoding(p, game_code, None)).transpose()?.map(|(d, _)| d);
+    fs::write(&out_path, write_ztr_translation(&source, target.as_ref(), options, game_code))?;
+    Ok(pair_units(&source, target.as_ref()).len())
+}
+
+/// Imports a translation file into a base ZTR and packs the result.
+///
+/// # Arguments
+///
+/// * `translation_path` - XLIFF or PO file from the CAT tool
+/// * `source_ztr` - Source-language ZTR the file was exported from
+/// * `base_ztr` - Target-language ZTR to update; `None` starts from the source
+/// * `out_ztr` - Where to write the packed file
+/// * `encoding` - Character table for the output; `None` keeps the base file's
+///
+/// # Errors
+///
+/// Returns an error, and writes nothing, if any translation has control
+/// codes that don't match its source entry or an ID the source lacks.
+pub fn import_ztr_translation<P: AsRef<Path>>(
+    translation_path: P,
+    source_ztr: P,
+    base_ztr: Option<P>,
+    out_ztr: P,
+    game_code: GameCode,
+    encoding: Option<&str>,
+) -> Result<ZtrTranslationImport> {
+    let translation_path = translation_path.as_ref();
+    let text = fs::read_to_string(translation_path)
+        .with_context(|| format!("Failed to read translation {:?}", translation_path))?;
+    let source = parse_ztr_with_encoding(source_ztr, game_code, None)?;
+    let (base, base_encoding) = match base_ztr {
+        Some(path) => parse_ztr_with_encoding(path, game_code, None)?,
+        None => source.clone(),
+    };
+    let import = read_ztr_translation(&text, &source.0, &base, game_code)
+        .with_context(|| format!("Failed to import {:?}", translation_path))?;
+
+    if !import.issues.is_empty() {
+        let listed: Vec<String> = import
+            .issues
+            .iter()
+            .take(MAX_REPORTED_ISSUES)
+            .map(|i| format!("{}: {}", i.id, i.message))
+            .collect();
+        bail!(
+            "{} translations don't match the source file:\n{}",
+            import.issues.len(),
+            listed.join("\n")
+        );
+    }
+    pack_ztr_from_struct_with_encoding(&import.data, out_ztr, game_code, encoding.unwrap_or(&base_encoding))?;
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::ztr::api::{pack_ztr_from_struct, parse_ztr};
+
+    fn ztr(entries: &[(&str, &str)]) -> ZtrData {
+        ZtrData {
+            entries: entries.iter().map(|(id, text)| ZtrEntry { id: id.to_string(), text: text.to_string() }).collect(),
+            mappings: Vec::new(),
+        }
+    }
+
+    fn options(format: ZtrTranslationFormat) -> ZtrTranslationOptions {
+        ZtrTranslationOptions {
+            format,
+            source_language: "en".to_string(),
+            target_language: "fr".to_string(),
+            original: "txtres_us.ztr".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_segments() {
+        let gc = GameCode::FF13_1;
+        assert_eq!(
+            segments("Press {Btn A} {Caf{é}} <&>\u{6}", gc),
+            vec![
+                Segment::Text("Press ".into()),
+                Segment::Code("{Btn A}".into()),
+                Segment::Text(" {Café} <&>".into()),
+                Segment::Code("{06}".into()),
+            ]
+        );
+        assert_eq!(control_codes("{Color Gold}a{Btn A}{F9}", gc), vec!["{Btn A}", "{Color Gold}", "{F9}"]);
+        assert_eq!(check_codes("{Btn A}x{Btn B}", "{Btn B}y{Btn A}", gc), None);
+        assert_eq!(
+            check_codes("{Btn A}{Btn A}", "{Btn A}{Btn B}", gc).unwrap(),
+            "missing {Btn A}; unexpected {Btn B}"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let gc = GameCode::FF13_1;
+        let us = ztr(&[
+            ("txt_001", "Press {Btn A} to {Color Gold}continue{Color White}"),
+            ("txt_002", "Fish & \"Chips\" <b>\nNew line"),
+            ("txt_003", ""),
+            ("txt_004", "Untranslated"),
+        ]);
+        let fr = ztr(&[("txt_001", "Appuyez sur {Btn A}"), ("txt_002", "Caf{é}")]);
+
+        for format in [ZtrTranslationFormat::Xliff12, ZtrTranslationFormat::Xliff20, ZtrTranslationFormat::Po] {
+            let text = write_ztr_translation(&us, Some(&fr), &options(format), gc);
+            assert!(!text.contains("txt_003"), "{:?}", format);
+            assert!(text.contains("Café"), "{:?}: {}", format, text);
+            if format != ZtrTranslationFormat::Po {
+                assert!(text.contains("<ph id=\"1\""), "{:?}", format);
+                assert!(text.contains("&lt;b>") || text.contains("&lt;b&gt;"), "{:?}", format);
+            }
+
+            // A translator moves the codes and fills in the rest
+            let text = text.replace("Appuyez sur", "Pour {Color Gold}continuer{Color White}, appuyez sur");
+            let text = match format {
+                ZtrTranslationFormat::Xliff12 => text.replace(
+                    "Pour {Color Gold}continuer{Color White}",
+                    "Pour <ph id=\"2\">{Color Gold}</ph>continuer<ph id=\"3\"></ph>",
+                ),
+                ZtrTranslationFormat::Xliff20 => text.replace(
+                    "Pour {Color Gold}continuer{Color White}",
+                    "Pour <ph id=\"2\" dataRef=\"d2\"/>continuer<ph id=\"3\" dataRef=\"d3\"/>",
+                ),
+                ZtrTranslationFormat::Po => text,
+            };
+
+            let import = read_ztr_translation(&text, &us, &fr, gc).unwrap();
+            assert!(import.issues.is_empty(), "{:?}: {:?}", format, import.issues);
+            assert_eq!((import.translated, import.untranslated), (2, 1), "{:?}", format);
+            assert_eq!(
+                import.data.entries[0].text,
+                "Pour {Color Gold}continuer{Color White}, appuyez sur {Btn A}",
+                "{:?}",
+                format
+            );
+            assert_eq!(import.data.entries[1].text, "Caf{é}", "{:?}", format);
+
+            // Codes are checked against the source ZTR, not the file's copy of it
+            let other = ztr(&[("txt_001", "Press {Btn B}"), ("txt_002", "x")]);
+            let import = read_ztr_translation(&text, &other, &fr, gc).unwrap();
+            assert_eq!(import.issues.len(), 1, "{:?}", format);
+            assert_eq!(import.issues[0].id, "txt_001");
+            assert!(import.issues[0].message.starts_with("missing {Btn B}"), "{:?}", import.issues);
+            let mut partial = us.clone();
+            partial.entries.truncate(1);
+            let import = read_ztr_translation(&text, &partial, &fr, gc).unwrap();
+            assert_eq!(import.issues.len(), 1, "{:?}", format);
+            assert_eq!(import.issues[0].id, "txt_002");
+            assert_eq!(import.issues[0].message, "not in the source file");
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_lost_codes() {
+        let gc = GameCode::FF13_1;
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test_ztr_translation");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let us = ztr(&[("txt_001", "Press {Btn A}"), ("txt_002", "Hello")]);
+        pack_ztr_from_struct(&us, dir.join("txtres_us.ztr"), gc).unwrap();
+
+        let mut opts = options(ZtrTranslationFormat::Po);
+        opts.target_language = "de".to_string();
+        let po = dir.join("de.po");
+        assert_eq!(export_ztr_translation(dir.join("txtres_us.ztr"), None, po.clone(), &opts, gc).unwrap(), 2);
+        let text = fs::read_to_string(&po).unwrap();
+        assert!(text.contains("#. Keep control codes: {Btn A}"));
+
+        // The translator drops {Btn A}, from the msgid too
+        let broken = text
+            .replacen("msgstr \"\"", "MSGSTR", 1)
+            .replace("msgid \"Press {Btn A}\"\nmsgstr \"\"", "msgid \"Press\"\nmsgstr \"Drücken\"")
+            .replace("msgid \"Hello\"\nmsgstr \"\"", "msgid \"Hello\"\nmsgstr \"Hallo\"")
+            .replacen("MSGSTR", "msgstr \"\"", 1);
+        fs::write(&po, &broken).unwrap();
+        let out = dir.join("txtres_de.ztr");
+        let err = import_ztr_translation(po.clone(), dir.join("txtres_us.ztr"), None, out.clone(), gc, None).unwrap_err();
+        assert!(err.to_string().contains("txt_001: missing {Btn A}"), "{}", err);
+        assert!(!out.exists());
+
+        fs::write(&po, broken.replace("\"Drücken\"", "\"{Btn A} drücken\"")).unwrap();
+        let import = import_ztr_translation(po, dir.join("txtres_us.ztr"), None, out.clone(), gc, None).unwrap();
+        assert_eq!(import.translated, 2);
+        let de = parse_ztr(&out, gc).unwrap();
+        assert_eq!(de.entries[0].text, "{Btn A} dr{ü}cken");
+        assert_eq!(de.entries[1].text, "Hallo");
+    }
+}
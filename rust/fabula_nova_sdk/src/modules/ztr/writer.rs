@@ -28,6 +28,7 @@
 use byteorder::{BigEndian, WriteBytesExt};
 use std::io::{Seek, Write};
 
+use super::charsets::DEFAULT_ZTR_ENCODING;
 use super::key_dicts::GameCode;
 use super::structs::{LineInfo, ZtrFileHeader};
 
@@ -60,18 +61,27 @@ pub struct ZtrWriter<W: Write + Seek> {
     writer: W,
     /// Target game for control code encoding
     game_code: GameCode,
+    /// Character table for literal text
+    encoding: String,
 }
 
 impl<W: Write + Seek> ZtrWriter<W> {
     pub fn new(writer: W, game_code: GameCode) -> Self {
-        Self { writer, game_code }
+        Self { writer, game_code, encoding: DEFAULT_ZTR_ENCODING.to_string() }
+    }
+
+    /// Encodes literal text with another character table (e.g. "Big5").
+    pub fn with_encoding(mut self, encoding: &str) -> Self {
+        self.encoding = encoding.to_string();
+        self
     }
 
     pub fn write(&mut self, entries: &[(String, String)]) -> anyhow::Result<()> {
         log::info!(
-            "Writing ZTR file with {} entries (GameCode: {:?})",
+            "Writing ZTR file with {} entries (GameCode: {:?}, encoding: {})",
             entries.len(),
-            self.game_code
+            self.game_code,
+            self.encoding
         );
         // 1. Process IDs
         let mut ids_stream = Vec::new();
@@ -96,7 +106,7 @@ impl<W: Write + Seek> ZtrWriter<W> {
 
         let mut processed_lines = Vec::new();
         for (_, text) in entries {
-            let mut encoded = encode_ztr_line(text, self.game_code, "Shift-JIS");
+            let mut encoded = encode_ztr_line(text, self.game_code, &self.encoding);
 
             // Ensure 00 00 termination
             let len = encoded.len();